@@ -12,6 +12,23 @@ pub struct Config<'a> {
     pub min_size: (u32, u32),
     /// Whether the window should start in fullscreen mode.
     pub fullscreen: bool,
+    /// The maximum number of frames per second the application is allowed to produce.
+    ///
+    /// When `None`, the frame rate is only limited by the present mode of the surface.
+    ///
+    /// # Remarks
+    ///
+    /// This has no effect on web, where the browser decides when frames are produced.
+    pub max_fps: Option<u32>,
+    /// The icon of the window.
+    ///
+    /// The image is converted to RGBA8, the only pixel format supported by the windowing
+    /// system, before being used. When `None`, the platform's default icon is used.
+    ///
+    /// # Remarks
+    ///
+    /// This is ignored on platforms that don't support window icons, such as web.
+    pub icon: Option<bns_image::Image>,
 }
 
 impl<'a> Default for Config<'a> {
@@ -21,6 +38,8 @@ impl<'a> Default for Config<'a> {
             min_size: (400, 400),
             fullscreen: false,
             size: None,
+            max_fps: None,
+            icon: None,
         }
     }
 }
@@ -4,9 +4,13 @@ use std::time::Duration;
 
 use hashbrown::HashMap;
 use rustc_hash::FxHasher;
-use winit::event::MouseScrollDelta;
+use winit::event::{Ime, MouseScrollDelta};
 use winit::window::{CursorGrabMode, Fullscreen, Window};
 
+use crate::resize::ResizeDebounce;
+use crate::text_input::TextInput;
+use crate::{Action, InputMap};
+
 pub use winit::keyboard::{KeyCode, NamedKey, NativeKey, NativeKeyCode, SmolStr};
 
 /// Contains the application context, which can be used to access the window and other
@@ -22,16 +26,37 @@ pub struct Ctx {
     just_resized: bool,
     /// The current size of the window's client area.
     size: (u32, u32),
+    /// Coalesces the resize events received while the user is resizing the window.
+    resize: ResizeDebounce,
 
     /// The current state of the buttons.
     buttons: HashMap<AnyButton, ButtonState, BuildHasherDefault<FxHasher>>,
 
-    /// The amount of movement accumulated by the mouse since the last tick.
+    /// The amount of movement accumulated by the cursor since the last tick, in window pixels.
     mouse_delta: (f64, f64),
+    /// The amount of movement accumulated by the mouse device since the last tick.
+    ///
+    /// Unlike `mouse_delta`, this is not affected by the pointer acceleration of the OS and
+    /// keeps being reported when the cursor is locked.
+    raw_mouse_delta: (f64, f64),
+    /// The last known position of the cursor within the window.
+    ///
+    /// This is `None` when the cursor is outside of the window.
+    cursor_position: Option<(f64, f64)>,
+    /// Whether the cursor is currently grabbed by the window.
+    cursor_grabbed: bool,
 
     /// The amount of scroll data accumulated by the mouse since the last tick.
     mouse_scroll: (f64, f64),
 
+    /// The current value of the gamepad axes.
+    ///
+    /// Axes that have never been moved are not present in the map.
+    gamepad_axes: HashMap<GamepadAxis, f32, BuildHasherDefault<FxHasher>>,
+
+    /// The buttons bound to each [`Action`].
+    input_map: InputMap,
+
     /// Whether the window currently has focus.
     focused: bool,
     /// Whether the window has just gained or lost focus.
@@ -40,8 +65,8 @@ pub struct Ctx {
     /// Whether the window is currently in fullscreen mode.
     is_fullscreen: bool,
 
-    /// The text that was typed since the last tick.
-    typed: String,
+    /// Accumulates the text typed by the user.
+    text_input: TextInput,
 
     /// The clock that's used to compute times.
     clock: quanta::Clock,
@@ -67,13 +92,17 @@ impl Ctx {
             closing: false,
             just_resized: true,
             size: window.inner_size().into(),
+            resize: ResizeDebounce::new(window.inner_size().into()),
             buttons: HashMap::default(),
             mouse_delta: (0.0, 0.0),
+            raw_mouse_delta: (0.0, 0.0),
+            cursor_position: None,
+            cursor_grabbed: false,
             focused: true,
             focus_just_changed: false,
             is_fullscreen: window.fullscreen().is_some(),
             window,
-            typed: String::new(),
+            text_input: TextInput::default(),
             initial_instant: now,
             last_tick_instant: now,
             since_startup: Duration::ZERO,
@@ -81,6 +110,8 @@ impl Ctx {
             delta_seconds: 0.0,
             clock,
             mouse_scroll: (0.0, 0.0),
+            gamepad_axes: HashMap::default(),
+            input_map: InputMap::default(),
         }
     }
 
@@ -91,10 +122,13 @@ impl Ctx {
     }
 
     /// Notifies the context that the window it controls has been resized.
+    ///
+    /// Resize events are coalesced until the size of the window has remained the same for
+    /// [`ResizeDebounce::DELAY`], and events that don't actually change the size of the window
+    /// are ignored.
     #[inline]
     pub(crate) fn notify_resized(&mut self, w: u32, h: u32) {
-        self.just_resized = true;
-        self.size = (w, h);
+        self.resize.notify((w, h), self.clock.now());
     }
 
     /// Notifies the context that the event loop has been requested to close by the user.
@@ -103,16 +137,32 @@ impl Ctx {
         self.closing = true;
     }
 
-    /// Notifies the context that the mouse has moved.
+    /// Notifies the context that the cursor has moved to the provided position within the
+    /// window.
+    pub(crate) fn notify_cursor_moved(&mut self, x: f64, y: f64) {
+        if let Some((last_x, last_y)) = self.cursor_position {
+            self.mouse_delta.0 += x - last_x;
+            self.mouse_delta.1 += y - last_y;
+        }
+        self.cursor_position = Some((x, y));
+    }
+
+    /// Notifies the context that the cursor has left the window.
+    #[inline]
+    pub(crate) fn notify_cursor_left(&mut self) {
+        self.cursor_position = None;
+    }
+
+    /// Notifies the context that the mouse device has moved.
     #[inline]
-    pub(crate) fn notify_mouse_moved(&mut self, dx: f64, dy: f64) {
-        self.mouse_delta.0 += dx;
-        self.mouse_delta.1 += dy;
+    pub(crate) fn notify_raw_mouse_moved(&mut self, dx: f64, dy: f64) {
+        self.raw_mouse_delta.0 += dx;
+        self.raw_mouse_delta.1 += dy;
     }
 
     /// Returns the state of the requested button, if present.
-    fn button(&self, btn: AnyButton) -> ButtonState {
-        self.buttons.get(&btn).map_or(ButtonState::IDLE, |s| *s)
+    fn button(&self, btn: &AnyButton) -> ButtonState {
+        self.buttons.get(btn).map_or(ButtonState::IDLE, |s| *s)
     }
 
     /// Returns the state of the requested button, or inserts a new one if not present.
@@ -142,7 +192,13 @@ impl Ctx {
     /// Notifies the context that the text has been typed.
     #[inline]
     pub(crate) fn notify_typed(&mut self, text: &str) {
-        self.typed.push_str(text);
+        self.text_input.notify_typed(text);
+    }
+
+    /// Notifies the context that an input method event has been received.
+    #[inline]
+    pub(crate) fn notify_ime(&mut self, ime: Ime) {
+        self.text_input.notify_ime(ime);
     }
 
     /// Notifies the context that the mouse has been scrolled.
@@ -159,6 +215,24 @@ impl Ctx {
         }
     }
 
+    /// Notifies the context that a gamepad axis has changed.
+    #[inline]
+    pub(crate) fn notify_gamepad_axis(&mut self, axis: GamepadAxis, value: f32) {
+        self.gamepad_axes.insert(axis, value);
+    }
+
+    /// Notifies the context that the gamepad has been disconnected.
+    ///
+    /// Axes go back to their resting position. Buttons are released.
+    pub(crate) fn notify_gamepad_disconnected(&mut self) {
+        self.gamepad_axes.clear();
+        for (btn, state) in self.buttons.iter_mut() {
+            if matches!(btn, AnyButton::Gamepad(_)) && state.pressed() {
+                state.notify_released();
+            }
+        }
+    }
+
     /// Notifies the context that the tick function has started.
     pub(crate) fn notify_start_of_tick(&mut self) {
         let now = self.clock.now();
@@ -166,6 +240,11 @@ impl Ctx {
         self.since_startup = now - self.initial_instant;
         self.delta_seconds = self.since_last_tick.as_secs_f32();
         self.last_tick_instant = now;
+
+        if let Some(size) = self.resize.poll(now) {
+            self.just_resized = true;
+            self.size = size;
+        }
     }
 
     /// Notifies the context that the tick function has returned. New events are about to be
@@ -173,8 +252,9 @@ impl Ctx {
     pub(crate) fn notify_end_of_tick(&mut self) {
         self.just_resized = false;
         self.mouse_delta = (0.0, 0.0);
+        self.raw_mouse_delta = (0.0, 0.0);
         self.focus_just_changed = false;
-        self.typed.clear();
+        self.text_input.notify_end_of_tick();
         self.mouse_scroll = (0.0, 0.0);
         self.buttons
             .values_mut()
@@ -245,6 +325,11 @@ impl Ctx {
     ///
     /// The new size of the window can be queried by calling [`size`].
     ///
+    /// # Remarks
+    ///
+    /// This is only set once the size of the window has settled. While the user is dragging the
+    /// edge of the window, the intermediate sizes are not reported.
+    ///
     /// [`size`]: Self::size
     #[inline]
     pub fn just_resized(&self) -> bool {
@@ -254,39 +339,92 @@ impl Ctx {
     /// Returns whether the requested button has been pressed since the last frame.
     #[inline]
     pub fn just_pressed(&self, btn: impl Into<AnyButton>) -> bool {
-        self.button(btn.into()).just_pressed()
+        self.button(&btn.into()).just_pressed()
     }
 
     /// Returns whether the requested button has been released since the last frame.
     #[inline]
     pub fn just_released(&self, btn: impl Into<AnyButton>) -> bool {
-        self.button(btn.into()).just_released()
+        self.button(&btn.into()).just_released()
     }
 
     /// Returns whether the requested button is currently pressed.
     #[inline]
     pub fn pressing(&self, btn: impl Into<AnyButton>) -> bool {
-        self.button(btn.into()).pressed()
+        self.button(&btn.into()).pressed()
     }
 
-    /// The relative position of the mouse since the last tick.
+    /// Returns the [`InputMap`] used to resolve [`Action`]s.
+    #[inline]
+    pub fn input_map(&self) -> &InputMap {
+        &self.input_map
+    }
+
+    /// Returns a mutable reference to the [`InputMap`] used to resolve [`Action`]s.
+    ///
+    /// This can be used to change individual bindings.
+    #[inline]
+    pub fn input_map_mut(&mut self) -> &mut InputMap {
+        &mut self.input_map
+    }
+
+    /// Replaces the [`InputMap`] used to resolve [`Action`]s.
+    #[inline]
+    pub fn set_input_map(&mut self, map: InputMap) {
+        self.input_map = map;
+    }
+
+    /// Returns whether any of the buttons bound to the requested action has been pressed since
+    /// the last frame.
+    pub fn action_just_pressed(&self, action: Action) -> bool {
+        self.input_map
+            .bindings(action)
+            .iter()
+            .any(|btn| self.button(btn).just_pressed())
+    }
+
+    /// Returns whether any of the buttons bound to the requested action is currently pressed.
+    pub fn action_pressing(&self, action: Action) -> bool {
+        self.input_map
+            .bindings(action)
+            .iter()
+            .any(|btn| self.button(btn).pressed())
+    }
+
+    /// The relative position of the cursor since the last tick, in window pixels.
+    ///
+    /// # Remarks
+    ///
+    /// This is subject to the pointer acceleration of the OS, and usually stays at zero while
+    /// the cursor is grabbed. Use [`raw_mouse_delta`] to control a camera.
+    ///
+    /// [`raw_mouse_delta`]: Self::raw_mouse_delta
     #[inline]
     pub fn mouse_delta(&self) -> (f64, f64) {
         self.mouse_delta
     }
 
-    /// The relative position of the mouse on the X axis since the last tick.
+    /// The relative position of the cursor on the X axis since the last tick.
     #[inline]
     pub fn mouse_delta_x(&self) -> f64 {
         self.mouse_delta.0
     }
 
-    /// The relative position of the mouse on the Y axis since the last tick.
+    /// The relative position of the cursor on the Y axis since the last tick.
     #[inline]
     pub fn mouse_delta_y(&self) -> f64 {
         self.mouse_delta.1
     }
 
+    /// The movement reported by the mouse device since the last tick.
+    ///
+    /// The unit of the returned values is platform-specific, but they are not affected by the
+    /// pointer acceleration of the OS and keep being reported while the cursor is grabbed.
+    #[inline]
+    pub fn raw_mouse_delta(&self) -> (f64, f64) {
+        self.raw_mouse_delta
+    }
+
     /// Returns whether the window is currently is fullscreen mode.
     #[inline]
     pub fn fullscreen(&self) -> bool {
@@ -302,9 +440,47 @@ impl Ctx {
     }
 
     /// Returns the text that has been typed since the last tick.
+    ///
+    /// Unlike [`just_pressed`], this takes the keyboard layout, modifiers and key repeats into
+    /// account, making it suitable for text entry.
+    ///
+    /// # Remarks
+    ///
+    /// This is always empty unless text input has been enabled with [`set_text_input`].
+    ///
+    /// [`just_pressed`]: Ctx::just_pressed
+    /// [`set_text_input`]: Ctx::set_text_input
     #[inline]
     pub fn typed(&self) -> &str {
-        &self.typed
+        self.text_input.typed()
+    }
+
+    /// Returns whether text input is currently enabled.
+    #[inline]
+    pub fn text_input(&self) -> bool {
+        self.text_input.enabled()
+    }
+
+    /// Enables or disables text input.
+    ///
+    /// While text input is enabled, the text typed by the user is accumulated and made
+    /// available through [`typed`](Ctx::typed). Regular button events are still reported.
+    ///
+    /// Enabling text input also allows the platform's input method to be used.
+    pub fn set_text_input(&mut self, enabled: bool) {
+        self.text_input.set_enabled(enabled);
+        self.window.set_ime_allowed(enabled);
+    }
+
+    /// Returns the text that is currently being composed through an input method.
+    ///
+    /// This text has not been committed yet and is not part of [`typed`](Ctx::typed). It
+    /// should be displayed to the user (usually at the position of the cursor) but not
+    /// interpreted. Once the composition is committed, the resulting text is reported through
+    /// [`typed`](Ctx::typed) and the composition becomes empty.
+    #[inline]
+    pub fn composition(&self) -> &str {
+        self.text_input.composition()
     }
 
     /// Attempt to grab the cursor, hiding it and locking it to the window.
@@ -314,6 +490,7 @@ impl Ctx {
             .or_else(|_| self.window.set_cursor_grab(CursorGrabMode::Confined))
             .expect("failed to grab the cursor");
         self.window.set_cursor_visible(false);
+        self.cursor_grabbed = true;
     }
 
     /// Attempt to release the cursor, showing it and unlocking it from the window.
@@ -322,6 +499,13 @@ impl Ctx {
             .set_cursor_grab(CursorGrabMode::None)
             .expect("failed to release the cursor");
         self.window.set_cursor_visible(true);
+        self.cursor_grabbed = false;
+    }
+
+    /// Returns whether the cursor is currently grabbed by the window.
+    #[inline]
+    pub fn cursor_grabbed(&self) -> bool {
+        self.cursor_grabbed
     }
 
     /// Returns the amount of time elapsed since the application started.
@@ -354,6 +538,38 @@ impl Ctx {
         self.focus_just_changed
     }
 
+    /// Returns the current value of the requested gamepad axis, between `-1.0` and `1.0`.
+    ///
+    /// # Remarks
+    ///
+    /// Values within [`GAMEPAD_DEAD_ZONE`] of the resting position are reported as `0.0`, as
+    /// sticks rarely rest exactly at the center. When multiple gamepads are connected, the last
+    /// one to move an axis wins.
+    pub fn gamepad_axis(&self, axis: GamepadAxis) -> f32 {
+        let value = self.gamepad_axes.get(&axis).copied().unwrap_or(0.0);
+        if value.abs() < GAMEPAD_DEAD_ZONE {
+            0.0
+        } else {
+            value
+        }
+    }
+
+    /// Returns whether the requested gamepad button is currently pressed.
+    ///
+    /// This is equivalent to calling [`pressing`](Ctx::pressing) with the button.
+    #[inline]
+    pub fn gamepad_pressing(&self, btn: GamepadButton) -> bool {
+        self.pressing(btn)
+    }
+
+    /// Returns whether the requested gamepad button has been pressed since the last frame.
+    ///
+    /// This is equivalent to calling [`just_pressed`](Ctx::just_pressed) with the button.
+    #[inline]
+    pub fn gamepad_just_pressed(&self, btn: GamepadButton) -> bool {
+        self.just_pressed(btn)
+    }
+
     /// Returns the amount of scroll data accumulated by the mouse since the last tick.
     #[inline]
     pub fn mouse_scroll(&self) -> (f64, f64) {
@@ -373,9 +589,14 @@ impl Ctx {
     }
 }
 
+pub use gilrs::{Axis as GamepadAxis, Button as GamepadButton};
 pub use winit::event::MouseButton;
 pub use winit::keyboard::{Key, PhysicalKey};
 
+/// The distance from the resting position under which gamepad axes are considered to be at
+/// rest.
+pub const GAMEPAD_DEAD_ZONE: f32 = 0.15;
+
 /// Any kind of button that's supposed by the [`Ctx`] type.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AnyButton {
@@ -385,6 +606,15 @@ pub enum AnyButton {
     LogicalKey(Key),
     /// A physical keyboard key.
     PhysicalKey(PhysicalKey),
+    /// A gamepad button.
+    Gamepad(GamepadButton),
+}
+
+impl From<GamepadButton> for AnyButton {
+    #[inline]
+    fn from(btn: GamepadButton) -> Self {
+        Self::Gamepad(btn)
+    }
 }
 
 impl From<MouseButton> for AnyButton {
@@ -4,7 +4,7 @@ use winit::dpi::PhysicalSize;
 use winit::event::ElementState::{Pressed, Released};
 use winit::event::{DeviceEvent, Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop, EventLoopBuilder};
-use winit::window::{Fullscreen, Window, WindowBuilder};
+use winit::window::{Fullscreen, Icon, Window, WindowBuilder};
 
 use crate::{Config, Ctx};
 
@@ -55,6 +55,11 @@ pub fn create_window(event_loop: &EventLoop<UserEvent>, config: Config) -> Arc<W
         builder = builder.with_inner_size(PhysicalSize::<u32>::from(size));
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(icon) = config.icon {
+        builder = builder.with_window_icon(create_icon(icon));
+    }
+
     #[cfg(target_arch = "wasm32")]
     {
         use winit::platform::web::WindowBuilderExtWebSys;
@@ -69,18 +74,47 @@ pub fn create_window(event_loop: &EventLoop<UserEvent>, config: Config) -> Arc<W
         .into()
 }
 
+/// Converts the provided image into a window [`Icon`].
+///
+/// Errors are logged and result in `None`, in which case the default icon is used.
+#[cfg(not(target_arch = "wasm32"))]
+fn create_icon(mut image: bns_image::Image) -> Option<Icon> {
+    image.ensure_rgba();
+
+    let (width, height) = (image.metadata.width, image.metadata.height);
+    Icon::from_rgba(image.pixels, width, height)
+        .map_err(|err| bns_log::warning!("failed to create the window icon: {err}"))
+        .ok()
+}
+
 /// Runs the event loop until completion.
 ///
 /// # Panics
 ///
 /// This function panics if the event loop cannot be run.
 #[allow(clippy::collapsible_match, clippy::single_match)]
-pub fn run<F>(event_loop: EventLoop<UserEvent>, window: Arc<Window>, mut tick: F)
-where
+pub fn run<F>(
+    event_loop: EventLoop<UserEvent>,
+    window: Arc<Window>,
+    max_fps: Option<u32>,
+    mut tick: F,
+) where
     F: FnMut(&mut Ctx),
 {
     let mut ctx = Ctx::new(window);
 
+    // On web, the browser decides when frames are produced.
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut frame_limiter = max_fps.map(crate::frame_limiter::FrameLimiter::new);
+    #[cfg(target_arch = "wasm32")]
+    let _ = max_fps;
+
+    // Gamepads are optional. When the backend is not available, the application simply runs
+    // without them.
+    let mut gilrs = gilrs::Gilrs::new()
+        .map_err(|err| bns_log::warning!("gamepad support is not available: {err}"))
+        .ok();
+
     // Tick the application once, hoping that it will try to draw something to the window
     // before we actually show it.
     tick(&mut ctx);
@@ -99,6 +133,12 @@ where
                 // we don't waste CPU cycles rendering frames that will never be displayed (this
                 // is done automatically by the GPU on most platform, but not web!)
                 ctx.winit_window().request_redraw();
+
+                // Gamepad events are not reported by winit, they have to be polled before each
+                // tick.
+                if let Some(gilrs) = &mut gilrs {
+                    poll_gamepad_events(gilrs, &mut ctx);
+                }
             }
             Event::WindowEvent { event, .. } => match event {
                 WindowEvent::CloseRequested => ctx.notify_close_requested(),
@@ -125,8 +165,16 @@ where
                         ctx.notify_typed(&txt)
                     }
                 }
+                WindowEvent::Ime(ime) => ctx.notify_ime(ime),
                 WindowEvent::Focused(yes) => ctx.notify_focus_changed(yes),
                 WindowEvent::RedrawRequested => {
+                    // Waiting before the start of the tick ensures that the frame time measured
+                    // by the context includes the time spent waiting.
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if let Some(frame_limiter) = &mut frame_limiter {
+                        frame_limiter.wait();
+                    }
+
                     ctx.notify_start_of_tick();
 
                     tick(&mut ctx);
@@ -140,13 +188,32 @@ where
                     ctx.notify_end_of_tick();
                 }
                 WindowEvent::MouseWheel { delta, .. } => ctx.notify_mouse_scrolled(delta),
+                WindowEvent::CursorMoved { position, .. } => {
+                    ctx.notify_cursor_moved(position.x, position.y)
+                }
+                WindowEvent::CursorLeft { .. } => ctx.notify_cursor_left(),
                 _ => (),
             },
             Event::DeviceEvent { event, .. } => match event {
-                DeviceEvent::MouseMotion { delta: (dx, dy) } => ctx.notify_mouse_moved(dx, dy),
+                DeviceEvent::MouseMotion { delta: (dx, dy) } => ctx.notify_raw_mouse_moved(dx, dy),
                 _ => (),
             },
             _ => (),
         })
         .expect("failed to run the winit event loop");
 }
+
+/// Folds the pending gamepad events into the state of the provided [`Ctx`].
+fn poll_gamepad_events(gilrs: &mut gilrs::Gilrs, ctx: &mut Ctx) {
+    use gilrs::EventType;
+
+    while let Some(event) = gilrs.next_event() {
+        match event.event {
+            EventType::ButtonPressed(btn, _) => ctx.notify_button_pressed(btn.into()),
+            EventType::ButtonReleased(btn, _) => ctx.notify_button_released(btn.into()),
+            EventType::AxisChanged(axis, value, _) => ctx.notify_gamepad_axis(axis, value),
+            EventType::Disconnected => ctx.notify_gamepad_disconnected(),
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use std::time::{Duration, Instant};
+
+/// The amount of time before the deadline during which the limiter spins instead of sleeping.
+///
+/// Most platforms can't sleep with a precision much better than a millisecond. Sleeping until
+/// right before the deadline and spinning for the rest gives a much more stable frame rate.
+const SPIN_DURATION: Duration = Duration::from_millis(1);
+
+/// Limits the rate at which frames are produced by blocking the thread.
+pub struct FrameLimiter {
+    /// The minimum amount of time between two frames.
+    budget: Duration,
+    /// The instant at which the last frame started.
+    last_frame: Instant,
+}
+
+impl FrameLimiter {
+    /// Creates a new [`FrameLimiter`] that produces at most `max_fps` frames per second.
+    pub fn new(max_fps: u32) -> Self {
+        Self {
+            budget: Duration::from_secs(1) / max_fps.max(1),
+            last_frame: Instant::now(),
+        }
+    }
+
+    /// Blocks the current thread until the budget of the current frame has been exhausted.
+    pub fn wait(&mut self) {
+        let deadline = self.last_frame + self.budget;
+
+        let now = Instant::now();
+        if let Some(remaining) = deadline.checked_duration_since(now) {
+            if remaining > SPIN_DURATION {
+                std::thread::sleep(remaining - SPIN_DURATION);
+            }
+
+            while Instant::now() < deadline {
+                std::hint::spin_loop();
+            }
+
+            self.last_frame = deadline;
+        } else {
+            // We're already late. Don't try to catch up with the lost time.
+            self.last_frame = now;
+        }
+    }
+}
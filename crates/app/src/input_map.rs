@@ -0,0 +1,118 @@
+use std::hash::BuildHasherDefault;
+
+use hashbrown::HashMap;
+use rustc_hash::FxHasher;
+
+use crate::{AnyButton, GamepadButton, KeyCode, MouseButton};
+
+/// An action that the user can perform using their input devices.
+///
+/// Actions are mapped to physical buttons through an [`InputMap`], allowing the bindings to be
+/// changed without touching the code that reacts to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Move forward.
+    ///
+    /// Pressing this action twice in a short amount of time starts sprinting.
+    MoveForward,
+    /// Move backward.
+    MoveBackward,
+    /// Strafe to the left.
+    MoveLeft,
+    /// Strafe to the right.
+    MoveRight,
+    /// Jump, swim upwards, or fly upwards when flying.
+    ///
+    /// Pressing this action twice in a short amount of time toggles flight.
+    Jump,
+    /// Fly downwards.
+    FlyDown,
+    /// Start sprinting.
+    Sprint,
+    /// Break the block the player is looking at.
+    BreakBlock,
+    /// Place a block against the block the player is looking at.
+    PlaceBlock,
+    /// Select the block the player is looking at as the current material.
+    PickBlock,
+    /// Increase the render distance.
+    IncreaseRenderDistance,
+    /// Decrease the render distance.
+    DecreaseRenderDistance,
+}
+
+/// Maps [`Action`]s to the buttons that trigger them.
+///
+/// An action may be bound to any number of buttons. It is considered pressed as long as any of
+/// them is.
+#[derive(Debug, Clone)]
+pub struct InputMap {
+    /// The buttons bound to each action.
+    ///
+    /// Actions that are not present in the map are not bound to any button.
+    bindings: HashMap<Action, Vec<AnyButton>, BuildHasherDefault<FxHasher>>,
+}
+
+impl InputMap {
+    /// Creates a new [`InputMap`] with no bindings at all.
+    pub fn empty() -> Self {
+        Self {
+            bindings: HashMap::default(),
+        }
+    }
+
+    /// Returns the buttons bound to the provided action.
+    #[inline]
+    pub fn bindings(&self, action: Action) -> &[AnyButton] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Adds a button to the bindings of the provided action.
+    ///
+    /// The existing bindings of the action are kept.
+    pub fn bind(&mut self, action: Action, btn: impl Into<AnyButton>) {
+        let btn = btn.into();
+        let bindings = self.bindings.entry(action).or_default();
+        if !bindings.contains(&btn) {
+            bindings.push(btn);
+        }
+    }
+
+    /// Replaces the bindings of the provided action.
+    pub fn set_bindings(&mut self, action: Action, btns: impl IntoIterator<Item = AnyButton>) {
+        let bindings = self.bindings.entry(action).or_default();
+        bindings.clear();
+        bindings.extend(btns);
+    }
+
+    /// Removes all the bindings of the provided action.
+    #[inline]
+    pub fn unbind(&mut self, action: Action) {
+        self.bindings.remove(&action);
+    }
+}
+
+impl Default for InputMap {
+    /// Creates the default bindings of the game.
+    fn default() -> Self {
+        let mut map = Self::empty();
+
+        map.bind(Action::MoveForward, KeyCode::KeyW);
+        map.bind(Action::MoveBackward, KeyCode::KeyS);
+        map.bind(Action::MoveLeft, KeyCode::KeyA);
+        map.bind(Action::MoveRight, KeyCode::KeyD);
+        map.bind(Action::Jump, KeyCode::Space);
+        map.bind(Action::Jump, GamepadButton::South);
+        map.bind(Action::FlyDown, KeyCode::ShiftLeft);
+        map.bind(Action::FlyDown, GamepadButton::East);
+        map.bind(Action::Sprint, KeyCode::ControlLeft);
+        map.bind(Action::Sprint, GamepadButton::LeftThumb);
+        map.bind(Action::BreakBlock, MouseButton::Left);
+        map.bind(Action::PlaceBlock, MouseButton::Right);
+        map.bind(Action::PickBlock, MouseButton::Middle);
+        map.bind(Action::IncreaseRenderDistance, KeyCode::ArrowUp);
+        map.bind(Action::DecreaseRenderDistance, KeyCode::ArrowDown);
+
+        map
+    }
+}
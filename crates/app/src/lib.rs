@@ -6,11 +6,17 @@ use raw_window_handle as rwh;
 
 mod config;
 mod ctx;
+mod input_map;
+mod resize;
+mod text_input;
 
 pub use config::*;
 pub use ctx::*;
+pub use input_map::*;
 
 mod event_loop;
+#[cfg(not(target_arch = "wasm32"))]
+mod frame_limiter;
 
 /// an opaque window object that guarantees that the window is valid while the object is alive.
 pub struct OpaqueWindow(Arc<winit::window::Window>);
@@ -33,14 +39,20 @@ impl rwh::HasDisplayHandle for OpaqueWindow {
 pub struct App {
     event_loop: winit::event_loop::EventLoop<event_loop::UserEvent>,
     window: Arc<winit::window::Window>,
+    max_fps: Option<u32>,
 }
 
 impl App {
     /// Creates a new [`App`] instance with the given [`Config`].
     pub fn new(config: Config) -> Self {
+        let max_fps = config.max_fps;
         let event_loop = event_loop::create_event_loop();
         let window = event_loop::create_window(&event_loop, config);
-        Self { event_loop, window }
+        Self {
+            event_loop,
+            window,
+            max_fps,
+        }
     }
 
     /// Returns an [`OpaqueWindow`] that guarantees that the window is valid while the object is
@@ -55,7 +67,7 @@ impl App {
     where
         F: FnMut(&mut Ctx),
     {
-        event_loop::run(self.event_loop, self.window, tick)
+        event_loop::run(self.event_loop, self.window, self.max_fps, tick)
     }
 }
 
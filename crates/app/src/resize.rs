@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+/// Coalesces the resize events of a window until its size settles.
+///
+/// Resizing the surface and the size-dependent resources of the renderer is expensive. While the
+/// user is dragging the edge of the window, a new size is reported almost every frame, and
+/// applying every one of them causes the application to stutter.
+pub(crate) struct ResizeDebounce {
+    /// The size that was last reported by [`ResizeDebounce::poll`].
+    size: (u32, u32),
+    /// The last size received, along with the instant at which it was received.
+    ///
+    /// This is `None` when no resize is pending.
+    pending: Option<((u32, u32), quanta::Instant)>,
+}
+
+impl ResizeDebounce {
+    /// The amount of time the size of the window must remain the same before it is applied.
+    pub const DELAY: Duration = Duration::from_millis(100);
+
+    /// Creates a new [`ResizeDebounce`] for a window of the provided size.
+    pub fn new(size: (u32, u32)) -> Self {
+        Self {
+            size,
+            pending: None,
+        }
+    }
+
+    /// Notifies the debouncer that the window has been resized at `now`.
+    pub fn notify(&mut self, size: (u32, u32), now: quanta::Instant) {
+        if self.pending.map_or(self.size, |(pending, _)| pending) != size {
+            self.pending = Some((size, now));
+        }
+    }
+
+    /// Returns the new size of the window if it has remained the same for at least
+    /// [`ResizeDebounce::DELAY`].
+    ///
+    /// # Returns
+    ///
+    /// `None` if no resize is pending, or if the size of the window has changed too recently.
+    pub fn poll(&mut self, now: quanta::Instant) -> Option<(u32, u32)> {
+        let (size, since) = self.pending?;
+
+        if now.duration_since(since) < Self::DELAY {
+            return None;
+        }
+
+        self.pending = None;
+
+        if size == self.size {
+            return None;
+        }
+
+        self.size = size;
+        Some(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rapid_resizes_are_applied_once() {
+        let (clock, mock) = quanta::Clock::mock();
+        let mut debounce = ResizeDebounce::new((800, 600));
+
+        for i in 0..20 {
+            debounce.notify((800 + i, 600 + i), clock.now());
+            mock.increment(Duration::from_millis(5));
+            assert_eq!(debounce.poll(clock.now()), None);
+        }
+
+        mock.increment(ResizeDebounce::DELAY);
+        assert_eq!(debounce.poll(clock.now()), Some((819, 619)));
+        assert_eq!(debounce.poll(clock.now()), None);
+    }
+
+    #[test]
+    fn resizing_back_to_the_original_size_is_ignored() {
+        let (clock, mock) = quanta::Clock::mock();
+        let mut debounce = ResizeDebounce::new((800, 600));
+
+        debounce.notify((1024, 768), clock.now());
+        debounce.notify((800, 600), clock.now());
+        mock.increment(ResizeDebounce::DELAY);
+        assert_eq!(debounce.poll(clock.now()), None);
+    }
+
+    #[test]
+    fn repeated_sizes_do_not_delay_the_resize() {
+        let (clock, mock) = quanta::Clock::mock();
+        let mut debounce = ResizeDebounce::new((800, 600));
+
+        debounce.notify((1024, 768), clock.now());
+        mock.increment(ResizeDebounce::DELAY);
+        debounce.notify((1024, 768), clock.now());
+        assert_eq!(debounce.poll(clock.now()), Some((1024, 768)));
+    }
+}
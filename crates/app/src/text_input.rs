@@ -0,0 +1,146 @@
+use winit::event::Ime;
+
+/// Accumulates the text typed by the user while text input is enabled.
+///
+/// Unlike physical key presses, the text received from the platform takes the keyboard layout,
+/// modifiers and key repeats into account.
+#[derive(Default)]
+pub(crate) struct TextInput {
+    /// Whether text input is currently enabled.
+    ///
+    /// When disabled, typed text is not accumulated in `typed`.
+    enabled: bool,
+    /// The text that was typed since the last tick.
+    typed: String,
+    /// The text currently being composed through an input method.
+    ///
+    /// Unlike `typed`, this is not cleared at the end of every tick. It's replaced every time
+    /// the input method updates its composition.
+    composition: String,
+}
+
+impl TextInput {
+    /// Returns whether text input is currently enabled.
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables or disables text input.
+    ///
+    /// Disabling text input discards the text that was typed since the last tick, as well as
+    /// the current composition.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.typed.clear();
+            self.composition.clear();
+        }
+    }
+
+    /// Returns the text that has been typed since the last tick.
+    #[inline]
+    pub fn typed(&self) -> &str {
+        &self.typed
+    }
+
+    /// Notifies the text input that the provided text has been typed.
+    #[inline]
+    pub fn notify_typed(&mut self, text: &str) {
+        if self.enabled {
+            self.typed.push_str(text);
+        }
+    }
+
+    /// Returns the text that is currently being composed through an input method.
+    #[inline]
+    pub fn composition(&self) -> &str {
+        &self.composition
+    }
+
+    /// Notifies the text input that an input method event has been received.
+    pub fn notify_ime(&mut self, ime: Ime) {
+        self.composition.clear();
+        match ime {
+            Ime::Preedit(text, _) => {
+                if self.enabled {
+                    self.composition = text;
+                }
+            }
+            Ime::Commit(text) => self.notify_typed(&text),
+            Ime::Enabled | Ime::Disabled => (),
+        }
+    }
+
+    /// Notifies the text input that the tick has ended.
+    #[inline]
+    pub fn notify_end_of_tick(&mut self) {
+        self.typed.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_text_is_accumulated_until_the_end_of_the_tick() {
+        let mut input = TextInput::default();
+        input.set_enabled(true);
+
+        input.notify_typed("a");
+        input.notify_typed("B");
+        input.notify_typed("/!é");
+        assert_eq!(input.typed(), "aB/!é");
+
+        input.notify_end_of_tick();
+        assert_eq!(input.typed(), "");
+
+        input.notify_typed("c");
+        assert_eq!(input.typed(), "c");
+    }
+
+    #[test]
+    fn typed_text_is_ignored_while_disabled() {
+        let mut input = TextInput::default();
+        input.notify_typed("a");
+        assert_eq!(input.typed(), "");
+
+        input.set_enabled(true);
+        input.notify_typed("b");
+        input.set_enabled(false);
+        assert_eq!(input.typed(), "");
+    }
+
+    #[test]
+    fn composition_is_only_typed_once_committed() {
+        let mut input = TextInput::default();
+        input.set_enabled(true);
+
+        input.notify_typed("a");
+        input.notify_ime(Ime::Preedit("k".into(), Some((1, 1))));
+        input.notify_ime(Ime::Preedit("かn".into(), Some((2, 2))));
+        assert_eq!(input.composition(), "かn");
+        assert_eq!(input.typed(), "a");
+
+        input.notify_end_of_tick();
+        assert_eq!(input.composition(), "かn");
+
+        input.notify_ime(Ime::Preedit("かな".into(), Some((2, 2))));
+        input.notify_ime(Ime::Commit("仮名".into()));
+        assert_eq!(input.composition(), "");
+        assert_eq!(input.typed(), "仮名");
+    }
+
+    #[test]
+    fn cancelled_composition_is_discarded() {
+        let mut input = TextInput::default();
+        input.set_enabled(true);
+
+        input.notify_ime(Ime::Preedit("かな".into(), None));
+        input.notify_ime(Ime::Preedit(String::new(), None));
+        input.notify_ime(Ime::Disabled);
+        assert_eq!(input.composition(), "");
+        assert_eq!(input.typed(), "");
+    }
+}
@@ -70,12 +70,16 @@ impl BlockId {
             Self::Air => BlockInfo {
                 appearance: BlockAppearance::Invisible,
                 visibility: BlockVisibility::Invisible,
-                flags: BlockFlags::empty(),
+                flags: BlockFlags::REPLACEABLE,
+                light_emission: 0,
+                hardness: 0.0,
             },
             Self::Stone => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::Stone),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 1.5,
             },
             Self::Grass => BlockInfo {
                 appearance: BlockAppearance::Regular {
@@ -85,36 +89,50 @@ impl BlockId {
                 },
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 0.6,
             },
             Self::Dirt => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::Dirt),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 0.5,
             },
             Self::Andesite => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::Andesite),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 1.5,
             },
             Self::Clay => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::Clay),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 0.6,
             },
             Self::Diorite => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::Diorite),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 1.5,
             },
             Self::Granite => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::Granite),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 1.5,
             },
             Self::Gravel => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::Gravel),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 0.6,
             },
             Self::Podzol => BlockInfo {
                 appearance: BlockAppearance::Regular {
@@ -124,16 +142,22 @@ impl BlockId {
                 },
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 0.5,
             },
             Self::RedSand => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::RedSand),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 0.5,
             },
             Self::Sand => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::Sand),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 0.5,
             },
             Self::Sandstone => BlockInfo {
                 appearance: BlockAppearance::Regular {
@@ -143,6 +167,8 @@ impl BlockId {
                 },
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 0.8,
             },
             Self::RedSandstone => BlockInfo {
                 appearance: BlockAppearance::Regular {
@@ -152,89 +178,119 @@ impl BlockId {
                 },
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 0.8,
             },
             Self::Water => BlockInfo {
                 appearance: BlockAppearance::Liquid(TextureId::Water),
                 visibility: BlockVisibility::Transparent,
-                flags: BlockFlags::CULLS_ITSELF,
+                flags: BlockFlags::CULLS_ITSELF.union(BlockFlags::REPLACEABLE),
+                light_emission: 0,
+                hardness: 0.0,
             },
             Self::Bedrock => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::Bedrock),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: f32::INFINITY,
             },
             Self::Daffodil => BlockInfo {
                 appearance: BlockAppearance::Flat(TextureId::Daffodil),
                 visibility: BlockVisibility::SemiOpaque,
-                flags: BlockFlags::TANGIBLE,
+                flags: BlockFlags::TANGIBLE.union(BlockFlags::REPLACEABLE),
+                light_emission: 0,
+                hardness: 0.0,
             },
             Self::Pebbles => BlockInfo {
                 appearance: BlockAppearance::Flat(TextureId::Pebbles),
                 visibility: BlockVisibility::SemiOpaque,
-                flags: BlockFlags::TANGIBLE,
+                flags: BlockFlags::TANGIBLE.union(BlockFlags::REPLACEABLE),
+                light_emission: 0,
+                hardness: 0.0,
             },
             Self::Cobblestone => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::Cobblestone),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 2.0,
             },
             Self::MossyCobblestone => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::MossyCobblestone),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 2.0,
             },
             Self::DiamondOre => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::DiamondOre),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 6,
+                hardness: 3.0,
             },
             Self::OakLog => BlockInfo {
-                appearance: BlockAppearance::Regular {
-                    top: TextureId::OakLogTop,
-                    bottom: TextureId::OakLogTop,
+                appearance: BlockAppearance::Pillar {
+                    end: TextureId::OakLogTop,
                     side: TextureId::OakLogSide,
                 },
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 2.0,
             },
             Self::OakLeaves => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::OakLeaves),
                 visibility: BlockVisibility::SemiOpaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 0.2,
             },
             Self::PineLog => BlockInfo {
-                appearance: BlockAppearance::Regular {
-                    top: TextureId::PineLogTop,
-                    bottom: TextureId::PineLogTop,
+                appearance: BlockAppearance::Pillar {
+                    end: TextureId::PineLogTop,
                     side: TextureId::PineLogSide,
                 },
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 2.0,
             },
             Self::PineLeaves => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::PineLeaves),
                 visibility: BlockVisibility::SemiOpaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 0.2,
             },
             Self::StructureBlock => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::StructureBlock),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::TANGIBLE,
+                light_emission: 0,
+                hardness: 0.0,
             },
             Self::StructureOriginBlock => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::StructureOriginBlock),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::TANGIBLE,
+                light_emission: 0,
+                hardness: 0.0,
             },
             Self::OakPlanks => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::OakPlanks),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 2.0,
             },
             Self::PinePlanks => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::PinePlanks),
                 visibility: BlockVisibility::Opaque,
                 flags: BlockFlags::SOLID.union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 2.0,
             },
             Self::Glass => BlockInfo {
                 appearance: BlockAppearance::uniform(TextureId::Glass),
@@ -242,6 +298,8 @@ impl BlockId {
                 flags: BlockFlags::CULLS_ITSELF
                     .union(BlockFlags::SOLID)
                     .union(BlockFlags::TANGIBLE),
+                light_emission: 0,
+                hardness: 0.3,
             },
         }
     }
@@ -277,6 +335,52 @@ impl Face {
             Self::NegZ => IVec3::NEG_Z,
         }
     }
+
+    /// Returns the face that's facing the opposite direction.
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::X => Self::NegX,
+            Self::NegX => Self::X,
+            Self::Y => Self::NegY,
+            Self::NegY => Self::Y,
+            Self::Z => Self::NegZ,
+            Self::NegZ => Self::Z,
+        }
+    }
+
+    /// Returns the axis that the face is perpendicular to.
+    pub fn axis(self) -> Axis {
+        match self {
+            Self::X | Self::NegX => Axis::X,
+            Self::Y | Self::NegY => Axis::Y,
+            Self::Z | Self::NegZ => Axis::Z,
+        }
+    }
+
+    /// Returns an iterator over all the faces.
+    pub fn iter() -> impl Iterator<Item = Self> {
+        [
+            Self::X,
+            Self::NegX,
+            Self::Y,
+            Self::NegY,
+            Self::Z,
+            Self::NegZ,
+        ]
+        .into_iter()
+    }
+}
+
+/// One of the three axes of the world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Axis {
+    /// The X axis.
+    X,
+    /// The Y axis.
+    Y,
+    /// The Z axis.
+    Z,
 }
 
 /// Some metadata about the appearance of a block.
@@ -287,6 +391,10 @@ pub union AppearanceMetadata {
     ///
     /// This metadata indicates which direction of the block is facing.
     pub flat: Face,
+    /// The block has a pillar appearance.
+    ///
+    /// This metadata indicates the axis along which the block is oriented.
+    pub axis: Axis,
 }
 
 impl AppearanceMetadata {
@@ -302,6 +410,10 @@ impl AppearanceMetadata {
                 let face = self.flat;
                 Self { flat: face }
             }
+            BlockAppearance::Pillar { .. } => {
+                let axis = self.axis;
+                Self { axis }
+            }
             _ => Self { no_metadata: () },
         }
     }
@@ -340,6 +452,18 @@ pub enum BlockAppearance {
     /// When this appearance is used, an appearance metadata is stored in the chunk that contains
     /// the block.
     Flat(TextureId),
+    /// The block is a pillar that may be oriented along any of the three axes.
+    ///
+    /// When this appearance is used, an appearance metadata is stored in the chunk that contains
+    /// the block.
+    Pillar {
+        /// The texture that should be applied to both ends of the pillar.
+        end: TextureId,
+        /// The texture that should be applied to the sides of the pillar.
+        ///
+        /// The texture is drawn upright when the pillar is oriented along the Y axis.
+        side: TextureId,
+    },
 }
 
 impl BlockAppearance {
@@ -361,6 +485,7 @@ impl BlockAppearance {
             Self::Regular { .. } => false,
             Self::Liquid(..) => false,
             Self::Flat(..) => true,
+            Self::Pillar { .. } => true,
         }
     }
 }
@@ -399,6 +524,9 @@ bitflags! {
         /// Whether the block culls itself (this only applies if the block is not opaque or
         /// invisible).
         const CULLS_ITSELF = 1 << 2;
+        /// Placing a block against this block replaces it, rather than putting the new block
+        /// next to it.
+        const REPLACEABLE = 1 << 3;
     }
 }
 
@@ -416,8 +544,21 @@ pub struct BlockInfo {
     pub visibility: BlockVisibility,
     /// The flags associated with the block.
     pub flags: BlockFlags,
+    /// The amount of light emitted by the block.
+    ///
+    /// This ranges from `0` (the block emits no light) to [`MAX_LIGHT_LEVEL`]. The light level
+    /// decreases by one for every block it travels through.
+    pub light_emission: u8,
+    /// The time it takes to break the block, in seconds.
+    ///
+    /// A hardness of `0.0` means that the block breaks instantly, while an infinite hardness
+    /// means that the block cannot be broken at all.
+    pub hardness: f32,
 }
 
+/// The maximum light level that a block can emit.
+pub const MAX_LIGHT_LEVEL: u8 = 15;
+
 /// A block that is instanciated in the world.
 ///
 /// # Remarks
@@ -441,12 +582,26 @@ impl BlockInstance {
     pub const fn new(id: BlockId) -> Self {
         let appearance = match id.info().appearance {
             BlockAppearance::Flat(..) => AppearanceMetadata { flat: Face::Y },
+            BlockAppearance::Pillar { .. } => AppearanceMetadata { axis: Axis::Y },
             _ => AppearanceMetadata { no_metadata: () },
         };
 
         unsafe { Self::new_unchecked(id, appearance) }
     }
 
+    /// Creates a new [`BlockInstance`] oriented along the provided axis.
+    ///
+    /// If the provided block does not have a [`BlockAppearance::Pillar`] appearance, the axis
+    /// is ignored and this function behaves like [`BlockInstance::new`].
+    pub const fn with_axis(id: BlockId, axis: Axis) -> Self {
+        match id.info().appearance {
+            BlockAppearance::Pillar { .. } => unsafe {
+                Self::new_unchecked(id, AppearanceMetadata { axis })
+            },
+            _ => Self::new(id),
+        }
+    }
+
     /// Creates a new [`BlockInstance`] instance.
     ///
     /// # Safety
@@ -504,12 +659,14 @@ impl std::fmt::Debug for BlockInstance {
         let mut f = f.debug_struct("BlockInstance");
         f.field("id", &self.id);
 
-        #[allow(clippy::single_match)]
         unsafe {
             match self.id.info().appearance {
                 BlockAppearance::Flat(..) => {
                     f.field("appearance", &self.appearance.flat);
                 }
+                BlockAppearance::Pillar { .. } => {
+                    f.field("appearance", &self.appearance.axis);
+                }
                 _ => (),
             }
         }
@@ -536,6 +693,9 @@ impl serde::Serialize for BlockInstance {
         let appearance = unsafe {
             match self.id.info().appearance {
                 BlockAppearance::Flat(..) => AppearanceMetadataHelper::Flat(self.appearance.flat),
+                BlockAppearance::Pillar { .. } => {
+                    AppearanceMetadataHelper::Axis(self.appearance.axis)
+                }
                 _ => AppearanceMetadataHelper::NoMetadata,
             }
         };
@@ -563,6 +723,13 @@ impl<'de> serde::Deserialize<'de> for BlockInstance {
             (AppearanceMetadataHelper::Flat(face), BlockAppearance::Flat(..)) => {
                 AppearanceMetadata { flat: face }
             }
+            (AppearanceMetadataHelper::Axis(axis), BlockAppearance::Pillar { .. }) => {
+                AppearanceMetadata { axis }
+            }
+            // Pillars saved before they could be oriented always stood upright.
+            (AppearanceMetadataHelper::NoMetadata, BlockAppearance::Pillar { .. }) => {
+                AppearanceMetadata { axis: Axis::Y }
+            }
             (AppearanceMetadataHelper::NoMetadata, _) => AppearanceMetadata { no_metadata: () },
             _ => return Err(serde::de::Error::custom("invalid appearance metadata")),
         };
@@ -579,13 +746,14 @@ impl<'de> serde::Deserialize<'de> for BlockInstance {
 mod serde_InstanciateBlock {
     use serde::{Deserialize, Serialize};
 
-    use crate::{BlockId, Face};
+    use crate::{Axis, BlockId, Face};
 
     #[derive(Default, Serialize, Deserialize)]
     pub enum AppearanceMetadataHelper {
         #[default]
         NoMetadata,
         Flat(Face),
+        Axis(Axis),
     }
 
     impl AppearanceMetadataHelper {
@@ -605,3 +773,35 @@ mod serde_InstanciateBlock {
         pub appearance: AppearanceMetadataHelper,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opposite_faces_point_the_other_way() {
+        for face in Face::iter() {
+            assert_eq!(face.opposite().opposite(), face);
+            assert_ne!(face.opposite(), face);
+            assert_eq!(face.opposite().normal(), -face.normal());
+            assert_eq!(face.opposite().axis(), face.axis());
+        }
+    }
+
+    #[test]
+    fn iter_yields_every_face_once() {
+        let faces: Vec<Face> = Face::iter().collect();
+        assert_eq!(faces.len(), 6);
+
+        for face in [
+            Face::X,
+            Face::NegX,
+            Face::Y,
+            Face::NegY,
+            Face::Z,
+            Face::NegZ,
+        ] {
+            assert_eq!(faces.iter().filter(|&&f| f == face).count(), 1);
+        }
+    }
+}
@@ -3,15 +3,30 @@ use std::hash::Hash;
 use std::mem::MaybeUninit;
 use std::ops::{Index, IndexMut};
 
-use bytemuck::Zeroable;
+use bytemuck::{Contiguous, Zeroable};
 use glam::{IVec2, IVec3, Vec3};
 
-use crate::{AppearanceMetadata, BlockId, BlockInstance};
+use crate::{AppearanceMetadata, Axis, BlockAppearance, BlockId, BlockInstance, Face};
 
 const X_MASK: u16 = 0b11111;
 const Y_MASK: u16 = 0b11111 << 5;
 const Z_MASK: u16 = 0b11111 << 10;
 
+// `LocalPos` packs each coordinate in 5 bits of a `u16`. Changing the size of chunks requires
+// updating the masks above, along with the shifts used by `LocalPos`.
+const _: () = assert!(
+    Chunk::SIDE == 32,
+    "`LocalPos` stores 5 bits per axis, which requires `Chunk::SIDE` to be 32",
+);
+const _: () = assert!(
+    X_MASK | Y_MASK | Z_MASK == (Chunk::SIZE - 1) as u16,
+    "the `LocalPos` masks must cover exactly the indices of a chunk",
+);
+const _: () = assert!(
+    X_MASK & Y_MASK == 0 && Y_MASK & Z_MASK == 0 && X_MASK & Z_MASK == 0,
+    "the `LocalPos` masks must not overlap",
+);
+
 /// A local block position within a [`Chunk`].
 ///
 /// # Representation
@@ -309,6 +324,34 @@ impl Debug for LocalPos {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for LocalPos {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for LocalPos {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let index = u16::deserialize(deserializer)?;
+
+        if index as usize >= Chunk::SIZE {
+            return Err(serde::de::Error::custom(format_args!(
+                "local position index out of bounds: {index}"
+            )));
+        }
+
+        Ok(Self(index))
+    }
+}
+
 /// A simple wrapper around a static array that can be indexed with a [`LocalPos`] with
 /// no bound checking.
 #[derive(Clone, Copy, Hash, Zeroable)]
@@ -331,6 +374,217 @@ impl<T> IndexMut<LocalPos> for ChunkStore<T> {
     }
 }
 
+/// A compact representation of the blocks of a chunk that uses a small number of distinct
+/// block types.
+///
+/// Each block is represented by an index into `palette`, packed into `words` using `bits` bits
+/// per block.
+#[derive(Clone)]
+struct PaletteStore {
+    /// The block IDs referenced by the indices.
+    ///
+    /// This list never has more than `1 << bits` elements, and never contains the same ID
+    /// twice.
+    palette: Vec<BlockId>,
+    /// The number of bits used to represent a single index.
+    ///
+    /// This is always a power of two that's less than or equal to [`PaletteStore::MAX_BITS`],
+    /// ensuring that indices never straddle two words.
+    bits: u32,
+    /// The packed indices.
+    words: Box<[u64]>,
+}
+
+impl PaletteStore {
+    /// The maximum number of bits per index.
+    ///
+    /// Beyond that, a [`BlockStore::Direct`] representation is more efficient.
+    const MAX_BITS: u32 = 4;
+
+    /// Creates a new [`PaletteStore`] with the provided palette, where every block refers to
+    /// the first entry of the palette.
+    fn new(palette: Vec<BlockId>, bits: u32) -> Self {
+        debug_assert!(palette.len() <= 1 << bits);
+
+        Self {
+            palette,
+            bits,
+            words: vec![0; Chunk::SIZE * bits as usize / 64].into_boxed_slice(),
+        }
+    }
+
+    /// Returns the smallest number of bits that can represent `count` distinct indices.
+    fn bits_for(count: usize) -> u32 {
+        match count {
+            0..=2 => 1,
+            3..=4 => 2,
+            _ => 4,
+        }
+    }
+
+    /// Returns the index stored at the provided position.
+    #[inline]
+    fn get_index(&self, pos: LocalPos) -> usize {
+        let bit = pos.index() * self.bits as usize;
+        let mask = (1 << self.bits) - 1;
+        // SAFETY:
+        //  `words` has enough room for `Chunk::SIZE` indices.
+        let word = unsafe { *self.words.get_unchecked(bit / 64) };
+        ((word >> (bit % 64)) & mask) as usize
+    }
+
+    /// Sets the index stored at the provided position.
+    #[inline]
+    fn set_index(&mut self, pos: LocalPos, index: usize) {
+        let bit = pos.index() * self.bits as usize;
+        let mask = (1 << self.bits) - 1;
+        // SAFETY:
+        //  `words` has enough room for `Chunk::SIZE` indices.
+        let word = unsafe { self.words.get_unchecked_mut(bit / 64) };
+        *word = (*word & !(mask << (bit % 64))) | ((index as u64 & mask) << (bit % 64));
+    }
+
+    /// Returns the block at the provided position.
+    #[inline]
+    fn get(&self, pos: LocalPos) -> BlockId {
+        // SAFETY:
+        //  Indices stored in `words` are always valid indices into `palette`.
+        unsafe { *self.palette.get_unchecked(self.get_index(pos)) }
+    }
+
+    /// Attempts to set the block at the provided position.
+    ///
+    /// If the palette is full and does not include `id` already, `false` is returned and the
+    /// store is left untouched.
+    fn try_set(&mut self, pos: LocalPos, id: BlockId) -> bool {
+        let index = match self.palette.iter().position(|&p| p == id) {
+            Some(index) => index,
+            None if self.palette.len() < 1 << self.bits => {
+                self.palette.push(id);
+                self.palette.len() - 1
+            }
+            None => return false,
+        };
+
+        self.set_index(pos, index);
+        true
+    }
+
+    /// Re-creates this store with twice as many bits per index.
+    fn widen(&self) -> Self {
+        let mut new = Self::new(self.palette.clone(), self.bits * 2);
+        for pos in LocalPos::iter_all() {
+            new.set_index(pos, self.get_index(pos));
+        }
+        new
+    }
+}
+
+/// The storage of the block IDs of a [`Chunk`].
+///
+/// Chunks automatically move from one representation to a larger one as new blocks are
+/// inserted. [`Chunk::compact`] can be used to go back to the smallest representation
+/// possible.
+enum BlockStore {
+    /// Every block of the chunk is the same.
+    Uniform(BlockId),
+    /// The chunk uses a small number of distinct blocks, stored as indices into a palette.
+    Palette(PaletteStore),
+    /// The block IDs are stored directly.
+    Direct(Box<ChunkStore<BlockId>>),
+}
+
+impl BlockStore {
+    /// Returns the block at the provided position.
+    #[inline]
+    fn get(&self, pos: LocalPos) -> BlockId {
+        match self {
+            Self::Uniform(id) => *id,
+            Self::Palette(store) => store.get(pos),
+            Self::Direct(data) => data[pos],
+        }
+    }
+
+    /// Converts this store into a [`BlockStore::Direct`] one if it's not already, and returns
+    /// the underlying array.
+    fn make_direct(&mut self) -> &mut ChunkStore<BlockId> {
+        if !matches!(self, Self::Direct(_)) {
+            let mut data: Box<ChunkStore<BlockId>> = bytemuck::zeroed_box();
+            match self {
+                Self::Uniform(BlockId::Air) => (),
+                Self::Uniform(id) => data.0.fill(*id),
+                Self::Palette(store) => {
+                    for pos in LocalPos::iter_all() {
+                        data[pos] = store.get(pos);
+                    }
+                }
+                Self::Direct(_) => unreachable!(),
+            }
+            *self = Self::Direct(data);
+        }
+
+        match self {
+            Self::Direct(data) => data,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Sets the block at the provided position, growing the representation of the store if
+    /// needed.
+    fn set(&mut self, pos: LocalPos, id: BlockId) {
+        match self {
+            Self::Uniform(current) if *current == id => (),
+            Self::Uniform(current) => {
+                let mut store = PaletteStore::new(vec![*current, id], 1);
+                store.set_index(pos, 1);
+                *self = Self::Palette(store);
+            }
+            Self::Palette(store) => {
+                loop {
+                    if store.try_set(pos, id) {
+                        return;
+                    }
+                    if store.bits >= PaletteStore::MAX_BITS {
+                        break;
+                    }
+                    *store = store.widen();
+                }
+
+                // The palette can't grow anymore.
+                self.make_direct()[pos] = id;
+            }
+            Self::Direct(data) => data[pos] = id,
+        }
+    }
+
+    /// Returns the number of bytes allocated on the heap by this store.
+    fn heap_size(&self) -> usize {
+        match self {
+            Self::Uniform(_) => 0,
+            Self::Palette(store) => {
+                store.palette.capacity() * std::mem::size_of::<BlockId>()
+                    + std::mem::size_of_val(&*store.words)
+            }
+            Self::Direct(_) => std::mem::size_of::<ChunkStore<BlockId>>(),
+        }
+    }
+}
+
+impl Clone for BlockStore {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Uniform(id) => Self::Uniform(*id),
+            Self::Palette(store) => Self::Palette(store.clone()),
+            Self::Direct(data) => {
+                // Avoid going through the stack, the array is pretty large.
+                let mut new: Box<ChunkStore<BlockId>> = bytemuck::zeroed_box();
+                new.0.copy_from_slice(&data.0);
+                Self::Direct(new)
+            }
+        }
+    }
+}
+
 /// Represents the content of a chunk.
 ///
 /// # Remarks
@@ -342,7 +596,7 @@ impl<T> IndexMut<LocalPos> for ChunkStore<T> {
 /// Those should instead be stored in a separate structure defined in downstream crates.
 pub struct Chunk {
     /// The inner blocks of the chunk.
-    blocks: Option<Box<ChunkStore<BlockId>>>,
+    blocks: BlockStore,
     /// Metadata about the chunk's appearance.
     ///
     /// # Note
@@ -368,7 +622,7 @@ impl Chunk {
     #[inline]
     pub fn empty() -> Self {
         Self {
-            blocks: None,
+            blocks: BlockStore::Uniform(BlockId::Air),
             appearances: None,
         }
     }
@@ -376,10 +630,7 @@ impl Chunk {
     /// Returns the block at the provided position.
     #[inline]
     pub fn get_block(&self, pos: LocalPos) -> BlockId {
-        match &self.blocks {
-            Some(data) => data[pos],
-            None => BlockId::Air,
-        }
+        self.blocks.get(pos)
     }
 
     /// Returns the [`AppearanceMetadata`] of the block at the provided position.
@@ -416,6 +667,10 @@ impl Chunk {
     /// that the value you're trying to insert is [`BlockId::Air`], you should skip calling
     /// the function.
     ///
+    /// Compact representations of the chunk cannot hand out references to individual blocks,
+    /// so calling this function always switches the chunk to its largest representation. Use
+    /// [`Chunk::compact`] to shrink it again once you're done.
+    ///
     /// # Safety
     ///
     /// This function is unsafe because it allows the caller to change the block at the provided
@@ -424,17 +679,16 @@ impl Chunk {
     /// If the inserted block requires additional metadata, it must be added manually.
     #[inline]
     pub unsafe fn get_block_mut(&mut self, pos: LocalPos) -> &mut BlockId {
-        &mut self.blocks.get_or_insert_with(bytemuck::zeroed_box)[pos]
+        &mut self.blocks.make_direct()[pos]
     }
 
     /// Sets the block at the provided position.
     pub fn set_block(&mut self, pos: LocalPos, block: BlockInstance) {
         let (block, appearance) = block.into_parts();
 
+        self.blocks.set(pos, block);
+
         unsafe {
-            if block != BlockId::Air || self.blocks.is_some() {
-                *self.get_block_mut(pos) = block;
-            }
             if block.info().appearance.has_metadata() {
                 *self.get_appearance_mut(pos) = appearance;
             }
@@ -462,15 +716,389 @@ impl Chunk {
     /// Returns whether the chunk is empty.
     #[inline]
     pub fn is_empty(&self) -> bool {
-        match self.blocks.as_ref() {
-            Some(data) => data.0.iter().all(|&id| id == BlockId::Air),
-            None => true,
+        self.is_uniform() == Some(BlockId::Air)
+    }
+
+    /// If every block of the chunk has the same [`BlockId`], returns it.
+    ///
+    /// # Remarks
+    ///
+    /// Only the block IDs are compared. Blocks with the same ID but different appearance
+    /// metadata are still considered equal.
+    pub fn is_uniform(&self) -> Option<BlockId> {
+        match &self.blocks {
+            BlockStore::Uniform(id) => Some(*id),
+            BlockStore::Palette(store) => {
+                // Palette entries are unique, comparing indices is enough.
+                let first = store.get_index(LocalPos::from_xyz(0, 0, 0));
+                LocalPos::iter_all()
+                    .all(|pos| store.get_index(pos) == first)
+                    .then(|| store.palette[first])
+            }
+            BlockStore::Direct(data) => {
+                let first = data.0[0];
+                data.0.iter().all(|&id| id == first).then_some(first)
+            }
+        }
+    }
+
+    /// Returns whether the chunk may contain blocks that emit light.
+    ///
+    /// # Remarks
+    ///
+    /// This check is conservative and does not go through the blocks of the chunk. It may
+    /// return `true` for chunks that contain no light-emitting block, but never returns `false`
+    /// for chunks that do.
+    pub fn may_emit_light(&self) -> bool {
+        match &self.blocks {
+            BlockStore::Uniform(id) => id.info().light_emission != 0,
+            BlockStore::Palette(store) => {
+                store.palette.iter().any(|id| id.info().light_emission != 0)
+            }
+            BlockStore::Direct(_) => true,
+        }
+    }
+
+    /// Switches the chunk to the smallest representation that can hold its current content.
+    ///
+    /// # Remarks
+    ///
+    /// This requires going through every block of the chunk, and should only be called once a
+    /// batch of modifications is complete (for example, after the chunk has been generated).
+    pub fn compact(&mut self) {
+        let mut palette = Vec::new();
+        for pos in LocalPos::iter_all() {
+            let id = self.blocks.get(pos);
+            if !palette.contains(&id) {
+                palette.push(id);
+                if palette.len() > 1 << PaletteStore::MAX_BITS {
+                    // Too many distinct blocks, the direct representation is the best we can do.
+                    self.blocks.make_direct();
+                    return;
+                }
+            }
+        }
+
+        if let [id] = *palette {
+            self.blocks = BlockStore::Uniform(id);
+            return;
+        }
+
+        let mut store = PaletteStore::new(Vec::new(), PaletteStore::bits_for(palette.len()));
+        for pos in LocalPos::iter_all() {
+            let inserted = store.try_set(pos, self.blocks.get(pos));
+            debug_assert!(inserted);
+        }
+        self.blocks = BlockStore::Palette(store);
+    }
+
+    /// Returns the number of bytes allocated on the heap to store the content of this chunk.
+    pub fn heap_size(&self) -> usize {
+        let appearances = match self.appearances {
+            Some(_) => std::mem::size_of::<ChunkStore<MaybeUninit<AppearanceMetadata>>>(),
+            None => 0,
+        };
+
+        self.blocks.heap_size() + appearances
+    }
+
+    /// Counts the number of occurrences of each block in the chunk.
+    ///
+    /// The returned array is indexed by [`BlockId`], and its elements always sum up to
+    /// [`Chunk::SIZE`].
+    pub fn block_histogram(&self) -> [u32; BlockId::COUNT] {
+        let mut histogram = [0; BlockId::COUNT];
+
+        match &self.blocks {
+            BlockStore::Uniform(id) => histogram[*id as usize] = Self::SIZE as u32,
+            BlockStore::Palette(store) => {
+                // Count the palette indices first to avoid looking up the palette for
+                // every block.
+                let mut counts = [0u32; 1 << PaletteStore::MAX_BITS];
+                for pos in LocalPos::iter_all() {
+                    counts[store.get_index(pos)] += 1;
+                }
+                for (&id, &count) in store.palette.iter().zip(&counts) {
+                    histogram[id as usize] += count;
+                }
+            }
+            BlockStore::Direct(data) => {
+                for &id in data.0.iter() {
+                    histogram[id as usize] += 1;
+                }
+            }
+        }
+
+        histogram
+    }
+}
+
+impl Chunk {
+    /// The version of the format produced by [`Chunk::serialize`].
+    const FORMAT_VERSION: u8 = 3;
+
+    /// Serializes the content of the chunk into a compact byte representation.
+    ///
+    /// The result can be turned back into a [`Chunk`] using [`Chunk::deserialize`].
+    ///
+    /// # Format
+    ///
+    /// 1. A single byte indicating the version of the format.
+    ///
+    /// 2. The block IDs of the chunk, run-length encoded. Each run is made of a little-endian
+    ///    `u16` length followed by the ID of the block (one byte). The sum of the lengths of the
+    ///    runs is always [`Chunk::SIZE`].
+    ///
+    /// 3. The appearance metadata of the blocks that have some (see
+    ///    [`BlockAppearance::has_metadata`]), in the order of their [`LocalPos::index`].
+    ///
+    /// 4. A little-endian `u32` CRC-32 checksum of all the previous bytes, used to detect
+    ///    corrupted data when the chunk is loaded back.
+    ///
+    /// An empty chunk is encoded using only eight bytes.
+    ///
+    /// [`BlockAppearance::has_metadata`]: crate::BlockAppearance::has_metadata
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = vec![Self::FORMAT_VERSION];
+
+        let mut run: Option<(BlockId, u16)> = None;
+        for pos in LocalPos::iter_all() {
+            let block = self.get_block(pos);
+            match &mut run {
+                Some((id, len)) if *id == block => *len += 1,
+                _ => {
+                    if let Some((id, len)) = run {
+                        out.extend_from_slice(&len.to_le_bytes());
+                        out.push(id as u8);
+                    }
+                    run = Some((block, 1));
+                }
+            }
+        }
+        if let Some((id, len)) = run {
+            out.extend_from_slice(&len.to_le_bytes());
+            out.push(id as u8);
+        }
+
+        for pos in LocalPos::iter_all() {
+            let block = self.get_block(pos);
+            match block.info().appearance {
+                BlockAppearance::Flat(..) => {
+                    // SAFETY:
+                    //  The block has a flat appearance, meaning that its metadata is a `Face`.
+                    let face = unsafe { self.get_appearance(pos).flat };
+                    out.push(face as u8);
+                }
+                BlockAppearance::Pillar { .. } => {
+                    // SAFETY:
+                    //  The block has a pillar appearance, meaning that its metadata is an `Axis`.
+                    let axis = unsafe { self.get_appearance(pos).axis };
+                    out.push(axis as u8);
+                }
+                _ => (),
+            }
+        }
+
+        let checksum = crc32(&out);
+        out.extend_from_slice(&checksum.to_le_bytes());
+
+        out
+    }
+
+    /// Deserializes a chunk that was previously serialized with [`Chunk::serialize`].
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the provided bytes are not a valid serialized chunk.
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, ChunkDecodeError> {
+        let Some((bytes, &[c0, c1, c2, c3])) = bytes.split_last_chunk::<4>() else {
+            return Err(ChunkDecodeError::UnexpectedEnd);
+        };
+        if crc32(bytes) != u32::from_le_bytes([c0, c1, c2, c3]) {
+            return Err(ChunkDecodeError::Checksum);
+        }
+
+        let (&version, mut bytes) = bytes.split_first().ok_or(ChunkDecodeError::UnexpectedEnd)?;
+        if version != Self::FORMAT_VERSION {
+            return Err(ChunkDecodeError::UnsupportedVersion(version));
+        }
+
+        let mut chunk = Chunk::empty();
+
+        let mut index = 0;
+        while index < Self::SIZE {
+            let &[l0, l1, id, ref rest @ ..] = bytes else {
+                return Err(ChunkDecodeError::UnexpectedEnd);
+            };
+            bytes = rest;
+
+            let len = u16::from_le_bytes([l0, l1]) as usize;
+            if len == 0 || index + len > Self::SIZE {
+                return Err(ChunkDecodeError::InvalidRunLength);
+            }
+            let id = BlockId::from_integer(id).ok_or(ChunkDecodeError::InvalidBlockId(id))?;
+
+            // Air runs don't need to be written, avoiding the allocation of empty chunks.
+            if id != BlockId::Air {
+                for i in index..index + len {
+                    // SAFETY:
+                    //  `i` is less than `Chunk::SIZE`.
+                    let pos = unsafe { LocalPos::new_unchecked(i) };
+                    chunk.blocks.set(pos, id);
+                }
+            }
+
+            index += len;
+        }
+
+        for pos in LocalPos::iter_all() {
+            match chunk.get_block(pos).info().appearance {
+                BlockAppearance::Flat(..) => {
+                    let (&face, rest) =
+                        bytes.split_first().ok_or(ChunkDecodeError::UnexpectedEnd)?;
+                    bytes = rest;
+
+                    let face = Face::iter()
+                        .nth(face as usize)
+                        .ok_or(ChunkDecodeError::InvalidMetadata)?;
+
+                    // SAFETY:
+                    //  The block has a flat appearance, which expects a `Face` as metadata.
+                    unsafe { *chunk.get_appearance_mut(pos) = AppearanceMetadata { flat: face } };
+                }
+                BlockAppearance::Pillar { .. } => {
+                    let (&axis, rest) =
+                        bytes.split_first().ok_or(ChunkDecodeError::UnexpectedEnd)?;
+                    bytes = rest;
+
+                    let axis = match axis {
+                        0 => Axis::X,
+                        1 => Axis::Y,
+                        2 => Axis::Z,
+                        _ => return Err(ChunkDecodeError::InvalidMetadata),
+                    };
+
+                    // SAFETY:
+                    //  The block has a pillar appearance, which expects an `Axis` as metadata.
+                    unsafe { *chunk.get_appearance_mut(pos) = AppearanceMetadata { axis } };
+                }
+                _ => (),
+            }
+        }
+
+        if !bytes.is_empty() {
+            return Err(ChunkDecodeError::TrailingBytes);
+        }
+
+        Ok(chunk)
+    }
+}
+
+/// An error that might occur when deserializing a [`Chunk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkDecodeError {
+    /// The format version is not supported.
+    UnsupportedVersion(u8),
+    /// The input ended before the chunk could be fully decoded.
+    UnexpectedEnd,
+    /// A run of blocks has an invalid length.
+    InvalidRunLength,
+    /// An invalid block ID was found.
+    InvalidBlockId(u8),
+    /// The appearance metadata of a block is invalid.
+    InvalidMetadata,
+    /// Some bytes remain after the chunk was decoded.
+    TrailingBytes,
+    /// The checksum stored alongside the chunk does not match its content.
+    ///
+    /// This usually means that the data has been corrupted.
+    Checksum,
+}
+
+impl std::fmt::Display for ChunkDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::UnsupportedVersion(v) => write!(f, "unsupported chunk format version: {v}"),
+            Self::UnexpectedEnd => write!(f, "unexpected end of chunk data"),
+            Self::InvalidRunLength => write!(f, "invalid block run length"),
+            Self::InvalidBlockId(id) => write!(f, "invalid block ID: {id}"),
+            Self::InvalidMetadata => write!(f, "invalid block appearance metadata"),
+            Self::TrailingBytes => write!(f, "trailing bytes after chunk data"),
+            Self::Checksum => write!(f, "chunk checksum mismatch"),
+        }
+    }
+}
+
+impl std::error::Error for ChunkDecodeError {}
+
+/// The lookup table used by [`crc32`], computed for the reflected `0xEDB88320` polynomial.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Computes the CRC-32 (IEEE) checksum of the provided bytes.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in bytes {
+        crc = CRC32_TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+// Sanity check of the generated lookup table.
+const _: () = assert!(CRC32_TABLE[1] == 0x77073096);
+
+impl Clone for Chunk {
+    /// Creates a deep copy of the chunk.
+    ///
+    /// # Remarks
+    ///
+    /// This allocates new storage for the blocks and their appearance metadata. Modifying the
+    /// returned chunk won't affect the original one.
+    fn clone(&self) -> Self {
+        let blocks = self.blocks.clone();
+
+        let appearances = self.appearances.as_ref().map(|data| {
+            let mut new = new_uninit_store::<AppearanceMetadata>();
+            for pos in LocalPos::iter_all() {
+                let block = self.get_block(pos);
+                if block.info().appearance.has_metadata() {
+                    // SAFETY:
+                    //  `AppearanceMetadata` instances are always initialized, and the metadata
+                    //  stored at `pos` is the one associated with `block`.
+                    let appearance = unsafe { data[pos].assume_init_ref().clone_with(block) };
+                    new[pos] = MaybeUninit::new(appearance);
+                }
+            }
+            new
+        });
+
+        Self {
+            blocks,
+            appearances,
         }
     }
 }
 
 /// The 3D position of a chunk in the world.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChunkPos {
     /// The X coordinate of the chunk.
     pub x: i32,
@@ -648,3 +1276,305 @@ fn new_uninit_store<T>() -> Box<ChunkStore<MaybeUninit<T>>> {
     }
     unsafe { Box::from_raw(ptr as *mut ChunkStore<MaybeUninit<T>>) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_chunk_histogram_is_all_air() {
+        let histogram = Chunk::empty().block_histogram();
+
+        assert_eq!(histogram[BlockId::Air as usize], Chunk::SIZE as u32);
+        assert_eq!(histogram.iter().sum::<u32>(), Chunk::SIZE as u32);
+    }
+
+    #[test]
+    fn histogram_counts_mixed_blocks() {
+        let mut chunk = Chunk::empty();
+        for x in 0..10 {
+            chunk.set_block(LocalPos::from_xyz(x, 0, 0), BlockId::Stone.into());
+        }
+        for z in 0..5 {
+            chunk.set_block(LocalPos::from_xyz(0, 1, z), BlockId::Dirt.into());
+        }
+
+        let histogram = chunk.block_histogram();
+        assert_eq!(histogram[BlockId::Stone as usize], 10);
+        assert_eq!(histogram[BlockId::Dirt as usize], 5);
+        assert_eq!(histogram[BlockId::Air as usize], Chunk::SIZE as u32 - 15);
+        assert_eq!(histogram.iter().sum::<u32>(), Chunk::SIZE as u32);
+    }
+
+    #[test]
+    fn local_pos_round_trips_every_coordinate() {
+        for z in 0..Chunk::SIDE {
+            for y in 0..Chunk::SIDE {
+                for x in 0..Chunk::SIDE {
+                    let pos = LocalPos::from_xyz(x, y, z);
+                    assert_eq!((pos.x(), pos.y(), pos.z()), (x, y, z));
+
+                    let index = (x + y * Chunk::SIDE + z * Chunk::SIDE * Chunk::SIDE) as usize;
+                    assert_eq!(pos.index(), index);
+                    assert!(pos.index() < Chunk::SIZE);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn local_pos_iter_all_visits_every_index_once() {
+        let mut seen = vec![false; Chunk::SIZE];
+        for pos in LocalPos::iter_all() {
+            assert!(!seen[pos.index()], "{:?} was visited twice", pos.to_ivec3());
+            seen[pos.index()] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+
+    /// Creates a [`BlockInstance`] for a block with a [`BlockAppearance::Flat`] appearance,
+    /// facing the provided direction.
+    fn facing(id: BlockId, face: Face) -> BlockInstance {
+        assert!(matches!(id.info().appearance, BlockAppearance::Flat(..)));
+        // SAFETY:
+        //  The block has a flat appearance, which expects a `Face` as metadata.
+        unsafe { BlockInstance::new_unchecked(id, AppearanceMetadata { flat: face }) }
+    }
+
+    /// Returns the direction that the flat block at the provided position is facing.
+    fn face_at(chunk: &Chunk, pos: LocalPos) -> Face {
+        assert!(matches!(
+            chunk.get_block(pos).info().appearance,
+            BlockAppearance::Flat(..)
+        ));
+        // SAFETY:
+        //  The block has a flat appearance, meaning that its metadata is a `Face`.
+        unsafe { chunk.get_appearance(pos).flat }
+    }
+
+    #[test]
+    fn empty_chunk_round_trips() {
+        let bytes = Chunk::empty().serialize();
+        assert_eq!(bytes.len(), 8);
+
+        let chunk = Chunk::deserialize(&bytes).unwrap();
+        assert!(chunk.is_empty());
+        assert!(chunk.appearances.is_none());
+    }
+
+    #[test]
+    fn stone_chunk_round_trips() {
+        let mut chunk = Chunk::empty();
+        for pos in LocalPos::iter_all() {
+            chunk.set_block(pos, BlockId::Stone.into());
+        }
+
+        let bytes = chunk.serialize();
+        // A single run covers the whole chunk.
+        assert_eq!(bytes.len(), 8);
+
+        let chunk = Chunk::deserialize(&bytes).unwrap();
+        assert_eq!(chunk.is_uniform(), Some(BlockId::Stone));
+    }
+
+    #[test]
+    fn flat_metadata_round_trips() {
+        let mut chunk = Chunk::empty();
+        for x in 0..Chunk::SIDE {
+            for z in 0..Chunk::SIDE {
+                chunk.set_block(LocalPos::from_xyz(x, 0, z), BlockId::Stone.into());
+            }
+        }
+
+        let flowers: Vec<(LocalPos, BlockId, Face)> = Face::iter()
+            .enumerate()
+            .map(|(i, face)| {
+                let id = if i % 2 == 0 {
+                    BlockId::Daffodil
+                } else {
+                    BlockId::Pebbles
+                };
+                (LocalPos::from_xyz(i as i32 * 3, 1, 7), id, face)
+            })
+            .collect();
+        for &(pos, id, face) in &flowers {
+            chunk.set_block(pos, facing(id, face));
+        }
+
+        let decoded = Chunk::deserialize(&chunk.serialize()).unwrap();
+        for pos in LocalPos::iter_all() {
+            assert_eq!(decoded.get_block(pos), chunk.get_block(pos), "at {pos:?}");
+        }
+        for &(pos, id, face) in &flowers {
+            assert_eq!(decoded.get_block(pos), id);
+            assert_eq!(face_at(&decoded, pos), face, "at {pos:?}");
+        }
+    }
+
+    #[test]
+    fn corrupted_chunk_fails_checksum() {
+        let mut chunk = Chunk::empty();
+        chunk.set_block(LocalPos::from_xyz(1, 2, 3), BlockId::Stone.into());
+        chunk.set_block(
+            LocalPos::from_xyz(4, 5, 6),
+            facing(BlockId::Daffodil, Face::Z),
+        );
+        let bytes = chunk.serialize();
+
+        for i in 0..bytes.len() {
+            let mut corrupted = bytes.clone();
+            corrupted[i] ^= 0x10;
+            assert_eq!(
+                Chunk::deserialize(&corrupted).err(),
+                Some(ChunkDecodeError::Checksum),
+                "byte {i} was corrupted",
+            );
+        }
+    }
+
+    #[test]
+    fn palette_uses_less_memory_than_direct_storage() {
+        let mut chunk = Chunk::empty();
+        assert_eq!(chunk.heap_size(), 0);
+
+        for x in 0..Chunk::SIDE {
+            for z in 0..Chunk::SIDE {
+                chunk.set_block(LocalPos::from_xyz(x, 0, z), BlockId::Stone.into());
+            }
+        }
+        chunk.compact();
+        let palette = chunk.heap_size();
+
+        let mut direct = chunk.clone();
+        // SAFETY:
+        //  The block is not modified, this only switches the chunk to the direct representation.
+        unsafe { direct.get_block_mut(LocalPos::from_xyz(0, 0, 0)) };
+        assert_eq!(
+            direct.heap_size(),
+            std::mem::size_of::<ChunkStore<BlockId>>()
+        );
+
+        assert!(palette > 0);
+        assert!(
+            palette * 4 < direct.heap_size(),
+            "palette: {palette} bytes, direct: {} bytes",
+            direct.heap_size(),
+        );
+    }
+
+    #[test]
+    fn is_uniform_tracks_every_representation() {
+        let corner = LocalPos::from_xyz(0, 0, 0);
+        let other = LocalPos::from_xyz(5, 6, 7);
+
+        let mut chunk = Chunk::empty();
+        assert_eq!(chunk.is_uniform(), Some(BlockId::Air));
+
+        // Palette representation.
+        for pos in LocalPos::iter_all() {
+            chunk.set_block(pos, BlockId::Stone.into());
+        }
+        assert!(matches!(chunk.blocks, BlockStore::Palette(_)));
+        assert_eq!(chunk.is_uniform(), Some(BlockId::Stone));
+        chunk.set_block(other, BlockId::Dirt.into());
+        assert_eq!(chunk.is_uniform(), None);
+        chunk.set_block(other, BlockId::Stone.into());
+        assert_eq!(chunk.is_uniform(), Some(BlockId::Stone));
+
+        // Uniform representation.
+        chunk.compact();
+        assert!(matches!(chunk.blocks, BlockStore::Uniform(BlockId::Stone)));
+        assert_eq!(chunk.is_uniform(), Some(BlockId::Stone));
+
+        // Direct representation.
+        // SAFETY:
+        //  Stone has no appearance metadata.
+        unsafe { *chunk.get_block_mut(corner) = BlockId::Stone };
+        assert!(matches!(chunk.blocks, BlockStore::Direct(_)));
+        assert_eq!(chunk.is_uniform(), Some(BlockId::Stone));
+        chunk.set_block(other, BlockId::Dirt.into());
+        assert_eq!(chunk.is_uniform(), None);
+
+        chunk.compact();
+        assert_eq!(chunk.is_uniform(), None);
+        chunk.set_block(other, BlockId::Stone.into());
+        chunk.compact();
+        assert_eq!(chunk.is_uniform(), Some(BlockId::Stone));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn positions_round_trip_through_serde() {
+        for local_pos in [
+            LocalPos::from_xyz(0, 0, 0),
+            LocalPos::from_xyz(3, 17, 29),
+            LocalPos::from_xyz(31, 31, 31),
+        ] {
+            let text = ron::to_string(&local_pos).unwrap();
+            assert_eq!(ron::from_str::<LocalPos>(&text).unwrap(), local_pos);
+        }
+
+        for chunk_pos in [ChunkPos::new(0, 0, 0), ChunkPos::new(-4, 12, i32::MAX)] {
+            let text = ron::to_string(&chunk_pos).unwrap();
+            assert_eq!(ron::from_str::<ChunkPos>(&text).unwrap(), chunk_pos);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn out_of_range_local_pos_fails_to_deserialize() {
+        let last = (Chunk::SIZE - 1).to_string();
+        assert_eq!(
+            ron::from_str::<LocalPos>(&last).unwrap(),
+            LocalPos::from_xyz(31, 31, 31)
+        );
+
+        for index in [Chunk::SIZE, u16::MAX as usize] {
+            assert!(ron::from_str::<LocalPos>(&index.to_string()).is_err());
+        }
+    }
+
+    #[test]
+    fn mutating_a_clone_leaves_the_original_unchanged() {
+        let stone = LocalPos::from_xyz(1, 2, 3);
+        let flower = LocalPos::from_xyz(4, 5, 6);
+        let air = LocalPos::from_xyz(7, 8, 9);
+
+        let mut original = Chunk::empty();
+        original.set_block(stone, BlockId::Stone.into());
+        original.set_block(flower, facing(BlockId::Daffodil, Face::X));
+
+        let check_original = |chunk: &Chunk| {
+            assert_eq!(chunk.get_block(stone), BlockId::Stone);
+            assert_eq!(chunk.get_block(flower), BlockId::Daffodil);
+            assert_eq!(face_at(chunk, flower), Face::X);
+            assert_eq!(chunk.get_block(air), BlockId::Air);
+        };
+
+        // Compact representation.
+        let mut copy = original.clone();
+        check_original(&copy);
+        copy.set_block(stone, BlockId::Dirt.into());
+        copy.set_block(flower, facing(BlockId::Daffodil, Face::NegZ));
+        copy.set_block(air, BlockId::Stone.into());
+        check_original(&original);
+        assert_eq!(copy.get_block(stone), BlockId::Dirt);
+        assert_eq!(face_at(&copy, flower), Face::NegZ);
+        assert_eq!(copy.get_block(air), BlockId::Stone);
+
+        // Direct representation.
+        // SAFETY:
+        //  Stone has no appearance metadata.
+        unsafe { *original.get_block_mut(stone) = BlockId::Stone };
+        assert!(matches!(original.blocks, BlockStore::Direct(_)));
+
+        let mut copy = original.clone();
+        // SAFETY:
+        //  Dirt has no appearance metadata.
+        unsafe { *copy.get_block_mut(stone) = BlockId::Dirt };
+        copy.set_block(flower, facing(BlockId::Daffodil, Face::Y));
+        check_original(&original);
+        assert_eq!(copy.get_block(stone), BlockId::Dirt);
+        assert_eq!(face_at(&copy, flower), Face::Y);
+    }
+}
@@ -1,5 +1,6 @@
 //! A simple image loading library.
 
+use std::sync::OnceLock;
 use std::{fmt, io};
 
 mod png;
@@ -46,6 +47,28 @@ pub enum Format {
     GrayscaleAlpha,
 }
 
+impl Format {
+    /// Returns the number of bytes used to represent a single pixel in this format.
+    #[inline]
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Rgba | Self::Bgra => 4,
+            Self::Rgb | Self::Bgr => 3,
+            Self::Grayscale => 1,
+            Self::GrayscaleAlpha => 2,
+        }
+    }
+}
+
+/// The filter used when resizing an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResizeFilter {
+    /// Each destination pixel takes the value of the closest source pixel.
+    Nearest,
+    /// Each destination pixel takes the average value of the source pixels it covers.
+    Box,
+}
+
 /// The color space of a loaded image.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ColorSpace {
@@ -71,6 +94,7 @@ pub struct ImageMetadata {
 }
 
 /// A loaded image.
+#[derive(Clone)]
 pub struct Image {
     /// The pixels of the loaded image, encoded in RGBA format, one byte per channel.
     pub pixels: Vec<u8>,
@@ -79,12 +103,227 @@ pub struct Image {
 }
 
 impl Image {
+    /// Loads an image from the provided reader, detecting its format from the first few bytes
+    /// of the stream.
+    ///
+    /// # Errors
+    ///
+    /// If the format of the image is not recognized (or if no decoder is available for it),
+    /// [`Error::UnsupportedFormat`] is returned.
+    pub fn load(mut reader: impl io::Read) -> Result<Self, Error> {
+        /// The signature of PNG files.
+        const PNG_MAGIC: &[u8] = &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+
+        let mut magic = [0u8; 8];
+        let len = read_up_to(&mut reader, &mut magic).map_err(Error::Io)?;
+        let magic = &magic[..len];
+
+        // The bytes that have been consumed to detect the format must be given back to the
+        // actual decoder.
+        let reader = io::Read::chain(magic, reader);
+
+        if magic.starts_with(PNG_MAGIC) {
+            png::load(reader)
+        } else {
+            Err(Error::UnsupportedFormat)
+        }
+    }
+
     /// Load an image that's known to be in the PNG format.
     #[inline]
     pub fn load_png(reader: impl io::Read) -> Result<Self, Error> {
         png::load(reader)
     }
 
+    /// Resizes the image to the provided dimensions using the provided filter.
+    ///
+    /// The format of the image is preserved.
+    ///
+    /// # Remarks
+    ///
+    /// If either of the new dimensions is zero, or if the image is empty, this function does
+    /// nothing.
+    pub fn resize(&mut self, new_width: u32, new_height: u32, filter: ResizeFilter) {
+        let (width, height) = (self.metadata.width, self.metadata.height);
+
+        if new_width == 0 || new_height == 0 || width == 0 || height == 0 {
+            return;
+        }
+        if new_width == width && new_height == height {
+            return;
+        }
+
+        let bpp = self.metadata.format.bytes_per_pixel();
+        let src_stride = width as usize * bpp;
+        let mut pixels = Vec::with_capacity(new_width as usize * new_height as usize * bpp);
+
+        for y in 0..new_height as usize {
+            for x in 0..new_width as usize {
+                match filter {
+                    ResizeFilter::Nearest => {
+                        let sx = x * width as usize / new_width as usize;
+                        let sy = y * height as usize / new_height as usize;
+                        let start = sy * src_stride + sx * bpp;
+                        pixels.extend_from_slice(&self.pixels[start..start + bpp]);
+                    }
+                    ResizeFilter::Box => {
+                        // The range of source pixels covered by the destination pixel. At least
+                        // one pixel is always covered (when upscaling).
+                        let x0 = x * width as usize / new_width as usize;
+                        let x1 = ((x + 1) * width as usize / new_width as usize).max(x0 + 1);
+                        let y0 = y * height as usize / new_height as usize;
+                        let y1 = ((y + 1) * height as usize / new_height as usize).max(y0 + 1);
+
+                        let count = ((x1 - x0) * (y1 - y0)) as u32;
+                        for channel in 0..bpp {
+                            let mut sum = 0u32;
+                            for sy in y0..y1 {
+                                for sx in x0..x1 {
+                                    sum += self.pixels[sy * src_stride + sx * bpp + channel] as u32;
+                                }
+                            }
+                            pixels.push(((sum + count / 2) / count) as u8);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.pixels = pixels;
+        self.metadata.width = new_width;
+        self.metadata.height = new_height;
+    }
+
+    /// Generates the mipmap chain of the image.
+    ///
+    /// Each level is half the size of the previous one (rounded down, with a minimum of 1),
+    /// computed using a box filter, until a 1x1 level is reached.
+    ///
+    /// # Remarks
+    ///
+    /// The returned list does *not* include the original image (level 0). Its first element is
+    /// level 1.
+    ///
+    /// Every level keeps the format and color space of the original image.
+    pub fn generate_mipmaps(&self) -> Vec<Image> {
+        let mut levels: Vec<Image> = Vec::new();
+
+        loop {
+            let prev = levels.last().unwrap_or(self);
+            let (width, height) = (prev.metadata.width, prev.metadata.height);
+
+            if width == 0 || height == 0 || (width == 1 && height == 1) {
+                break;
+            }
+
+            let mut level = prev.clone();
+            level.resize((width / 2).max(1), (height / 2).max(1), ResizeFilter::Box);
+            levels.push(level);
+        }
+
+        levels
+    }
+
+    /// Flips the image vertically, swapping its top and bottom rows.
+    pub fn flip_vertical(&mut self) {
+        let row_size = self.metadata.width as usize * self.metadata.format.bytes_per_pixel();
+        let height = self.metadata.height as usize;
+
+        if row_size == 0 {
+            return;
+        }
+
+        for y in 0..height / 2 {
+            let (top, bottom) = self.pixels.split_at_mut((height - 1 - y) * row_size);
+            top[y * row_size..(y + 1) * row_size].swap_with_slice(&mut bottom[..row_size]);
+        }
+    }
+
+    /// Flips the image horizontally, swapping its left and right columns.
+    pub fn flip_horizontal(&mut self) {
+        let bpp = self.metadata.format.bytes_per_pixel();
+        let row_size = self.metadata.width as usize * bpp;
+
+        if row_size == 0 {
+            return;
+        }
+
+        for row in self.pixels.chunks_exact_mut(row_size) {
+            // Reversing the whole row reverses the order of the pixels, but also the order of
+            // the channels within each pixel. Reversing each pixel afterwards fixes that.
+            row.reverse();
+            row.chunks_exact_mut(bpp).for_each(<[u8]>::reverse);
+        }
+    }
+
+    /// Encodes the image in the PNG format and writes the result to the provided writer.
+    ///
+    /// # Errors
+    ///
+    /// If the format of the image cannot be represented by the PNG format (such as [`Bgra`]),
+    /// [`Error::UnsupportedFormat`] is returned.
+    ///
+    /// [`Bgra`]: Format::Bgra
+    #[inline]
+    pub fn encode_png(&self, writer: impl io::Write) -> Result<(), Error> {
+        png::encode(self, writer)
+    }
+
+    /// Ensures that the image is encoded in the [`Linear`] color space, eventually converting it
+    /// if needed.
+    ///
+    /// # Remarks
+    ///
+    /// Images whose color space is [`Unknown`] are left untouched. The alpha channel is never
+    /// modified as it is always linear.
+    ///
+    /// [`Linear`]: ColorSpace::Linear
+    /// [`Unknown`]: ColorSpace::Unknown
+    pub fn ensure_linear(&mut self) {
+        if self.metadata.color_space != ColorSpace::Srgb {
+            return;
+        }
+
+        self.map_color_channels(srgb_to_linear);
+        self.metadata.color_space = ColorSpace::Linear;
+    }
+
+    /// Ensures that the image is encoded in the [`Srgb`] color space, eventually converting it
+    /// if needed.
+    ///
+    /// # Remarks
+    ///
+    /// Images whose color space is [`Unknown`] are left untouched. The alpha channel is never
+    /// modified as it is always linear.
+    ///
+    /// [`Srgb`]: ColorSpace::Srgb
+    /// [`Unknown`]: ColorSpace::Unknown
+    pub fn ensure_srgb(&mut self) {
+        if self.metadata.color_space != ColorSpace::Linear {
+            return;
+        }
+
+        let table = linear_to_srgb_table();
+        self.map_color_channels(|c| table[c as usize]);
+        self.metadata.color_space = ColorSpace::Srgb;
+    }
+
+    /// Applies the provided function to every color channel of the image, skipping the alpha
+    /// channel.
+    fn map_color_channels(&mut self, f: impl Fn(u8) -> u8) {
+        let stride = self.metadata.format.bytes_per_pixel();
+        let color_channels = match self.metadata.format {
+            Format::Rgba | Format::Bgra | Format::Rgb | Format::Bgr => 3,
+            Format::Grayscale | Format::GrayscaleAlpha => 1,
+        };
+
+        for pixel in self.pixels.chunks_exact_mut(stride) {
+            for channel in &mut pixel[..color_channels] {
+                *channel = f(*channel);
+            }
+        }
+    }
+
     /// Ensures that the image is encoded in [`Rgba`] format, eventually converting it if needed.
     ///
     /// [`Rgba`]: Format::Rgba
@@ -142,9 +381,13 @@ impl Image {
 
                 for i in (0..cnt).rev() {
                     unsafe {
-                        *self.pixels.get_unchecked_mut(i * 4 + 0) = *self.pixels.get_unchecked(i);
-                        *self.pixels.get_unchecked_mut(i * 4 + 1) = *self.pixels.get_unchecked(i);
-                        *self.pixels.get_unchecked_mut(i * 4 + 2) = *self.pixels.get_unchecked(i);
+                        // The source pixel must be read before anything is written as the
+                        // source and destination ranges overlap for the first pixels.
+                        let l = *self.pixels.get_unchecked(i);
+
+                        *self.pixels.get_unchecked_mut(i * 4 + 0) = l;
+                        *self.pixels.get_unchecked_mut(i * 4 + 1) = l;
+                        *self.pixels.get_unchecked_mut(i * 4 + 2) = l;
                         *self.pixels.get_unchecked_mut(i * 4 + 3) = 255;
                     }
                 }
@@ -155,14 +398,14 @@ impl Image {
 
                 for i in (0..cnt).rev() {
                     unsafe {
-                        *self.pixels.get_unchecked_mut(i * 4 + 0) =
-                            *self.pixels.get_unchecked(i * 2 + 0);
-                        *self.pixels.get_unchecked_mut(i * 4 + 1) =
-                            *self.pixels.get_unchecked(i * 2 + 0);
-                        *self.pixels.get_unchecked_mut(i * 4 + 2) =
-                            *self.pixels.get_unchecked(i * 2 + 0);
-                        *self.pixels.get_unchecked_mut(i * 4 + 3) =
-                            *self.pixels.get_unchecked(i * 2 + 1);
+                        // Same as above, read the whole source pixel before writing.
+                        let l = *self.pixels.get_unchecked(i * 2 + 0);
+                        let a = *self.pixels.get_unchecked(i * 2 + 1);
+
+                        *self.pixels.get_unchecked_mut(i * 4 + 0) = l;
+                        *self.pixels.get_unchecked_mut(i * 4 + 1) = l;
+                        *self.pixels.get_unchecked_mut(i * 4 + 2) = l;
+                        *self.pixels.get_unchecked_mut(i * 4 + 3) = a;
                     }
                 }
             }
@@ -171,3 +414,195 @@ impl Image {
         self.metadata.format = Format::Rgba;
     }
 }
+
+/// Reads as many bytes as possible from `reader` into `buf`, stopping early only when the
+/// end of the stream is reached.
+///
+/// Returns the number of bytes that were read.
+fn read_up_to(reader: &mut impl io::Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(len)
+}
+
+/// Converts a color channel from the sRGB color space to the linear color space.
+pub fn srgb_to_linear(value: u8) -> u8 {
+    let c = value as f32 / 255.0;
+
+    let linear = if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    };
+
+    (linear * 255.0).round() as u8
+}
+
+/// Converts a color channel from the linear color space to the sRGB color space.
+#[inline]
+pub fn linear_to_srgb(value: u8) -> u8 {
+    linear_to_srgb_table()[value as usize]
+}
+
+/// Returns a lookup table mapping every linear color channel value to its sRGB counterpart.
+///
+/// The table is computed once, the first time it is requested.
+fn linear_to_srgb_table() -> &'static [u8; 256] {
+    static TABLE: OnceLock<[u8; 256]> = OnceLock::new();
+
+    TABLE.get_or_init(|| {
+        std::array::from_fn(|i| {
+            let c = i as f32 / 255.0;
+
+            let srgb = if c <= 0.0031308 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            };
+
+            (srgb * 255.0).round() as u8
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, format: Format, pixels: Vec<u8>) -> Image {
+        Image {
+            pixels,
+            metadata: ImageMetadata {
+                width,
+                height,
+                format,
+                color_space: ColorSpace::Unknown,
+            },
+        }
+    }
+
+    #[test]
+    fn grayscale_expands_to_rgba() {
+        let mut image = image(4, 1, Format::Grayscale, vec![0, 64, 128, 255]);
+        image.ensure_rgba();
+
+        assert_eq!(image.metadata.format, Format::Rgba);
+        assert_eq!(
+            image.pixels,
+            [0, 0, 0, 255, 64, 64, 64, 255, 128, 128, 128, 255, 255, 255, 255, 255],
+        );
+    }
+
+    #[test]
+    fn grayscale_alpha_expands_to_rgba() {
+        let mut image = image(2, 1, Format::GrayscaleAlpha, vec![10, 20, 30, 40]);
+        image.ensure_rgba();
+
+        assert_eq!(image.metadata.format, Format::Rgba);
+        assert_eq!(image.pixels, [10, 10, 10, 20, 30, 30, 30, 40]);
+    }
+
+    #[test]
+    fn linear_to_srgb_table_matches_formula() {
+        for value in 0..=255u8 {
+            let c = value as f32 / 255.0;
+
+            let srgb = if c <= 0.0031308 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            };
+
+            assert_eq!(
+                linear_to_srgb(value),
+                (srgb * 255.0).round() as u8,
+                "mismatch for {value}",
+            );
+        }
+    }
+
+    /// Returns a 4x4 grayscale checkerboard with single-pixel cells, starting with black.
+    fn checkerboard() -> Image {
+        let pixels = (0..16)
+            .map(|i| if (i % 4 + i / 4) % 2 == 0 { 0 } else { 255 })
+            .collect();
+        image(4, 4, Format::Grayscale, pixels)
+    }
+
+    #[test]
+    fn nearest_downscale_samples_the_checkerboard() {
+        let mut image = checkerboard();
+        image.resize(2, 2, ResizeFilter::Nearest);
+
+        assert_eq!(image.metadata.width, 2);
+        assert_eq!(image.metadata.height, 2);
+        // Every sampled source pixel has even coordinates, so all are black.
+        assert_eq!(image.pixels, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn box_downscale_averages_the_checkerboard() {
+        let mut image = checkerboard();
+        image.resize(2, 2, ResizeFilter::Box);
+
+        assert_eq!(image.metadata.width, 2);
+        assert_eq!(image.metadata.height, 2);
+        // Each destination pixel covers two black and two white pixels.
+        assert_eq!(image.pixels, [128, 128, 128, 128]);
+    }
+
+    /// Returns a 2x2 RGBA image whose corners all have distinct colors.
+    fn corners() -> Image {
+        #[rustfmt::skip]
+        let pixels = vec![
+            1, 2, 3, 4,     5, 6, 7, 8,
+            9, 10, 11, 12,  13, 14, 15, 16,
+        ];
+        image(2, 2, Format::Rgba, pixels)
+    }
+
+    #[test]
+    fn flip_vertical_swaps_rows() {
+        let mut image = corners();
+        image.flip_vertical();
+
+        assert_eq!(
+            image.pixels,
+            [9, 10, 11, 12, 13, 14, 15, 16, 1, 2, 3, 4, 5, 6, 7, 8],
+        );
+    }
+
+    #[test]
+    fn flip_horizontal_swaps_columns() {
+        let mut image = corners();
+        image.flip_horizontal();
+
+        assert_eq!(
+            image.pixels,
+            [5, 6, 7, 8, 1, 2, 3, 4, 13, 14, 15, 16, 9, 10, 11, 12],
+        );
+    }
+
+    #[test]
+    fn flipping_twice_is_identity() {
+        let original = corners();
+
+        let mut image = original.clone();
+        image.flip_vertical();
+        image.flip_vertical();
+        assert_eq!(image.pixels, original.pixels);
+
+        image.flip_horizontal();
+        image.flip_horizontal();
+        assert_eq!(image.pixels, original.pixels);
+    }
+}
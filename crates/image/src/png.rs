@@ -5,8 +5,20 @@ use crate::ImageMetadata;
 /// Loads an image from the provided reader.
 ///
 /// The image is expected to be in the PNG format.
+///
+/// # Remarks
+///
+/// Images with 16 bits per channel are down-converted to 8 bits per channel by only keeping
+/// the most significant byte of each channel. This is lossy, but it allows those images to be
+/// represented using the regular [`crate::Format`] variants.
+///
+/// Indexed (palette) images are expanded using their `PLTE` chunk. The resulting image uses
+/// [`crate::Format::Rgba`] when a `tRNS` chunk is present, and [`crate::Format::Rgb`]
+/// otherwise. Grayscale images with less than 8 bits per channel are expanded to 8 bits.
 pub fn load(reader: impl io::Read) -> Result<crate::Image, crate::Error> {
     let mut decoder = png::Decoder::new(reader);
+    // `EXPAND` takes care of palette images (including their transparency chunk) and
+    // low-bit-depth grayscale images.
     decoder.set_transformations(png::Transformations::STRIP_16 | png::Transformations::EXPAND);
     let mut reader = decoder.read_info().map_err(map_error)?;
 
@@ -40,6 +52,30 @@ pub fn load(reader: impl io::Read) -> Result<crate::Image, crate::Error> {
     })
 }
 
+/// Encodes the provided image to the PNG format, writing the result to `writer`.
+pub fn encode(image: &crate::Image, writer: impl io::Write) -> Result<(), crate::Error> {
+    let color_type = match image.metadata.format {
+        crate::Format::Rgba => png::ColorType::Rgba,
+        crate::Format::Rgb => png::ColorType::Rgb,
+        crate::Format::Grayscale => png::ColorType::Grayscale,
+        crate::Format::GrayscaleAlpha => png::ColorType::GrayscaleAlpha,
+        crate::Format::Bgra | crate::Format::Bgr => return Err(crate::Error::UnsupportedFormat),
+    };
+
+    let mut encoder = png::Encoder::new(writer, image.metadata.width, image.metadata.height);
+    encoder.set_color(color_type);
+    encoder.set_depth(png::BitDepth::Eight);
+    if image.metadata.color_space == crate::ColorSpace::Srgb {
+        encoder.set_srgb(png::SrgbRenderingIntent::Perceptual);
+    }
+
+    let mut writer = encoder.write_header().map_err(map_encoding_error)?;
+    writer
+        .write_image_data(&image.pixels)
+        .map_err(map_encoding_error)?;
+    writer.finish().map_err(map_encoding_error)
+}
+
 /// Maps the provided error to a [`crate::Error`].
 fn map_error(err: png::DecodingError) -> crate::Error {
     match err {
@@ -50,6 +86,16 @@ fn map_error(err: png::DecodingError) -> crate::Error {
     }
 }
 
+/// Maps the provided encoding error to a [`crate::Error`].
+fn map_encoding_error(err: png::EncodingError) -> crate::Error {
+    match err {
+        png::EncodingError::Parameter(err) => panic!("invalid PNG parameters: {err}"),
+        png::EncodingError::IoError(io) => crate::Error::Io(io),
+        png::EncodingError::LimitsExceeded => panic!("PNG limits exceeded"),
+        png::EncodingError::Format(..) => crate::Error::Format,
+    }
+}
+
 /// Maps the provided format to a [`crate::Format`].
 fn map_format(format: png::ColorType) -> Result<crate::Format, crate::Error> {
     match format {
@@ -60,3 +106,50 @@ fn map_format(format: png::ColorType) -> Result<crate::Format, crate::Error> {
         _ => Err(crate::Error::UnsupportedFormat),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sixteen_bit_grayscale_keeps_high_byte() {
+        // A 3x1 grayscale image with 16 bits per channel (big-endian).
+        let samples: [u16; 3] = [0x0000, 0x80ff, 0xff01];
+
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, 3, 1);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Sixteen);
+        let mut writer = encoder.write_header().unwrap();
+        let data: Vec<u8> = samples.iter().flat_map(|s| s.to_be_bytes()).collect();
+        writer.write_image_data(&data).unwrap();
+        writer.finish().unwrap();
+
+        let image = load(bytes.as_slice()).unwrap();
+        assert_eq!(image.metadata.width, 3);
+        assert_eq!(image.metadata.height, 1);
+        assert_eq!(image.metadata.format, crate::Format::Grayscale);
+        assert_eq!(image.pixels, [0x00, 0x80, 0xff]);
+    }
+
+    #[test]
+    fn indexed_with_transparency_expands_to_rgba() {
+        // A 3x1 palette image. The `tRNS` chunk is shorter than the palette, so the last
+        // entry is fully opaque.
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, 3, 1);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_palette(vec![255, 0, 0, 0, 255, 0, 0, 0, 255]);
+        encoder.set_trns(vec![0, 128]);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[2, 0, 1]).unwrap();
+        writer.finish().unwrap();
+
+        let image = load(bytes.as_slice()).unwrap();
+        assert_eq!(image.metadata.width, 3);
+        assert_eq!(image.metadata.height, 1);
+        assert_eq!(image.metadata.format, crate::Format::Rgba);
+        assert_eq!(image.pixels, [0, 0, 255, 255, 255, 0, 0, 0, 0, 255, 0, 128]);
+    }
+}
@@ -27,6 +27,16 @@ pub struct ColorDeref {
 }
 
 /// A color, represented as four 8-bit unsigned bytes.
+///
+/// # Layout
+///
+/// A [`Color`] is stored as a single `u32` whose most significant byte is the red component,
+/// followed by the green, blue and alpha components (`0xRRGGBBAA`). This is the layout
+/// expected by the `unpack_color` function of the shaders, which receive colors as `Uint32`
+/// vertex attributes or uniform fields.
+///
+/// No color space conversion is performed when packing or unpacking a color; the components
+/// are passed to the shaders as-is.
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Zeroable, Pod)]
 #[repr(transparent)]
 pub struct Color(u32);
@@ -103,3 +113,61 @@ impl DerefMut for Color {
         unsafe { &mut *(self as *mut Self as *mut ColorDeref) }
     }
 }
+
+/// Converts an sRGB-encoded component in `[0, 255]` to a linear value in `[0.0, 1.0]`.
+fn srgb_to_linear(component: u8) -> f64 {
+    let c = component as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl From<Color> for wgpu::Color {
+    /// Converts the provided [`Color`] to a [`wgpu::Color`].
+    ///
+    /// The color components of a [`Color`] are sRGB-encoded, while `wgpu` expects linear
+    /// values (which are encoded again when written to an sRGB render target). The alpha
+    /// component is already linear and is simply mapped from `[0, 255]` to `[0.0, 1.0]`.
+    #[inline]
+    fn from(color: Color) -> Self {
+        Self {
+            r: srgb_to_linear(color.r),
+            g: srgb_to_linear(color.g),
+            b: srgb_to_linear(color.b),
+            a: color.a as f64 / 255.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_layout() {
+        // The layout of `Color` is relied upon by the shaders.
+        assert_eq!(bytemuck::cast::<Color, u32>(Color::RED), 0xFF0000FF);
+        assert_eq!(
+            bytemuck::cast::<Color, u32>(Color::rgba(1, 2, 3, 4)),
+            0x01020304
+        );
+
+        let color = bytemuck::cast::<u32, Color>(0x12345678);
+        assert_eq!(
+            (color.r, color.g, color.b, color.a),
+            (0x12, 0x34, 0x56, 0x78)
+        );
+        assert_eq!(bytemuck::cast::<Color, u32>(color), 0x12345678);
+    }
+
+    #[test]
+    fn wgpu_color_is_linear() {
+        let color = wgpu::Color::from(Color::rgba(0, 128, 255, 128));
+        assert_eq!(color.r, 0.0);
+        assert!((color.g - 0.2158605).abs() < 1e-6);
+        assert_eq!(color.b, 1.0);
+        assert!((color.a - 128.0 / 255.0).abs() < 1e-9);
+    }
+}
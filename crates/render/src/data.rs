@@ -1,9 +1,13 @@
+use crate::renderer::FramePlan;
 use crate::shaders::quad::Quads;
 use crate::{Gpu, Texture, VertexBufferSlice};
 
 pub use crate::color::Color;
-pub use crate::shaders::common::{FrameFlags, FrameUniforms};
+pub use crate::shaders::common::{
+    FrameFlags, FrameUniforms, PointLight, MAX_POINT_LIGHTS, MAX_SHADOW_CASCADES,
+};
 pub use crate::shaders::line::{LineFlags, LineInstance};
+pub use crate::shaders::particle::ParticleInstance;
 pub use crate::shaders::quad::{ChunkUniforms, QuadFlags, QuadInstance};
 pub use crate::shaders::text::{CharacterFlags, CharacterInstance, CharacterInstanceCursor};
 pub use crate::shaders::ui_atlas_sprite::AtlasSprite;
@@ -26,6 +30,23 @@ pub enum Ui<'a> {
     AtlasSprite(VertexBufferSlice<'a, AtlasSprite>),
 }
 
+/// Some statistics about the content of a [`RenderData`] instance.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RenderStats {
+    /// The number of draw calls that will be issued to render the frame.
+    pub draw_calls: u32,
+    /// The number of quad instances, opaque and transparent alike.
+    pub quads: u32,
+    /// The number of line instances.
+    pub lines: u32,
+    /// The number of particle instances.
+    pub particles: u32,
+    /// The number of UI elements.
+    pub ui_elements: u32,
+    /// The number of characters drawn by the UI text elements.
+    pub text_chars: u32,
+}
+
 /// The data required to render a frame.
 pub struct RenderData<'res> {
     /// The frame uniforms for the frame.
@@ -45,6 +66,11 @@ pub struct RenderData<'res> {
     /// Right now, the lines are mainly used for debugging purposes, so this is not a problem.
     pub lines: Vec<LineInstance>,
 
+    /// The particle instances to render.
+    ///
+    /// Like lines, the content of this buffer is uploaded to the GPU on every frame.
+    pub particles: Vec<ParticleInstance>,
+
     /// A collection of UI elements.
     pub ui: Vec<Ui<'res>>,
 
@@ -52,6 +78,21 @@ pub struct RenderData<'res> {
     pub fog_enabled: bool,
     /// Whether shadows should be enabled.
     pub shadows_enabled: bool,
+    /// Whether the skybox should be rendered.
+    ///
+    /// When disabled, the background is filled with [`clear_color`](Self::clear_color)
+    /// instead.
+    pub skybox_enabled: bool,
+    /// Whether the [`lines`](Self::lines) should be rendered.
+    pub lines_enabled: bool,
+    /// Whether the [`particles`](Self::particles) should be rendered.
+    pub particles_enabled: bool,
+    /// Whether the [`ui`](Self::ui) should be rendered.
+    pub ui_enabled: bool,
+    /// The color used to clear the render target before the scene is drawn.
+    ///
+    /// This color is only visible when the skybox is disabled.
+    pub clear_color: Color,
 }
 
 impl<'res> RenderData<'res> {
@@ -61,17 +102,81 @@ impl<'res> RenderData<'res> {
             uniforms: FrameUniforms::default(),
             quads: Quads::new(gpu),
             lines: Vec::new(),
+            particles: Vec::new(),
             ui: Vec::new(),
             fog_enabled: true,
             shadows_enabled: true,
+            skybox_enabled: true,
+            lines_enabled: true,
+            particles_enabled: true,
+            ui_enabled: true,
+            clear_color: Color::BLACK,
+        }
+    }
+
+    /// Adds a point light to the frame.
+    ///
+    /// # Remarks
+    ///
+    /// At most [`MAX_POINT_LIGHTS`] lights can affect a single frame. Lights pushed past that
+    /// limit are dropped, and a warning is logged in debug builds.
+    ///
+    /// The lights are stored in [`FrameUniforms`], meaning that they must be pushed *after*
+    /// the uniforms have been set for the frame.
+    pub fn push_light(&mut self, light: PointLight) {
+        let count = self.uniforms.point_light_count as usize;
+
+        if count >= MAX_POINT_LIGHTS {
+            if cfg!(debug_assertions) {
+                bns_log::warning!("too many point lights in a single frame, dropping one");
+            }
+            return;
         }
+
+        self.uniforms.point_lights[count] = light;
+        self.uniforms.point_light_count += 1;
+    }
+
+    /// Adds a particle to the frame.
+    #[inline]
+    pub fn push_particle(&mut self, particle: ParticleInstance) {
+        self.particles.push(particle);
+    }
+
+    /// Computes some statistics about the content of this [`RenderData`].
+    ///
+    /// # Remarks
+    ///
+    /// The number of draw calls is derived from the same conditions the renderer uses to record
+    /// the frame, including the current state of the `gpu` (number of shadow cascades, render
+    /// scale).
+    pub fn stats(&self, gpu: &Gpu) -> RenderStats {
+        let mut stats = RenderStats {
+            quads: self.quads.quad_count(),
+            lines: self.lines.len() as u32,
+            particles: self.particles.len() as u32,
+            ui_elements: self.ui.len() as u32,
+            ..RenderStats::default()
+        };
+
+        for elem in &self.ui {
+            if let Ui::Text(slice) = elem {
+                stats.text_chars += slice.len();
+            }
+        }
+
+        stats.draw_calls = FramePlan::new(self, &gpu.resources.read()).draw_calls(self);
+
+        stats
     }
 
     /// Re-creates this [`RenderData`] with a potentially longer lifetime, while keeping the
     /// original allocations.
     pub fn reset<'res2>(mut self) -> RenderData<'res2> {
         self.lines.clear();
+        self.particles.clear();
         self.ui.clear();
+        self.uniforms.point_light_count = 0;
 
         // SAFETY:
         //  Any type has the same layout regardless of which lifetime it uses. No
@@ -83,9 +188,86 @@ impl<'res> RenderData<'res> {
             uniforms: self.uniforms,
             quads: self.quads.reset(),
             lines: self.lines,
+            particles: self.particles,
             ui,
             fog_enabled: true,
             shadows_enabled: true,
+            skybox_enabled: true,
+            lines_enabled: true,
+            particles_enabled: true,
+            ui_enabled: true,
+            clear_color: Color::BLACK,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytemuck::Zeroable;
+
+    use super::*;
+    use crate::DynamicVertexBuffer;
+
+    #[test]
+    #[ignore = "requires a GPU adapter"]
+    fn stats_match_pushed_data() {
+        let gpu = crate::gpu::block_on(Gpu::new_headless()).expect("no GPU adapter available");
+        let gpu = Arc::new(gpu);
+
+        let opaque = DynamicVertexBuffer::new_with_data(gpu.clone(), &[QuadInstance::zeroed(); 4]);
+        let transparent =
+            DynamicVertexBuffer::new_with_data(gpu.clone(), &[QuadInstance::zeroed(); 3]);
+        let text =
+            DynamicVertexBuffer::new_with_data(gpu.clone(), &[CharacterInstance::zeroed(); 7]);
+        let sprites = DynamicVertexBuffer::new_with_data(gpu.clone(), &[AtlasSprite::zeroed(); 2]);
+
+        let mut data = RenderData::new(&gpu);
+        data.shadows_enabled = false;
+
+        let chunk = data.quads.register_chunk(&ChunkUniforms::zeroed());
+        data.quads.register_opaque_quads(chunk, opaque.slice());
+        data.quads.register_opaque_quads(chunk, opaque.slice());
+        data.quads
+            .register_transparent_quads(chunk, transparent.slice());
+        data.ui.push(Ui::Text(text.slice()));
+        data.ui.push(Ui::AtlasSprite(sprites.slice()));
+        for _ in 0..3 {
+            data.lines.push(LineInstance::zeroed());
+        }
+        for _ in 0..5 {
+            data.push_particle(ParticleInstance::zeroed());
+        }
+
+        // The upscaling pass depends on the render scale of the GPU, not on the data.
+        let upscale = gpu.resources.read().scaled_color.is_some() as u32;
+
+        let stats = data.stats(&gpu);
+        assert_eq!(
+            stats,
+            RenderStats {
+                // opaque + transparent + skybox, lines, particles, fog + ui
+                draw_calls: 2 + 1 + 4 + 2 + upscale,
+                quads: 2 * 4 + 3,
+                lines: 3,
+                particles: 5,
+                ui_elements: 2,
+                text_chars: 7,
+            }
+        );
+
+        // Opaque quads are drawn once more for every shadow cascade.
+        data.shadows_enabled = true;
+        data.uniforms.shadow_cascade_count = 1;
+        assert_eq!(data.stats(&gpu).draw_calls, stats.draw_calls + 2);
+
+        data.shadows_enabled = false;
+        data.lines_enabled = false;
+        data.particles_enabled = false;
+        data.skybox_enabled = false;
+        data.fog_enabled = false;
+        data.ui_enabled = false;
+        assert_eq!(data.stats(&gpu).draw_calls, 2 + 1 + upscale);
+    }
+}
@@ -11,8 +11,14 @@ use crate::TextureAtlasConfig;
 /// Specifically, this handle can be used to create new GPU resources and to submit commands to
 /// the GPU, such as transfer commands to copy data from the CPU to the GPU.
 pub struct Gpu {
+    /// Information about the adapter that was selected.
+    info: GpuInfo,
     /// The limits that have been imposed on the GPU.
     pub(crate) limits: wgpu::Limits,
+    /// The adapter that was selected.
+    ///
+    /// This is mainly used to query the capabilities of the GPU.
+    pub(crate) adapter: wgpu::Adapter,
 
     /// The device that is used to communicate with the GPU.
     ///
@@ -38,11 +44,18 @@ pub struct Gpu {
 
 impl Gpu {
     /// Creates a new [`Gpu`] instance.
-    pub(crate) fn new(device: wgpu::Device, queue: wgpu::Queue) -> Self {
+    pub(crate) fn new(
+        info: GpuInfo,
+        adapter: wgpu::Adapter,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+    ) -> Self {
         let resources = RwLock::new(CommonResources::new(&device, &queue));
 
         Self {
+            info,
             limits: device.limits(),
+            adapter,
             queue,
 
             #[cfg(not(target_arch = "wasm32"))]
@@ -60,6 +73,44 @@ impl Gpu {
         }
     }
 
+    /// Creates a new [`Gpu`] instance that is not attached to any surface.
+    ///
+    /// The returned [`Gpu`] can only be used to render off-screen, using
+    /// [`RenderTarget::from_texture`](crate::RenderTarget::from_texture).
+    ///
+    /// # Returns
+    ///
+    /// [`None`] is returned if no GPU is found to render on, or if the connection with it could
+    /// not be established.
+    pub async fn new_headless() -> Option<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await?;
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    required_limits: wgpu::Limits::default(),
+                    required_features: wgpu::Features::empty(),
+                    label: Some("Headless GPU Device"),
+                },
+                None,
+            )
+            .await
+            .ok()?;
+
+        Some(Self::new(GpuInfo::of(&adapter), adapter, device, queue))
+    }
+
+    /// Returns information about the adapter that was selected to render on.
+    #[inline]
+    pub fn info(&self) -> &GpuInfo {
+        &self.info
+    }
+
     /// Returns the temporary command encoder for the current thread.
     pub(crate) fn temp_command_encoder(&self) -> &Mutex<wgpu::CommandEncoder> {
         #[cfg(not(target_arch = "wasm32"))]
@@ -96,6 +147,40 @@ impl Gpu {
         }
     }
 
+    /// Runs the provided function within an error scope, logging any validation error that
+    /// occured during its execution along with the provided label.
+    ///
+    /// # Remarks
+    ///
+    /// Error scopes are only used in debug builds. On web, errors are reported asynchronously
+    /// and this function simply calls `f`.
+    pub(crate) fn error_scope<R>(&self, label: &str, f: impl FnOnce() -> R) -> R {
+        #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+        {
+            use std::future::Future;
+            use std::task::{Context, Poll, Waker};
+
+            self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+            let ret = f();
+
+            // On native platforms, the future returned by `pop_error_scope` is always
+            // immediately ready. We don't need a proper executor to poll it.
+            let mut fut = std::pin::pin!(self.device.pop_error_scope());
+            let mut cx = Context::from_waker(Waker::noop());
+            if let Poll::Ready(Some(err)) = fut.as_mut().poll(&mut cx) {
+                bns_log::error!("GPU error during '{label}': {err}");
+            }
+
+            ret
+        }
+
+        #[cfg(not(all(debug_assertions, not(target_arch = "wasm32"))))]
+        {
+            let _ = label;
+            f()
+        }
+    }
+
     /// Notifies the GPU that the render target has been resized.
     pub fn notify_resized(&self, width: u32, height: u32) {
         self.resources
@@ -110,3 +195,110 @@ impl Gpu {
             .set_texture_atlas(&self.device, &self.queue, texture);
     }
 }
+
+/// Drives the provided future to completion by busy-polling it.
+///
+/// On native platforms, the futures returned by `wgpu` are resolved by polling the device, so
+/// this is enough to use them in tests.
+#[cfg(test)]
+pub(crate) fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+    use std::task::{Context, Poll, Waker};
+
+    let mut fut = std::pin::pin!(fut);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(ret) = fut.as_mut().poll(&mut cx) {
+            return ret;
+        }
+        std::thread::yield_now();
+    }
+}
+
+/// The kind of a [`Gpu`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuKind {
+    /// The GPU is a separate piece of hardware.
+    Discrete,
+    /// The GPU is integrated with the CPU.
+    Integrated,
+    /// The GPU is a virtual device (usually running in a virtual machine).
+    Virtual,
+    /// The GPU is emulated in software by the CPU.
+    Cpu,
+    /// The kind of the GPU could not be determined.
+    Unknown,
+}
+
+impl From<wgpu::DeviceType> for GpuKind {
+    fn from(value: wgpu::DeviceType) -> Self {
+        match value {
+            wgpu::DeviceType::DiscreteGpu => Self::Discrete,
+            wgpu::DeviceType::IntegratedGpu => Self::Integrated,
+            wgpu::DeviceType::VirtualGpu => Self::Virtual,
+            wgpu::DeviceType::Cpu => Self::Cpu,
+            wgpu::DeviceType::Other => Self::Unknown,
+        }
+    }
+}
+
+/// Information about the adapter a [`Gpu`] is running on.
+#[derive(Debug, Clone)]
+pub struct GpuInfo {
+    /// The name of the adapter.
+    pub name: String,
+    /// The kind of the adapter.
+    pub kind: GpuKind,
+    /// The maximum size of a 2D texture supported by the adapter.
+    pub max_texture_size: u32,
+}
+
+impl GpuInfo {
+    /// Creates a new [`GpuInfo`] from the provided adapter.
+    pub(crate) fn of(adapter: &wgpu::Adapter) -> Self {
+        let info = adapter.get_info();
+
+        Self {
+            name: info.name,
+            kind: info.device_type.into(),
+            max_texture_size: adapter.limits().max_texture_dimension_2d,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a buffer that can be mapped both for reading and writing, which is invalid
+    /// without the `MAPPABLE_PRIMARY_BUFFERS` feature.
+    fn create_invalid_buffer(gpu: &Gpu) -> wgpu::Buffer {
+        gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Invalid Buffer"),
+            size: 4,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::MAP_WRITE,
+            mapped_at_creation: false,
+        })
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[ignore = "requires a GPU adapter"]
+    fn error_scope_captures_validation_errors() {
+        let gpu = block_on(Gpu::new_headless()).expect("no GPU adapter available");
+
+        // Make sure that the buffer is actually invalid.
+        gpu.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        create_invalid_buffer(&gpu);
+        assert!(block_on(gpu.device.pop_error_scope()).is_some());
+
+        // The error is captured by the inner scope and does not reach the outer one (or the
+        // uncaptured error handler, which would panic).
+        gpu.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let ret = gpu.error_scope("invalid buffer", || {
+            create_invalid_buffer(&gpu);
+            42
+        });
+        assert_eq!(ret, 42);
+        assert!(block_on(gpu.device.pop_error_scope()).is_none());
+    }
+}
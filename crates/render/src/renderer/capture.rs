@@ -0,0 +1,187 @@
+use std::fmt;
+
+use bns_image::{ColorSpace, Format, Image, ImageMetadata};
+use wgpu::TextureFormat;
+
+use crate::data::RenderData;
+use crate::{RenderTarget, RenderTexture, Renderer};
+
+/// An error that might occur when capturing a frame with [`Renderer::capture`].
+#[derive(Debug, Clone)]
+pub enum CaptureError {
+    /// Capturing frames is not supported on the current platform.
+    ///
+    /// This is the case on web, where reading data back from the GPU cannot be done
+    /// synchronously.
+    Unsupported,
+    /// The output format of the renderer cannot be converted to an [`Image`].
+    UnsupportedFormat(TextureFormat),
+    /// The buffer that holds the captured pixels could not be mapped.
+    Map,
+}
+
+impl fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Unsupported => write!(f, "capturing frames is not supported on this platform"),
+            Self::UnsupportedFormat(format) => {
+                write!(f, "cannot capture frames in the {format:?} format")
+            }
+            Self::Map => write!(f, "failed to map the capture buffer"),
+        }
+    }
+}
+
+impl Renderer {
+    /// Renders the provided [`RenderData`] to an off-screen texture and reads the result back.
+    ///
+    /// The captured image has the size of the render target (as last reported through
+    /// [`Gpu::notify_resized`](crate::Gpu::notify_resized)) and is always returned in the
+    /// [`Format::Rgba`] format.
+    ///
+    /// # Remarks
+    ///
+    /// This function blocks until the GPU has finished rendering the frame. It is meant to take
+    /// occasional screenshots, not to be called every frame.
+    ///
+    /// # Errors
+    ///
+    /// On web, this function always returns [`CaptureError::Unsupported`] because mapping a
+    /// buffer only completes once control has been given back to the browser.
+    ///
+    /// [`CaptureError::UnsupportedFormat`] is returned if the output format of the renderer is
+    /// not an 8-bit RGBA or BGRA format.
+    pub fn capture(&mut self, data: &mut RenderData) -> Result<Image, CaptureError> {
+        if cfg!(target_arch = "wasm32") {
+            return Err(CaptureError::Unsupported);
+        }
+
+        let swap_red_blue = match self.output_format {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+            format => return Err(CaptureError::UnsupportedFormat(format)),
+        };
+
+        let (width, height) = self.gpu.resources.read().size();
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = RenderTexture::new(&self.gpu, width, height, self.output_format);
+        self.render(RenderTarget::from_texture(&texture), data);
+
+        // Rows copied to a buffer must be aligned to `COPY_BYTES_PER_ROW_ALIGNMENT` bytes. The
+        // padding is stripped when reading the buffer back.
+        let unpadded_row = width as usize * 4;
+        let padded_row =
+            wgpu::util::align_to(unpadded_row, wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize);
+
+        let buffer = self.gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Buffer"),
+            size: (padded_row * height as usize) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Capture Command Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            texture.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row as u32),
+                    rows_per_image: Some(height),
+                },
+            },
+            size,
+        );
+        self.gpu.queue.submit(Some(encoder.finish()));
+
+        // Wait for the copy to complete and map the buffer.
+        let slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.gpu.device.poll(wgpu::Maintain::Wait);
+        match receiver.recv() {
+            Ok(Ok(())) => (),
+            _ => return Err(CaptureError::Map),
+        }
+
+        let mut pixels = Vec::with_capacity(unpadded_row * height as usize);
+        for row in slice.get_mapped_range().chunks_exact(padded_row) {
+            pixels.extend_from_slice(&row[..unpadded_row]);
+        }
+        buffer.unmap();
+
+        if swap_red_blue {
+            pixels
+                .chunks_exact_mut(4)
+                .for_each(|pixel| pixel.swap(0, 2));
+        }
+
+        Ok(Image {
+            pixels,
+            metadata: ImageMetadata {
+                width,
+                height,
+                format: Format::Rgba,
+                color_space: if self.output_format.is_srgb() {
+                    ColorSpace::Srgb
+                } else {
+                    ColorSpace::Linear
+                },
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::Color;
+    use crate::{Gpu, RendererConfig};
+
+    use super::*;
+
+    #[test]
+    fn render_texture_clear_color_readback() {
+        let Some(gpu) = crate::gpu::block_on(Gpu::new_headless()) else {
+            return;
+        };
+        let gpu = std::sync::Arc::new(gpu);
+        gpu.notify_resized(8, 4);
+
+        let mut renderer = Renderer::new(
+            gpu.clone(),
+            RendererConfig {
+                output_format: TextureFormat::Rgba8Unorm,
+                sample_count: 1,
+                shadow_map_resolution: 16,
+                shadow_cascade_count: 1,
+            },
+        );
+
+        let mut data = RenderData::new(&gpu);
+        data.skybox_enabled = false;
+        data.fog_enabled = false;
+        data.shadows_enabled = false;
+        data.clear_color = Color::MAGENTA;
+
+        let image = renderer.capture(&mut data).unwrap();
+        assert_eq!((image.metadata.width, image.metadata.height), (8, 4));
+        assert_eq!(image.metadata.format, Format::Rgba);
+        assert!(image
+            .pixels
+            .chunks_exact(4)
+            .all(|pixel| pixel == [255, 0, 255, 255]));
+    }
+}
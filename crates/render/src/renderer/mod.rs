@@ -2,18 +2,25 @@ use std::sync::Arc;
 
 use wgpu::TextureFormat;
 
+use crate::shaders::blit::BlitPipeline;
+use crate::shaders::common::MAX_SHADOW_CASCADES;
 use crate::shaders::fog::FogPipeline;
 use crate::shaders::line::LinePipeline;
+use crate::shaders::particle::ParticlePipeline;
 use crate::shaders::quad::QuadPipeline;
 use crate::shaders::skybox::SkyboxPipeline;
 use crate::shaders::text::TextPipeline;
-use crate::Gpu;
+use crate::{Gpu, RenderTexture};
 
-pub use crate::shaders::common::TextureAtlasConfig;
+pub use crate::shaders::common::{AtlasUvRect, TextureAtlasConfig};
 use crate::shaders::ui_atlas_sprite::UiAtlasSpritePipeline;
 use crate::shaders::ui_sprite::UiSpritePipeline;
 
 mod render;
+pub(crate) use render::FramePlan;
+
+mod capture;
+pub use capture::*;
 
 /// A target on which things can be rendered.
 #[derive(Clone, Copy, Debug)]
@@ -22,6 +29,29 @@ pub struct RenderTarget<'a> {
     ///
     /// This texture must have the `RENDER_ATTACHMENT` usage.
     pub(crate) view: &'a wgpu::TextureView,
+    /// The size of the target texture, in pixels.
+    pub(crate) size: (u32, u32),
+}
+
+impl<'a> RenderTarget<'a> {
+    /// Creates a [`RenderTarget`] that renders to the provided [`RenderTexture`].
+    ///
+    /// This makes it possible to render frames off-screen, without involving a
+    /// [`Surface`](crate::Surface).
+    ///
+    /// # Remarks
+    ///
+    /// The depth buffer and the intermediate color targets used by the [`Renderer`] are shared
+    /// by all render targets and have the size last reported through
+    /// [`Gpu::notify_resized`]. The texture must have that same size when the returned target
+    /// is passed to [`Renderer::render`].
+    #[inline]
+    pub fn from_texture(texture: &'a RenderTexture) -> Self {
+        Self {
+            view: &texture.view,
+            size: (texture.width(), texture.height()),
+        }
+    }
 }
 
 /// The static configuration of the [`Renderer`].
@@ -37,12 +67,49 @@ pub struct RendererConfig {
     /// Providing a [`RenderTarget`] that has an output format different from this one will likely
     /// result in a panic.
     pub output_format: TextureFormat,
+    /// The number of samples per pixel used for multisample anti-aliasing.
+    ///
+    /// A value of `1` disables multisampling. When the requested value is not supported by the
+    /// GPU, the renderer falls back to `1`.
+    ///
+    /// Like the other fields of this struct, changing this value requires re-creating the
+    /// [`Renderer`].
+    pub sample_count: u32,
+    /// The length of the sides of the shadow map, in pixels.
+    ///
+    /// Higher values produce sharper shadows at the cost of memory and fill rate. The value is
+    /// clamped to the maximum texture size supported by the GPU.
+    ///
+    /// See [`RendererConfig::DEFAULT_SHADOW_MAP_RESOLUTION`] for a reasonable default.
+    pub shadow_map_resolution: u32,
+    /// The number of cascades the shadow map of the sun is split into.
+    ///
+    /// Each cascade covers a slice of the view frustum and has its own
+    /// [`shadow_map_resolution`](Self::shadow_map_resolution). The value is clamped between `1`
+    /// and [`MAX_SHADOW_CASCADES`].
+    ///
+    /// See [`RendererConfig::DEFAULT_SHADOW_CASCADE_COUNT`] for a reasonable default.
+    pub shadow_cascade_count: u32,
+}
+
+impl RendererConfig {
+    /// The default value of [`RendererConfig::shadow_map_resolution`].
+    pub const DEFAULT_SHADOW_MAP_RESOLUTION: u32 = 2048;
+
+    /// The default value of [`RendererConfig::shadow_cascade_count`].
+    pub const DEFAULT_SHADOW_CASCADE_COUNT: u32 = 3;
 }
 
 /// Contains the state required to render things using GPU resources.
 pub struct Renderer {
     /// A reference to the GPU.
     gpu: Arc<Gpu>,
+    /// The format of the output image of the renderer.
+    output_format: TextureFormat,
+    /// The scale of the resolution at which the scene is rendered.
+    ///
+    /// See [`Renderer::set_render_scale`].
+    render_scale: f32,
 
     /// The pipeline responsible for rendering the skybox.
     skybox_pipeline: SkyboxPipeline,
@@ -50,6 +117,8 @@ pub struct Renderer {
     quad_pipeline: QuadPipeline,
     /// The pipeline responsible for rendering lines.
     line_pipeline: LinePipeline,
+    /// The pipeline responsible for rendering particles.
+    particle_pipeline: ParticlePipeline,
 
     /// The pipeline responsible for rendering fog.
     fog_pipeline: FogPipeline,
@@ -60,34 +129,172 @@ pub struct Renderer {
     ui_sprite_pipeline: UiSpritePipeline,
     /// The pipeline responsible for rendering sprites in the UI using the global texture atlas.
     ui_atlas_sprite_pipeline: UiAtlasSpritePipeline,
+
+    /// The pipeline responsible for upscaling the scene when the render scale is less than `1.0`.
+    blit_pipeline: BlitPipeline,
 }
 
 impl Renderer {
     /// Creates a new [`Renderer`] instance.
     pub fn new(gpu: Arc<Gpu>, config: RendererConfig) -> Self {
-        let quad_pipeline = QuadPipeline::new(&gpu, config.output_format);
-        let skybox_pipeline = SkyboxPipeline::new(&gpu, config.output_format);
-        let line_pipeline = LinePipeline::new(&gpu, config.output_format);
-        let fog_pipeline = FogPipeline::new(&gpu, config.output_format);
-        let text_pipeline = TextPipeline::new(&gpu, config.output_format);
-        let ui_sprite_pipeline = UiSpritePipeline::new(&gpu, config.output_format);
-        let ui_atlas_sprite_pipeline = UiAtlasSpritePipeline::new(&gpu, config.output_format);
+        let output_format = config.output_format;
+        let sample_count = validate_sample_count(&gpu, output_format, config.sample_count);
+        let shadow_map_resolution = config
+            .shadow_map_resolution
+            .clamp(1, gpu.limits.max_texture_dimension_2d);
+        let shadow_cascade_count = config
+            .shadow_cascade_count
+            .clamp(1, MAX_SHADOW_CASCADES as u32);
+
+        {
+            let mut res = gpu.resources.write();
+            res.set_sample_count(&gpu.device, sample_count, output_format);
+            res.set_shadow_map(&gpu.device, shadow_map_resolution, shadow_cascade_count);
+        }
+
+        let quad_pipeline = gpu.error_scope("quad pipeline", || {
+            QuadPipeline::new(&gpu, output_format, sample_count)
+        });
+        let skybox_pipeline = gpu.error_scope("skybox pipeline", || {
+            SkyboxPipeline::new(&gpu, output_format, sample_count)
+        });
+        let line_pipeline = gpu.error_scope("line pipeline", || {
+            LinePipeline::new(&gpu, output_format, sample_count)
+        });
+        let particle_pipeline = gpu.error_scope("particle pipeline", || {
+            ParticlePipeline::new(&gpu, output_format, sample_count)
+        });
+        let fog_pipeline = gpu.error_scope("fog pipeline", || {
+            FogPipeline::new(&gpu, output_format, sample_count)
+        });
+        let text_pipeline =
+            gpu.error_scope("text pipeline", || TextPipeline::new(&gpu, output_format));
+        let ui_sprite_pipeline = gpu.error_scope("ui sprite pipeline", || {
+            UiSpritePipeline::new(&gpu, output_format)
+        });
+        let ui_atlas_sprite_pipeline = gpu.error_scope("ui atlas sprite pipeline", || {
+            UiAtlasSpritePipeline::new(&gpu, output_format)
+        });
+        let blit_pipeline =
+            gpu.error_scope("blit pipeline", || BlitPipeline::new(&gpu, output_format));
 
         Self {
             gpu,
+            output_format,
+            render_scale: 1.0,
             quad_pipeline,
             skybox_pipeline,
             line_pipeline,
+            particle_pipeline,
             fog_pipeline,
             text_pipeline,
             ui_sprite_pipeline,
             ui_atlas_sprite_pipeline,
+            blit_pipeline,
         }
     }
 
+    /// The minimum value accepted by [`Renderer::set_render_scale`].
+    pub const MIN_RENDER_SCALE: f32 = 0.25;
+
+    /// The maximum value accepted by [`Renderer::set_render_scale`].
+    pub const MAX_RENDER_SCALE: f32 = 1.0;
+
+    /// Sets the scale of the resolution at which the scene is rendered, relative to the size of
+    /// the render target.
+    ///
+    /// The world, the skybox and the fog are rendered at the scaled resolution and upscaled to
+    /// the render target. The UI is always rendered at the native resolution of the render
+    /// target.
+    ///
+    /// # Remarks
+    ///
+    /// The provided value is clamped between [`Renderer::MIN_RENDER_SCALE`] and
+    /// [`Renderer::MAX_RENDER_SCALE`].
+    pub fn set_render_scale(&mut self, scale: f32) {
+        let scale = scale.clamp(Self::MIN_RENDER_SCALE, Self::MAX_RENDER_SCALE);
+
+        if scale == self.render_scale {
+            return;
+        }
+
+        self.render_scale = scale;
+        self.gpu
+            .resources
+            .write()
+            .set_render_scale(&self.gpu.device, scale);
+    }
+
+    /// Returns the scale of the resolution at which the scene is rendered.
+    ///
+    /// See [`Renderer::set_render_scale`].
+    #[inline]
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Sets whether the opaque geometry of the world should be rendered as wireframe.
+    ///
+    /// Quads are rendered as triangle strips, meaning that the wireframe shows the two triangles
+    /// that make up each quad.
+    ///
+    /// # Remarks
+    ///
+    /// This requires the `POLYGON_MODE_LINE` feature, which is not available on every GPU (and
+    /// never on web). When it is missing, enabling the wireframe mode logs a warning and does
+    /// nothing.
+    pub fn set_wireframe(&mut self, yes: bool) {
+        if yes && !self.quad_pipeline.supports_wireframe() {
+            bns_log::warning!("wireframe rendering is not supported by the GPU");
+            return;
+        }
+
+        self.quad_pipeline.set_wireframe(yes);
+    }
+
+    /// Returns whether the opaque geometry of the world is currently rendered as wireframe.
+    #[inline]
+    pub fn wireframe(&self) -> bool {
+        self.quad_pipeline.wireframe()
+    }
+
     /// Returns a reference to the underlying [`Gpu`] instance.
     #[inline]
     pub fn gpu(&self) -> &Arc<Gpu> {
         &self.gpu
     }
 }
+
+/// Checks whether the GPU supports rendering with `sample_count` samples per pixel to both the
+/// output format and the depth buffer.
+///
+/// # Returns
+///
+/// `sample_count` if it is supported, or `1` otherwise.
+fn validate_sample_count(gpu: &Gpu, output_format: TextureFormat, sample_count: u32) -> u32 {
+    if sample_count == 1 {
+        return 1;
+    }
+
+    // Without this feature, only the sample counts guaranteed by WebGPU (1 and 4) may be used.
+    let adapter_specific = gpu
+        .device
+        .features()
+        .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+    let supported = |format: TextureFormat| {
+        gpu.adapter
+            .get_texture_format_features(format)
+            .flags
+            .sample_count_supported(sample_count)
+    };
+
+    if (sample_count == 4 || adapter_specific)
+        && supported(output_format)
+        && supported(crate::DEPTH_FORMAT)
+    {
+        sample_count
+    } else {
+        bns_log::warning!("MSAA x{sample_count} is not supported by the GPU, disabling it");
+        1
+    }
+}
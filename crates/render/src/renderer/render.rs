@@ -1,11 +1,91 @@
 use crate::data::{RenderData, Ui};
+use crate::shaders::common::CommonResources;
 use crate::{RenderTarget, Renderer};
 
+/// The optional passes and draw calls the renderer records for a frame.
+///
+/// This is computed once per frame from the [`RenderData`] and the current state of the
+/// [`CommonResources`], and used both to record the frame and to count its draw calls (see
+/// [`RenderData::stats`]).
+pub(crate) struct FramePlan {
+    /// The number of shadow map cascades to render, or `0` if shadows are disabled.
+    pub shadow_cascades: usize,
+    /// Whether the skybox is drawn.
+    pub skybox: bool,
+    /// Whether the lines are drawn.
+    pub lines: bool,
+    /// Whether the particles are drawn.
+    pub particles: bool,
+    /// Whether the fog is drawn.
+    pub fog: bool,
+    /// Whether the scene is rendered at a lower resolution and upscaled to the render target.
+    pub upscale: bool,
+    /// Whether the UI is drawn.
+    pub ui: bool,
+}
+
+impl FramePlan {
+    /// Computes the plan of a frame.
+    pub fn new(data: &RenderData, res: &CommonResources) -> Self {
+        let shadow_cascades = if data.shadows_enabled {
+            (data.uniforms.shadow_cascade_count as usize).clamp(1, res.shadow_map_cascades.len())
+        } else {
+            0
+        };
+
+        Self {
+            shadow_cascades,
+            skybox: data.skybox_enabled,
+            lines: data.lines_enabled && !data.lines.is_empty(),
+            particles: data.particles_enabled && !data.particles.is_empty(),
+            fog: data.fog_enabled,
+            upscale: res.scaled_color.is_some(),
+            ui: data.ui_enabled,
+        }
+    }
+
+    /// Returns the number of draw calls that recording `data` according to this plan issues.
+    pub fn draw_calls(&self, data: &RenderData) -> u32 {
+        let opaque = data.quads.opaque_buffer_count() as u32;
+        let transparent = data.quads.transparent_buffer_count() as u32;
+
+        let mut count = opaque * self.shadow_cascades as u32 + opaque + transparent;
+        count += self.skybox as u32;
+        count += self.lines as u32;
+        count += self.particles as u32;
+        count += self.fog as u32;
+        count += self.upscale as u32;
+        if self.ui {
+            count += data.ui.len() as u32;
+        }
+        count
+    }
+}
+
 impl Renderer {
     /// Renders to the provided [`RenderTarget`] using the provided [`RenderData`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the size of `target` differs from the size last reported
+    /// through [`Gpu::notify_resized`](crate::Gpu::notify_resized).
     #[profiling::function]
     pub fn render(&mut self, target: RenderTarget, data: &mut RenderData) {
-        let res = self.gpu.resources.read();
+        let gpu = self.gpu.clone();
+        gpu.error_scope("render", || self.render_inner(target, data));
+    }
+
+    /// The actual implementation of [`Renderer::render`].
+    fn render_inner(&mut self, target: RenderTarget, data: &mut RenderData) {
+        let gpu = self.gpu.clone();
+        let res = gpu.resources.read();
+        let plan = FramePlan::new(data, &res);
+
+        assert_eq!(
+            target.size,
+            res.size(),
+            "the render target must have the size reported through `Gpu::notify_resized`",
+        );
 
         self.gpu.queue.write_buffer(
             &res.frame_uniforms_buffer,
@@ -21,48 +101,135 @@ impl Renderer {
                 label: Some("Render Command Encoder"),
             });
 
-        self.quad_pipeline.prepare(&self.gpu, &data.quads);
+        // When the render scale is less than 1, the scene is rendered to an intermediate target
+        // that's upscaled to the actual render target before rendering the UI.
+        let scene_target = match &res.scaled_color {
+            Some((view, _)) => view,
+            None => target.view,
+        };
+
+        self.render_scene(&mut encoder, &res, &plan, scene_target, data);
 
-        if data.shadows_enabled {
+        if let Some((_, scaled_color)) = &res.scaled_color {
             // ========================================
-            // Shadow Map
+            // Upscaling
             // ========================================
 
             let mut rp = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Shadow Map Render Pass"),
-                color_attachments: &[],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
+                label: Some("Upscaling Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                         store: wgpu::StoreOp::Store,
-                    }),
-                    stencil_ops: None,
-                    view: &res.shadow_map,
-                }),
+                    },
+                    resolve_target: None,
+                    view: target.view,
+                })],
+                depth_stencil_attachment: None,
                 occlusion_query_set: None,
                 timestamp_writes: None,
             });
 
-            rp.set_bind_group(0, &res.frame_uniforms_bind_group, &[]);
-
-            self.quad_pipeline.render_shadows(&mut rp, &data.quads);
+            self.blit_pipeline.render(&mut rp, scaled_color);
 
             drop(rp);
         }
 
+        if plan.ui {
+            self.render_ui(&mut encoder, &res, target.view, data);
+        }
+
+        // ========================================
+        // Submit
+        // ========================================
+
+        let iter = self
+            .gpu
+            .iter_temp_command_encoders()
+            .map(|e| {
+                std::mem::replace(
+                    &mut *e.lock(),
+                    self.gpu
+                        .device
+                        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                            label: Some("Temporary Command Encoder"),
+                        }),
+                )
+                .finish()
+            })
+            .chain(std::iter::once(encoder.finish()));
+
+        self.gpu.queue.submit(iter);
+    }
+
+    /// Records the passes that render the scene (the world, the skybox and the fog) to the
+    /// provided target.
+    ///
+    /// The target has the size of the scaled render target, as returned by
+    /// `CommonResources::scaled_size`.
+    fn render_scene(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        res: &CommonResources,
+        plan: &FramePlan,
+        target: &wgpu::TextureView,
+        data: &RenderData,
+    ) {
+        self.quad_pipeline.prepare(&self.gpu, &data.quads);
+
+        if plan.shadow_cascades > 0 {
+            // ========================================
+            // Shadow Map
+            // ========================================
+
+            // Each cascade of the shadow map is rendered in its own pass.
+            let cascades = &res.shadow_map_cascades[..plan.shadow_cascades];
+            for (cascade, view) in cascades.iter().enumerate() {
+                let mut rp = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Shadow Map Render Pass"),
+                    color_attachments: &[],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: wgpu::StoreOp::Store,
+                        }),
+                        stencil_ops: None,
+                        view,
+                    }),
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+
+                rp.set_bind_group(0, &res.frame_uniforms_bind_group, &[]);
+
+                self.quad_pipeline
+                    .render_shadows(&mut rp, &data.quads, cascade as u32);
+
+                drop(rp);
+            }
+        }
+
         // ========================================
         // Base Scene
         // ========================================
 
+        // When multisampling is enabled, the scene is rendered to the multisampled color target
+        // and resolved to the actual render target at the end of the pass. The following passes
+        // work directly with the resolved image.
+        let (view, resolve_target, store) = match &res.multisampled_color {
+            Some(multisampled) => (multisampled, Some(target), wgpu::StoreOp::Discard),
+            None => (target, None, wgpu::StoreOp::Store),
+        };
+
         let mut rp = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Main Render Pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                    store: wgpu::StoreOp::Store,
+                    load: wgpu::LoadOp::Clear(data.clear_color.into()),
+                    store,
                 },
-                resolve_target: None,
-                view: target.view,
+                resolve_target,
+                view,
             })],
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                 depth_ops: Some(wgpu::Operations {
@@ -81,9 +248,17 @@ impl Renderer {
         rp.set_bind_group(2, &res.texture_atlas_bind_group, &[]);
         rp.set_bind_group(3, &res.shadow_map_bind_group, &[]);
 
-        self.skybox_pipeline.render(&self.gpu, &mut rp);
+        if plan.skybox {
+            self.skybox_pipeline.render(&self.gpu, &mut rp);
+        }
         self.quad_pipeline.render(&mut rp, &data.quads);
-        self.line_pipeline.render(&self.gpu, &mut rp, &data.lines);
+        if plan.lines {
+            self.line_pipeline.render(&self.gpu, &mut rp, &data.lines);
+        }
+        if plan.particles {
+            self.particle_pipeline
+                .render(&self.gpu, &mut rp, &data.particles);
+        }
 
         drop(rp);
 
@@ -99,7 +274,7 @@ impl Renderer {
                     store: wgpu::StoreOp::Store,
                 },
                 resolve_target: None,
-                view: target.view,
+                view: target,
             })],
             depth_stencil_attachment: None,
             occlusion_query_set: None,
@@ -109,12 +284,23 @@ impl Renderer {
         rp.set_bind_group(0, &res.frame_uniforms_bind_group, &[]);
         rp.set_bind_group(1, &res.depth_buffer_bind_group, &[]);
 
-        if data.fog_enabled {
+        if plan.fog {
             self.fog_pipeline.render(&self.gpu, &mut rp);
         }
 
         drop(rp);
+    }
 
+    /// Records the pass that renders the UI to the provided target.
+    ///
+    /// Unlike the scene, the UI is always rendered at the native resolution of the render target.
+    fn render_ui(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        res: &CommonResources,
+        target: &wgpu::TextureView,
+        data: &RenderData,
+    ) {
         // ========================================
         // UI
         // ========================================
@@ -127,7 +313,7 @@ impl Renderer {
                     store: wgpu::StoreOp::Store,
                 },
                 resolve_target: None,
-                view: target.view,
+                view: target,
             })],
             depth_stencil_attachment: None,
             occlusion_query_set: None,
@@ -146,33 +332,41 @@ impl Renderer {
                 }
                 Ui::AtlasSprite(instances) => {
                     self.ui_atlas_sprite_pipeline
-                        .render(&res, &mut rp, *instances);
+                        .render(res, &mut rp, *instances);
                 }
             }
         }
 
         drop(rp);
+    }
+}
 
-        // ========================================
-        // Submit
-        // ========================================
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Gpu;
 
-        let iter = self
-            .gpu
-            .iter_temp_command_encoders()
-            .map(|e| {
-                std::mem::replace(
-                    &mut *e.lock(),
-                    self.gpu
-                        .device
-                        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                            label: Some("Temporary Command Encoder"),
-                        }),
-                )
-                .finish()
-            })
-            .chain(std::iter::once(encoder.finish()));
+    #[test]
+    fn disabled_passes_are_skipped() {
+        let Some(gpu) = crate::gpu::block_on(Gpu::new_headless()) else {
+            return;
+        };
 
-        self.gpu.queue.submit(iter);
+        let mut data = RenderData::new(&gpu);
+        data.lines.push(bytemuck::Zeroable::zeroed());
+        data.push_particle(bytemuck::Zeroable::zeroed());
+
+        let plan = FramePlan::new(&data, &gpu.resources.read());
+        assert!(plan.skybox && plan.lines && plan.particles && plan.fog && plan.ui);
+
+        data.fog_enabled = false;
+        data.lines_enabled = false;
+        data.ui_enabled = false;
+
+        let plan = FramePlan::new(&data, &gpu.resources.read());
+        assert!(!plan.fog);
+        assert!(!plan.lines);
+        assert!(!plan.ui);
+        assert!(plan.skybox && plan.particles);
     }
 }
@@ -133,12 +133,88 @@ impl<T: NoUninit> DynamicVertexBuffer<T> {
         self.len = new_len;
     }
 
-    /// Edits the buffer at the provided index.
-    pub fn edit(&mut self, at: u32, data: &[T]) {
+    /// Overwrites the elements of the buffer starting at `offset` with the provided data.
+    ///
+    /// Unlike [`extend`](Self::extend), this never re-allocates the buffer, and the elements
+    /// outside of the written range are left untouched. This makes it possible to update a
+    /// small part of a large buffer without uploading the whole thing again.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the written range goes past the current length of the buffer.
+    #[track_caller]
+    pub fn write_at(&mut self, offset: usize, data: &[T]) {
+        assert!(
+            offset + data.len() <= self.len as usize,
+            "attempted to write past the end of the buffer (offset: {offset}, count: {}, len: {})",
+            data.len(),
+            self.len,
+        );
+
+        if data.is_empty() {
+            return;
+        }
+
         self.gpu.queue.write_buffer(
             &self.buffer,
-            at as u64 * size_of::<T>() as u64,
+            offset as wgpu::BufferAddress * size_of::<T>() as wgpu::BufferAddress,
             bytemuck::cast_slice(data),
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads the content of the provided buffer back from the GPU.
+    fn read_back(buffer: &DynamicVertexBuffer<u32>) -> Vec<u32> {
+        let gpu = &buffer.gpu;
+        let size = buffer.len as u64 * size_of::<u32>() as u64;
+
+        let staging = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_buffer_to_buffer(&buffer.buffer, 0, &staging, 0, size);
+        gpu.queue.submit(Some(encoder.finish()));
+
+        let slice = staging.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        gpu.device.poll(wgpu::Maintain::Wait);
+        receiver.recv().unwrap().unwrap();
+
+        let view = slice.get_mapped_range();
+        let data = bytemuck::cast_slice::<u8, u32>(&view).to_vec();
+        drop(view);
+        staging.unmap();
+        data
+    }
+
+    #[test]
+    fn write_at_only_touches_the_written_range() {
+        let Some(gpu) = crate::gpu::block_on(Gpu::new_headless()) else {
+            return;
+        };
+
+        let mut buffer = DynamicVertexBuffer::new_with_data(Arc::new(gpu), &[0u32, 1, 2, 3, 4, 5]);
+        buffer.write_at(2, &[20, 30]);
+        buffer.write_at(6, &[]);
+        assert_eq!(read_back(&buffer), [0, 1, 20, 30, 4, 5]);
+
+        let past_end = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            buffer.write_at(5, &[50, 60]);
+        }));
+        assert!(past_end.is_err());
+        assert_eq!(read_back(&buffer), [0, 1, 20, 30, 4, 5]);
+    }
+}
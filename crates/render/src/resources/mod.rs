@@ -6,6 +6,9 @@ pub use dynamic_vertex_buffer::*;
 mod texture;
 pub use texture::*;
 
+mod render_texture;
+pub use render_texture::*;
+
 use std::marker::PhantomData;
 
 /// A slice into a [`VertexBuffer`].
@@ -18,3 +21,17 @@ pub struct VertexBufferSlice<'a, T> {
     /// The marker that includes the type of the vertices.
     pub(crate) marker: PhantomData<&'a [T]>,
 }
+
+impl<'a, T> VertexBufferSlice<'a, T> {
+    /// Returns the number of vertices in the slice.
+    #[inline]
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    /// Returns whether the slice is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
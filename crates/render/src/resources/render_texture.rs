@@ -0,0 +1,62 @@
+use crate::{Gpu, TextureFormat};
+
+/// An owned texture that can be rendered to.
+///
+/// A [`RenderTarget`](crate::RenderTarget) can be created from this texture using
+/// [`RenderTarget::from_texture`](crate::RenderTarget::from_texture), allowing frames to be
+/// rendered off-screen instead of to a [`Surface`](crate::Surface).
+pub struct RenderTexture {
+    /// The underlying texture.
+    pub(crate) texture: wgpu::Texture,
+    /// A view into the whole texture.
+    pub(crate) view: wgpu::TextureView,
+}
+
+impl RenderTexture {
+    /// Creates a new [`RenderTexture`] instance.
+    ///
+    /// # Remarks
+    ///
+    /// The [`Renderer`](crate::Renderer) can only render to textures that have the size last
+    /// reported through [`Gpu::notify_resized`], and `format` must match the output format it
+    /// was created with.
+    pub fn new(gpu: &Gpu, width: u32, height: u32, format: TextureFormat) -> Self {
+        let texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Render Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self { texture, view }
+    }
+
+    /// Returns the width of the texture, in pixels.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.texture.width()
+    }
+
+    /// Returns the height of the texture, in pixels.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.texture.height()
+    }
+
+    /// Returns the format of the texture.
+    #[inline]
+    pub fn format(&self) -> TextureFormat {
+        self.texture.format()
+    }
+}
@@ -0,0 +1,30 @@
+// The structure that's interpolated accross the trangles
+// generated by the vertex shader.
+struct Interpolator {
+    // The position of the vertex in clip-space coordinates.
+    @builtin(position) position: vec4<f32>,
+    // The texture coordinates of the vertex.
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+) -> Interpolator {
+    let uv = vec2<f32>(f32(vertex_index & 1u), 1.0 - f32(vertex_index >> 1u));
+
+    var out: Interpolator;
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    out.tex_coords = vec2<f32>(uv.x, 1.0 - uv.y);
+    return out;
+}
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+@fragment
+fn fs_main(in: Interpolator) -> @location(0) vec4<f32> {
+    return textureSample(source, source_sampler, in.tex_coords);
+}
@@ -0,0 +1,93 @@
+use crate::Gpu;
+
+/// A simple render pipeline that copies a texture to the whole render target, scaling it as
+/// needed.
+///
+/// This is used to upscale the scene when it is rendered at a lower resolution than the render
+/// target.
+pub struct BlitPipeline {
+    /// The pipeline responsible for the copy.
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl BlitPipeline {
+    /// Creates a new [`BlitPipeline`] instance.
+    pub fn new(gpu: &Gpu, output_format: wgpu::TextureFormat) -> Self {
+        let pipeline = create_shader(gpu, output_format);
+        Self { pipeline }
+    }
+
+    /// Copies the texture bound by `source` to the render target.
+    ///
+    /// `source` must have been created from the `scaled_color_layout` of the common resources.
+    #[profiling::function]
+    pub fn render<'res>(
+        &'res self,
+        rp: &mut wgpu::RenderPass<'res>,
+        source: &'res wgpu::BindGroup,
+    ) {
+        rp.set_pipeline(&self.pipeline);
+        rp.set_bind_group(0, source, &[]);
+        rp.draw(0..4, 0..1);
+    }
+}
+
+/// Creates a pipeline that's responsible for copying a texture to the render target.
+///
+/// # Arrachments
+///
+/// This pipeline expects a single color attachment. Its format must be of `output_format`.
+fn create_shader(gpu: &Gpu, output_format: wgpu::TextureFormat) -> wgpu::RenderPipeline {
+    let res = gpu.resources.read();
+
+    let shader_module = gpu
+        .device
+        .create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Blit Shader Module"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("blit.wgsl").into()),
+        });
+
+    let pipeline_layout = gpu
+        .device
+        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Blit Pipeline Layout"),
+            bind_group_layouts: &[&res.scaled_color_layout],
+            push_constant_ranges: &[],
+        });
+
+    gpu.device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Blit Pipeline"),
+            vertex: wgpu::VertexState {
+                buffers: &[],
+                entry_point: "vs_main",
+                module: &shader_module,
+            },
+            fragment: Some(wgpu::FragmentState {
+                entry_point: "fs_main",
+                module: &shader_module,
+                targets: &[Some(wgpu::ColorTargetState {
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    format: output_format,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            depth_stencil: None,
+            layout: Some(&pipeline_layout),
+            multisample: wgpu::MultisampleState {
+                alpha_to_coverage_enabled: false,
+                count: 1,
+                mask: !0,
+            },
+            multiview: None,
+            primitive: wgpu::PrimitiveState {
+                conservative: false,
+                cull_mode: None,
+                front_face: wgpu::FrontFace::Ccw,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                strip_index_format: None,
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                unclipped_depth: false,
+            },
+        })
+}
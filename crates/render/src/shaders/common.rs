@@ -3,7 +3,7 @@ use std::mem::size_of;
 
 use bitflags::bitflags;
 use bytemuck::{Pod, Zeroable};
-use glam::{Mat4, Vec2, Vec3};
+use glam::{Mat4, Vec2, Vec3, Vec4};
 use wgpu::util::DeviceExt;
 use wgpu::TextureFormat;
 
@@ -23,6 +23,36 @@ bitflags! {
 unsafe impl Zeroable for FrameFlags {}
 unsafe impl Pod for FrameFlags {}
 
+/// The maximum number of cascades that the shadow map of the sun can be split into.
+///
+/// This is the length of [`FrameUniforms::light_transforms`]. The shaders hardcode that length
+/// as well, meaning that they must be updated if this value changes.
+pub const MAX_SHADOW_CASCADES: usize = 4;
+
+/// The maximum number of point lights that can affect a single frame.
+///
+/// This is the length of [`FrameUniforms::point_lights`]. The shaders hardcode that length as
+/// well, meaning that they must be updated if this value changes.
+pub const MAX_POINT_LIGHTS: usize = 16;
+
+/// A light that emits in every direction from a single point.
+#[derive(Debug, Default, Clone, Copy, Zeroable, Pod)]
+#[repr(C)]
+pub struct PointLight {
+    /// The position of the light, in world-space coordinates.
+    pub position: Vec3,
+    /// The distance at which the light stops having any effect.
+    pub radius: f32,
+    /// The color of the light.
+    ///
+    /// The alpha component is ignored.
+    pub color: Color,
+    /// Padding required to round the size of the structure up to a multiple of 16 bytes, as
+    /// expected by arrays in uniform buffers.
+    #[doc(hidden)]
+    pub _padding: [u32; 3],
+}
+
 /// Information about a texture atlas to be created.
 #[derive(Clone, Debug)]
 pub struct TextureAtlasConfig<'a> {
@@ -38,6 +68,12 @@ pub struct TextureAtlasConfig<'a> {
     pub mip_level_count: u32,
     /// The format of the textures in the atlas.
     pub format: TextureFormat,
+    /// The distance, in texels, by which the sampled area of each texture is moved inwards on
+    /// every side.
+    ///
+    /// This prevents texels from the opposite edge of a texture (or from the neighboring texture
+    /// at lower mip levels) from bleeding into the sampled result.
+    pub uv_inset: f32,
 }
 
 impl<'a> TextureAtlasConfig<'a> {
@@ -50,8 +86,38 @@ impl<'a> TextureAtlasConfig<'a> {
             count: COUNT as u32,
             mip_level_count: 1,
             format: TextureFormat::R8Unorm,
+            uv_inset: 0.0,
         }
     }
+
+    /// Computes the UV rectangle that's actually sampled for each texture of the atlas,
+    /// taking the configured [`uv_inset`] into account.
+    ///
+    /// Because every texture of the atlas has the same size, the returned rectangle is the same
+    /// for all of them.
+    ///
+    /// [`uv_inset`]: TextureAtlasConfig::uv_inset
+    pub fn uv_rect(&self) -> AtlasUvRect {
+        let inset = Vec2::new(
+            self.uv_inset / self.width as f32,
+            self.uv_inset / self.height as f32,
+        );
+
+        AtlasUvRect {
+            min: inset,
+            max: Vec2::ONE - inset,
+        }
+    }
+}
+
+/// The area of a texture that's sampled by the shaders, in normalized texture coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Zeroable, Pod)]
+#[repr(C)]
+pub struct AtlasUvRect {
+    /// The top-left corner of the rectangle.
+    pub min: Vec2,
+    /// The bottom-right corner of the rectangle.
+    pub max: Vec2,
 }
 
 /// The frame uniforms passed to shaders.
@@ -66,8 +132,15 @@ pub struct FrameUniforms {
     pub view: Mat4,
     /// The inverse of `view`.
     pub inverse_view: Mat4,
-    /// Transforms world-space coordinates to light-space coordinates (the sun).
-    pub light_transform: Mat4,
+    /// Transforms world-space coordinates to light-space coordinates (the sun), for each
+    /// cascade of the shadow map.
+    ///
+    /// Only the first `shadow_cascade_count` matrices are used.
+    pub light_transforms: [Mat4; MAX_SHADOW_CASCADES],
+    /// The view-space depth at which each cascade of the shadow map ends.
+    ///
+    /// Fragments that are further than the last used cascade receive no shadows.
+    pub shadow_cascade_splits: Vec4,
     /// The resolution of the render target.
     pub resolution: Vec2,
     /// The density of the fog.
@@ -91,6 +164,29 @@ pub struct FrameUniforms {
     /// 0 means that the fog takes the whole sky. The larger the value, the lower (compared to
     /// the horizon) the fog will be.
     pub fog_height: f32,
+    /// The ambient light color.
+    ///
+    /// This color is added to the lighting of every quad, ensuring that even unlit surfaces
+    /// aren't completely black. The alpha component is ignored.
+    pub ambient_color: Color,
+    /// The number of cascades of the shadow map that are actually used.
+    ///
+    /// This must be between `1` and the number of cascades the renderer was created with.
+    pub shadow_cascade_count: u32,
+    /// The number of point lights that are actually used.
+    ///
+    /// This must be at most [`MAX_POINT_LIGHTS`].
+    pub point_light_count: u32,
+    /// The apparent brightness of the sun in the sky, between `0.0` and `1.0`.
+    ///
+    /// This only affects the sun drawn by the skybox, not the lighting of the world.
+    pub sun_intensity: f32,
+    /// The point lights that affect the frame.
+    ///
+    /// Only the first `point_light_count` lights are used. See [`RenderData::push_light`].
+    ///
+    /// [`RenderData::push_light`]: crate::data::RenderData::push_light
+    pub point_lights: [PointLight; MAX_POINT_LIGHTS],
 }
 
 /// Some resources commonly used through the renderer.
@@ -123,7 +219,33 @@ pub struct CommonResources {
     /// The bind group used to bind the shadow map to the shaders.
     pub shadow_map_bind_group: wgpu::BindGroup,
     /// The shadow map texture.
+    ///
+    /// This is a texture array with one layer per cascade.
     pub shadow_map: wgpu::TextureView,
+    /// A view over each layer of the shadow map, used to render the individual cascades.
+    pub shadow_map_cascades: Vec<wgpu::TextureView>,
+    /// The number of samples per pixel of the depth buffer and of the multisampled color
+    /// target.
+    pub sample_count: u32,
+    /// The multisampled color target that the scene is rendered to before being resolved to the
+    /// actual render target.
+    ///
+    /// This is [`None`] when `sample_count` is `1`.
+    pub multisampled_color: Option<wgpu::TextureView>,
+    /// The bind group layout used to bind `scaled_color` to the shaders.
+    pub scaled_color_layout: wgpu::BindGroupLayout,
+    /// The color target that the scene is rendered to when the render scale is less than `1.0`,
+    /// along with the bind group used to upscale it to the actual render target.
+    ///
+    /// This is [`None`] when the scene is rendered at the resolution of the render target.
+    pub scaled_color: Option<(wgpu::TextureView, wgpu::BindGroup)>,
+    /// The format of `multisampled_color` and `scaled_color`.
+    multisampled_format: TextureFormat,
+    /// The current size of the render target.
+    size: (u32, u32),
+    /// The scale of the resolution at which the scene is rendered, relative to the size of the
+    /// render target.
+    render_scale: f32,
 }
 
 impl CommonResources {
@@ -141,13 +263,15 @@ impl CommonResources {
         let frame_uniforms_layout = create_frame_uniforms_layout(device);
         let (frame_uniforms_buffer, frame_uniforms_bind_group) =
             create_frame_uniforms_buffer(device, &frame_uniforms_layout);
-        let depth_buffer_layout = create_depth_buffer_layout(device);
+        let depth_buffer_layout = create_depth_buffer_layout(device, 1);
         let linear_sampler = create_linear_sampler(device);
         let (depth_buffer, depth_buffer_bind_group) =
-            create_depth_buffer(device, &depth_buffer_layout, &linear_sampler, 1, 1);
+            create_depth_buffer(device, &depth_buffer_layout, &linear_sampler, 1, 1, 1);
         let texture_layout = create_texture_layout(device);
         let shadow_map_layout = create_shadow_map_layout(device);
-        let (shadow_map, shadow_map_bind_group) = create_shadow_map(device, &shadow_map_layout);
+        let scaled_color_layout = create_scaled_color_layout(device);
+        let (shadow_map, shadow_map_cascades, shadow_map_bind_group) =
+            create_shadow_map(device, &shadow_map_layout, 1, 1);
 
         Self {
             pixel_sampler,
@@ -163,7 +287,15 @@ impl CommonResources {
             texture_layout,
             shadow_map_bind_group,
             shadow_map,
+            shadow_map_cascades,
             shadow_map_layout,
+            sample_count: 1,
+            multisampled_color: None,
+            scaled_color_layout,
+            scaled_color: None,
+            multisampled_format: TextureFormat::Rgba8UnormSrgb,
+            size: (1, 1),
+            render_scale: 1.0,
         }
     }
 
@@ -183,15 +315,102 @@ impl CommonResources {
         );
     }
 
+    /// Returns the current size of the render target.
+    #[inline]
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Returns the size at which the scene is rendered, taking the render scale into account.
+    pub fn scaled_size(&self) -> (u32, u32) {
+        let (width, height) = self.size;
+        let scale = |x: u32| ((x as f32 * self.render_scale).round() as u32).clamp(1, x.max(1));
+        (scale(width), scale(height))
+    }
+
+    /// Sets the scale of the resolution at which the scene is rendered, relative to the size of
+    /// the render target.
+    ///
+    /// This re-creates the depth buffer and the intermediate color targets.
+    pub fn set_render_scale(&mut self, device: &wgpu::Device, scale: f32) {
+        self.render_scale = scale;
+        self.recreate_render_targets(device);
+    }
+
     /// Notifies this [`CommonResources`] that the render target has been resized.
     pub fn notify_resized(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.size = (width, height);
+        self.recreate_render_targets(device);
+    }
+
+    /// Sets the number of samples per pixel used when rendering the scene.
+    ///
+    /// This re-creates the depth buffer (along with its bind group layout) and the multisampled
+    /// color target, whose format will be `format`.
+    ///
+    /// # Remarks
+    ///
+    /// Any pipeline that was created using the previous depth buffer layout must be re-created.
+    pub fn set_sample_count(
+        &mut self,
+        device: &wgpu::Device,
+        sample_count: u32,
+        format: TextureFormat,
+    ) {
+        self.sample_count = sample_count;
+        self.multisampled_format = format;
+        self.depth_buffer_layout = create_depth_buffer_layout(device, sample_count);
+        self.recreate_render_targets(device);
+    }
+
+    /// Re-creates the shadow map with the provided resolution and number of cascades.
+    ///
+    /// Each cascade of the shadow map is a square texture, `resolution` being the length of its
+    /// sides.
+    pub fn set_shadow_map(&mut self, device: &wgpu::Device, resolution: u32, cascades: u32) {
+        (
+            self.shadow_map,
+            self.shadow_map_cascades,
+            self.shadow_map_bind_group,
+        ) = create_shadow_map(device, &self.shadow_map_layout, resolution, cascades);
+    }
+
+    /// Re-creates the resources whose size depends on the size of the render target.
+    fn recreate_render_targets(&mut self, device: &wgpu::Device) {
+        let (width, height) = self.scaled_size();
+
         (self.depth_buffer, self.depth_buffer_bind_group) = create_depth_buffer(
             device,
             &self.depth_buffer_layout,
             &self.linear_sampler,
             width,
             height,
+            self.sample_count,
         );
+
+        self.multisampled_color = if self.sample_count > 1 {
+            Some(create_multisampled_color(
+                device,
+                self.multisampled_format,
+                width,
+                height,
+                self.sample_count,
+            ))
+        } else {
+            None
+        };
+
+        self.scaled_color = if (width, height) != self.size {
+            Some(create_scaled_color(
+                device,
+                &self.scaled_color_layout,
+                self.multisampled_format,
+                width,
+                height,
+            ))
+        } else {
+            None
+        };
     }
 }
 
@@ -271,6 +490,16 @@ fn create_texture_atlas_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
                 ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
                 visibility: wgpu::ShaderStages::FRAGMENT,
             },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                count: None,
+                ty: wgpu::BindingType::Buffer {
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                    ty: wgpu::BufferBindingType::Uniform,
+                },
+                visibility: wgpu::ShaderStages::FRAGMENT,
+            },
         ],
     })
 }
@@ -304,6 +533,12 @@ fn create_texture_atlas(
 
     let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
+    let uv_rect = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Texture Atlas UV Rect"),
+        contents: bytemuck::bytes_of(&config.uv_rect()),
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+
     let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Texture Atlas Bind Group"),
         layout,
@@ -316,13 +551,17 @@ fn create_texture_atlas(
                 binding: 1,
                 resource: wgpu::BindingResource::Sampler(sampler),
             },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: uv_rect.as_entire_binding(),
+            },
         ],
     });
 
     bind_group
 }
 
-fn create_depth_buffer_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+fn create_depth_buffer_layout(device: &wgpu::Device, sample_count: u32) -> wgpu::BindGroupLayout {
     device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
         label: Some("Depth Buffer Bind Group Layout"),
         entries: &[
@@ -330,7 +569,7 @@ fn create_depth_buffer_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
                 binding: 0,
                 count: None,
                 ty: wgpu::BindingType::Texture {
-                    multisampled: false,
+                    multisampled: sample_count > 1,
                     sample_type: wgpu::TextureSampleType::Depth,
                     view_dimension: wgpu::TextureViewDimension::D2,
                 },
@@ -369,6 +608,7 @@ fn create_depth_buffer(
     sampler: &wgpu::Sampler,
     width: u32,
     height: u32,
+    sample_count: u32,
 ) -> (wgpu::TextureView, wgpu::BindGroup) {
     let depth_buffer = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Depth Buffer"),
@@ -378,7 +618,7 @@ fn create_depth_buffer(
             depth_or_array_layers: 1,
         },
         mip_level_count: 1,
-        sample_count: 1,
+        sample_count,
         dimension: wgpu::TextureDimension::D2,
         format: wgpu::TextureFormat::Depth32Float,
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
@@ -405,6 +645,113 @@ fn create_depth_buffer(
     (view, bind_group)
 }
 
+fn create_multisampled_color(
+    device: &wgpu::Device,
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+    sample_count: u32,
+) -> wgpu::TextureView {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Multisampled Color Target"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+
+    texture.create_view(&wgpu::TextureViewDescriptor::default())
+}
+
+fn create_scaled_color_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Scaled Color Bind Group Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                count: None,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                visibility: wgpu::ShaderStages::FRAGMENT,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                count: None,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                visibility: wgpu::ShaderStages::FRAGMENT,
+            },
+        ],
+    })
+}
+
+fn create_scaled_color(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+) -> (wgpu::TextureView, wgpu::BindGroup) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Scaled Color Target"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    // Clamp to the edges to avoid bleeding from the opposite side of the image when upscaling.
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Scaled Color Sampler"),
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        lod_min_clamp: 0.0,
+        lod_max_clamp: 32.0,
+        compare: None,
+        anisotropy_clamp: 1,
+        border_color: None,
+    });
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Scaled Color Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&sampler),
+            },
+        ],
+    });
+
+    (view, bind_group)
+}
+
 fn create_texture_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
     device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
         label: Some("Texture Bind Group Layout"),
@@ -438,7 +785,7 @@ fn create_shadow_map_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
                 count: None,
                 ty: wgpu::BindingType::Texture {
                     sample_type: wgpu::TextureSampleType::Depth,
-                    view_dimension: wgpu::TextureViewDimension::D2,
+                    view_dimension: wgpu::TextureViewDimension::D2Array,
                     multisampled: false,
                 },
                 visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
@@ -456,13 +803,15 @@ fn create_shadow_map_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
 fn create_shadow_map(
     device: &wgpu::Device,
     layout: &wgpu::BindGroupLayout,
-) -> (wgpu::TextureView, wgpu::BindGroup) {
+    resolution: u32,
+    cascades: u32,
+) -> (wgpu::TextureView, Vec<wgpu::TextureView>, wgpu::BindGroup) {
     let texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Shadow Map"),
         size: wgpu::Extent3d {
-            width: 8192,
-            height: 8192,
-            depth_or_array_layers: 1,
+            width: resolution,
+            height: resolution,
+            depth_or_array_layers: cascades,
         },
         mip_level_count: 1,
         sample_count: 1,
@@ -472,7 +821,22 @@ fn create_shadow_map(
         view_formats: &[],
     });
 
-    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let view = texture.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(wgpu::TextureViewDimension::D2Array),
+        ..Default::default()
+    });
+
+    let cascade_views = (0..cascades)
+        .map(|layer| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("Shadow Map Cascade"),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_array_layer: layer,
+                array_layer_count: Some(1),
+                ..Default::default()
+            })
+        })
+        .collect();
 
     let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
         label: Some("Shadow Map Sampler"),
@@ -504,5 +868,33 @@ fn create_shadow_map(
         ],
     });
 
-    (view, bind_group)
+    (view, cascade_views, bind_group)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uv_rect_is_inset_by_the_configured_amount() {
+        let config = TextureAtlasConfig {
+            width: 16,
+            height: 32,
+            uv_inset: 0.5,
+            ..TextureAtlasConfig::dummy::<4>()
+        };
+
+        let rect = config.uv_rect();
+        assert_eq!(rect.min, Vec2::new(0.5 / 16.0, 0.5 / 32.0));
+        assert_eq!(rect.max, Vec2::new(1.0 - 0.5 / 16.0, 1.0 - 0.5 / 32.0));
+
+        // Without any inset, the whole texture is sampled.
+        assert_eq!(
+            TextureAtlasConfig::dummy::<4>().uv_rect(),
+            AtlasUvRect {
+                min: Vec2::ZERO,
+                max: Vec2::ONE,
+            }
+        );
+    }
 }
@@ -1,10 +1,18 @@
+// Rust counterpart: `src/shaders/common.rs`
+struct PointLight {
+    position: vec3<f32>,
+    radius: f32,
+    color: u32,
+}
+
 // Rust counterpart: `src/shaders/common.rs`
 struct FrameUniforms {
     projection: mat4x4<f32>,
     inverse_projection: mat4x4<f32>,
     view: mat4x4<f32>,
     inverse_view: mat4x4<f32>,
-    light_transform: mat4x4<f32>,
+    light_transforms: array<mat4x4<f32>, 4>,
+    shadow_cascade_splits: vec4<f32>,
     resolution: vec2<f32>,
     fog_density: f32,
     fog_distance: f32,
@@ -14,6 +22,11 @@ struct FrameUniforms {
     milliseconds: u32,
     sun_direction: vec3<f32>,
     fog_height: f32,
+    ambient_color: u32,
+    shadow_cascade_count: u32,
+    point_light_count: u32,
+    sun_intensity: f32,
+    point_lights: array<PointLight, 16>,
 }
 
 @group(0) @binding(0)
@@ -58,6 +71,8 @@ fn vs_main(
 
 
 // Unpacks the provided color.
+//
+// Rust counterpart: `Color::to_packed` in `src/color.rs`
 fn unpack_color(color: u32) -> vec4<f32> {
     return vec4<f32>(
         f32((color >> 24u) & 0xFFu) / 255.0,
@@ -77,13 +92,12 @@ fn skybox(height: f32) -> vec4<f32> {
     }
 }
 
-@group(1) @binding(0)
-var depth_texture: texture_depth_2d;
-@group(1) @binding(1)
-var the_sampler: sampler;
+// The depth buffer and the `load_depth` function are declared in `fog_depth.wgsl` or
+// `fog_depth_multisampled.wgsl`, depending on whether the depth buffer is multisampled (see
+// `create_shader` in `mod.rs`).
 
-fn depth_value(uv: vec2<f32>) -> f32 {
-    var depth = textureSample(depth_texture, the_sampler, vec2(uv.x, -uv.y));
+fn depth_value(uv: vec2<f32>, pixel: vec2<f32>) -> f32 {
+    var depth = load_depth(vec2<i32>(pixel));
     let clip_space = vec4<f32>(vec2(0.0, 1.0) - uv * 2.0 - 1.0, depth * 2.0 - 1.0, 1.0);
     let view_space = frame.inverse_projection * clip_space;
     return view_space.z / view_space.w;
@@ -94,7 +108,7 @@ fn fs_main(
     in: Interpolator,
 ) -> @location(0) vec4<f32> {
     let eye_dir = normalize(in.eye_direction);
-    let depth = max(0.0, depth_value(in.uv) - frame.fog_distance);
+    let depth = max(0.0, depth_value(in.uv, in.position.xy) - frame.fog_distance);
     var fog_amount = 1.0 - pow(2.0, -depth * frame.fog_density);
     fog_amount *= pow(2.0, -max(0.0, eye_dir.y) * frame.fog_height);
     var fog_color = skybox(eye_dir.y);
@@ -0,0 +1,7 @@
+@group(1) @binding(0)
+var depth_texture: texture_depth_multisampled_2d;
+
+// Loads the depth of the provided pixel, using its first sample.
+fn load_depth(pixel: vec2<i32>) -> f32 {
+    return textureLoad(depth_texture, pixel, 0);
+}
@@ -1,5 +1,14 @@
 use crate::Gpu;
 
+/// The source code of the fog shader, for a single-sampled depth buffer.
+const SOURCE: &str = concat!(include_str!("fog.wgsl"), include_str!("fog_depth.wgsl"));
+
+/// The source code of the fog shader, for a multisampled depth buffer.
+const SOURCE_MULTISAMPLED: &str = concat!(
+    include_str!("fog.wgsl"),
+    include_str!("fog_depth_multisampled.wgsl"),
+);
+
 /// A simple render pipeline that renders fog using the depth buffer.
 pub struct FogPipeline {
     /// The pipeline responsible for the fog.
@@ -8,8 +17,8 @@ pub struct FogPipeline {
 
 impl FogPipeline {
     /// Creates a new [`FogPipeline`] instance.
-    pub fn new(gpu: &Gpu, output_format: wgpu::TextureFormat) -> Self {
-        let pipeline = create_shader(gpu, output_format);
+    pub fn new(gpu: &Gpu, output_format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let pipeline = create_shader(gpu, output_format, sample_count);
         Self { pipeline }
     }
 
@@ -32,14 +41,29 @@ impl FogPipeline {
 ///
 /// The provided `frame_uniforms_layout` is expected to include the bind group for the
 /// frame uniforms.
-pub fn create_shader(gpu: &Gpu, output_format: wgpu::TextureFormat) -> wgpu::RenderPipeline {
+///
+/// # Multisampling
+///
+/// The fog itself is always rendered to a single-sampled target, but it reads the depth buffer,
+/// which has `sample_count` samples per pixel.
+pub fn create_shader(
+    gpu: &Gpu,
+    output_format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
     let res = gpu.resources.read();
 
+    let source = if sample_count > 1 {
+        SOURCE_MULTISAMPLED
+    } else {
+        SOURCE
+    };
+
     let shader_module = gpu
         .device
         .create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Fog Shader Module"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("fog.wgsl").into()),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
         });
 
     let pipeline_layout = gpu
@@ -86,3 +110,27 @@ pub fn create_shader(gpu: &Gpu, output_format: wgpu::TextureFormat) -> wgpu::Ren
             },
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipeline_matches_depth_buffer_sample_count() {
+        let Some(gpu) = crate::gpu::block_on(Gpu::new_headless()) else {
+            return;
+        };
+
+        let format = wgpu::TextureFormat::Rgba8Unorm;
+        for sample_count in [1, 4] {
+            gpu.resources
+                .write()
+                .set_sample_count(&gpu.device, sample_count, format);
+
+            gpu.device.push_error_scope(wgpu::ErrorFilter::Validation);
+            create_shader(&gpu, format, sample_count);
+            let error = crate::gpu::block_on(gpu.device.pop_error_scope());
+            assert!(error.is_none(), "{sample_count} samples: {error:?}");
+        }
+    }
+}
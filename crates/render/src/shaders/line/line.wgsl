@@ -1,10 +1,18 @@
+// Rust counterpart: `src/shaders/common.rs`
+struct PointLight {
+    position: vec3<f32>,
+    radius: f32,
+    color: u32,
+}
+
 // Rust counterpart: `src/shaders/common.rs`
 struct FrameUniforms {
     projection: mat4x4<f32>,
     inverse_projection: mat4x4<f32>,
     view: mat4x4<f32>,
     inverse_view: mat4x4<f32>,
-    light_transform: mat4x4<f32>,
+    light_transforms: array<mat4x4<f32>, 4>,
+    shadow_cascade_splits: vec4<f32>,
     resolution: vec2<f32>,
     fog_density: f32,
     fog_distance: f32,
@@ -14,6 +22,11 @@ struct FrameUniforms {
     milliseconds: u32,
     sun_direction: vec3<f32>,
     fog_height: f32,
+    ambient_color: u32,
+    shadow_cascade_count: u32,
+    point_light_count: u32,
+    sun_intensity: f32,
+    point_lights: array<PointLight, 16>,
 }
 
 @group(0) @binding(0)
@@ -107,6 +120,8 @@ fn vs_main(in: Instance, @builtin(vertex_index) vertex_index: u32) -> Interpolat
 }
 
 // Unpacks the provided color.
+//
+// Rust counterpart: `Color::to_packed` in `src/color.rs`
 fn unpack_color(color: u32) -> vec4<f32> {
     return vec4<f32>(
         f32((color >> 24u) & 0xFFu) / 255.0,
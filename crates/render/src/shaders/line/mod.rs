@@ -18,8 +18,8 @@ pub struct LinePipeline {
 
 impl LinePipeline {
     /// Creates a new [`LinePipeline`] instance.
-    pub fn new(gpu: &Gpu, output_format: wgpu::TextureFormat) -> Self {
-        let pipeline = create_pipeline(gpu, output_format);
+    pub fn new(gpu: &Gpu, output_format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let pipeline = create_pipeline(gpu, output_format, sample_count);
         let buffer = create_line_instance_buffer(gpu);
 
         Self { pipeline, buffer }
@@ -63,7 +63,11 @@ impl LinePipeline {
 }
 
 /// Creates the render pipeline that's responsible for drawing lines.
-fn create_pipeline(gpu: &Gpu, output_format: wgpu::TextureFormat) -> wgpu::RenderPipeline {
+fn create_pipeline(
+    gpu: &Gpu,
+    output_format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
     let res = gpu.resources.read();
 
     let shader_module = gpu
@@ -112,7 +116,7 @@ fn create_pipeline(gpu: &Gpu, output_format: wgpu::TextureFormat) -> wgpu::Rende
             },
             multisample: wgpu::MultisampleState {
                 alpha_to_coverage_enabled: false,
-                count: 1,
+                count: sample_count,
                 mask: !0,
             },
             multiview: None,
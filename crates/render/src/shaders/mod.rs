@@ -1,5 +1,7 @@
+pub mod blit;
 pub mod fog;
 pub mod line;
+pub mod particle;
 pub mod quad;
 pub mod skybox;
 pub mod text;
@@ -0,0 +1,18 @@
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+
+use crate::color::Color;
+
+/// A particle, rendered as a small square that always faces the camera.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Zeroable, Pod)]
+pub struct ParticleInstance {
+    /// The position of the center of the particle, in world space.
+    pub position: Vec3,
+    /// The length of the sides of the particle, in world units.
+    pub size: f32,
+    /// The color of the particle.
+    ///
+    /// Particles are alpha-blended with the rest of the scene.
+    pub color: Color,
+}
@@ -0,0 +1,207 @@
+use std::mem::{size_of, size_of_val};
+
+mod instance;
+pub use instance::*;
+
+use wgpu::util::DeviceExt;
+use wgpu::RenderPass;
+
+use crate::Gpu;
+
+/// Contains the state required to draw particles using GPU resources.
+pub struct ParticlePipeline {
+    /// The pipeline responsible for rendering particles.
+    pipeline: wgpu::RenderPipeline,
+    /// The buffer responsible for storing the particle instances.
+    buffer: wgpu::Buffer,
+}
+
+impl ParticlePipeline {
+    /// Creates a new [`ParticlePipeline`] instance.
+    pub fn new(gpu: &Gpu, output_format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let pipeline = create_pipeline(gpu, output_format, sample_count);
+        let buffer = create_particle_instance_buffer(gpu);
+
+        Self { pipeline, buffer }
+    }
+
+    /// Renders the provided particles.
+    ///
+    /// # Remarks
+    ///
+    /// This function expects the bind group 0 to be bound to the frame uniforms.
+    #[profiling::function]
+    pub fn render<'res>(
+        &'res mut self,
+        gpu: &Gpu,
+        rp: &mut RenderPass<'res>,
+        particles: &[ParticleInstance],
+    ) {
+        if particles.is_empty() {
+            return;
+        }
+
+        self.upload(gpu, particles);
+
+        // Draw all the particles as a batch.
+        rp.set_pipeline(&self.pipeline);
+        rp.set_vertex_buffer(0, self.buffer.slice(..));
+        rp.draw(0..4, 0..particles.len() as u32);
+    }
+
+    /// Copies the provided particles into the GPU buffer, eventually resizing it if needed.
+    ///
+    /// The buffer is only ever grown, meaning that it's reused as long as the particles fit.
+    fn upload(&mut self, gpu: &Gpu, particles: &[ParticleInstance]) {
+        if self.buffer.size() < size_of_val(particles) as u64 {
+            self.buffer = gpu
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    contents: bytemuck::cast_slice(particles),
+                    label: Some("Particle Instance Buffer"),
+                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                });
+        } else {
+            gpu.queue
+                .write_buffer(&self.buffer, 0, bytemuck::cast_slice(particles));
+        }
+    }
+}
+
+/// Creates the render pipeline that's responsible for drawing particles.
+fn create_pipeline(
+    gpu: &Gpu,
+    output_format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    let res = gpu.resources.read();
+
+    let shader_module = gpu
+        .device
+        .create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Particle Shader Module"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("particle.wgsl").into()),
+        });
+
+    let pipeline_layout = gpu
+        .device
+        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Particle Pipeline Layout"),
+            bind_group_layouts: &[&res.frame_uniforms_layout],
+            push_constant_ranges: &[],
+        });
+
+    gpu.device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: crate::DEPTH_FORMAT,
+                // Particles are transparent and must not hide what's behind them.
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            fragment: Some(wgpu::FragmentState {
+                entry_point: "fs_main",
+                module: &shader_module,
+                targets: &[Some(wgpu::ColorTargetState {
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    format: output_format,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            label: Some("Particle Pipeline"),
+            layout: Some(&pipeline_layout),
+            primitive: wgpu::PrimitiveState {
+                conservative: false,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                front_face: wgpu::FrontFace::Cw,
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                strip_index_format: None,
+                unclipped_depth: false,
+            },
+            multisample: wgpu::MultisampleState {
+                alpha_to_coverage_enabled: false,
+                count: sample_count,
+                mask: !0,
+            },
+            multiview: None,
+            vertex: wgpu::VertexState {
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: size_of::<ParticleInstance>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &[
+                        // position
+                        wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Float32x3,
+                            offset: 0,
+                            shader_location: 0,
+                        },
+                        // size
+                        wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Float32,
+                            offset: 12,
+                            shader_location: 1,
+                        },
+                        // color
+                        wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Uint32,
+                            offset: 16,
+                            shader_location: 2,
+                        },
+                    ],
+                }],
+                entry_point: "vs_main",
+                module: &shader_module,
+            },
+        })
+}
+
+/// Creates a buffer that can be used to store particle instances.
+fn create_particle_instance_buffer(gpu: &Gpu) -> wgpu::Buffer {
+    gpu.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Particle Instance Buffer"),
+        mapped_at_creation: false,
+        size: 256 * size_of::<ParticleInstance>() as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn buffer_grows_and_is_reused() {
+        let Some(gpu) = crate::gpu::block_on(Gpu::new_headless()) else {
+            return;
+        };
+
+        let mut pipeline = ParticlePipeline::new(&gpu, wgpu::TextureFormat::Rgba8Unorm, 1);
+        let initial_size = pipeline.buffer.size();
+        let instance = ParticleInstance {
+            position: Vec3::ZERO,
+            size: 0.1,
+            color: Color::WHITE,
+        };
+
+        // A few particles fit in the initial buffer.
+        pipeline.upload(&gpu, &[instance; 4]);
+        assert_eq!(pipeline.buffer.size(), initial_size);
+
+        // Too many particles require a bigger buffer.
+        let many = vec![instance; 1000];
+        pipeline.upload(&gpu, &many);
+        let grown_size = pipeline.buffer.size();
+        assert!(grown_size >= size_of_val(many.as_slice()) as u64);
+        assert!(grown_size > initial_size);
+
+        // The bigger buffer is kept for smaller batches.
+        pipeline.upload(&gpu, &[instance; 4]);
+        assert_eq!(pipeline.buffer.size(), grown_size);
+    }
+}
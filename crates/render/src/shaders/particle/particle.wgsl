@@ -0,0 +1,83 @@
+// Rust counterpart: `src/shaders/common.rs`
+struct PointLight {
+    position: vec3<f32>,
+    radius: f32,
+    color: u32,
+}
+
+// Rust counterpart: `src/shaders/common.rs`
+struct FrameUniforms {
+    projection: mat4x4<f32>,
+    inverse_projection: mat4x4<f32>,
+    view: mat4x4<f32>,
+    inverse_view: mat4x4<f32>,
+    light_transforms: array<mat4x4<f32>, 4>,
+    shadow_cascade_splits: vec4<f32>,
+    resolution: vec2<f32>,
+    fog_density: f32,
+    fog_distance: f32,
+    fog_color: u32,
+    sky_color: u32,
+    flags: u32,
+    milliseconds: u32,
+    sun_direction: vec3<f32>,
+    fog_height: f32,
+    ambient_color: u32,
+    shadow_cascade_count: u32,
+    point_light_count: u32,
+    sun_intensity: f32,
+    point_lights: array<PointLight, 16>,
+}
+
+@group(0) @binding(0)
+var<uniform> frame: FrameUniforms;
+
+// The vertex data expected by the shader.
+struct Instance {
+    // The position of the center of the particle, in world space.
+    @location(0) position: vec3<f32>,
+    // The length of the sides of the particle.
+    @location(1) size: f32,
+    // The color of the particle.
+    @location(2) color: u32,
+}
+
+// The position of the vertex after the vertex shader has been run.
+struct Interpolator {
+    // The clip-space position of the vertex.
+    @builtin(position) position: vec4<f32>,
+    // The color of the vertex.
+    @location(0) @interpolate(flat) color: u32,
+}
+
+@vertex
+fn vs_main(in: Instance, @builtin(vertex_index) vertex_index: u32) -> Interpolator {
+    // The corner of the quad, between -0.5 and 0.5 on both axes.
+    let corner = vec2<f32>(f32(vertex_index & 1u), f32(vertex_index >> 1u)) - 0.5;
+
+    // The quad is expanded in view-space so that it always faces the camera.
+    var view_pos = frame.view * vec4<f32>(in.position, 1.0);
+    view_pos += vec4<f32>(corner * in.size, 0.0, 0.0);
+
+    var out: Interpolator;
+    out.position = frame.projection * view_pos;
+    out.color = in.color;
+    return out;
+}
+
+// Unpacks the provided color.
+//
+// Rust counterpart: `Color::to_packed` in `src/color.rs`
+fn unpack_color(color: u32) -> vec4<f32> {
+    return vec4<f32>(
+        f32((color >> 24u) & 0xFFu) / 255.0,
+        f32((color >> 16u) & 0xFFu) / 255.0,
+        f32((color >> 8u) & 0xFFu) / 255.0,
+        f32(color & 0xFFu) / 255.0,
+    );
+}
+
+@fragment
+fn fs_main(in: Interpolator) -> @location(0) vec4<f32> {
+    return unpack_color(in.color);
+}
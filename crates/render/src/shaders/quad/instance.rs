@@ -9,10 +9,74 @@ use glam::IVec3;
 pub struct QuadInstance {
     /// Some flags associated with this instance.
     pub flags: QuadFlags,
-    /// The index of the texture to use for this quad.
+    /// The index of the texture to use for this quad, along with its light level.
+    ///
+    /// # Representation
+    ///
+    /// | Bits  | Field         | Description                                    |
+    /// |-------|---------------|------------------------------------------------|
+    /// | 0-23  | `texture`     | The index of the texture to use.               |
+    /// | 24-27 | `darkness`    | `MAX_LIGHT` minus the light level of the quad. |
+    /// | 28-31 | `block_light` | The light received from light-emitting blocks. |
+    ///
+    /// The darkness is stored instead of the light level in order for quads that don't specify
+    /// any light level to be fully lit. The block light is added on top of the other sources of
+    /// light, so quads that don't specify any receive none.
     pub texture: u32,
 }
 
+impl QuadInstance {
+    /// The maximum light level of a quad.
+    pub const MAX_LIGHT: u8 = 15;
+
+    /// The bits of [`QuadInstance::texture`] that store the index of the texture.
+    pub const TEXTURE_MASK: u32 = 0x00FF_FFFF;
+    /// The bits of [`QuadInstance::texture`] that store the darkness of the quad.
+    const DARKNESS_MASK: u32 = 0x0F00_0000;
+    /// The bits of [`QuadInstance::texture`] that store the block light of the quad.
+    const BLOCK_LIGHT_MASK: u32 = 0xF000_0000;
+
+    /// Returns the light level of this quad.
+    #[inline]
+    pub fn light(&self) -> u8 {
+        Self::MAX_LIGHT - ((self.texture >> 24) & 0xF) as u8
+    }
+
+    /// Sets the light level of this quad.
+    ///
+    /// Values larger than [`QuadInstance::MAX_LIGHT`] are clamped.
+    #[inline]
+    pub fn with_light(mut self, light: u8) -> Self {
+        let darkness = (Self::MAX_LIGHT - light.min(Self::MAX_LIGHT)) as u32;
+        self.texture = (self.texture & !Self::DARKNESS_MASK) | (darkness << 24);
+        self
+    }
+
+    /// Returns the amount of light this quad receives from light-emitting blocks.
+    #[inline]
+    pub fn block_light(&self) -> u8 {
+        ((self.texture & Self::BLOCK_LIGHT_MASK) >> 28) as u8
+    }
+
+    /// Sets the amount of light this quad receives from light-emitting blocks.
+    ///
+    /// Values larger than [`QuadInstance::MAX_LIGHT`] are clamped.
+    #[inline]
+    pub fn set_block_light(&mut self, light: u8) {
+        let light = light.min(Self::MAX_LIGHT) as u32;
+        self.texture = (self.texture & !Self::BLOCK_LIGHT_MASK) | (light << 28);
+    }
+}
+
+// The fields packed in `QuadInstance::texture` must not overlap, and must cover all of its bits.
+const _: () = {
+    let texture = QuadInstance::TEXTURE_MASK;
+    let darkness = QuadInstance::DARKNESS_MASK;
+    let block_light = QuadInstance::BLOCK_LIGHT_MASK;
+    assert!(texture & darkness == 0 && texture & block_light == 0 && darkness & block_light == 0);
+    assert!(texture | darkness | block_light == u32::MAX);
+};
+
 bitflags! {
     /// Some flags that are stored in a [`QuadInstance`] to describe it.
     ///
@@ -230,3 +294,41 @@ pub struct ChunkUniforms {
     /// The position of the chunk, in world-space.
     pub position: IVec3,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn light_fields_round_trip() {
+        let texture = QuadInstance::TEXTURE_MASK;
+
+        for light in 0..=QuadInstance::MAX_LIGHT {
+            for block_light in 0..=QuadInstance::MAX_LIGHT {
+                let mut quad = QuadInstance {
+                    flags: QuadFlags::empty(),
+                    texture,
+                }
+                .with_light(light);
+                quad.set_block_light(block_light);
+
+                assert_eq!(quad.light(), light);
+                assert_eq!(quad.block_light(), block_light);
+                assert_eq!(quad.texture & QuadInstance::TEXTURE_MASK, texture);
+            }
+        }
+    }
+
+    #[test]
+    fn light_levels_are_clamped() {
+        let mut quad = QuadInstance {
+            flags: QuadFlags::empty(),
+            texture: 0,
+        }
+        .with_light(200);
+        quad.set_block_light(200);
+
+        assert_eq!(quad.light(), QuadInstance::MAX_LIGHT);
+        assert_eq!(quad.block_light(), QuadInstance::MAX_LIGHT);
+    }
+}
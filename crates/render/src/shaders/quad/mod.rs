@@ -2,6 +2,7 @@ use wgpu::util::DeviceExt;
 
 use std::mem::size_of;
 
+use crate::shaders::common::MAX_SHADOW_CASCADES;
 use crate::Gpu;
 
 mod instance;
@@ -39,6 +40,23 @@ pub struct QuadPipeline {
     opaque_pipeline: wgpu::RenderPipeline,
     /// The render pipeline responsible for rendering transparent geometry.
     transparent_pipeline: wgpu::RenderPipeline,
+    /// The render pipeline responsible for rendering opaque geometry as wireframe.
+    ///
+    /// This is [`None`] when the GPU does not support the `POLYGON_MODE_LINE` feature.
+    wireframe_pipeline: Option<wgpu::RenderPipeline>,
+    /// Whether opaque geometry is currently rendered using `wireframe_pipeline`.
+    wireframe: bool,
+
+    /// The alignment of the cascade indices in `cascade_uniforms_buffer`.
+    cascade_align: usize,
+    /// This buffer contains the index of each cascade of the shadow map, one per aligned slot.
+    ///
+    /// It is bound to bind group 2 of the shadow pipeline using `cascade_uniforms_bind_group`.
+    /// Like `chunk_uniforms_buffer`, a dynamic offset is used to select the cascade being
+    /// rendered.
+    _cascade_uniforms_buffer: wgpu::Buffer,
+    /// The bind group that's used to bind `cascade_uniforms_buffer`.
+    cascade_uniforms_bind_group: wgpu::BindGroup,
 
     /// The pipeline responsible for rendering the depth map from the perspective of the sun.
     shadow_pipeline: wgpu::RenderPipeline,
@@ -46,7 +64,7 @@ pub struct QuadPipeline {
 
 impl QuadPipeline {
     /// Creates a new [`QuadPipeline`] instance.
-    pub fn new(gpu: &Gpu, output_format: wgpu::TextureFormat) -> Self {
+    pub fn new(gpu: &Gpu, output_format: wgpu::TextureFormat, sample_count: u32) -> Self {
         let chunk_align = get_chunk_alignment(gpu);
         let chunk_uniforms_layout = create_chunk_uniforms_bind_group_layout(gpu, chunk_align);
         let (chunk_uniforms_buffer, chunk_uniforms_bind_group) = create_chunk_uniforms_buffer(
@@ -62,6 +80,7 @@ impl QuadPipeline {
             &pipeline_layout,
             &shader_module,
             output_format,
+            sample_count,
             PipelineFlavor::Opaque,
         );
         let transparent_pipeline = create_pipeline(
@@ -69,9 +88,29 @@ impl QuadPipeline {
             &pipeline_layout,
             &shader_module,
             output_format,
+            sample_count,
             PipelineFlavor::Transparent,
         );
-        let shadow_pipeline = create_shadow_pipeline(gpu, &chunk_uniforms_layout);
+        let wireframe_pipeline = gpu
+            .device
+            .features()
+            .contains(wgpu::Features::POLYGON_MODE_LINE)
+            .then(|| {
+                create_pipeline(
+                    gpu,
+                    &pipeline_layout,
+                    &shader_module,
+                    output_format,
+                    sample_count,
+                    PipelineFlavor::Wireframe,
+                )
+            });
+        let cascade_align = gpu.limits.min_uniform_buffer_offset_alignment as usize;
+        let cascade_uniforms_layout = create_cascade_uniforms_bind_group_layout(gpu);
+        let (cascade_uniforms_buffer, cascade_uniforms_bind_group) =
+            create_cascade_uniforms_buffer(gpu, &cascade_uniforms_layout, cascade_align);
+        let shadow_pipeline =
+            create_shadow_pipeline(gpu, &chunk_uniforms_layout, &cascade_uniforms_layout);
 
         Self {
             chunk_uniforms_layout,
@@ -80,10 +119,35 @@ impl QuadPipeline {
             chunk_align,
             opaque_pipeline,
             transparent_pipeline,
+            wireframe_pipeline,
+            wireframe: false,
+            cascade_align,
+            _cascade_uniforms_buffer: cascade_uniforms_buffer,
+            cascade_uniforms_bind_group,
             shadow_pipeline,
         }
     }
 
+    /// Returns whether opaque geometry can be rendered as wireframe.
+    #[inline]
+    pub fn supports_wireframe(&self) -> bool {
+        self.wireframe_pipeline.is_some()
+    }
+
+    /// Sets whether opaque geometry should be rendered as wireframe.
+    ///
+    /// This has no effect if [`supports_wireframe`](Self::supports_wireframe) returns `false`.
+    #[inline]
+    pub fn set_wireframe(&mut self, yes: bool) {
+        self.wireframe = yes && self.supports_wireframe();
+    }
+
+    /// Returns whether opaque geometry is currently rendered as wireframe.
+    #[inline]
+    pub fn wireframe(&self) -> bool {
+        self.wireframe
+    }
+
     /// Prepares the pipeline for rendering the provided [`Quads`].
     pub fn prepare(&mut self, gpu: &Gpu, quads: &Quads) {
         // Copy the chunk data into the GPU buffer, eventually resizing it if needed.
@@ -108,11 +172,37 @@ impl QuadPipeline {
         }
     }
 
-    /// Renders to the shadowmap.
-    pub fn render_shadows<'res>(&'res self, rp: &mut wgpu::RenderPass<'res>, quads: &Quads<'res>) {
+    /// Renders to the provided cascade of the shadow map.
+    ///
+    /// # Remarks
+    ///
+    /// The provided render pass must have the `frame_uniforms` bind group (bind group 0) set
+    /// upon entering this function.
+    ///
+    /// This function will clobber bind groups 1 and 2.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `cascade` is not less than [`MAX_SHADOW_CASCADES`].
+    pub fn render_shadows<'res>(
+        &'res self,
+        rp: &mut wgpu::RenderPass<'res>,
+        quads: &Quads<'res>,
+        cascade: u32,
+    ) {
+        assert!(
+            (cascade as usize) < MAX_SHADOW_CASCADES,
+            "invalid shadow cascade"
+        );
+
         // Draw each instance buffer registered, binding it to the correct chunk uniforms
         // using dynamic offsets.
         rp.set_pipeline(&self.shadow_pipeline);
+        rp.set_bind_group(
+            2,
+            &self.cascade_uniforms_bind_group,
+            &[cascade * self.cascade_align as u32],
+        );
         for buf in &quads.opaque_buffers {
             rp.set_bind_group(
                 1,
@@ -139,7 +229,10 @@ impl QuadPipeline {
     pub fn render<'res>(&'res self, rp: &mut wgpu::RenderPass<'res>, quads: &Quads<'res>) {
         // Draw each instance buffer registered, binding it to the correct chunk uniforms
         // using dynamic offsets.
-        rp.set_pipeline(&self.opaque_pipeline);
+        match &self.wireframe_pipeline {
+            Some(wireframe) if self.wireframe => rp.set_pipeline(wireframe),
+            _ => rp.set_pipeline(&self.opaque_pipeline),
+        }
         for buf in &quads.opaque_buffers {
             rp.set_bind_group(
                 1,
@@ -219,6 +312,61 @@ fn create_chunk_uniforms_bind_group(
     })
 }
 
+fn create_cascade_uniforms_bind_group_layout(gpu: &Gpu) -> wgpu::BindGroupLayout {
+    gpu.device
+        .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Cascade Uniforms Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                count: None,
+                ty: wgpu::BindingType::Buffer {
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(size_of::<u32>() as wgpu::BufferAddress),
+                    ty: wgpu::BufferBindingType::Uniform,
+                },
+                visibility: wgpu::ShaderStages::VERTEX,
+            }],
+        })
+}
+
+/// Creates the buffer that contains the index of each cascade of the shadow map, along with the
+/// bind group that binds it.
+///
+/// The index of cascade `i` is stored at offset `i * align`.
+fn create_cascade_uniforms_buffer(
+    gpu: &Gpu,
+    layout: &wgpu::BindGroupLayout,
+    align: usize,
+) -> (wgpu::Buffer, wgpu::BindGroup) {
+    let mut contents = vec![0u8; align * MAX_SHADOW_CASCADES];
+    for (index, slot) in contents.chunks_exact_mut(align).enumerate() {
+        slot[..4].copy_from_slice(&(index as u32).to_ne_bytes());
+    }
+
+    let buf = gpu
+        .device
+        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            contents: &contents,
+            label: Some("Cascade Uniforms Buffer"),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+    let bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Cascade Uniforms Bind Group"),
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer: &buf,
+                offset: 0,
+                size: wgpu::BufferSize::new(16),
+            }),
+        }],
+    });
+
+    (buf, bind_group)
+}
+
 /// A way of creating a [`wgpu::RenderPipeline`].
 ///
 /// This is needed because some of the geometry we're rendering is opaque, and some of it
@@ -233,6 +381,11 @@ enum PipelineFlavor {
     /// The transparent pipeline does not write to the depth buffer, but uses blending
     /// when writing colors.
     Transparent,
+    /// The wireframe pipeline behaves like the opaque one, but only rasterizes the edges of
+    /// the triangles.
+    ///
+    /// Because quads are rendered as triangle strips, each quad shows up as two triangles.
+    Wireframe,
 }
 
 fn create_pipeline_layout(
@@ -268,6 +421,7 @@ fn create_pipeline(
     layout: &wgpu::PipelineLayout,
     shader_module: &wgpu::ShaderModule,
     output_format: wgpu::TextureFormat,
+    sample_count: u32,
     flavor: PipelineFlavor,
 ) -> wgpu::RenderPipeline {
     gpu.device
@@ -275,6 +429,7 @@ fn create_pipeline(
             label: Some(match flavor {
                 PipelineFlavor::Opaque => "Quad Opaque Pipeline",
                 PipelineFlavor::Transparent => "Quad Transparent Pipeline",
+                PipelineFlavor::Wireframe => "Quad Wireframe Pipeline",
             }),
             layout: Some(layout),
             vertex: wgpu::VertexState {
@@ -302,7 +457,10 @@ fn create_pipeline(
             primitive: wgpu::PrimitiveState {
                 conservative: false,
                 cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
+                polygon_mode: match flavor {
+                    PipelineFlavor::Opaque | PipelineFlavor::Transparent => wgpu::PolygonMode::Fill,
+                    PipelineFlavor::Wireframe => wgpu::PolygonMode::Line,
+                },
                 front_face: wgpu::FrontFace::Cw,
                 topology: wgpu::PrimitiveTopology::TriangleStrip,
                 strip_index_format: None,
@@ -313,7 +471,9 @@ fn create_pipeline(
                 module: shader_module,
                 targets: &[Some(wgpu::ColorTargetState {
                     blend: Some(match flavor {
-                        PipelineFlavor::Opaque => wgpu::BlendState::REPLACE,
+                        PipelineFlavor::Opaque | PipelineFlavor::Wireframe => {
+                            wgpu::BlendState::REPLACE
+                        }
                         PipelineFlavor::Transparent => wgpu::BlendState::ALPHA_BLENDING,
                     }),
                     format: output_format,
@@ -324,7 +484,7 @@ fn create_pipeline(
                 bias: wgpu::DepthBiasState::default(),
                 depth_compare: wgpu::CompareFunction::LessEqual,
                 depth_write_enabled: match flavor {
-                    PipelineFlavor::Opaque => true,
+                    PipelineFlavor::Opaque | PipelineFlavor::Wireframe => true,
                     PipelineFlavor::Transparent => false,
                 },
                 format: crate::DEPTH_FORMAT,
@@ -332,7 +492,7 @@ fn create_pipeline(
             }),
             multisample: wgpu::MultisampleState {
                 alpha_to_coverage_enabled: false,
-                count: 1,
+                count: sample_count,
                 mask: !0,
             },
             multiview: None,
@@ -342,6 +502,7 @@ fn create_pipeline(
 fn create_shadow_pipeline(
     gpu: &Gpu,
     chunk_uniforms_layout: &wgpu::BindGroupLayout,
+    cascade_uniforms_layout: &wgpu::BindGroupLayout,
 ) -> wgpu::RenderPipeline {
     let shader_module = gpu
         .device
@@ -356,7 +517,11 @@ fn create_shadow_pipeline(
         gpu.device
             .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Quad Shadow Pipeline Layout"),
-                bind_group_layouts: &[&res.frame_uniforms_layout, &chunk_uniforms_layout],
+                bind_group_layouts: &[
+                    &res.frame_uniforms_layout,
+                    chunk_uniforms_layout,
+                    cascade_uniforms_layout,
+                ],
                 push_constant_ranges: &[],
             });
 
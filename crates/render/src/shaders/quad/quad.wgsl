@@ -1,10 +1,18 @@
+// Rust counterpart: `src/shaders/common.rs`
+struct PointLight {
+    position: vec3<f32>,
+    radius: f32,
+    color: u32,
+}
+
 // Rust counterpart: `src/shaders/common.rs`
 struct FrameUniforms {
     projection: mat4x4<f32>,
     inverse_projection: mat4x4<f32>,
     view: mat4x4<f32>,
     inverse_view: mat4x4<f32>,
-    light_transform: mat4x4<f32>,
+    light_transforms: array<mat4x4<f32>, 4>,
+    shadow_cascade_splits: vec4<f32>,
     resolution: vec2<f32>,
     fog_density: f32,
     fog_distance: f32,
@@ -14,6 +22,11 @@ struct FrameUniforms {
     milliseconds: u32,
     sun_direction: vec3<f32>,
     fog_height: f32,
+    ambient_color: u32,
+    shadow_cascade_count: u32,
+    point_light_count: u32,
+    sun_intensity: f32,
+    point_lights: array<PointLight, 16>,
 }
 
 @group(0) @binding(0)
@@ -47,8 +60,49 @@ struct Interpolator {
     @location(2) @interpolate(flat) flags: u32,
     // The normal of the vertex.
     @location(3) @interpolate(flat) normal: vec3<f32>,
-    // The position of the fragment, from the perspective of the light.
-    @location(4) light_position: vec4<f32>,
+    // The position of the fragment in world-space coordinates.
+    @location(4) world_position: vec3<f32>,
+    // The brightness of the quad, computed from its light level.
+    @location(5) @interpolate(flat) brightness: f32,
+    // The depth of the fragment in view-space, used to select the cascade of the shadow map.
+    @location(6) view_depth: f32,
+    // The brightness added by light-emitting blocks, computed from the block light level.
+    @location(7) @interpolate(flat) block_light: f32,
+    // The coordinates of the vertex on its face, before the texture is rotated or mirrored.
+    //
+    // This is used to compute ambient occlusion, which does not depend on the orientation of
+    // the texture.
+    @location(8) face_coords: vec2<f32>,
+}
+
+// Unpacks the provided color.
+//
+// Rust counterpart: `Color::to_packed` in `src/color.rs`
+fn unpack_color(color: u32) -> vec4<f32> {
+    return vec4<f32>(
+        f32((color >> 24u) & 0xFFu) / 255.0,
+        f32((color >> 16u) & 0xFFu) / 255.0,
+        f32((color >> 8u) & 0xFFu) / 255.0,
+        f32(color & 0xFFu) / 255.0,
+    );
+}
+
+// Converts the darkness of a quad (the maximum light level minus its actual light level)
+// into a brightness factor.
+//
+// Rust counterpart: `QuadInstance::with_light` in `src/shaders/quad/instance.rs`
+fn light_curve(darkness: u32) -> f32 {
+    return pow(0.8, f32(darkness));
+}
+
+// Converts the block light level of a quad into a brightness factor.
+//
+// Rust counterpart: `QuadInstance::set_block_light` in `src/shaders/quad/instance.rs`
+fn block_light_curve(level: u32) -> f32 {
+    if level == 0u {
+        return 0.0;
+    }
+    return light_curve(15u - level);
 }
 
 // Returns a number between 0.0 and 1.0 that wraps around every `millis` milliseconds.
@@ -161,13 +215,19 @@ fn vs_main(
         world_pos.y += (-1.0/8.0) + cos(TAU * periodic_mod(4000u) + world_pos.x * 0.2) * (2.0/8.0);
     }
 
+    let view_pos = frame.view * vec4(world_pos, 1.0);
+
     var output: Interpolator;
-    output.position = frame.projection * frame.view * vec4(world_pos, 1.0);
+    output.position = frame.projection * view_pos;
     output.tex_coords = tex_coords;
-    output.tex_index = instance.texture;
+    output.face_coords = TEX_COORDS[vertex_index];
+    output.tex_index = instance.texture & 0xFFFFFFu;
+    output.brightness = light_curve((instance.texture >> 24u) & 15u);
+    output.block_light = block_light_curve(instance.texture >> 28u);
     output.normal = normal;
     output.flags = instance.flags;
-    output.light_position = frame.light_transform * vec4(world_pos, 1.0);
+    output.world_position = world_pos;
+    output.view_depth = view_pos.z;
     return output;
 }
 
@@ -176,8 +236,19 @@ var texture_atlas: texture_2d_array<f32>;
 @group(2) @binding(1)
 var texture_atlas_sampler: sampler;
 
+// The area of each texture of the atlas that's actually sampled.
+//
+// Rust counterpart: `AtlasUvRect` in `src/shaders/common.rs`
+struct AtlasUvRect {
+    min: vec2<f32>,
+    max: vec2<f32>,
+}
+
+@group(2) @binding(2)
+var<uniform> texture_atlas_uv_rect: AtlasUvRect;
+
 @group(3) @binding(0)
-var shadow_map: texture_depth_2d;
+var shadow_map: texture_depth_2d_array;
 @group(3) @binding(1)
 var shadow_map_sampler: sampler_comparison;
 
@@ -185,7 +256,8 @@ const LIGHT_INTENCITY: f32 = 0.8;
 
 const AMBIENT_OCCLUSION_INTENCITY: f32 = 0.5;
 
-const AMBIENT: vec3<f32> = vec3<f32>(0.0, 0.0, 0.0);
+// The color of the light emitted by blocks.
+const BLOCK_LIGHT_COLOR: vec3<f32> = vec3<f32>(1.0, 0.85, 0.6);
 
 // Computes the occlusion mask for the given texture coordinates and flags.
 fn occlusion_mask(v: vec2<f32>, flags: u32) -> f32 {
@@ -196,8 +268,33 @@ fn occlusion_mask(v: vec2<f32>, flags: u32) -> f32 {
     return top * bottom * left * right;
 }
 
+// Returns the number of cascades of the shadow map that are in use.
+fn cascade_count() -> u32 {
+    return clamp(frame.shadow_cascade_count, 1u, 4u);
+}
+
+// Returns the index of the shadow map cascade that covers the provided view-space depth.
+//
+// When the depth is beyond the last cascade, the number of cascades is returned.
+fn select_cascade(view_depth: f32) -> u32 {
+    let count = cascade_count();
+
+    var cascade = 0u;
+    while cascade < count && view_depth > frame.shadow_cascade_splits[cascade] {
+        cascade += 1u;
+    }
+    return cascade;
+}
+
 // 1 = no shadow, 0 = full shadow
-fn lookup_shadow(light_pos: vec4<f32>) -> f32 {
+fn lookup_shadow(world_pos: vec3<f32>, view_depth: f32) -> f32 {
+    let cascade = select_cascade(view_depth);
+    if cascade >= cascade_count() {
+        return 1.0;
+    }
+
+    let light_pos = frame.light_transforms[cascade] * vec4(world_pos, 1.0);
+
     // Compensate for the Y-flip in the shadow map.
     let flip_correction = vec2<f32>(0.5, -0.5);
 
@@ -206,10 +303,14 @@ fn lookup_shadow(light_pos: vec4<f32>) -> f32 {
     let light_local = light_pos.xy * flip_correction * proj_correction + vec2<f32>(0.5, 0.5);
 
     // Actually do the lookup.
-    let in_shadow = textureSampleCompare(
+    //
+    // The explicit level variant is used because the cascade (and thus whether we get here at
+    // all) is not uniform across the quad.
+    let in_shadow = textureSampleCompareLevel(
         shadow_map,
         shadow_map_sampler,
         light_local,
+        cascade,
         light_pos.z * proj_correction - 0.001,
     );
 
@@ -220,12 +321,37 @@ fn lookup_shadow(light_pos: vec4<f32>) -> f32 {
     }
 }
 
+// Computes the light received from the point lights of the frame.
+fn point_lighting(world_pos: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
+    var total = vec3<f32>(0.0, 0.0, 0.0);
+
+    for (var i = 0u; i < min(frame.point_light_count, 16u); i += 1u) {
+        let light = frame.point_lights[i];
+        let to_light = light.position - world_pos;
+        let dist = length(to_light);
+
+        if dist >= light.radius {
+            continue;
+        }
+
+        let falloff = 1.0 - dist / light.radius;
+        let facing = max(0.0, dot(normal, to_light / max(dist, 0.0001)));
+        total += unpack_color(light.color).rgb * falloff * falloff * facing;
+    }
+
+    return total;
+}
+
 @fragment
 fn fs_main(input: Interpolator) -> @location(0) vec4<f32> {
+    // Keep the sampled coordinates within the inset rectangle to avoid bleeding from the
+    // edges of the texture.
+    let uv = mix(texture_atlas_uv_rect.min, texture_atlas_uv_rect.max, input.tex_coords);
+
     let albedo = textureSample(
         texture_atlas,
         texture_atlas_sampler,
-        input.tex_coords,
+        uv,
         input.tex_index,
     );
 
@@ -235,13 +361,24 @@ fn fs_main(input: Interpolator) -> @location(0) vec4<f32> {
         discard;
     }
 
-    let occlusion = (1.0 - AMBIENT_OCCLUSION_INTENCITY) + AMBIENT_OCCLUSION_INTENCITY * occlusion_mask(input.tex_coords, input.flags);
+    let occlusion = (1.0 - AMBIENT_OCCLUSION_INTENCITY) + AMBIENT_OCCLUSION_INTENCITY * occlusion_mask(input.face_coords, input.flags);
     let diffuse = (1.0 - LIGHT_INTENCITY) + LIGHT_INTENCITY * max(0.0, dot(input.normal, -frame.sun_direction));
 
     var shadow = 1.0;
     if (frame.flags & 2u) != 0u {
-        shadow = lookup_shadow(input.light_position) * LIGHT_INTENCITY + (1.0 - LIGHT_INTENCITY);
+        shadow = lookup_shadow(input.world_position, input.view_depth) * LIGHT_INTENCITY + (1.0 - LIGHT_INTENCITY);
+    }
+
+    let ambient = unpack_color(frame.ambient_color).rgb;
+
+    var color = ambient + albedo.rgb * occlusion * shadow * diffuse * input.brightness;
+
+    color += albedo.rgb * occlusion * input.block_light * BLOCK_LIGHT_COLOR;
+
+    // Most frames have no point lights at all.
+    if frame.point_light_count != 0u {
+        color += albedo.rgb * occlusion * point_lighting(input.world_position, input.normal);
     }
 
-    return vec4<f32>(AMBIENT + albedo.rgb * occlusion * shadow * diffuse, albedo.a);
+    return vec4<f32>(color, albedo.a);
 }
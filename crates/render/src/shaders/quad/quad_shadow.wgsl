@@ -1,10 +1,18 @@
+// Rust counterpart: `src/shaders/common.rs`
+struct PointLight {
+    position: vec3<f32>,
+    radius: f32,
+    color: u32,
+}
+
 // Rust counterpart: `src/shaders/common.rs`
 struct FrameUniforms {
     projection: mat4x4<f32>,
     inverse_projection: mat4x4<f32>,
     view: mat4x4<f32>,
     inverse_view: mat4x4<f32>,
-    light_transform: mat4x4<f32>,
+    light_transforms: array<mat4x4<f32>, 4>,
+    shadow_cascade_splits: vec4<f32>,
     resolution: vec2<f32>,
     fog_density: f32,
     fog_distance: f32,
@@ -14,6 +22,11 @@ struct FrameUniforms {
     milliseconds: u32,
     sun_direction: vec3<f32>,
     fog_height: f32,
+    ambient_color: u32,
+    shadow_cascade_count: u32,
+    point_light_count: u32,
+    sun_intensity: f32,
+    point_lights: array<PointLight, 16>,
 }
 
 @group(0) @binding(0)
@@ -28,6 +41,15 @@ struct ChunkUniforms {
 @group(1) @binding(0)
 var<uniform> chunk: ChunkUniforms;
 
+// The uniform data that's written once per cascade of the shadow map.
+struct CascadeUniforms {
+    // The index of the cascade being rendered.
+    index: u32,
+}
+
+@group(2) @binding(0)
+var<uniform> cascade: CascadeUniforms;
+
 // The instance data provided by the instance buffer.
 struct Instance {
     @location(0) flags: u32,
@@ -144,5 +166,5 @@ fn vs_main(
         world_pos.y += (-1.0/8.0) + cos(TAU * periodic_mod(4000u) + world_pos.x * 0.2) * (2.0/8.0);
     }
 
-    return frame.light_transform * vec4(world_pos, 1.0);
+    return frame.light_transforms[cascade.index] * vec4(world_pos, 1.0);
 }
@@ -70,6 +70,28 @@ impl<'res> Quads<'res> {
         }
     }
 
+    /// Returns the number of opaque instance buffers that have been registered.
+    #[inline]
+    pub fn opaque_buffer_count(&self) -> usize {
+        self.opaque_buffers.len()
+    }
+
+    /// Returns the number of transparent instance buffers that have been registered.
+    #[inline]
+    pub fn transparent_buffer_count(&self) -> usize {
+        self.transparent_buffers.len()
+    }
+
+    /// Returns the total number of quad instances that have been registered, opaque and
+    /// transparent alike.
+    pub fn quad_count(&self) -> u32 {
+        self.opaque_buffers
+            .iter()
+            .chain(&self.transparent_buffers)
+            .map(|buf| buf.slice.len)
+            .sum()
+    }
+
     /// Registers a [`ChunkUniforms`] instance to be used.
     pub fn register_chunk(&mut self, chunk: &ChunkUniforms) -> u32 {
         let index = self.chunks.len() / self.chunk_align;
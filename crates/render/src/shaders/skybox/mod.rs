@@ -8,8 +8,8 @@ pub struct SkyboxPipeline {
 
 impl SkyboxPipeline {
     /// Creates a new [`SkyboxPipeline`] instance.
-    pub fn new(gpu: &Gpu, output_format: wgpu::TextureFormat) -> Self {
-        let pipeline = create_shader(gpu, output_format);
+    pub fn new(gpu: &Gpu, output_format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let pipeline = create_shader(gpu, output_format, sample_count);
         Self { pipeline }
     }
 
@@ -24,14 +24,19 @@ impl SkyboxPipeline {
 ///
 /// # Arrachments
 ///
-/// This pipeline expects a single color attachment. Its format must be of `output_format`.
+/// This pipeline expects a single color attachment. Its format must be of `output_format`, and
+/// it must have `sample_count` samples per pixel.
 ///
 ///
 /// # Layout
 ///
 /// The provided `frame_uniforms_layout` is expected to include the bind group for the
 /// frame uniforms.
-pub fn create_shader(gpu: &Gpu, output_format: wgpu::TextureFormat) -> wgpu::RenderPipeline {
+pub fn create_shader(
+    gpu: &Gpu,
+    output_format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
     let res = gpu.resources.read();
 
     let shader_module = gpu
@@ -76,7 +81,7 @@ pub fn create_shader(gpu: &Gpu, output_format: wgpu::TextureFormat) -> wgpu::Ren
             layout: Some(&pipeline_layout),
             multisample: wgpu::MultisampleState {
                 alpha_to_coverage_enabled: false,
-                count: 1,
+                count: sample_count,
                 mask: !0,
             },
             multiview: None,
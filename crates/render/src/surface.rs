@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::{Gpu, RenderTarget};
+use crate::{Gpu, GpuInfo, RenderTarget};
 
 pub use wgpu::{PresentMode, TextureFormat};
 
@@ -46,6 +46,9 @@ pub struct Surface<'window> {
     //
     alpha_mode: wgpu::CompositeAlphaMode,
 
+    /// The present modes that are supported by the surface.
+    present_modes: Vec<PresentMode>,
+
     /// The original format of the surface, the one that should be used when
     /// re-configuring the surface.
     original_format: TextureFormat,
@@ -91,7 +94,11 @@ impl<'w> Surface<'w> {
             .request_device(
                 &wgpu::DeviceDescriptor {
                     required_limits: wgpu::Limits::default(),
-                    required_features: wgpu::Features::empty(),
+                    // Allows sample counts other than 1 and 4 to be used when the adapter
+                    // supports them, as well as the wireframe debug mode.
+                    required_features: adapter.features()
+                        & (wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+                            | wgpu::Features::POLYGON_MODE_LINE),
                     label: Some("GPU Device"),
                 },
                 None,
@@ -100,17 +107,21 @@ impl<'w> Surface<'w> {
             .expect("failed to establish a connection with the selected GPU");
 
         bns_log::info!("established a connection with the GPU!");
-        bns_log::info!("GPU: {}", adapter.get_info().name);
+
+        let info = GpuInfo::of(&adapter);
+        bns_log::info!("GPU: {} ({:?})", info.name, info.kind);
 
         let config = surface
             .get_default_config(&adapter, 0, 0)
             .expect("the selected GPU is not compatible with the surface");
 
+        let present_modes = surface.get_capabilities(&adapter).present_modes;
+
         bns_log::info!("surface format: {:?}", config.format);
         bns_log::info!("present mode: {:?}", config.present_mode);
 
         #[allow(clippy::arc_with_non_send_sync)]
-        let gpu = Arc::new(Gpu::new(device, queue));
+        let gpu = Arc::new(Gpu::new(info, adapter, device, queue));
 
         Self {
             gpu,
@@ -126,6 +137,7 @@ impl<'w> Surface<'w> {
             config_dirty: false,
 
             alpha_mode: config.alpha_mode,
+            present_modes,
             original_format: config.format,
         }
     }
@@ -145,6 +157,58 @@ impl<'w> Surface<'w> {
         &mut self.config
     }
 
+    /// Sets the size of the surface.
+    ///
+    /// # Returns
+    ///
+    /// Whether the size of the surface has actually changed. When it didn't, the surface is
+    /// not re-created.
+    ///
+    /// # Remarks
+    ///
+    /// A surface can't have a size of zero (which happens when the window is minimized). In
+    /// that case, the surface keeps its previous size and `false` is returned.
+    pub fn resize(&mut self, width: u32, height: u32) -> bool {
+        if width == 0 || height == 0 || (self.config.width, self.config.height) == (width, height) {
+            return false;
+        }
+
+        let config = self.config_mut();
+        config.width = width;
+        config.height = height;
+        true
+    }
+
+    /// Returns the present modes that are supported by the surface.
+    ///
+    /// [`PresentMode::Fifo`] is always supported.
+    #[inline]
+    pub fn supported_present_modes(&self) -> &[PresentMode] {
+        &self.present_modes
+    }
+
+    /// Sets the present mode of the surface.
+    ///
+    /// # Remarks
+    ///
+    /// When the requested mode is not supported by the surface, a warning is logged and
+    /// [`PresentMode::Fifo`] is used instead.
+    ///
+    /// The surface is only re-configured when the present mode actually changes.
+    pub fn set_present_mode(&mut self, mode: PresentMode) {
+        let mode = if self.present_modes.contains(&mode) {
+            mode
+        } else {
+            bns_log::warning!("present mode {mode:?} is not supported, falling back to Fifo");
+            PresentMode::Fifo
+        };
+
+        if self.config.present_mode != mode {
+            bns_log::info!("present mode: {:?}", mode);
+            self.config_mut().present_mode = mode;
+        }
+    }
+
     /// Returns a shared reference to the [`SurfaceConfig`] of this [`Surface`].
     #[inline]
     pub fn config(&self) -> &SurfaceConfig {
@@ -241,7 +305,10 @@ impl Frame {
     /// Returns the [`RenderTarget`] of this frame.
     #[inline]
     pub fn target(&self) -> RenderTarget {
-        RenderTarget { view: &self.view }
+        RenderTarget {
+            view: &self.view,
+            size: (self.texture.texture.width(), self.texture.texture.height()),
+        }
     }
 
     /// Present this frame to the [`Surface`].
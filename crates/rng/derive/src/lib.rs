@@ -1,7 +1,12 @@
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{quote, ToTokens};
 
 /// Derives the [`FromRng`] trait.
+///
+/// - For structs, every field is generated using its own [`FromRng`] implementation.
+///
+/// - For enums, a variant is picked uniformly, and its fields (if any) are generated using
+///   their own [`FromRng`] implementation.
 #[proc_macro_derive(FromRng)]
 pub fn derive_from_rng(input: TokenStream) -> TokenStream {
     let s: syn::Item = syn::parse(input).expect("failed to parse the token stream");
@@ -9,19 +14,38 @@ pub fn derive_from_rng(input: TokenStream) -> TokenStream {
     let ret = match s {
         syn::Item::Struct(s) => {
             let name = s.ident;
-            let field_init = s.fields.iter().map(|field| {
-                let ident = field.ident.as_ref().unwrap();
+            let init = fields_init(&s.fields);
+
+            quote! {
+                impl bns_rng::FromRng for #name {
+                    #[allow(unused_variables)]
+                    fn from_rng(rng: &mut impl bns_rng::Rng) -> Self {
+                        Self #init
+                    }
+                }
+            }
+        }
+        syn::Item::Enum(e) => {
+            let name = e.ident;
+            let count = e.variants.len() as u32;
+            assert!(count != 0, "can't derive `FromRng` for an empty enum");
+
+            let arms = e.variants.iter().enumerate().map(|(index, variant)| {
+                let index = index as u32;
+                let ident = &variant.ident;
+                let init = fields_init(&variant.fields);
 
                 quote! {
-                    #ident : bns_rng::FromRng::from_rng(rng),
+                    #index => Self::#ident #init,
                 }
             });
 
             quote! {
                 impl bns_rng::FromRng for #name {
                     fn from_rng(rng: &mut impl bns_rng::Rng) -> Self {
-                        Self {
-                            #(#field_init)*
+                        match bns_rng::Rng::next_u32(rng) % #count {
+                            #(#arms)*
+                            _ => unreachable!(),
                         }
                     }
                 }
@@ -32,3 +56,39 @@ pub fn derive_from_rng(input: TokenStream) -> TokenStream {
 
     ret.into()
 }
+
+/// Generates the code that initializes the provided fields, to be placed after the path
+/// of the struct or variant being created.
+fn fields_init(fields: &syn::Fields) -> impl ToTokens {
+    match fields {
+        syn::Fields::Named(fields) => {
+            let field_init = fields.named.iter().map(|field| {
+                let ident = field.ident.as_ref().unwrap();
+
+                quote! {
+                    #ident : bns_rng::FromRng::from_rng(rng),
+                }
+            });
+
+            quote! {
+                {
+                    #(#field_init)*
+                }
+            }
+        }
+        syn::Fields::Unnamed(fields) => {
+            let field_init = fields.unnamed.iter().map(|_| {
+                quote! {
+                    bns_rng::FromRng::from_rng(rng),
+                }
+            });
+
+            quote! {
+                (
+                    #(#field_init)*
+                )
+            }
+        }
+        syn::Fields::Unit => quote! {},
+    }
+}
@@ -1,5 +1,7 @@
 //! A pseudo-random number generation library.
 
+use std::ops::Range;
+
 pub mod utility;
 
 pub mod noises;
@@ -39,6 +41,43 @@ pub trait Rng {
         self.next_u64() as u32
     }
 
+    /// Generates a random `u32` value in the range `[0, bound)`.
+    ///
+    /// Unlike `next_u32() % bound`, every possible value is equally likely to be returned. This
+    /// uses Lemire's multiply-shift method, which only rarely needs more than one call to
+    /// [`next_u32`](Rng::next_u32).
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `bound` is `0`.
+    fn next_u32_below(&mut self, bound: u32) -> u32 {
+        assert!(bound != 0, "`bound` must be non-zero");
+
+        let mut m = self.next_u32() as u64 * bound as u64;
+        if (m as u32) < bound {
+            // `2^32 % bound`, computed without overflowing.
+            let threshold = bound.wrapping_neg() % bound;
+            while (m as u32) < threshold {
+                m = self.next_u32() as u64 * bound as u64;
+            }
+        }
+
+        (m >> 32) as u32
+    }
+
+    /// Generates a random `i32` value in the provided range.
+    ///
+    /// Every value of the range is equally likely to be returned.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the range is empty.
+    fn next_range_i32(&mut self, range: Range<i32>) -> i32 {
+        assert!(range.start < range.end, "the range must not be empty");
+        let span = range.end.wrapping_sub(range.start) as u32;
+        range.start.wrapping_add(self.next_u32_below(span) as i32)
+    }
+
     /// Generates a random `f32` value in the range `[0.0, 1.0]`.
     #[inline(always)]
     fn next_f32_01(&mut self) -> f32 {
@@ -104,4 +143,99 @@ pub trait Noise<I> {
 
     /// Samples the provided input.
     fn sample(&self, input: I) -> Self::Output;
+
+    /// Estimates the gradient of the noise at the provided input.
+    ///
+    /// Each component of the returned value is the rate of change of the noise along the
+    /// corresponding axis. This is useful to compute normals or slopes of a terrain generated
+    /// from the noise.
+    ///
+    /// # Remarks
+    ///
+    /// The default implementation uses central finite differences, meaning that it samples the
+    /// noise twice per axis. Implementations are encouraged to provide an analytic version when
+    /// one is available.
+    fn gradient(&self, input: I) -> I
+    where
+        Self: Noise<I, Output = f32>,
+        I: DifferentiableInput,
+    {
+        input.finite_difference(|input| self.sample(input))
+    }
+}
+
+/// An input of a [`Noise`] along which the gradient of the noise can be estimated.
+///
+/// This is used by the default implementation of [`Noise::gradient`].
+pub trait DifferentiableInput: Copy {
+    /// Estimates the gradient of `f` at `self` using central finite differences.
+    fn finite_difference(self, f: impl FnMut(Self) -> f32) -> Self;
+}
+
+impl<const N: usize> DifferentiableInput for [f32; N] {
+    fn finite_difference(self, mut f: impl FnMut(Self) -> f32) -> Self {
+        /// The distance between the sampled points and the input.
+        ///
+        /// Smaller values are more accurate in theory, but quickly suffer from the lack of
+        /// precision of `f32` far from the origin.
+        const EPSILON: f32 = 1.0 / 512.0;
+
+        std::array::from_fn(|axis| {
+            let mut plus = self;
+            let mut minus = self;
+            plus[axis] += EPSILON;
+            minus[axis] -= EPSILON;
+            (f(plus) - f(minus)) / (2.0 * EPSILON)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_u32_below_is_below_bound() {
+        let mut rng = DefaultRng::from_seed(1);
+
+        for bound in [1, 2, 3, 7, 100, 1 << 31, u32::MAX] {
+            for _ in 0..1000 {
+                assert!(rng.next_u32_below(bound) < bound);
+            }
+        }
+    }
+
+    #[test]
+    fn next_u32_below_is_uniform() {
+        const BUCKETS: u32 = 10;
+        const SAMPLES: u32 = 100_000;
+
+        let mut rng = DefaultRng::from_seed(2);
+        let mut counts = [0u32; BUCKETS as usize];
+        for _ in 0..SAMPLES {
+            counts[rng.next_u32_below(BUCKETS) as usize] += 1;
+        }
+
+        // Every bucket is expected to receive 10000 samples, with a standard deviation of
+        // about 95. Allow for five of those.
+        let expected = SAMPLES / BUCKETS;
+        for count in counts {
+            assert!(count.abs_diff(expected) < 500, "{counts:?}");
+        }
+    }
+
+    #[test]
+    fn next_range_i32_covers_range() {
+        let mut rng = DefaultRng::from_seed(3);
+        let mut seen = [false; 7];
+
+        for _ in 0..1000 {
+            let value = rng.next_range_i32(-3..4);
+            assert!((-3..4).contains(&value));
+            seen[(value + 3) as usize] = true;
+        }
+
+        assert!(seen.iter().all(|&seen| seen));
+        assert_eq!(rng.next_range_i32(i32::MIN..i32::MIN + 1), i32::MIN);
+    }
 }
@@ -0,0 +1,117 @@
+use crate::Noise;
+
+/// An input of a [`Noise`] that can be scaled.
+///
+/// This is used by [`Fbm`] to sample the wrapped noise at different frequencies.
+pub trait ScalableInput: Copy {
+    /// Multiplies every coordinate of the input by `factor`.
+    fn scaled(self, factor: f32) -> Self;
+}
+
+impl<const N: usize> ScalableInput for [f32; N] {
+    #[inline(always)]
+    fn scaled(self, factor: f32) -> Self {
+        self.map(|x| x * factor)
+    }
+}
+
+/// Fractal brownian motion.
+///
+/// This wrapper sums multiple octaves of the wrapped noise, each with a higher frequency and
+/// a lower amplitude than the previous one.
+///
+/// # Output
+///
+/// The sum of all octaves is divided by the sum of their amplitudes. This means that if the
+/// wrapped noise returns values in the range `[-a, a]`, so does the [`Fbm`], regardless of the
+/// number of octaves (as long as the persistence is positive).
+///
+/// With a single octave, the output is exactly the output of the wrapped noise.
+#[derive(Clone, Debug)]
+pub struct Fbm<N> {
+    /// The wrapped noise.
+    pub noise: N,
+    /// The number of octaves to sum.
+    ///
+    /// A value of `0` is treated as `1`.
+    pub octaves: u32,
+    /// The factor by which the frequency is multiplied for every octave.
+    ///
+    /// This is usually `2.0`.
+    pub lacunarity: f32,
+    /// The factor by which the amplitude is multiplied for every octave.
+    ///
+    /// This is usually `0.5`.
+    pub persistence: f32,
+}
+
+impl<N> Fbm<N> {
+    /// Creates a new [`Fbm`] instance.
+    #[inline]
+    pub fn new(noise: N, octaves: u32, lacunarity: f32, persistence: f32) -> Self {
+        Self {
+            noise,
+            octaves,
+            lacunarity,
+            persistence,
+        }
+    }
+}
+
+impl<I, N> Noise<I> for Fbm<N>
+where
+    I: ScalableInput,
+    N: Noise<I, Output = f32>,
+{
+    type Output = f32;
+
+    fn sample(&self, input: I) -> Self::Output {
+        let mut total = self.noise.sample(input);
+        let mut total_amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+
+        for _ in 1..self.octaves {
+            frequency *= self.lacunarity;
+            amplitude *= self.persistence;
+            total += self.noise.sample(input.scaled(frequency)) * amplitude;
+            total_amplitude += amplitude;
+        }
+
+        total / total_amplitude
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noises::Perlin;
+    use crate::{DefaultRng, FromRng, Rng};
+
+    fn perlin() -> Perlin {
+        Perlin::from_rng(&mut DefaultRng::from_seed(3))
+    }
+
+    #[test]
+    fn single_octave_is_wrapped_noise() {
+        let noise = perlin();
+        let fbm = Fbm::new(noise.clone(), 1, 2.0, 0.5);
+
+        for i in 0..256 {
+            let p = [i as f32 * 0.31, i as f32 * -0.17];
+            assert_eq!(fbm.sample(p), noise.sample(p));
+        }
+    }
+
+    #[test]
+    fn octaves_stay_within_wrapped_range() {
+        for octaves in [2, 4, 8] {
+            let fbm = Fbm::new(perlin(), octaves, 2.0, 0.5);
+
+            for i in 0..256 {
+                let value = fbm.sample([i as f32 * 0.31, i as f32 * -0.17, i as f32 * 0.11]);
+                assert!((-1.0..=1.0).contains(&value), "{octaves} octaves: {value}");
+            }
+        }
+    }
+}
@@ -6,3 +6,12 @@ pub use mixer::*;
 
 mod voronoi;
 pub use voronoi::*;
+
+mod perlin;
+pub use perlin::*;
+
+mod fbm;
+pub use fbm::*;
+
+mod worley;
+pub use worley::*;
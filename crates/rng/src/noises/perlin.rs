@@ -0,0 +1,345 @@
+use crate::utility::floor_i32;
+use crate::{FromRng, Noise, Rng};
+
+/// Implementation of Ken Perlin's improved gradient noise.
+///
+/// # Output
+///
+/// The sampled values are always in the range `[-1.0, 1.0]`. Points whose coordinates are all
+/// integers lie on the lattice of the noise and always produce exactly `0.0`.
+///
+/// The noise repeats itself every 256 units along each axis.
+#[derive(Clone, Debug)]
+pub struct Perlin {
+    /// The permutation table used to pick the gradient of each lattice point.
+    ///
+    /// The first 256 entries are a permutation of `0..256`, and the last 256 entries are a
+    /// copy of the first ones. This avoids having to wrap indices when looking up the table.
+    perm: [u8; 512],
+}
+
+impl FromRng for Perlin {
+    fn from_rng(rng: &mut impl Rng) -> Self {
+        let mut perm = [0u8; 512];
+        for (i, p) in perm[..256].iter_mut().enumerate() {
+            *p = i as u8;
+        }
+
+        crate::utility::shuffle(rng, &mut perm[..256]);
+
+        perm.copy_within(0..256, 256);
+
+        Self { perm }
+    }
+}
+
+impl Perlin {
+    /// Looks up the permutation table.
+    #[inline(always)]
+    fn p(&self, index: usize) -> usize {
+        // SAFETY:
+        //  Callers only ever provide indices that are less than 512.
+        debug_assert!(index < 512);
+        unsafe { *self.perm.get_unchecked(index) as usize }
+    }
+}
+
+/// The fade curve used to interpolate between lattice points.
+///
+/// `6t^5 - 15t^4 + 10t^3`
+#[inline(always)]
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+/// The derivative of [`fade`].
+///
+/// `30t^4 - 60t^3 + 30t^2`
+#[inline(always)]
+fn fade_derivative(t: f32) -> f32 {
+    30.0 * t * t * (t - 1.0) * (t - 1.0)
+}
+
+/// Linearly interpolates between `a` and `b`.
+#[inline(always)]
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Returns the 2D gradient selected by `hash`.
+///
+/// This is the vector that [`grad2`] computes the dot product with.
+#[inline(always)]
+fn grad2_vector(hash: usize) -> [f32; 2] {
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    match hash & 7 {
+        0 => [1.0, 0.0],
+        1 => [-1.0, 0.0],
+        2 => [0.0, 1.0],
+        3 => [0.0, -1.0],
+        4 => [FRAC_1_SQRT_2, FRAC_1_SQRT_2],
+        5 => [-FRAC_1_SQRT_2, FRAC_1_SQRT_2],
+        6 => [FRAC_1_SQRT_2, -FRAC_1_SQRT_2],
+        _ => [-FRAC_1_SQRT_2, -FRAC_1_SQRT_2],
+    }
+}
+
+/// Computes the dot product between the offset `(x, y)` and the 2D gradient selected by `hash`.
+///
+/// Gradients are unit vectors.
+#[inline(always)]
+fn grad2(hash: usize, x: f32, y: f32) -> f32 {
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    match hash & 7 {
+        0 => x,
+        1 => -x,
+        2 => y,
+        3 => -y,
+        4 => (x + y) * FRAC_1_SQRT_2,
+        5 => (-x + y) * FRAC_1_SQRT_2,
+        6 => (x - y) * FRAC_1_SQRT_2,
+        _ => (-x - y) * FRAC_1_SQRT_2,
+    }
+}
+
+/// Computes the dot product between the offset `(x, y, z)` and the 3D gradient selected by
+/// `hash`.
+///
+/// Gradients point towards the middle of the edges of a cube, as described in the improved
+/// noise paper.
+#[inline(always)]
+fn grad3(hash: usize, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+impl Noise<[f32; 2]> for Perlin {
+    type Output = f32;
+
+    fn sample(&self, [x, y]: [f32; 2]) -> Self::Output {
+        let xi = floor_i32(x);
+        let yi = floor_i32(y);
+        let xf = x - xi as f32;
+        let yf = y - yi as f32;
+        let xi = (xi & 255) as usize;
+        let yi = (yi & 255) as usize;
+
+        let a = self.p(xi) + yi;
+        let b = self.p(xi + 1) + yi;
+
+        let u = fade(xf);
+        let v = fade(yf);
+
+        let value = lerp(
+            lerp(grad2(self.p(a), xf, yf), grad2(self.p(b), xf - 1.0, yf), u),
+            lerp(
+                grad2(self.p(a + 1), xf, yf - 1.0),
+                grad2(self.p(b + 1), xf - 1.0, yf - 1.0),
+                u,
+            ),
+            v,
+        );
+
+        // With unit gradients, the value of 2D gradient noise is bounded by `sqrt(2) / 2`.
+        (value * std::f32::consts::SQRT_2).clamp(-1.0, 1.0)
+    }
+
+    /// Computes the gradient of the noise analytically.
+    ///
+    /// The final clamp of [`sample`](Noise::sample) is ignored, as it almost never has any
+    /// effect.
+    fn gradient(&self, [x, y]: [f32; 2]) -> [f32; 2] {
+        let xi = floor_i32(x);
+        let yi = floor_i32(y);
+        let xf = x - xi as f32;
+        let yf = y - yi as f32;
+        let xi = (xi & 255) as usize;
+        let yi = (yi & 255) as usize;
+
+        let a = self.p(xi) + yi;
+        let b = self.p(xi + 1) + yi;
+
+        let g00 = grad2_vector(self.p(a));
+        let g10 = grad2_vector(self.p(b));
+        let g01 = grad2_vector(self.p(a + 1));
+        let g11 = grad2_vector(self.p(b + 1));
+
+        let n00 = g00[0] * xf + g00[1] * yf;
+        let n10 = g10[0] * (xf - 1.0) + g10[1] * yf;
+        let n01 = g01[0] * xf + g01[1] * (yf - 1.0);
+        let n11 = g11[0] * (xf - 1.0) + g11[1] * (yf - 1.0);
+
+        let u = fade(xf);
+        let v = fade(yf);
+        let du = fade_derivative(xf);
+        let dv = fade_derivative(yf);
+
+        // The noise can be written as:
+        //
+        // n00 + u * (n10 - n00) + v * (n01 - n00) + u * v * (n00 - n10 - n01 + n11)
+        //
+        // Where the derivative of each `nij` along an axis is the corresponding component of
+        // its gradient.
+        let k = n00 - n10 - n01 + n11;
+        let dx = g00[0]
+            + du * (n10 - n00)
+            + u * (g10[0] - g00[0])
+            + v * (g01[0] - g00[0])
+            + du * v * k
+            + u * v * (g00[0] - g10[0] - g01[0] + g11[0]);
+        let dy = g00[1]
+            + u * (g10[1] - g00[1])
+            + dv * (n01 - n00)
+            + v * (g01[1] - g00[1])
+            + u * dv * k
+            + u * v * (g00[1] - g10[1] - g01[1] + g11[1]);
+
+        [dx * std::f32::consts::SQRT_2, dy * std::f32::consts::SQRT_2]
+    }
+}
+
+impl Noise<[f32; 3]> for Perlin {
+    type Output = f32;
+
+    fn sample(&self, [x, y, z]: [f32; 3]) -> Self::Output {
+        let xi = floor_i32(x);
+        let yi = floor_i32(y);
+        let zi = floor_i32(z);
+        let xf = x - xi as f32;
+        let yf = y - yi as f32;
+        let zf = z - zi as f32;
+        let xi = (xi & 255) as usize;
+        let yi = (yi & 255) as usize;
+        let zi = (zi & 255) as usize;
+
+        let a = self.p(xi) + yi;
+        let aa = self.p(a) + zi;
+        let ab = self.p(a + 1) + zi;
+        let b = self.p(xi + 1) + yi;
+        let ba = self.p(b) + zi;
+        let bb = self.p(b + 1) + zi;
+
+        let u = fade(xf);
+        let v = fade(yf);
+        let w = fade(zf);
+
+        let value = lerp(
+            lerp(
+                lerp(
+                    grad3(self.p(aa), xf, yf, zf),
+                    grad3(self.p(ba), xf - 1.0, yf, zf),
+                    u,
+                ),
+                lerp(
+                    grad3(self.p(ab), xf, yf - 1.0, zf),
+                    grad3(self.p(bb), xf - 1.0, yf - 1.0, zf),
+                    u,
+                ),
+                v,
+            ),
+            lerp(
+                lerp(
+                    grad3(self.p(aa + 1), xf, yf, zf - 1.0),
+                    grad3(self.p(ba + 1), xf - 1.0, yf, zf - 1.0),
+                    u,
+                ),
+                lerp(
+                    grad3(self.p(ab + 1), xf, yf - 1.0, zf - 1.0),
+                    grad3(self.p(bb + 1), xf - 1.0, yf - 1.0, zf - 1.0),
+                    u,
+                ),
+                v,
+            ),
+            w,
+        );
+
+        // The gradients are not normalized, which may cause the value to slightly exceed the
+        // unit range in rare cases.
+        value.clamp(-1.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultRng;
+
+    /// Returns a few sample points that are not on the lattice of the noise.
+    fn points() -> impl Iterator<Item = [f32; 3]> {
+        (0..512).map(|i| {
+            let i = i as f32;
+            [i * 0.37 - 40.0, i * 0.73 + 3.1, i * -0.19 + 7.7]
+        })
+    }
+
+    #[test]
+    fn same_seed_same_field() {
+        let a = Perlin::from_rng(&mut DefaultRng::from_seed(42));
+        let b = Perlin::from_rng(&mut DefaultRng::from_seed(42));
+        let c = Perlin::from_rng(&mut DefaultRng::from_seed(43));
+
+        let mut differs = false;
+        for [x, y, z] in points() {
+            let value: f32 = a.sample([x, y]);
+            assert_eq!(value, b.sample([x, y]));
+            assert_eq!(a.sample([x, y, z]), b.sample([x, y, z]));
+            differs |= value != c.sample([x, y]);
+        }
+        assert!(differs);
+    }
+
+    #[test]
+    fn lattice_points_are_zero() {
+        let noise = Perlin::from_rng(&mut DefaultRng::from_seed(7));
+
+        for x in -8..8 {
+            for y in -8..8 {
+                let (x, y) = (x as f32, y as f32);
+                assert_eq!(noise.sample([x, y]), 0.0);
+                assert_eq!(noise.sample([x, y, x - y]), 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn values_are_in_unit_range() {
+        let noise = Perlin::from_rng(&mut DefaultRng::from_seed(1));
+
+        for [x, y, z] in points() {
+            let value: f32 = noise.sample([x, y]);
+            assert!((-1.0..=1.0).contains(&value), "{value}");
+            let value: f32 = noise.sample([x, y, z]);
+            assert!((-1.0..=1.0).contains(&value), "{value}");
+        }
+    }
+
+    #[test]
+    fn analytic_gradient_matches_finite_differences() {
+        use crate::DifferentiableInput;
+
+        let noise = Perlin::from_rng(&mut DefaultRng::from_seed(5));
+
+        for [x, y, _] in points() {
+            let analytic = noise.gradient([x, y]);
+            let estimated = [x, y].finite_difference(|input| noise.sample(input));
+
+            for axis in 0..2 {
+                assert!(
+                    (analytic[axis] - estimated[axis]).abs() < 1e-2,
+                    "gradient at {:?}: {analytic:?}, expected {estimated:?}",
+                    [x, y],
+                );
+            }
+        }
+    }
+}
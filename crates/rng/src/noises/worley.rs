@@ -0,0 +1,198 @@
+use super::Mixer;
+use crate::utility::{f32_from_u32_01, floor_i32};
+use crate::{FromRng, Noise, Rng};
+
+/// The value returned by a [`Worley`] noise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WorleyMode {
+    /// The distance to the nearest feature point.
+    #[default]
+    F1,
+    /// The distance to the second nearest feature point, minus the distance to the nearest one.
+    ///
+    /// This produces thin ridges along the borders of the cells.
+    F2MinusF1,
+}
+
+/// Implementation of Worley (cellular) noise.
+///
+/// Every integer cell of the space contains exactly one feature point, whose position within
+/// the cell only depends on the seed of the noise and the coordinates of the cell.
+///
+/// # Output
+///
+/// The returned value is a distance, and is therefore never negative. See [`WorleyMode`] for
+/// the possible outputs.
+#[derive(Debug, Clone)]
+pub struct Worley {
+    /// Used to compute the X and Y coordinates of the feature points.
+    xy: Mixer<3>,
+    /// Used to compute the Z coordinate of the feature points.
+    z: Mixer<3>,
+    /// The value returned by the noise.
+    pub mode: WorleyMode,
+}
+
+impl FromRng for Worley {
+    fn from_rng(rng: &mut impl Rng) -> Self {
+        Self {
+            xy: Mixer::from_rng(rng),
+            z: Mixer::from_rng(rng),
+            mode: WorleyMode::F1,
+        }
+    }
+}
+
+impl Worley {
+    /// Returns a copy of this noise that returns the provided value.
+    #[inline]
+    pub fn with_mode(mut self, mode: WorleyMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Computes the position of the feature point of the provided cell.
+    ///
+    /// The returned position is relative to the cell's origin, with every coordinate in the
+    /// range `[0.0, 1.0]`.
+    pub fn feature_point(&self, [x, y, z]: [i32; 3]) -> [f32; 3] {
+        let cell = [x as u64, y as u64, z as u64];
+        let xy = self.xy.sample(cell);
+        [
+            f32_from_u32_01(xy as u32),
+            f32_from_u32_01((xy >> 32) as u32),
+            f32_from_u32_01(self.z.sample(cell) as u32),
+        ]
+    }
+
+    /// Computes the final value of the noise from the squared distances to the two nearest
+    /// feature points.
+    #[inline]
+    fn finish(&self, f1_sq: f32, f2_sq: f32) -> f32 {
+        match self.mode {
+            WorleyMode::F1 => f1_sq.sqrt(),
+            WorleyMode::F2MinusF1 => f2_sq.sqrt() - f1_sq.sqrt(),
+        }
+    }
+}
+
+/// Inserts the squared distance `d` in the sorted pair `(f1, f2)`.
+#[inline(always)]
+fn insert_distance(f1: &mut f32, f2: &mut f32, d: f32) {
+    if d < *f1 {
+        *f2 = *f1;
+        *f1 = d;
+    } else if d < *f2 {
+        *f2 = d;
+    }
+}
+
+impl Noise<[f32; 2]> for Worley {
+    type Output = f32;
+
+    fn sample(&self, [x, y]: [f32; 2]) -> Self::Output {
+        let xi = floor_i32(x);
+        let yi = floor_i32(y);
+        let xf = x - xi as f32;
+        let yf = y - yi as f32;
+
+        let mut f1 = f32::INFINITY;
+        let mut f2 = f32::INFINITY;
+
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let pt = self.feature_point([xi + dx, yi + dy, 0]);
+                let diff = [dx as f32 + pt[0] - xf, dy as f32 + pt[1] - yf];
+                insert_distance(&mut f1, &mut f2, diff[0] * diff[0] + diff[1] * diff[1]);
+            }
+        }
+
+        self.finish(f1, f2)
+    }
+}
+
+impl Noise<[f32; 3]> for Worley {
+    type Output = f32;
+
+    fn sample(&self, [x, y, z]: [f32; 3]) -> Self::Output {
+        let xi = floor_i32(x);
+        let yi = floor_i32(y);
+        let zi = floor_i32(z);
+        let xf = x - xi as f32;
+        let yf = y - yi as f32;
+        let zf = z - zi as f32;
+
+        let mut f1 = f32::INFINITY;
+        let mut f2 = f32::INFINITY;
+
+        for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let pt = self.feature_point([xi + dx, yi + dy, zi + dz]);
+                    let diff = [
+                        dx as f32 + pt[0] - xf,
+                        dy as f32 + pt[1] - yf,
+                        dz as f32 + pt[2] - zf,
+                    ];
+                    insert_distance(
+                        &mut f1,
+                        &mut f2,
+                        diff[0] * diff[0] + diff[1] * diff[1] + diff[2] * diff[2],
+                    );
+                }
+            }
+        }
+
+        self.finish(f1, f2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultRng;
+
+    /// Returns a few sample points, some of them far away from the origin.
+    fn points() -> impl Iterator<Item = [f32; 3]> {
+        (0..512).map(|i| {
+            let i = i as f32;
+            [i * 0.37 - 90.0, i * 0.73 + 3.1, i * -1.19 + 7.7]
+        })
+    }
+
+    #[test]
+    fn same_seed_same_field() {
+        let a = Worley::from_rng(&mut DefaultRng::from_seed(5));
+        let b = Worley::from_rng(&mut DefaultRng::from_seed(5));
+
+        for [x, y, z] in points() {
+            assert_eq!(a.sample([x, y]), b.sample([x, y]));
+            assert_eq!(a.sample([x, y, z]), b.sample([x, y, z]));
+        }
+    }
+
+    #[test]
+    fn distances_are_non_negative() {
+        for mode in [WorleyMode::F1, WorleyMode::F2MinusF1] {
+            let noise = Worley::from_rng(&mut DefaultRng::from_seed(9)).with_mode(mode);
+
+            for [x, y, z] in points() {
+                let value: f32 = noise.sample([x, y]);
+                assert!(value >= 0.0, "{mode:?}: {value}");
+                let value: f32 = noise.sample([x, y, z]);
+                assert!(value >= 0.0, "{mode:?}: {value}");
+            }
+        }
+    }
+
+    #[test]
+    fn feature_points_are_zero() {
+        let noise = Worley::from_rng(&mut DefaultRng::from_seed(11));
+
+        for cell in [[0, 0, 0], [-3, 7, 0], [120, -45, 0]] {
+            let [x, y, _] = noise.feature_point(cell);
+            let p = [cell[0] as f32 + x, cell[1] as f32 + y];
+            assert!(noise.sample(p) < 1e-4);
+        }
+    }
+}
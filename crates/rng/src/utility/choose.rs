@@ -0,0 +1,102 @@
+use crate::Rng;
+
+/// Picks a random element from the provided slice.
+///
+/// Every element is equally likely to be picked.
+///
+/// # Returns
+///
+/// This function returns [`None`] if the slice is empty.
+pub fn choose<'a, T>(rng: &mut impl Rng, items: &'a [T]) -> Option<&'a T> {
+    if items.is_empty() {
+        return None;
+    }
+
+    let len = u32::try_from(items.len()).expect("too many items to choose from");
+    Some(&items[rng.next_u32_below(len) as usize])
+}
+
+/// Picks a random element from the provided slice, where the probability of each element to
+/// be picked is proportional to its weight.
+///
+/// Negative weights are treated as zero. If every weight is zero, the elements are picked
+/// uniformly (as if [`choose`] had been called).
+///
+/// # Panics
+///
+/// This function panics if the slice is empty.
+pub fn choose_weighted<'a, T>(rng: &mut impl Rng, items: &'a [(T, f32)]) -> &'a T {
+    assert!(!items.is_empty(), "can't choose from an empty slice");
+
+    let total: f32 = items.iter().map(|(_, w)| w.max(0.0)).sum();
+    if total <= 0.0 {
+        return &choose(rng, items).unwrap().0;
+    }
+
+    let mut value = rng.next_f32_01() * total;
+    let mut last = None;
+    for (item, weight) in items {
+        let weight = weight.max(0.0);
+        if weight == 0.0 {
+            continue;
+        }
+        if value < weight {
+            return item;
+        }
+        value -= weight;
+        last = Some(item);
+    }
+
+    // Rounding errors (or a value of exactly `total`) may prevent the loop from returning.
+    // In that case, the last element with a non-zero weight is picked.
+    last.unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultRng;
+
+    #[test]
+    fn choose_is_deterministic() {
+        let items = [1, 2, 3, 4, 5, 6, 7, 8];
+        let picks = |seed| {
+            let mut rng = DefaultRng::from_seed(seed);
+            (0..32)
+                .map(|_| *choose(&mut rng, &items).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(picks(4), picks(4));
+        assert_eq!(choose(&mut DefaultRng::from_seed(4), &[] as &[i32]), None);
+    }
+
+    #[test]
+    fn choose_weighted_is_deterministic() {
+        let items = [('a', 1.0), ('b', 0.0), ('c', 3.0), ('d', -2.0)];
+        let picks = |seed| {
+            let mut rng = DefaultRng::from_seed(seed);
+            (0..64)
+                .map(|_| *choose_weighted(&mut rng, &items))
+                .collect::<Vec<_>>()
+        };
+
+        let a = picks(6);
+        assert_eq!(a, picks(6));
+        assert!(a.iter().all(|&c| c == 'a' || c == 'c'));
+    }
+
+    #[test]
+    fn choose_weighted_with_zero_weights_is_uniform() {
+        let items = [('a', 0.0), ('b', 0.0), ('c', 0.0)];
+        let mut rng = DefaultRng::from_seed(8);
+        let mut seen = [false; 3];
+
+        for _ in 0..100 {
+            let c = *choose_weighted(&mut rng, &items);
+            seen[(c as u8 - b'a') as usize] = true;
+        }
+
+        assert_eq!(seen, [true; 3]);
+    }
+}
@@ -8,3 +8,9 @@ pub use convert::*;
 
 mod primes;
 pub use primes::*;
+
+mod choose;
+pub use choose::*;
+
+mod shuffle;
+pub use shuffle::*;
@@ -0,0 +1,51 @@
+use crate::Rng;
+
+/// Shuffles the provided slice in place using the Fisher-Yates algorithm.
+///
+/// Every permutation of the slice is equally likely.
+///
+/// # Panics
+///
+/// This function panics if the slice has more than `u32::MAX` elements.
+pub fn shuffle<T>(rng: &mut impl Rng, slice: &mut [T]) {
+    let len = u32::try_from(slice.len()).expect("too many items to shuffle");
+    for i in (1..len).rev() {
+        let j = rng.next_u32_below(i + 1);
+        slice.swap(i as usize, j as usize);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultRng;
+
+    #[test]
+    fn shuffle_is_deterministic_and_keeps_elements() {
+        let shuffled = |seed| {
+            let mut items: Vec<u32> = (0..32).collect();
+            shuffle(&mut DefaultRng::from_seed(seed), &mut items);
+            items
+        };
+
+        let a = shuffled(12);
+        assert_eq!(a, shuffled(12));
+        assert_ne!(a, (0..32).collect::<Vec<_>>());
+
+        let mut sorted = a.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn short_slices_are_left_untouched() {
+        let mut rng = DefaultRng::from_seed(0);
+
+        let mut empty: [u32; 0] = [];
+        shuffle(&mut rng, &mut empty);
+
+        let mut single = [42];
+        shuffle(&mut rng, &mut single);
+        assert_eq!(single, [42]);
+    }
+}
@@ -0,0 +1,64 @@
+use bns_rng::{DefaultRng, FromRng, Rng};
+
+#[derive(FromRng, Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+#[derive(FromRng, Debug, Clone, Copy, PartialEq)]
+enum Shape {
+    Point,
+    Circle(f32),
+    Rect(u32, u32),
+    Named { id: u64 },
+}
+
+#[derive(FromRng, Debug, Clone, Copy, PartialEq)]
+struct Pair(u32, f32);
+
+/// Generates `count` values of type `T` from the provided seed.
+fn generate<T: FromRng>(seed: u64, count: usize) -> Vec<T> {
+    let mut rng = DefaultRng::from_seed(seed);
+    (0..count).map(|_| T::from_rng(&mut rng)).collect()
+}
+
+#[test]
+fn c_like_enum_picks_every_variant() {
+    let values = generate::<Direction>(1, 256);
+    assert_eq!(values, generate::<Direction>(1, 256));
+
+    for variant in [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ] {
+        assert!(values.contains(&variant), "{variant:?} was never picked");
+    }
+}
+
+#[test]
+fn enum_with_fields_generates_every_variant() {
+    let values = generate::<Shape>(2, 256);
+    assert_eq!(values, generate::<Shape>(2, 256));
+
+    assert!(values.iter().any(|s| matches!(s, Shape::Point)));
+    assert!(values.iter().any(|s| matches!(s, Shape::Circle(_))));
+    assert!(values.iter().any(|s| matches!(s, Shape::Rect(_, _))));
+    assert!(values.iter().any(|s| matches!(s, Shape::Named { .. })));
+
+    // The fields of the variants are generated too, rather than defaulted.
+    assert!(values
+        .iter()
+        .any(|s| matches!(s, Shape::Rect(a, b) if *a != 0 && *b != 0)));
+}
+
+#[test]
+fn tuple_struct_generates_every_field() {
+    let values = generate::<Pair>(3, 16);
+    assert_eq!(values, generate::<Pair>(3, 16));
+    assert!(values.iter().any(|p| p.0 != 0 && p.1 != 0.0));
+}
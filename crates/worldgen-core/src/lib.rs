@@ -2,7 +2,37 @@
 
 use bns_core::{Chunk, ChunkPos};
 
-use glam::IVec3;
+use glam::{IVec3, Vec3};
+
+/// An error that occurred while generating a chunk.
+#[derive(Debug, Clone)]
+pub struct GenerationError {
+    /// A message describing what went wrong.
+    message: String,
+}
+
+impl GenerationError {
+    /// Creates a new [`GenerationError`] with the provided message.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    /// Returns the message describing what went wrong.
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::fmt::Display for GenerationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for GenerationError {}
 
 /// Describes how to generate new chunks for a world.
 pub trait WorldGenerator: Send + Sync {
@@ -14,13 +44,67 @@ pub trait WorldGenerator: Send + Sync {
     /// should produce the same exact chunk.
     fn generate(&self, pos: ChunkPos) -> Chunk;
 
+    /// Attempts to generate a chunk for the provided position.
+    ///
+    /// Generators that may run into an unexpected state should override this function rather
+    /// than producing a partially generated chunk.
+    ///
+    /// The default implementation simply forwards to [`generate`] and never fails.
+    ///
+    /// # Errors
+    ///
+    /// This function fails if the generator was unable to produce the chunk. The chunk won't be
+    /// loaded in that case.
+    ///
+    /// [`generate`]: WorldGenerator::generate
+    fn try_generate(&self, pos: ChunkPos) -> Result<Chunk, GenerationError> {
+        Ok(self.generate(pos))
+    }
+
     /// Requests the world generator to cleanup any unused memory.
     ///
     /// The provided cylinder describes the area that should be kept in memory. Any chunks that
     /// are outside of this cylinder can be unloaded.
     fn request_cleanup(&self, center: ChunkPos, h_radius: u32, v_radius: u32);
 
+    /// Returns the seed that was used to create this world generator.
+    ///
+    /// Two generators of the same type created with the same seed are expected to generate the
+    /// exact same chunks.
+    fn seed(&self) -> u64;
+
+    /// Returns the color of the ambient light at the provided position, in linear RGB.
+    ///
+    /// This is used to give different areas of the world (such as biomes) a subtly different
+    /// atmosphere. The returned color is added to the lighting of the scene and should
+    /// therefore remain fairly dark.
+    ///
+    /// The default implementation returns black (no ambient light).
+    fn ambient_color(&self, pos: IVec3) -> Vec3 {
+        let _ = pos;
+        Vec3::ZERO
+    }
+
     /// Prints debug information about a particular position in the world using the provided
     /// buffer.
+    ///
+    /// # Errors
+    ///
+    /// This function only fails if writing to `buf` fails. The error is forwarded to the caller
+    /// as-is.
     fn debug_info(&self, buf: &mut dyn std::fmt::Write, pos: IVec3) -> std::fmt::Result;
+
+    /// Returns debug information about a particular position in the world as a list of
+    /// key/value pairs.
+    ///
+    /// Unlike [`debug_info`], the returned information is meant to be consumed by tools rather
+    /// than read by a human.
+    ///
+    /// The default implementation returns an empty list.
+    ///
+    /// [`debug_info`]: WorldGenerator::debug_info
+    fn debug_fields(&self, pos: IVec3) -> Vec<(String, String)> {
+        let _ = pos;
+        Vec::new()
+    }
 }
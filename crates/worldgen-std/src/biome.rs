@@ -4,7 +4,9 @@ use bns_core::{Chunk, ChunkPos};
 use bns_rng::{FromRng, Rng};
 
 use bytemuck::{Contiguous, Zeroable};
-use glam::{IVec2, IVec3};
+use glam::{IVec2, IVec3, Vec3};
+use parking_lot::RwLock;
+use smallvec::SmallVec;
 
 use crate::biomemap::Climate;
 use crate::chunk_gen::PendingStructure;
@@ -37,14 +39,36 @@ impl BiomeId {
     }
 }
 
-/// Stores information about a particular biome.
-pub struct BiomeInfo {
+/// The range of climates in which a biome is allowed to spawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClimateBounds {
     /// The allowed continentality range for the biome.
-    pub continentality_range: (f32, f32),
+    pub continentality: (f32, f32),
     /// The allowed temperature range for the biome.
-    pub temperature_range: (f32, f32),
+    pub temperature: (f32, f32),
     /// The allowed humidity range for the biome.
-    pub humidity_range: (f32, f32),
+    pub humidity: (f32, f32),
+}
+
+impl ClimateBounds {
+    /// Returns whether the provided [`Climate`] is within these bounds.
+    pub fn contains(&self, climate: &Climate) -> bool {
+        self.continentality.0 <= climate.continentality
+            && climate.continentality <= self.continentality.1
+            && self.temperature.0 <= climate.temperature
+            && climate.temperature <= self.temperature.1
+            && self.humidity.0 <= climate.humidity
+            && climate.humidity <= self.humidity.1
+    }
+}
+
+/// Stores information about a particular biome.
+pub struct BiomeInfo {
+    /// The default climate bounds of the biome.
+    ///
+    /// The bounds that are actually used to select biomes are stored in the [`BiomeRegistry`]
+    /// and may be modified at runtime.
+    pub climate: ClimateBounds,
     /// A weight value used to determine how likely the biome is to spawn compared to the other
     /// biomes.
     pub weight: u32,
@@ -55,26 +79,19 @@ pub struct BiomeInfo {
     ///
     /// [`register_structures`]: Biome::register_structures
     pub max_structure_size: i32,
+    /// The color of the ambient light in the biome, in linear RGB.
+    ///
+    /// See [`WorldGenerator::ambient_color`](bns_worldgen_core::WorldGenerator::ambient_color).
+    pub ambient_color: Vec3,
     /// The [`Biome`] implementation associated with the biome.
     pub implementation: Box<dyn Biome>,
 }
 
-impl BiomeInfo {
-    /// Returns whether the provided [`Climate`] is allowed to spawn in a biome with this
-    /// [`BiomeInfo`].
-    pub fn is_climate_allowed(&self, climate: &Climate) -> bool {
-        self.continentality_range.0 <= climate.continentality
-            && climate.continentality <= self.continentality_range.1
-            && self.temperature_range.0 <= climate.temperature
-            && climate.temperature <= self.temperature_range.1
-            && self.humidity_range.0 <= climate.humidity
-            && climate.humidity <= self.humidity_range.1
-    }
-}
-
 /// The interface that's provided to biomes to generate new chunks.
 pub trait Biome: FromRng + Send + Sync {
     /// Returns the height value of the biome at the provided position.
+    ///
+    /// The returned value is relative to the sea level of the world (see [`GenCtx::sea_level`]).
     fn height(&self, pos: IVec2) -> f32;
 
     /// Place the base blocks of the biome in the provided chunk.
@@ -103,6 +120,10 @@ pub struct BiomeRegistry {
     max_structure_size: i32,
     /// The created biomes.
     biomes: [BiomeInfo; BiomeId::COUNT],
+    /// The climate bounds currently used to select biomes.
+    ///
+    /// Those start out as the default bounds of each biome, but may be tuned at runtime.
+    climate_bounds: RwLock<[ClimateBounds; BiomeId::COUNT]>,
 }
 
 impl BiomeRegistry {
@@ -111,6 +132,36 @@ impl BiomeRegistry {
     pub fn max_structure_size(&self) -> i32 {
         self.max_structure_size
     }
+
+    /// Returns the climate bounds currently used for the provided biome.
+    #[inline]
+    pub fn climate_bounds(&self, biome: BiomeId) -> ClimateBounds {
+        self.climate_bounds.read()[biome as usize]
+    }
+
+    /// Sets the climate bounds used for the provided biome.
+    ///
+    /// # Remarks
+    ///
+    /// This does not invalidate any cached generation data. Callers are responsible for
+    /// clearing the cache if they want the change to be visible.
+    #[inline]
+    pub fn set_climate_bounds(&self, biome: BiomeId, bounds: ClimateBounds) {
+        self.climate_bounds.write()[biome as usize] = bounds;
+    }
+
+    /// Restores the default climate bounds of every biome.
+    pub fn reset_climate_bounds(&self) {
+        *self.climate_bounds.write() = std::array::from_fn(|i| self.biomes[i].climate);
+    }
+
+    /// Returns the list of biomes that are allowed to spawn in the provided [`Climate`].
+    pub fn biomes_for_climate(&self, climate: &Climate) -> SmallVec<[BiomeId; 8]> {
+        let bounds = self.climate_bounds.read();
+        BiomeId::iter_all()
+            .filter(|&x| bounds[x as usize].contains(climate))
+            .collect()
+    }
 }
 
 impl FromRng for BiomeRegistry {
@@ -118,62 +169,81 @@ impl FromRng for BiomeRegistry {
         let biomes = [
             // Plains
             BiomeInfo {
-                continentality_range: (0.0, 1.0),
-                temperature_range: (-1.0, 1.0),
-                humidity_range: (-1.0, 1.0),
+                climate: ClimateBounds {
+                    continentality: (0.0, 1.0),
+                    temperature: (-1.0, 1.0),
+                    humidity: (-1.0, 1.0),
+                },
                 weight: 200,
                 max_structure_size: 1,
+                ambient_color: Vec3::new(0.03, 0.03, 0.04),
                 implementation: Box::new(crate::biomes::Plains::from_rng(rng)),
             },
             // OakForest
             BiomeInfo {
-                continentality_range: (0.0, 1.0),
-                temperature_range: (-1.0, 1.0),
-                humidity_range: (-1.0, 1.0),
+                climate: ClimateBounds {
+                    continentality: (0.0, 1.0),
+                    temperature: (-1.0, 1.0),
+                    humidity: (-1.0, 1.0),
+                },
                 weight: 100,
                 max_structure_size: 1,
+                ambient_color: Vec3::new(0.02, 0.04, 0.02),
                 implementation: Box::new(crate::biomes::OakForest::from_rng(rng)),
             },
             // Desert
             BiomeInfo {
-                continentality_range: (0.0, 1.0),
-                temperature_range: (0.0, 1.0),
-                humidity_range: (0.0, 1.0),
+                climate: ClimateBounds {
+                    continentality: (0.0, 1.0),
+                    temperature: (0.0, 1.0),
+                    humidity: (0.0, 1.0),
+                },
                 weight: 100,
                 max_structure_size: 0,
+                ambient_color: Vec3::new(0.06, 0.05, 0.02),
                 implementation: Box::new(crate::biomes::Desert::from_rng(rng)),
             },
             // PineForest
             BiomeInfo {
-                continentality_range: (0.0, 0.2),
-                temperature_range: (-1.0, 1.0),
-                humidity_range: (-1.0, 0.5),
+                climate: ClimateBounds {
+                    continentality: (0.0, 0.2),
+                    temperature: (-1.0, 1.0),
+                    humidity: (-1.0, 0.5),
+                },
                 weight: 100,
                 max_structure_size: 0,
+                ambient_color: Vec3::new(0.02, 0.03, 0.04),
                 implementation: Box::new(crate::biomes::PineForest::from_rng(rng)),
             },
             // Ocean
             BiomeInfo {
-                continentality_range: (-1.0, 0.0),
-                temperature_range: (-1.0, 1.0),
-                humidity_range: (-1.0, 1.0),
+                climate: ClimateBounds {
+                    continentality: (-1.0, 0.0),
+                    temperature: (-1.0, 1.0),
+                    humidity: (-1.0, 1.0),
+                },
                 weight: 200,
                 max_structure_size: 0,
+                ambient_color: Vec3::new(0.01, 0.03, 0.06),
                 implementation: Box::new(crate::biomes::Ocean::from_rng(rng)),
             },
             // Mountain
             BiomeInfo {
-                continentality_range: (0.0, 1.0),
-                temperature_range: (-1.0, 1.0),
-                humidity_range: (-1.0, 1.0),
+                climate: ClimateBounds {
+                    continentality: (0.0, 1.0),
+                    temperature: (-1.0, 1.0),
+                    humidity: (-1.0, 1.0),
+                },
                 weight: 10,
                 max_structure_size: 0,
+                ambient_color: Vec3::new(0.04, 0.04, 0.05),
                 implementation: Box::new(crate::biomes::Mountain::from_rng(rng)),
             },
         ];
 
         Self {
             max_structure_size: biomes.iter().fold(0, |a, b| a.max(b.max_structure_size)),
+            climate_bounds: RwLock::new(std::array::from_fn(|i| biomes[i].climate)),
             biomes,
         }
     }
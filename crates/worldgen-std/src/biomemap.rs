@@ -1,8 +1,7 @@
 use bns_rng::noises::{Mixer, SuperSimplex2, Voronoi2};
 use bns_rng::{FromRng, Noise};
 
-use glam::IVec2;
-use smallvec::SmallVec;
+use glam::{IVec2, Vec2};
 
 use crate::biome::{BiomeId, BiomeRegistry};
 
@@ -104,11 +103,11 @@ impl BiomeCellMap {
     pub const DISPLACEMENT: f32 = 1.0 / 8.0;
 }
 
-impl Noise<IVec2> for BiomeCellMap {
+impl Noise<Vec2> for BiomeCellMap {
     type Output = BiomeCell;
 
-    fn sample(&self, pos: IVec2) -> Self::Output {
-        let pos = pos.as_vec2() * Self::SCALE * 0.5;
+    fn sample(&self, pos: Vec2) -> Self::Output {
+        let pos = pos * Self::SCALE * 0.5;
         let disp_x =
             self.displacement_x.sample((pos * Self::ROUGHNESS).into()) * Self::DISPLACEMENT;
         let disp_y =
@@ -129,50 +128,182 @@ pub struct BiomeMap {
 
 impl BiomeMap {
     /// Returns the biome that should be placed on the tile at the provided position.
+    ///
+    /// The `stretch` parameter is the amount by which the biomes are stretched along each
+    /// axis. A value of `1.0` on both axes produces regular (isotropic) biomes.
     #[profiling::function]
-    pub fn sample(&self, pos: IVec2, registry: &BiomeRegistry) -> BiomeId {
-        let cell = self.cells.sample(pos);
+    pub fn sample(&self, pos: IVec2, stretch: Vec2, registry: &BiomeRegistry) -> BiomeId {
+        let cell = self.cells.sample(pos.as_vec2() / stretch);
         let climate = self.climate.sample(cell);
-        let biomes: SmallVec<[BiomeId; 8]> = BiomeId::iter_all()
-            .filter(|&x| registry[x].is_climate_allowed(&climate))
-            .collect();
-        let total_weight = biomes.iter().map(|&id| registry[id].weight).sum::<u32>();
-        let mut biome_value =
-            self.hasher.sample([cell.x as u64, cell.y as u64]) as u32 % total_weight;
-
-        let mut index = 0;
-        while biome_value > 0 {
-            // SAFETY:
-            //  The biome value is non-zero, meaning that some biome must have existed to
-            //  increase the total weight.
-            debug_assert!(index < biomes.len());
-            let biome = unsafe { *biomes.get_unchecked(index) };
-            index += 1;
-
-            if biome_value < registry[biome].weight {
-                return biome;
-            }
-
-            biome_value -= registry[biome].weight;
-        }
+        let hash = self.hasher.sample([cell.x as u64, cell.y as u64]) as u32;
+        select_biome(&climate, hash, registry)
+    }
 
-        BiomeId::Plains
+    /// Returns the climate at the provided position.
+    ///
+    /// See [`BiomeMap::sample`] for a description of the `stretch` parameter.
+    pub fn climate_at(&self, pos: IVec2, stretch: Vec2) -> Climate {
+        self.climate
+            .sample(self.cells.sample(pos.as_vec2() / stretch))
     }
 
+    /// Writes the climate and the biome at the provided position to `w`.
+    ///
+    /// See [`BiomeMap::sample`] for a description of the `stretch` parameter.
     pub fn debug_info(
         &self,
         w: &mut dyn std::fmt::Write,
         registry: &BiomeRegistry,
+        stretch: Vec2,
         pos: IVec2,
     ) -> std::fmt::Result {
-        let climate = self.climate.sample(self.cells.sample(pos));
+        let climate = self.climate_at(pos, stretch);
         writeln!(
             w,
             "Climate: {:.2}, Temperature: {:.2}, Humidity: {:.2}",
             climate.continentality, climate.temperature, climate.humidity
         )?;
-        writeln!(w, "Biome: {:?}", self.sample(pos, registry))?;
+        writeln!(w, "Biome: {:?}", self.sample(pos, stretch, registry))?;
 
         Ok(())
     }
 }
+
+/// Selects a biome among those allowed in the provided [`Climate`], using `hash` to pick one
+/// according to their weights.
+///
+/// When no biome is allowed in the climate (which can happen once the climate bounds have been
+/// tuned at runtime), [`BiomeId::Plains`] is returned.
+fn select_biome(climate: &Climate, hash: u32, registry: &BiomeRegistry) -> BiomeId {
+    let biomes = registry.biomes_for_climate(climate);
+    let total_weight = biomes.iter().map(|&id| registry[id].weight).sum::<u32>();
+    if total_weight == 0 {
+        return BiomeId::Plains;
+    }
+
+    let mut biome_value = hash % total_weight;
+
+    let mut index = 0;
+    while biome_value > 0 {
+        // SAFETY:
+        //  The biome value is non-zero, meaning that some biome must have existed to
+        //  increase the total weight.
+        debug_assert!(index < biomes.len());
+        let biome = unsafe { *biomes.get_unchecked(index) };
+        index += 1;
+
+        if biome_value < registry[biome].weight {
+            return biome;
+        }
+
+        biome_value -= registry[biome].weight;
+    }
+
+    BiomeId::Plains
+}
+
+#[cfg(test)]
+mod tests {
+    use bns_rng::DefaultRng;
+
+    use super::*;
+    use crate::ClimateBounds;
+
+    /// Bounds that no climate can satisfy.
+    const NOWHERE: ClimateBounds = ClimateBounds {
+        continentality: (2.0, 2.0),
+        temperature: (2.0, 2.0),
+        humidity: (2.0, 2.0),
+    };
+
+    /// Bounds that every climate satisfies.
+    const EVERYWHERE: ClimateBounds = ClimateBounds {
+        continentality: (-1.0, 1.0),
+        temperature: (-1.0, 1.0),
+        humidity: (-1.0, 1.0),
+    };
+
+    fn registry() -> BiomeRegistry {
+        BiomeRegistry::from_seed::<DefaultRng>(42)
+    }
+
+    fn climate() -> Climate {
+        Climate {
+            continentality: 0.5,
+            temperature: 0.5,
+            humidity: 0.5,
+        }
+    }
+
+    /// Makes `biome` the only biome allowed anywhere.
+    fn only(registry: &BiomeRegistry, biome: BiomeId) {
+        for id in BiomeId::iter_all() {
+            registry.set_climate_bounds(id, NOWHERE);
+        }
+        registry.set_climate_bounds(biome, EVERYWHERE);
+    }
+
+    #[test]
+    fn tuning_bounds_changes_selected_biome() {
+        let registry = registry();
+
+        // A hash of `1` never lands on the zero remainder, which always maps to plains.
+        only(&registry, BiomeId::Desert);
+        assert_eq!(select_biome(&climate(), 1, &registry), BiomeId::Desert);
+
+        only(&registry, BiomeId::Mountain);
+        assert_eq!(select_biome(&climate(), 1, &registry), BiomeId::Mountain);
+
+        registry.reset_climate_bounds();
+        assert_eq!(
+            registry.climate_bounds(BiomeId::Desert),
+            registry[BiomeId::Desert].climate
+        );
+    }
+
+    #[test]
+    fn no_allowed_biome_falls_back_to_plains() {
+        let registry = registry();
+        for id in BiomeId::iter_all() {
+            registry.set_climate_bounds(id, NOWHERE);
+        }
+
+        assert_eq!(select_biome(&climate(), 12345, &registry), BiomeId::Plains);
+    }
+
+    /// Counts how many times the biome changes when walking `len` blocks along `step`, starting
+    /// from a few different positions.
+    fn biome_changes(map: &BiomeMap, registry: &BiomeRegistry, stretch: Vec2, step: IVec2) -> u32 {
+        let mut changes = 0;
+
+        for line in 0..16 {
+            let start = IVec2::new(line * 517, line * -311);
+            let mut prev = map.sample(start, stretch, registry);
+            for i in 1..4096 {
+                let biome = map.sample(start + step * i, stretch, registry);
+                if biome != prev {
+                    changes += 1;
+                    prev = biome;
+                }
+            }
+        }
+
+        changes
+    }
+
+    #[test]
+    fn stretched_biomes_persist_along_stretched_axis() {
+        let registry = registry();
+        let map = BiomeMap::from_seed::<DefaultRng>(42);
+        let stretch = Vec2::new(8.0, 1.0);
+
+        let along_x = biome_changes(&map, &registry, stretch, IVec2::X);
+        let along_y = biome_changes(&map, &registry, stretch, IVec2::Y);
+
+        assert!(along_y > 0);
+        assert!(
+            along_x * 2 < along_y,
+            "{along_x} changes along X, {along_y} changes along Y",
+        );
+    }
+}
@@ -50,14 +50,14 @@ impl Biome for Ocean {
             );
 
             if world_pos.y <= height {
-                if world_pos.y >= -1 {
+                if world_pos.y >= ctx.water_level() - 1 {
                     chunk.set_block(local_pos, BlockId::Sand.into());
                 } else if world_pos.y < height - gravel {
                     chunk.set_block(local_pos, BlockId::Stone.into());
                 } else {
                     chunk.set_block(local_pos, BlockId::Gravel.into());
                 }
-            } else if world_pos.y < 0 {
+            } else if world_pos.y < ctx.water_level() {
                 chunk.set_block(local_pos, BlockId::Water.into());
             }
         }
@@ -21,6 +21,7 @@ make_standard_biome! {
         biome.set_surface_block(BlockId::Grass.into());
         biome.set_dirt(BlockId::Dirt.into(), 6, 8);
         biome.set_underground(BlockId::Stone.into());
+        biome.set_steep_blocks(BlockId::Stone.into(), BlockId::Gravel.into(), 0.8);
         biome.set_base_height(10.0);
         biome.add_height_noise( 2.0, 0.06);
         biome.add_prop(BlockId::Pebbles.into(), 100);
@@ -35,6 +36,7 @@ make_standard_biome! {
         biome.set_surface_block(BlockId::Grass.into());
         biome.set_dirt(BlockId::Dirt.into(), 6, 8);
         biome.set_underground(BlockId::Stone.into());
+        biome.set_steep_blocks(BlockId::Stone.into(), BlockId::Gravel.into(), 0.8);
         biome.set_base_height(5.0);
         biome.add_height_noise(2.0, 0.03);
         biome.add_height_noise(1.0, 0.015);
@@ -53,6 +55,7 @@ make_standard_biome! {
         biome.set_surface_block(BlockId::Podzol.into());
         biome.set_dirt(BlockId::Dirt.into(), 6, 8);
         biome.set_underground(BlockId::Stone.into());
+        biome.set_steep_blocks(BlockId::Stone.into(), BlockId::Gravel.into(), 0.8);
         biome.set_base_height(4.0);
         biome.add_height_noise(0.02, 0.01);
         biome.add_prop(BlockId::Pebbles.into(), 400);
@@ -2,11 +2,11 @@ use bns_core::{BlockId, BlockInstance, Chunk, ChunkPos, LocalPos};
 use bns_rng::noises::{Mixer, SuperSimplex2};
 use bns_rng::{FromRng, Noise, Rng};
 use bns_worldgen_structure::Structure;
-use glam::{IVec2, IVec3};
+use glam::{IVec2, IVec3, Vec2, Vec3Swizzles};
 
 use crate::biome::BiomeId;
 use crate::chunk_gen::{PendingStructure, StructureTransformations};
-use crate::column_gen::{ColumnGen, ColumnPos};
+use crate::column_gen::{ColumnGen, ColumnPos, ColumnStore};
 use crate::GenCtx;
 
 /// A prop that can be spawned in a biome.
@@ -19,6 +19,9 @@ struct Props {
 }
 
 /// A set of stuctures that can spawn in a biome.
+///
+/// Every structure of the set is a variant of the same logical kind of structure (for example,
+/// different shapes of oak trees). When the set spawns, one of its variants is picked.
 struct StructureSet {
     set: &'static [&'static Structure<'static>],
     probability: u64,
@@ -27,6 +30,25 @@ struct StructureSet {
     transform_noise: Mixer<2>,
 }
 
+impl StructureSet {
+    /// Picks the variant of the structure that spawns at the provided world-space column.
+    ///
+    /// The choice only depends on the position of the column and the seed of the set, meaning
+    /// that the same column always picks the same variant.
+    fn variant(&self, pos: IVec2) -> &'static Structure<'static> {
+        let value = self.value_noise.sample([pos.x as u64, pos.y as u64]);
+        self.set[value as usize % self.set.len()]
+    }
+}
+
+/// The blocks that replace the surface and dirt blocks of a biome on steep slopes.
+struct SteepBlocks {
+    surface: BlockInstance,
+    dirt: BlockInstance,
+    /// The slope above which the terrain is considered steep, in blocks of height per block.
+    min_slope: f32,
+}
+
 /// A noise
 struct NoiseEntry {
     scale: f32,
@@ -53,6 +75,7 @@ impl<'a, R: Rng> StandardBiomeBuilder<'a, R> {
                 max_dirt_depth: 5,
                 base_height: 0.0,
                 height_noises: Vec::new(),
+                steep: None,
                 dirt_noise: SuperSimplex2::from_rng(rng),
                 props: Vec::new(),
                 structures: Vec::new(),
@@ -76,6 +99,24 @@ impl<'a, R: Rng> StandardBiomeBuilder<'a, R> {
         self.inner.max_dirt_depth = max_depth;
     }
 
+    /// Sets the blocks that replace the surface and dirt blocks where the terrain is steeper than
+    /// `min_slope`.
+    ///
+    /// The slope is measured in blocks of height per block, meaning that a value of `1.0`
+    /// corresponds to a 45 degrees angle.
+    pub fn set_steep_blocks(
+        &mut self,
+        surface: BlockInstance,
+        dirt: BlockInstance,
+        min_slope: f32,
+    ) {
+        self.inner.steep = Some(SteepBlocks {
+            surface,
+            dirt,
+            min_slope,
+        });
+    }
+
     /// Sets the underground block.
     pub fn set_underground(&mut self, block: BlockInstance) {
         self.inner.underground = block;
@@ -132,6 +173,7 @@ pub struct StandardBiome {
     max_dirt_depth: i32,
     base_height: f32,
     height_noises: Vec<NoiseEntry>,
+    steep: Option<SteepBlocks>,
     dirt_noise: SuperSimplex2,
     props: Vec<Props>,
     structures: Vec<StructureSet>,
@@ -152,10 +194,43 @@ impl StandardBiome {
         ret
     }
 
+    /// Computes the slope of the biome's requested height for the given column in world-space.
+    ///
+    /// The slope is the length of the gradient of [`StandardBiome::height`], in blocks of
+    /// height per block.
+    pub fn slope(&self, pos: IVec2) -> f32 {
+        let mut gradient = Vec2::ZERO;
+
+        for noise in &self.height_noises {
+            let [dx, dz] = noise.noise.gradient([
+                pos.x as f32 * noise.frequency,
+                pos.y as f32 * noise.frequency,
+            ]);
+
+            // The input of the noise is scaled by the frequency, and its output by the scale.
+            gradient += Vec2::new(dx, dz) * noise.frequency * noise.scale;
+        }
+
+        gradient.length()
+    }
+
+    /// Returns the surface and dirt blocks to use for the provided column in world-space,
+    /// taking the slope of the terrain into account.
+    fn surface_blocks(&self, pos: IVec2) -> (&BlockInstance, &BlockInstance) {
+        match &self.steep {
+            Some(steep) if self.slope(pos) > steep.min_slope => (&steep.surface, &steep.dirt),
+            _ => (&self.surface, &self.dirt),
+        }
+    }
+
     /// Builds the biome in the given chunk.
     pub fn build(&self, pos: ChunkPos, column: &ColumnGen, ctx: &GenCtx, chunk: &mut Chunk) {
         let biome_ids = &column.biome_stage(ctx).ids;
 
+        // The surface blocks of each column, computed lazily because estimating the slope of the
+        // terrain is relatively expensive.
+        let mut surface_blocks = ColumnStore::new(None);
+
         for local_pos in LocalPos::iter_all() {
             if biome_ids[local_pos.into()] != self.filter {
                 continue;
@@ -174,21 +249,26 @@ impl StandardBiome {
             if world_pos.y <= height {
                 if world_pos.y < height - dirt_depth {
                     chunk.set_block(local_pos, self.underground.clone());
-                } else if height <= 2 {
+                } else if height <= ctx.water_level() + 2 {
                     chunk.set_block(local_pos, BlockId::Sand.into());
-                } else if world_pos.y < height {
-                    chunk.set_block(local_pos, self.dirt.clone());
                 } else {
-                    chunk.set_block(local_pos, self.surface.clone());
+                    let (surface, dirt) = *surface_blocks[local_pos.into()]
+                        .get_or_insert_with(|| self.surface_blocks(world_pos.xz()));
+
+                    if world_pos.y < height {
+                        chunk.set_block(local_pos, dirt.clone());
+                    } else {
+                        chunk.set_block(local_pos, surface.clone());
+                    }
                 }
-            } else if world_pos.y < 0 {
+            } else if world_pos.y < ctx.water_level() {
                 chunk.set_block(local_pos, BlockId::Water.into());
-            } else if world_pos.y == height + 1 && world_pos.y >= 4 {
+            } else if world_pos.y == height + 1 && world_pos.y >= ctx.water_level() + 4 {
+                let density = ctx.decoration_density(world_pos.xz());
+
                 for prop in &self.props {
-                    if prop.noise.sample([world_pos.x as u64, world_pos.z as u64])
-                        % prop.probability
-                        == 0
-                    {
+                    let value = prop.noise.sample([world_pos.x as u64, world_pos.z as u64]);
+                    if value % prop.probability == 0 && passes_density(value, density) {
                         chunk.set_block(local_pos, prop.block.clone());
                     }
                 }
@@ -218,17 +298,14 @@ impl StandardBiome {
             }
 
             let world_pos = origin + IVec3::new(local_pos.x(), height, local_pos.z());
+            let density = ctx.decoration_density(world_pos.xz());
 
             for set in &self.structures {
-                if set.noise.sample([world_pos.x as u64, world_pos.z as u64]) % set.probability != 0
-                {
+                let spawn_value = set.noise.sample([world_pos.x as u64, world_pos.z as u64]);
+                if spawn_value % set.probability != 0 || !passes_density(spawn_value, density) {
                     continue;
                 }
 
-                let value = set
-                    .value_noise
-                    .sample([world_pos.x as u64, world_pos.z as u64]);
-
                 let transform_noise = set
                     .transform_noise
                     .sample([world_pos.x as u64, world_pos.y as u64]);
@@ -245,7 +322,7 @@ impl StandardBiome {
 
                 out.push(PendingStructure {
                     position: world_pos,
-                    contents: set.set[value as usize % set.set.len()].clone(),
+                    contents: set.variant(world_pos.xz()).clone(),
                     transformations,
                 });
             }
@@ -253,6 +330,16 @@ impl StandardBiome {
     }
 }
 
+/// Returns whether a decoration whose spawn noise returned `value` should spawn in a region
+/// with the provided decoration density.
+///
+/// The lower bits of `value` are used to check the base probability of the decoration, so the
+/// upper bits are used here to keep both checks independent.
+#[inline]
+fn passes_density(value: u64, density: f32) -> bool {
+    bns_rng::utility::f32_from_u32_01((value >> 32) as u32) < density
+}
+
 /// Creates a struct that implements the [`Biome`](crate::biome::Biome) trait using
 /// a [`StandardBiome`].
 #[macro_export]
@@ -309,3 +396,124 @@ macro_rules! make_standard_biome {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use bns_rng::DefaultRng;
+
+    use super::*;
+
+    /// Creates a biome that places stone and gravel on slopes steeper than `0.8`, with an
+    /// optional `(scale, frequency)` height noise.
+    fn biome(height_noise: Option<(f32, f32)>) -> StandardBiome {
+        let mut rng = DefaultRng::from_seed(7);
+        let mut builder = StandardBiomeBuilder::new(&mut rng, BiomeId::Plains);
+        builder.set_steep_blocks(BlockId::Stone.into(), BlockId::Gravel.into(), 0.8);
+        if let Some((scale, frequency)) = height_noise {
+            builder.add_height_noise(scale, frequency);
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn flat_column_is_grass() {
+        let biome = biome(None);
+        let pos = IVec2::new(12, -7);
+
+        assert_eq!(biome.slope(pos), 0.0);
+        let (surface, dirt) = biome.surface_blocks(pos);
+        assert_eq!(surface.id(), BlockId::Grass);
+        assert_eq!(dirt.id(), BlockId::Dirt);
+    }
+
+    #[test]
+    fn steep_column_is_rock() {
+        let biome = biome(Some((200.0, 0.02)));
+        let pos = (0..256)
+            .map(|x| IVec2::new(x, 0))
+            .find(|&pos| biome.slope(pos) > 1.0)
+            .expect("no steep column found");
+
+        let (surface, dirt) = biome.surface_blocks(pos);
+        assert_eq!(surface.id(), BlockId::Stone);
+        assert_eq!(dirt.id(), BlockId::Gravel);
+    }
+
+    #[test]
+    fn slope_matches_height_differences() {
+        let biome = biome(Some((20.0, 0.01)));
+
+        for i in -10..10 {
+            let pos = IVec2::new(i * 13, i * 7);
+            let dx = (biome.height(pos + IVec2::X) - biome.height(pos - IVec2::X)) / 2.0;
+            let dz = (biome.height(pos + IVec2::Y) - biome.height(pos - IVec2::Y)) / 2.0;
+            let expected = Vec2::new(dx, dz).length();
+
+            let slope = biome.slope(pos);
+            assert!(
+                (slope - expected).abs() < 0.05,
+                "slope at {pos} is {slope}, expected {expected}",
+            );
+        }
+    }
+
+    #[test]
+    fn structure_variant_depends_on_the_column() {
+        let mut rng = DefaultRng::from_seed(7);
+        let mut builder = StandardBiomeBuilder::new(&mut rng, BiomeId::Plains);
+        builder.add_structure(crate::biomes::structures::OAK_TREES, 100);
+        let biome = builder.build();
+        let set = &biome.structures[0];
+
+        let mut seen = [false; 4];
+        for i in -32..32 {
+            let pos = IVec2::new(i * 17, i * -5);
+            let variant = set.variant(pos);
+            assert!(std::ptr::eq(variant, set.variant(pos)));
+
+            let index = set
+                .set
+                .iter()
+                .position(|&s| std::ptr::eq(s, variant))
+                .unwrap();
+            seen[index] = true;
+        }
+
+        assert!(seen.iter().filter(|&&s| s).count() > 1);
+    }
+
+    #[test]
+    fn decoration_count_is_proportional_to_density() {
+        let mut rng = DefaultRng::from_seed(7);
+        let mut builder = StandardBiomeBuilder::new(&mut rng, BiomeId::Plains);
+        builder.add_prop(BlockId::Daffodil.into(), 4);
+        let biome = builder.build();
+        let prop = &biome.props[0];
+
+        // Counts the props that spawn over a 128x128 region with the provided density.
+        let count = |density: f32| {
+            let mut count = 0;
+            for x in 0..128u64 {
+                for z in 0..128u64 {
+                    let value = prop.noise.sample([x, z]);
+                    if value % prop.probability == 0 && passes_density(value, density) {
+                        count += 1;
+                    }
+                }
+            }
+            count
+        };
+
+        let full = count(1.0);
+        assert!(full > 0);
+        assert_eq!(count(0.0), 0);
+
+        for density in [0.25, 0.5, 0.75] {
+            let ratio = count(density) as f32 / full as f32;
+            assert!(
+                (ratio - density).abs() < 0.05,
+                "density {density} spawned {ratio} of the props",
+            );
+        }
+    }
+}
@@ -66,6 +66,14 @@ impl Cache {
             .clone()
     }
 
+    /// Removes every column and chunk from the cache.
+    ///
+    /// This must be called when the parameters used to generate them change.
+    pub fn clear(&self) {
+        self.columns.write().clear();
+        self.chunks.write().clear();
+    }
+
     /// Hints the collection that some columns are unlikely to be used anymore, and can therefor
     /// be unloaded.
     #[profiling::function]
@@ -1,11 +1,13 @@
 use std::sync::OnceLock;
 
-use bns_core::{Chunk, ChunkPos};
+use bns_core::{BlockId, Chunk, ChunkPos, LocalPos};
+use bns_rng::Noise;
 use bns_worldgen_structure::Structure;
 
 use bitflags::bitflags;
 use glam::IVec3;
 
+use crate::column_gen::{ColumnGen, ColumnPos};
 use crate::GenCtx;
 
 bitflags! {
@@ -115,3 +117,60 @@ impl ChunkGen {
         })
     }
 }
+
+/// The horizontal scale of the noises used to carve caves.
+const CAVE_HORIZONTAL_SCALE: f32 = 1.0 / 48.0;
+
+/// The vertical scale of the noises used to carve caves.
+///
+/// This is larger than [`CAVE_HORIZONTAL_SCALE`] to make caves flatter than they are wide.
+const CAVE_VERTICAL_SCALE: f32 = 1.0 / 24.0;
+
+/// The number of blocks below the surface that are never carved.
+///
+/// This prevents caves from opening holes in the terrain (and in the floor of oceans).
+const CAVE_CRUST_DEPTH: i32 = 4;
+
+/// Carves caves into the provided chunk.
+///
+/// A block is carved when both cave noises (see [`GenCtx::cave_noises`]) are close to zero at
+/// its position, which produces long and connected tunnels. Only blocks that are below the
+/// surface of the column are carved.
+///
+/// # Remarks
+///
+/// Caves only depend on the world-space position of the carved blocks, ensuring that they
+/// connect seamlessly at chunk boundaries.
+pub fn carve_caves(pos: ChunkPos, column: &ColumnGen, ctx: &GenCtx, chunk: &mut Chunk) {
+    if ctx.cave_density <= 0.0 {
+        return;
+    }
+
+    let heights = column.height_stage(ctx);
+    let origin = pos.origin();
+
+    // Skip the chunk if it's entirely above the carvable area.
+    let max_height = ColumnPos::iter_all().map(|p| heights[p]).max().unwrap_or(0);
+    if origin.y > max_height - CAVE_CRUST_DEPTH {
+        return;
+    }
+
+    for local_pos in LocalPos::iter_all() {
+        let world_pos = origin + local_pos.to_ivec3();
+        if world_pos.y > heights[local_pos.into()] - CAVE_CRUST_DEPTH {
+            continue;
+        }
+
+        let sample_pos = [
+            world_pos.x as f32 * CAVE_HORIZONTAL_SCALE,
+            world_pos.y as f32 * CAVE_VERTICAL_SCALE,
+            world_pos.z as f32 * CAVE_HORIZONTAL_SCALE,
+        ];
+
+        if ctx.cave_noises[0].sample(sample_pos).abs() < ctx.cave_density
+            && ctx.cave_noises[1].sample(sample_pos).abs() < ctx.cave_density
+        {
+            chunk.set_block(local_pos, BlockId::Air.into());
+        }
+    }
+}
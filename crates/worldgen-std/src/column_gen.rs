@@ -186,7 +186,9 @@ impl ColumnGen {
 
             for pos in ColumnPos::iter_all() {
                 let world_pos = self.pos * Chunk::SIDE + pos.to_ivec2();
-                let biome = ctx.biomes.sample(world_pos, &ctx.biome_registry);
+                let biome = ctx
+                    .biomes
+                    .sample(world_pos, ctx.biome_stretch, &ctx.biome_registry);
                 ids[pos] = biome;
 
                 if !unique_biomes.contains(&biome) {
@@ -290,11 +292,12 @@ impl ColumnGen {
                     a * (1.0 - f) + b * f
                 }
 
-                ret[pos] = bns_rng::utility::floor_i32(interpolate(
-                    interpolate(h00, h10, x),
-                    interpolate(h01, h11, x),
-                    z,
-                ));
+                ret[pos] = ctx.water_level()
+                    + bns_rng::utility::floor_i32(interpolate(
+                        interpolate(h00, h10, x),
+                        interpolate(h01, h11, x),
+                        z,
+                    ));
             }
 
             ret
@@ -1,14 +1,16 @@
 //! The standard world generator.
 
 use bns_core::{BlockId, Chunk, ChunkPos, LocalPos};
-use bns_rng::noises::Mixer;
-use bns_rng::{FromRng, Rng};
+use bns_rng::noises::{Mixer, SuperSimplex2, SuperSimplex3};
+use bns_rng::{FromRng, Noise, Rng};
 use bns_worldgen_core::WorldGenerator;
 
 use cache::Cache;
-use glam::{IVec2, IVec3, Vec3Swizzles};
+use glam::{IVec2, IVec3, Vec2, Vec3, Vec3Swizzles};
 
 use biome::BiomeRegistry;
+
+pub use biome::{BiomeId, ClimateBounds};
 use biomemap::BiomeMap;
 use column_gen::ColumnPos;
 
@@ -25,12 +27,79 @@ pub struct GenCtx {
     pub biomes: BiomeMap,
     /// The registry of all biomes that can be generated.
     pub biome_registry: BiomeRegistry,
+    /// The amount by which biomes are stretched along each horizontal axis.
+    ///
+    /// A value of `1.0` on both axes produces regular biomes. Increasing the value of one axis
+    /// makes biomes form elongated bands along it.
+    ///
+    /// Both components must be at least [`GenCtx::MIN_BIOME_STRETCH`].
+    pub biome_stretch: Vec2,
+    /// The height of the surface of the oceans.
+    ///
+    /// Blocks below this height that are not part of the terrain are filled with water. The
+    /// height of the terrain is offset by this value.
+    ///
+    /// This value is relative to [`y_offset`](Self::y_offset). See [`GenCtx::water_level`] for
+    /// the actual height of the surface of the oceans.
+    pub sea_level: i32,
+    /// The vertical offset of the whole world.
+    ///
+    /// The terrain, the surface of the oceans and the bedrock layer are all moved up by this
+    /// amount of blocks. Unlike [`sea_level`](Self::sea_level), this does not change the
+    /// shape of the world relative to its bedrock.
+    pub y_offset: i32,
     /// The cache that stores the generation data to avoid having to recompute
     /// it constantly.
     pub cache: Cache,
 
     /// The noises used to randomly find samples in the biome map.
     pub heightmap_noises: [Mixer<2>; 8],
+
+    /// A low-frequency noise that controls how dense decorations (props and structures) are.
+    pub decoration_density_noise: SuperSimplex2,
+
+    /// The noises used to carve caves.
+    pub cave_noises: [SuperSimplex3; 2],
+    /// How wide and frequent caves are.
+    ///
+    /// A value of `0.0` disables caves entirely.
+    pub cave_density: f32,
+}
+
+impl GenCtx {
+    /// The minimum value of each component of [`GenCtx::biome_stretch`].
+    pub const MIN_BIOME_STRETCH: f32 = 1.0 / 16.0;
+
+    /// The scale of the decoration density map.
+    pub const DECORATION_DENSITY_SCALE: f32 = 1.0 / 96.0;
+
+    /// The default value of [`GenCtx::cave_density`].
+    pub const DEFAULT_CAVE_DENSITY: f32 = 0.08;
+
+    /// The Y coordinate of the bedrock layer, before [`GenCtx::y_offset`] is applied.
+    pub const BEDROCK_Y: i32 = -4 * Chunk::SIDE;
+
+    /// Returns the Y coordinate of the surface of the oceans, taking the vertical offset of
+    /// the world into account.
+    #[inline]
+    pub fn water_level(&self) -> i32 {
+        self.sea_level + self.y_offset
+    }
+
+    /// Returns the Y coordinate of the bedrock layer, taking the vertical offset of the world
+    /// into account.
+    #[inline]
+    pub fn bedrock_level(&self) -> i32 {
+        Self::BEDROCK_Y + self.y_offset
+    }
+
+    /// Returns the density of decorations at the provided column, in the range `[0.0, 1.0]`.
+    ///
+    /// The spawn probability of decorations is multiplied by this value.
+    pub fn decoration_density(&self, pos: IVec2) -> f32 {
+        let pos = pos.as_vec2() * Self::DECORATION_DENSITY_SCALE;
+        (self.decoration_density_noise.sample(pos.into()) * 0.5 + 0.5).clamp(0.0, 1.0)
+    }
 }
 
 impl FromRng for GenCtx {
@@ -38,24 +107,107 @@ impl FromRng for GenCtx {
         Self {
             biomes: BiomeMap::from_rng(rng),
             biome_registry: BiomeRegistry::from_rng(rng),
+            biome_stretch: Vec2::ONE,
+            sea_level: 0,
+            y_offset: 0,
             cache: Cache::default(),
             heightmap_noises: FromRng::from_rng(rng),
+            decoration_density_noise: SuperSimplex2::from_rng(rng),
+            cave_noises: FromRng::from_rng(rng),
+            cave_density: GenCtx::DEFAULT_CAVE_DENSITY,
         }
     }
 }
 
 /// The standard [`WorldGenerator`] implementation.
 pub struct StandardWorldGenerator {
+    /// The seed that was used to create the generator.
+    seed: u64,
     /// The context required to generate new chunks.
     ctx: GenCtx,
 }
 
-impl FromRng for StandardWorldGenerator {
-    fn from_rng(rng: &mut impl Rng) -> Self {
+impl StandardWorldGenerator {
+    /// Creates a new [`StandardWorldGenerator`] from the provided seed.
+    ///
+    /// The random number generator `R` is seeded with `seed` and used to initialize the
+    /// generator.
+    pub fn new<R: Rng>(seed: u64) -> Self {
         Self {
-            ctx: GenCtx::from_rng(rng),
+            seed,
+            ctx: GenCtx::from_rng(&mut R::from_seed(seed)),
         }
     }
+
+    /// Sets the amount by which biomes are stretched along each horizontal axis.
+    ///
+    /// See [`GenCtx::biome_stretch`] for more information.
+    ///
+    /// # Remarks
+    ///
+    /// Each component of `stretch` is clamped to [`GenCtx::MIN_BIOME_STRETCH`], ensuring that
+    /// biomes never get squashed to nothing.
+    pub fn with_biome_stretch(mut self, stretch: Vec2) -> Self {
+        self.ctx.biome_stretch = stretch.max(Vec2::splat(GenCtx::MIN_BIOME_STRETCH));
+        self
+    }
+
+    /// Sets the height of the surface of the oceans.
+    ///
+    /// See [`GenCtx::sea_level`] for more information.
+    pub fn with_sea_level(mut self, sea_level: i32) -> Self {
+        self.ctx.sea_level = sea_level;
+        self
+    }
+
+    /// Sets the vertical offset of the whole world.
+    ///
+    /// See [`GenCtx::y_offset`] for more information.
+    pub fn with_y_offset(mut self, y_offset: i32) -> Self {
+        self.ctx.y_offset = y_offset;
+        self
+    }
+
+    /// Sets how wide and frequent caves are.
+    ///
+    /// See [`GenCtx::cave_density`] for more information.
+    pub fn with_cave_density(mut self, density: f32) -> Self {
+        self.ctx.cave_density = density;
+        self
+    }
+
+    /// Returns the biome generated at the provided horizontal position.
+    pub fn biome_at(&self, pos: IVec2) -> BiomeId {
+        let col_pos = IVec2::new(pos.x.div_euclid(Chunk::SIDE), pos.y.div_euclid(Chunk::SIDE));
+        let local_pos = ColumnPos::from_world_pos(pos);
+        let column = self.ctx.cache.get_column(col_pos);
+        column.biome_stage(&self.ctx).ids[local_pos]
+    }
+
+    /// Returns the climate bounds currently used to select the provided biome.
+    #[inline]
+    pub fn biome_climate_bounds(&self, biome: BiomeId) -> ClimateBounds {
+        self.ctx.biome_registry.climate_bounds(biome)
+    }
+
+    /// Sets the climate bounds used to select the provided biome.
+    ///
+    /// This can be called while chunks are being generated. The change affects every chunk
+    /// generated afterwards, but chunks that were already generated are left untouched.
+    ///
+    /// Climates that no biome accepts anymore generate plains.
+    pub fn set_biome_climate_bounds(&self, biome: BiomeId, bounds: ClimateBounds) {
+        self.ctx.biome_registry.set_climate_bounds(biome, bounds);
+        self.ctx.cache.clear();
+    }
+
+    /// Restores the default climate bounds of every biome.
+    ///
+    /// See [`set_biome_climate_bounds`](Self::set_biome_climate_bounds).
+    pub fn reset_biome_climate_bounds(&self) {
+        self.ctx.biome_registry.reset_climate_bounds();
+        self.ctx.cache.clear();
+    }
 }
 
 impl WorldGenerator for StandardWorldGenerator {
@@ -63,8 +215,14 @@ impl WorldGenerator for StandardWorldGenerator {
     fn generate(&self, chunk_pos: ChunkPos) -> Chunk {
         let mut ret = Chunk::empty();
 
-        // Only generate chunks betweens -4 and 4.
-        if chunk_pos.y < -4 || chunk_pos.y > 4 {
+        // Only generate chunks between -4 and 4 (offset by the sea level if it's above the
+        // default one, and by the vertical offset of the world).
+        let bottom = self.ctx.bedrock_level();
+        let top = 5 * Chunk::SIDE - 1
+            + self.ctx.sea_level.div_euclid(Chunk::SIDE).max(0) * Chunk::SIDE
+            + self.ctx.y_offset;
+        let chunk_bottom = chunk_pos.y * Chunk::SIDE;
+        if chunk_bottom + Chunk::SIDE <= bottom || chunk_bottom > top {
             return ret;
         }
 
@@ -75,6 +233,8 @@ impl WorldGenerator for StandardWorldGenerator {
                 .build(chunk_pos, &col, &self.ctx, &mut ret);
         }
 
+        chunk_gen::carve_caves(chunk_pos, &col, &self.ctx, &mut ret);
+
         // Insert the structures that were requested by the biomes.
         {
             let bounds = self.ctx.biome_registry.max_structure_size();
@@ -90,9 +250,16 @@ impl WorldGenerator for StandardWorldGenerator {
             }
         }
 
-        // Add a layer of bedrock at the bottom of the world.
-        if chunk_pos.y == -4 {
-            for pos in LocalPos::iter_surface(0) {
+        // Add a layer of bedrock at the bottom of the world, and remove anything that
+        // was generated below it.
+        if bottom.div_euclid(Chunk::SIDE) == chunk_pos.y {
+            let bedrock_y = bottom.rem_euclid(Chunk::SIDE);
+            for y in 0..bedrock_y {
+                for pos in LocalPos::iter_surface(y) {
+                    unsafe { *ret.get_block_mut(pos) = BlockId::Air };
+                }
+            }
+            for pos in LocalPos::iter_surface(bedrock_y) {
                 unsafe { *ret.get_block_mut(pos) = BlockId::Bedrock };
             }
         }
@@ -105,10 +272,23 @@ impl WorldGenerator for StandardWorldGenerator {
         self.ctx.cache.request_cleanup(center, h_radius, v_radius);
     }
 
+    #[inline]
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    #[inline]
+    fn ambient_color(&self, pos: IVec3) -> Vec3 {
+        self.ctx.biome_registry[self.biome_at(pos.xz())].ambient_color
+    }
+
     fn debug_info(&self, w: &mut dyn std::fmt::Write, pos: IVec3) -> std::fmt::Result {
-        self.ctx
-            .biomes
-            .debug_info(w, &self.ctx.biome_registry, pos.xz())?;
+        self.ctx.biomes.debug_info(
+            w,
+            &self.ctx.biome_registry,
+            self.ctx.biome_stretch,
+            pos.xz(),
+        )?;
 
         let col_pos = IVec2::new(pos.x.div_euclid(Chunk::SIDE), pos.z.div_euclid(Chunk::SIDE));
         let local_pos = ColumnPos::from_world_pos(pos.xz());
@@ -120,4 +300,199 @@ impl WorldGenerator for StandardWorldGenerator {
             .debug_info(w, pos)?;
         Ok(())
     }
+
+    fn debug_fields(&self, pos: IVec3) -> Vec<(String, String)> {
+        let climate = self.ctx.biomes.climate_at(pos.xz(), self.ctx.biome_stretch);
+
+        let col_pos = IVec2::new(pos.x.div_euclid(Chunk::SIDE), pos.z.div_euclid(Chunk::SIDE));
+        let local_pos = ColumnPos::from_world_pos(pos.xz());
+        let column = self.ctx.cache.get_column(col_pos);
+        let biome = column.biome_stage(&self.ctx).ids[local_pos];
+        let height = column.height_stage(&self.ctx)[local_pos];
+
+        vec![
+            ("biome".into(), format!("{biome:?}")),
+            (
+                "continentality".into(),
+                format!("{:.2}", climate.continentality),
+            ),
+            ("temperature".into(), format!("{:.2}", climate.temperature)),
+            ("humidity".into(), format!("{:.2}", climate.humidity)),
+            ("height".into(), height.to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bns_rng::DefaultRng;
+
+    use super::*;
+
+    #[test]
+    fn zero_biome_stretch_is_clamped() {
+        let generator =
+            StandardWorldGenerator::new::<DefaultRng>(1).with_biome_stretch(Vec2::new(0.0, 2.0));
+        assert_eq!(
+            generator.ctx.biome_stretch,
+            Vec2::new(GenCtx::MIN_BIOME_STRETCH, 2.0)
+        );
+    }
+
+    #[test]
+    fn debug_fields_describe_biome_climate_and_height() {
+        let generator = StandardWorldGenerator::new::<DefaultRng>(1);
+        let fields = generator.debug_fields(IVec3::new(100, 64, -250));
+        let keys: Vec<&str> = fields.iter().map(|(key, _)| key.as_str()).collect();
+
+        assert_eq!(
+            keys,
+            [
+                "biome",
+                "continentality",
+                "temperature",
+                "humidity",
+                "height"
+            ]
+        );
+        assert!(fields.iter().all(|(_, value)| !value.is_empty()));
+    }
+
+    #[test]
+    fn ambient_color_follows_the_biome_at_the_position() {
+        let generator = StandardWorldGenerator::new::<DefaultRng>(1);
+        let mut seen = Vec::new();
+
+        for x in -8..8 {
+            for z in -8..8 {
+                let pos = IVec3::new(x * 1024, 64, z * 1024);
+                let biome = generator.biome_at(pos.xz());
+                let color = generator.ambient_color(pos);
+
+                assert_eq!(color, generator.ctx.biome_registry[biome].ambient_color);
+                if !seen.contains(&biome) {
+                    seen.push(biome);
+                }
+            }
+        }
+
+        assert!(seen.len() >= 2, "only found {seen:?}");
+    }
+
+    #[test]
+    fn water_surface_follows_sea_level() {
+        const SEA_LEVEL: i32 = 40;
+
+        let generator = StandardWorldGenerator::new::<DefaultRng>(1)
+            .with_sea_level(SEA_LEVEL)
+            .with_cave_density(0.0);
+
+        // Look for a column of ocean whose floor is below the surface of the water.
+        let pos = (-16..16)
+            .flat_map(|x| (-16..16).map(move |z| IVec2::new(x * 256, z * 256)))
+            .find(|&pos| {
+                let col_pos =
+                    IVec2::new(pos.x.div_euclid(Chunk::SIDE), pos.y.div_euclid(Chunk::SIDE));
+                let column = generator.ctx.cache.get_column(col_pos);
+                let height = column.height_stage(&generator.ctx)[ColumnPos::from_world_pos(pos)];
+                generator.biome_at(pos) == BiomeId::Ocean && height < SEA_LEVEL - 1
+            })
+            .expect("no ocean found");
+
+        let chunk = generator.generate(ChunkPos::new(
+            pos.x.div_euclid(Chunk::SIDE),
+            SEA_LEVEL.div_euclid(Chunk::SIDE),
+            pos.y.div_euclid(Chunk::SIDE),
+        ));
+        let block_at = |y| chunk.get_block(LocalPos::from_world_pos(IVec3::new(pos.x, y, pos.y)));
+
+        assert_eq!(block_at(SEA_LEVEL - 1), BlockId::Water);
+        assert_eq!(block_at(SEA_LEVEL), BlockId::Air);
+    }
+
+    /// Returns the blocks of the provided chunk, in the order of [`LocalPos::iter_all`].
+    fn blocks(chunk: &Chunk) -> Vec<BlockId> {
+        LocalPos::iter_all()
+            .map(|pos| chunk.get_block(pos))
+            .collect()
+    }
+
+    #[test]
+    fn caves_are_deterministic() {
+        let a = StandardWorldGenerator::new::<DefaultRng>(3).with_cave_density(0.2);
+        let b = StandardWorldGenerator::new::<DefaultRng>(3).with_cave_density(0.2);
+        let solid = StandardWorldGenerator::new::<DefaultRng>(3).with_cave_density(0.0);
+
+        let mut carved = false;
+        for pos in [
+            ChunkPos::new(0, -1, 0),
+            ChunkPos::new(1, -1, 0),
+            ChunkPos::new(-7, -2, 4),
+        ] {
+            let chunk = blocks(&a.generate(pos));
+
+            // A fresh generator and one that has already cached the column must agree.
+            assert_eq!(chunk, blocks(&a.generate(pos)));
+            assert_eq!(chunk, blocks(&b.generate(pos)));
+
+            carved |= chunk != blocks(&solid.generate(pos));
+        }
+
+        assert!(carved);
+    }
+
+    /// Generates the chunk that contains `pos` and returns the block at that position.
+    fn generated_block(generator: &StandardWorldGenerator, pos: IVec3) -> BlockId {
+        let chunk = generator.generate(ChunkPos::from_world_pos_i(pos));
+        chunk.get_block(LocalPos::from_world_pos(pos))
+    }
+
+    #[test]
+    fn y_offset_shifts_surface_and_bedrock() {
+        const OFFSET: i32 = 45;
+
+        let base = StandardWorldGenerator::new::<DefaultRng>(5).with_cave_density(0.0);
+        let shifted = StandardWorldGenerator::new::<DefaultRng>(5)
+            .with_cave_density(0.0)
+            .with_y_offset(OFFSET);
+
+        let height_at = |generator: &StandardWorldGenerator, pos: IVec2| {
+            let col_pos = IVec2::new(pos.x.div_euclid(Chunk::SIDE), pos.y.div_euclid(Chunk::SIDE));
+            let column = generator.ctx.cache.get_column(col_pos);
+            column.height_stage(&generator.ctx)[ColumnPos::from_world_pos(pos)]
+        };
+
+        for pos in [
+            IVec2::new(3, 9),
+            IVec2::new(-150, 400),
+            IVec2::new(900, -20),
+        ] {
+            let height = height_at(&base, pos);
+            assert_eq!(height_at(&shifted, pos), height + OFFSET);
+
+            // Only look at the terrain, as the orientation of structures depends on their
+            // position.
+            for dy in -2..=0 {
+                let y = height + dy;
+                assert_eq!(
+                    generated_block(&shifted, IVec3::new(pos.x, y + OFFSET, pos.y)),
+                    generated_block(&base, IVec3::new(pos.x, y, pos.y)),
+                    "surface differs at {pos} (dy = {dy})",
+                );
+            }
+
+            let bedrock = GenCtx::BEDROCK_Y;
+            assert_eq!(shifted.ctx.bedrock_level(), bedrock + OFFSET);
+            let column = |y| IVec3::new(pos.x, y, pos.y);
+            assert_eq!(generated_block(&base, column(bedrock)), BlockId::Bedrock);
+            assert_eq!(
+                generated_block(&shifted, column(bedrock + OFFSET)),
+                BlockId::Bedrock
+            );
+            assert_eq!(
+                generated_block(&shifted, column(bedrock + OFFSET - 1)),
+                BlockId::Air
+            );
+        }
+    }
 }
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use bns_core::{BlockAppearance, BlockId, BlockInstance, Face};
+use bns_core::{Axis, BlockAppearance, BlockId, BlockInstance, Face};
 use bns_worldgen_structure_types::{Structure, StructureEdit};
 use glam::IVec3;
 
@@ -87,6 +87,14 @@ fn quote_face(face: Face) -> TokenStream {
     }
 }
 
+fn quote_axis(axis: Axis) -> TokenStream {
+    let variant = create_ident(&format!("{:?}", axis));
+
+    quote! {
+        ::bns_worldgen_structure::__private_macro::bns_core::Axis::#variant
+    }
+}
+
 fn quote_block_appearance(b: &BlockInstance) -> TokenStream {
     let core = quote! { ::bns_worldgen_structure::__private_macro::bns_core };
 
@@ -95,6 +103,10 @@ fn quote_block_appearance(b: &BlockInstance) -> TokenStream {
             let face = quote_face(unsafe { b.appearance().flat });
             quote! { #core ::AppearanceMetadata { flat: #face } }
         }
+        BlockAppearance::Pillar { .. } => {
+            let axis = quote_axis(unsafe { b.appearance().axis });
+            quote! { #core ::AppearanceMetadata { axis: #axis } }
+        }
         _ => quote! { #core ::AppearanceMetadata { no_metadata: () } },
     }
 }
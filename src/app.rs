@@ -2,9 +2,12 @@
 
 use bns_app::{App, KeyCode, MouseButton};
 use bns_render::data::RenderData;
-use bns_render::{Renderer, RendererConfig, Surface};
+use bns_render::{PresentMode, Renderer, RendererConfig, Surface};
+use glam::Vec3;
 
+use crate::game::player::Spawn;
 use crate::game::Game;
+use crate::settings::{available_memory, Settings};
 
 /// Runs the application until completion.
 pub fn run() {
@@ -29,6 +32,13 @@ async fn run_async() {
         title: "Blocks 'n Stuff",
         min_size: (300, 300),
         fullscreen: false,
+        max_fps: None,
+
+        // On web, the icon of the page is controlled by the HTML document.
+        #[cfg(not(target_arch = "wasm32"))]
+        icon: window_icon(),
+        #[cfg(target_arch = "wasm32")]
+        icon: None,
 
         // On platform other than web, we require a specific window size that's decently
         // large. But on web, we leave the css do that for us.
@@ -41,27 +51,34 @@ async fn run_async() {
     let mut surface = Surface::new(app.opaque_window()).await;
     let assets = crate::assets::Assets::load(surface.gpu()).await;
     let sounds = crate::assets::Sounds::load().await;
+    let mut settings = Settings::auto_detect(surface.gpu().info(), available_memory());
+    settings.apply_env_overrides();
     let mut renderer = Renderer::new(
         surface.gpu().clone(),
         RendererConfig {
             output_format: surface.info().format,
+            sample_count: settings.msaa_samples,
+            shadow_map_resolution: settings.shadow_map_resolution,
+            shadow_cascade_count: settings.shadow_cascades,
         },
     );
+    renderer.set_render_scale(settings.render_scale);
     renderer
         .gpu()
         .set_texture_atlas(&crate::assets::load_texture_atlas().await);
     let mut render_data = Some(RenderData::new(surface.gpu()));
 
-    let mut game = Game::new(surface.gpu().clone(), &sounds);
+    let mut game = Game::new(surface.gpu().clone(), &sounds, &settings, spawn_from_args());
+    if std::env::args().any(|arg| arg == "--benchmark") {
+        game.start_benchmark();
+    }
 
     app.run(|ctx| {
         // ==============================================
         // Update
         // ==============================================
 
-        if ctx.just_resized() {
-            surface.config_mut().width = ctx.width();
-            surface.config_mut().height = ctx.height();
+        if ctx.just_resized() && surface.resize(ctx.width(), ctx.height()) {
             renderer.gpu().notify_resized(ctx.width(), ctx.height());
         }
 
@@ -77,6 +94,14 @@ async fn run_async() {
             ctx.set_fullscreen(!ctx.fullscreen());
         }
 
+        if ctx.just_pressed(KeyCode::F12) {
+            toggle_vsync(&mut surface);
+        }
+
+        if ctx.just_pressed(KeyCode::F5) {
+            renderer.set_wireframe(!renderer.wireframe());
+        }
+
         if ctx.just_pressed(MouseButton::Left) {
             ctx.grab_cursor();
         }
@@ -97,6 +122,10 @@ async fn run_async() {
 
         let mut data = render_data.take().unwrap();
         game.render(ctx, &assets, &mut data);
+        #[cfg(not(target_arch = "wasm32"))]
+        if ctx.just_pressed(KeyCode::F2) {
+            save_screenshot(&mut renderer, &mut data);
+        }
         renderer.render(frame.target(), &mut data);
         frame.present();
         render_data = Some(data.reset());
@@ -104,3 +133,80 @@ async fn run_async() {
         profiling::finish_frame!();
     });
 }
+
+/// Switches the surface between a present mode that waits for vertical synchronization and one
+/// that does not.
+fn toggle_vsync(surface: &mut Surface) {
+    let mode = if surface.config().present_mode == PresentMode::Fifo {
+        // Mailbox does not cause tearing, prefer it when available.
+        if surface
+            .supported_present_modes()
+            .contains(&PresentMode::Mailbox)
+        {
+            PresentMode::Mailbox
+        } else {
+            PresentMode::Immediate
+        }
+    } else {
+        PresentMode::Fifo
+    };
+
+    surface.set_present_mode(mode);
+}
+
+/// Captures the provided frame and writes it to a PNG file in the current directory.
+#[cfg(not(target_arch = "wasm32"))]
+fn save_screenshot(renderer: &mut Renderer, data: &mut RenderData) {
+    const FILE_NAME: &str = "screenshot.png";
+
+    let image = match renderer.capture(data) {
+        Ok(image) => image,
+        Err(err) => {
+            bns_log::error!("failed to capture the frame: {err}");
+            return;
+        }
+    };
+
+    bns_log::info!("Writing screenshot to '{}'...", FILE_NAME);
+    let result = std::fs::File::create(FILE_NAME)
+        .map_err(bns_image::Error::Io)
+        .and_then(|file| image.encode_png(std::io::BufWriter::new(file)));
+    if let Err(err) = result {
+        bns_log::error!("failed to write the screenshot: {err}");
+    }
+}
+
+/// Parses the spawn point requested on the command line, if any.
+///
+/// The spawn point is provided using `--spawn=x,y,z` or `--spawn=x,y,z,yaw,pitch`, where the
+/// yaw and the pitch are expressed in degrees.
+fn spawn_from_args() -> Option<Spawn> {
+    let arg = std::env::args().find_map(|arg| arg.strip_prefix("--spawn=").map(str::to_owned))?;
+
+    let values: Result<Vec<f32>, _> = arg.split(',').map(|v| v.trim().parse()).collect();
+    match values.as_deref() {
+        Ok(&[x, y, z]) => Some(Spawn {
+            position: Vec3::new(x, y, z),
+            ..Spawn::default()
+        }),
+        Ok(&[x, y, z, yaw, pitch]) => Some(Spawn {
+            position: Vec3::new(x, y, z),
+            yaw: yaw.to_radians(),
+            pitch: pitch.to_radians(),
+        }),
+        _ => {
+            bns_log::warning!("invalid spawn point: `{arg}` (expected `x,y,z[,yaw,pitch]`)");
+            None
+        }
+    }
+}
+
+/// Loads the icon of the window.
+#[cfg(not(target_arch = "wasm32"))]
+fn window_icon() -> Option<bns_image::Image> {
+    bns_image::Image::load(std::io::Cursor::new(include_bytes!(
+        "../assets/grass_side.png"
+    )))
+    .map_err(|err| bns_log::warning!("failed to load the window icon: {err}"))
+    .ok()
+}
@@ -15,7 +15,7 @@ pub async fn load_texture_atlas() -> TextureAtlasConfig<'static> {
         let mut image = load_image(texture_id.file_name()).await;
         #[cfg(feature = "embedded-assets")]
         let mut image =
-            bns_image::Image::load_png(std::io::Cursor::new(texture_id.embeded_texture())).unwrap();
+            bns_image::Image::load(std::io::Cursor::new(texture_id.embeded_texture())).unwrap();
         image.ensure_rgba();
 
         match &metadata {
@@ -55,6 +55,7 @@ pub async fn load_texture_atlas() -> TextureAtlasConfig<'static> {
             bns_image::ColorSpace::Unknown => TextureFormat::Rgba8UnormSrgb,
             bns_image::ColorSpace::Linear => TextureFormat::Rgba8Unorm,
         },
+        uv_inset: 0.01,
     }
 }
 
@@ -76,7 +77,7 @@ macro_rules! load_texture {
     ($gpu:expr, $name:literal) => {
         load_texture(
             $gpu,
-            bns_image::Image::load_png(std::io::Cursor::new(include_bytes!(concat!(
+            bns_image::Image::load(std::io::Cursor::new(include_bytes!(concat!(
                 "../assets/",
                 $name,
                 ".png"
@@ -119,14 +120,14 @@ async fn load_image(asset_path: &str) -> bns_image::Image {
         let url = format!("assets/{}.png", asset_path);
         bns_log::trace!("downloading image from '{url}'...");
         let data = fetch_api::fetch(&url).await;
-        bns_image::Image::load_png(std::io::Cursor::new(data)).unwrap()
+        bns_image::Image::load(std::io::Cursor::new(data)).unwrap()
     }
 
     #[cfg(not(target_arch = "wasm32"))]
     {
         let path = format!("assets/{}.png", asset_path);
         bns_log::trace!("loading image from '{path}'...");
-        bns_image::Image::load_png(std::fs::File::open(path).unwrap()).unwrap()
+        bns_image::Image::load(std::fs::File::open(path).unwrap()).unwrap()
     }
 }
 
@@ -227,39 +228,39 @@ impl Sounds {
             | BlockId::Dirt
             | BlockId::OakLeaves
             | BlockId::PineLeaves
-            | BlockId::Clay => match rng.next_u32() % 3 {
+            | BlockId::Clay => match rng.next_u32_below(3) {
                 0 => self.break_grass1.clone(),
                 1 => self.break_grass2.clone(),
                 2 => self.break_grass3.clone(),
                 _ => unreachable!(),
             },
-            BlockId::Sand => match rng.next_u32() % 3 {
+            BlockId::Sand => match rng.next_u32_below(3) {
                 0 => self.break_sand1.clone(),
                 1 => self.break_sand2.clone(),
                 2 => self.break_sand3.clone(),
                 _ => unreachable!(),
             },
             BlockId::OakLog | BlockId::PineLog | BlockId::OakPlanks | BlockId::PinePlanks => {
-                match rng.next_u32() % 3 {
+                match rng.next_u32_below(3) {
                     0 => self.break_wood1.clone(),
                     1 => self.break_wood2.clone(),
                     2 => self.break_wood3.clone(),
                     _ => unreachable!(),
                 }
             }
-            BlockId::Gravel => match rng.next_u32() % 3 {
+            BlockId::Gravel => match rng.next_u32_below(3) {
                 0 => self.break_gravel1.clone(),
                 1 => self.break_gravel2.clone(),
                 2 => self.break_gravel3.clone(),
                 _ => unreachable!(),
             },
-            BlockId::Glass => match rng.next_u32() % 3 {
+            BlockId::Glass => match rng.next_u32_below(3) {
                 0 => self.break_glass1.clone(),
                 1 => self.break_glass2.clone(),
                 2 => self.break_glass3.clone(),
                 _ => unreachable!(),
             },
-            _ => match rng.next_u32() % 3 {
+            _ => match rng.next_u32_below(3) {
                 0 => self.break_stone1.clone(),
                 1 => self.break_stone2.clone(),
                 2 => self.break_stone3.clone(),
@@ -277,27 +278,27 @@ impl Sounds {
             | BlockId::OakLeaves
             | BlockId::Daffodil
             | BlockId::PineLeaves
-            | BlockId::Clay => match rng.next_u32() % 3 {
+            | BlockId::Clay => match rng.next_u32_below(3) {
                 0 => self.step_grass1.clone(),
                 1 => self.step_grass2.clone(),
                 2 => self.step_grass3.clone(),
                 _ => unreachable!(),
             },
-            BlockId::Sand | BlockId::Gravel => match rng.next_u32() % 3 {
+            BlockId::Sand | BlockId::Gravel => match rng.next_u32_below(3) {
                 0 => self.step_sand1.clone(),
                 1 => self.step_sand2.clone(),
                 2 => self.step_sand3.clone(),
                 _ => unreachable!(),
             },
             BlockId::OakLog | BlockId::PineLog | BlockId::OakPlanks | BlockId::PinePlanks => {
-                match rng.next_u32() % 3 {
+                match rng.next_u32_below(3) {
                     0 => self.step_wood1.clone(),
                     1 => self.step_wood2.clone(),
                     2 => self.step_wood3.clone(),
                     _ => unreachable!(),
                 }
             }
-            _ => match rng.next_u32() % 3 {
+            _ => match rng.next_u32_below(3) {
                 0 => self.step_stone1.clone(),
                 1 => self.step_stone2.clone(),
                 2 => self.step_stone3.clone(),
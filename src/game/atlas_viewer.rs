@@ -0,0 +1,186 @@
+//! A debug screen that displays the content of the texture atlas.
+
+use std::sync::Arc;
+
+use bns_core::TextureId;
+use bns_render::data::{AtlasSprite, Color, RenderData, Ui};
+use bns_render::{DynamicVertexBuffer, Gpu};
+
+use glam::Vec2;
+
+/// The space left between the edges of the screen and the grid, in pixels.
+const MARGIN: f32 = 32.0;
+
+/// The width of the lines that mark the boundaries of the tiles, in pixels.
+const LINE_WIDTH: f32 = 2.0;
+
+/// The color of the lines that mark the boundaries of the tiles.
+///
+/// The lines are sampled from an opaque texture of the atlas, meaning that only the alpha
+/// channel of this color is used as-is. Any other component darkens the texture.
+const LINE_COLOR: Color = Color::BLACK;
+
+/// The texture used to draw the lines of the grid. It must be fully opaque.
+const LINE_TEXTURE: TextureId = TextureId::Stone;
+
+/// Displays every texture of the atlas in a grid, with lines marking the boundaries of each
+/// tile.
+pub struct AtlasViewer {
+    /// Whether the atlas viewer is currently visible.
+    visible: bool,
+    /// The size of the screen for which the sprites have been built, if they have been built.
+    built_for: Option<(u32, u32)>,
+    /// The sprites of the atlas viewer.
+    sprites: DynamicVertexBuffer<AtlasSprite>,
+}
+
+impl AtlasViewer {
+    /// Creates a new [`AtlasViewer`] instance.
+    pub fn new(gpu: Arc<Gpu>) -> Self {
+        Self {
+            visible: false,
+            built_for: None,
+            sprites: DynamicVertexBuffer::new(gpu, TextureId::COUNT as u32 * 2),
+        }
+    }
+
+    /// Toggles the visibility of the atlas viewer.
+    #[inline]
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Renders the atlas viewer, if it is visible.
+    pub fn render<'res>(&'res mut self, width: u32, height: u32, frame: &mut RenderData<'res>) {
+        if !self.visible {
+            return;
+        }
+
+        if self.built_for != Some((width, height)) {
+            let mut sprites = Vec::new();
+            build_grid(
+                TextureId::COUNT,
+                Vec2::new(width as f32, height as f32),
+                &mut sprites,
+            );
+
+            self.sprites.clear();
+            self.sprites.extend(&sprites);
+            self.built_for = Some((width, height));
+        }
+
+        frame.ui.push(Ui::AtlasSprite(self.sprites.slice()));
+    }
+}
+
+/// Computes the number of columns and rows of a grid that contains `count` tiles.
+///
+/// The grid is as close to a square as possible.
+pub fn grid_dimensions(count: usize) -> (usize, usize) {
+    let columns = ((count as f32).sqrt().ceil() as usize).max(1);
+    let rows = count.div_ceil(columns).max(1);
+    (columns, rows)
+}
+
+/// Builds the sprites required to display `texture_count` textures of the atlas in a grid that
+/// fits a screen of the provided size.
+///
+/// The sprites of the textures come first (one per texture, in order), followed by the lines
+/// that mark the boundaries of the tiles: the vertical lines first, from left to right, then the
+/// horizontal ones, from top to bottom.
+pub fn build_grid(texture_count: usize, screen: Vec2, out: &mut Vec<AtlasSprite>) {
+    let (columns, rows) = grid_dimensions(texture_count);
+    let grid = Vec2::new(columns as f32, rows as f32);
+
+    let available = (screen - Vec2::splat(2.0 * MARGIN)).max(Vec2::ZERO);
+    let tile = (available / grid).min_element().floor();
+    let origin = ((screen - grid * tile) / 2.0).floor();
+
+    for i in 0..texture_count {
+        let cell = Vec2::new((i % columns) as f32, (i / columns) as f32);
+        out.push(AtlasSprite {
+            texture_id: i as u32,
+            ..AtlasSprite::dummy().with_rect(origin + cell * tile, Vec2::splat(tile))
+        });
+    }
+
+    let line = AtlasSprite {
+        texture_id: LINE_TEXTURE as u32,
+        ..AtlasSprite::dummy().with_color(LINE_COLOR)
+    };
+
+    for x in 0..=columns {
+        out.push(line.with_rect(
+            origin + Vec2::new(x as f32 * tile - LINE_WIDTH / 2.0, -LINE_WIDTH / 2.0),
+            Vec2::new(LINE_WIDTH, rows as f32 * tile + LINE_WIDTH),
+        ));
+    }
+
+    for y in 0..=rows {
+        out.push(line.with_rect(
+            origin + Vec2::new(-LINE_WIDTH / 2.0, y as f32 * tile - LINE_WIDTH / 2.0),
+            Vec2::new(columns as f32 * tile + LINE_WIDTH, LINE_WIDTH),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the size of the provided sprite.
+    fn size(sprite: &AtlasSprite) -> Vec2 {
+        Vec2::new(sprite.transform.x_axis.x, sprite.transform.y_axis.y)
+    }
+
+    #[test]
+    fn grid_is_as_square_as_possible() {
+        assert_eq!(grid_dimensions(0), (1, 1));
+        assert_eq!(grid_dimensions(1), (1, 1));
+        assert_eq!(grid_dimensions(5), (3, 2));
+        assert_eq!(grid_dimensions(16), (4, 4));
+        assert_eq!(grid_dimensions(17), (5, 4));
+    }
+
+    #[test]
+    fn lines_follow_tile_boundaries() {
+        let screen = Vec2::new(800.0, 600.0);
+        let mut sprites = Vec::new();
+        build_grid(10, screen, &mut sprites);
+
+        let (columns, rows) = grid_dimensions(10);
+        assert_eq!(sprites.len(), 10 + (columns + 1) + (rows + 1));
+
+        let (tiles, lines) = sprites.split_at(10);
+        let (vertical, horizontal) = lines.split_at(columns + 1);
+        let origin = tiles[0].position;
+        let tile = size(&tiles[0]).x;
+
+        for (i, sprite) in tiles.iter().enumerate() {
+            assert_eq!(sprite.texture_id, i as u32);
+            assert_eq!(size(sprite), Vec2::splat(tile));
+            assert!(sprite.position.cmpge(Vec2::splat(MARGIN)).all());
+            assert!((sprite.position + tile).cmple(screen - MARGIN).all());
+        }
+
+        for (x, line) in vertical.iter().enumerate() {
+            let center = line.position.x + LINE_WIDTH / 2.0;
+            assert_eq!(center, origin.x + x as f32 * tile);
+            assert_eq!(line.position.y, origin.y - LINE_WIDTH / 2.0);
+            assert_eq!(
+                size(line),
+                Vec2::new(LINE_WIDTH, rows as f32 * tile + LINE_WIDTH)
+            );
+        }
+
+        for (y, line) in horizontal.iter().enumerate() {
+            let center = line.position.y + LINE_WIDTH / 2.0;
+            assert_eq!(center, origin.y + y as f32 * tile);
+            assert_eq!(line.position.x, origin.x - LINE_WIDTH / 2.0);
+            assert_eq!(
+                size(line),
+                Vec2::new(columns as f32 * tile + LINE_WIDTH, LINE_WIDTH)
+            );
+        }
+    }
+}
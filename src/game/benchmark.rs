@@ -0,0 +1,264 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+use std::time::Duration;
+
+use glam::Vec3;
+
+/// The seed used to generate the world when running a benchmark.
+///
+/// Using a fixed seed ensures that two runs of the benchmark generate exactly the same
+/// chunks.
+pub const BENCHMARK_SEED: u64 = 0x6e69_6c73_626e_7300;
+
+/// A point on the path followed by the camera during a benchmark.
+struct Waypoint {
+    /// The position of the camera at this point.
+    position: Vec3,
+    /// The yaw of the camera at this point, in radians.
+    yaw: f32,
+    /// The pitch of the camera at this point, in radians.
+    pitch: f32,
+}
+
+/// The scripted path followed by the camera.
+const PATH: &[Waypoint] = &[
+    Waypoint {
+        position: Vec3::new(0.0, 40.0, 0.0),
+        yaw: 0.0,
+        pitch: 0.3,
+    },
+    Waypoint {
+        position: Vec3::new(0.0, 40.0, 400.0),
+        yaw: 0.0,
+        pitch: 0.1,
+    },
+    Waypoint {
+        position: Vec3::new(400.0, 60.0, 400.0),
+        yaw: FRAC_PI_2,
+        pitch: 0.2,
+    },
+    Waypoint {
+        position: Vec3::new(400.0, 20.0, 0.0),
+        yaw: PI,
+        pitch: 0.0,
+    },
+    Waypoint {
+        position: Vec3::new(0.0, 40.0, 0.0),
+        yaw: PI + FRAC_PI_2,
+        pitch: 0.3,
+    },
+];
+
+/// The amount of time spent going from one waypoint to the next one.
+const SEGMENT_DURATION: Duration = Duration::from_secs(10);
+
+/// The state of a running benchmark.
+///
+/// When a benchmark is running, the player's inputs are ignored and the camera follows a
+/// scripted path over a fixed-seed world. Statistics are collected every frame and reported
+/// once the path has been completed.
+pub struct Benchmark {
+    /// The amount of time that has passed since the start of the benchmark.
+    elapsed: Duration,
+    /// The frame times that have been recorded so far.
+    frame_times: Vec<Duration>,
+    /// The maximum number of chunks that were loading at the same time.
+    peak_loading_chunks: usize,
+    /// The number of chunks that were loaded at the end of the benchmark.
+    loaded_chunks: usize,
+    /// The sum of the number of quads rendered every frame.
+    total_quads: u64,
+}
+
+impl Benchmark {
+    /// Creates a new [`Benchmark`] instance.
+    pub fn new() -> Self {
+        Self {
+            elapsed: Duration::ZERO,
+            frame_times: Vec::new(),
+            peak_loading_chunks: 0,
+            loaded_chunks: 0,
+            total_quads: 0,
+        }
+    }
+
+    /// Advances the benchmark by `dt`, recording it as the duration of the last frame.
+    pub fn tick(&mut self, dt: Duration, loading_chunks: usize, loaded_chunks: usize) {
+        self.elapsed += dt;
+        self.frame_times.push(dt);
+        self.peak_loading_chunks = self.peak_loading_chunks.max(loading_chunks);
+        self.loaded_chunks = loaded_chunks;
+    }
+
+    /// Records the number of quads that were rendered during the last frame.
+    #[inline]
+    pub fn record_quads(&mut self, count: u32) {
+        self.total_quads += count as u64;
+    }
+
+    /// Returns whether the camera has reached the end of the path.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= SEGMENT_DURATION * (PATH.len() as u32 - 1)
+    }
+
+    /// Returns the position, yaw and pitch that the camera should have at the current point
+    /// of the benchmark.
+    pub fn camera(&self) -> (Vec3, f32, f32) {
+        let t = self.elapsed.as_secs_f32() / SEGMENT_DURATION.as_secs_f32();
+        let index = (t as usize).min(PATH.len() - 2);
+        let f = (t - index as f32).min(1.0);
+
+        let a = &PATH[index];
+        let b = &PATH[index + 1];
+
+        (
+            a.position.lerp(b.position, f),
+            a.yaw + (b.yaw - a.yaw) * f,
+            a.pitch + (b.pitch - a.pitch) * f,
+        )
+    }
+
+    /// Aggregates the statistics that have been collected during the benchmark.
+    ///
+    /// # Returns
+    ///
+    /// [`None`] if no frame has been recorded.
+    fn stats(&mut self) -> Option<Stats> {
+        let frame_count = self.frame_times.len();
+        if frame_count == 0 {
+            return None;
+        }
+
+        let average = self.frame_times.iter().sum::<Duration>() / frame_count as u32;
+
+        self.frame_times.sort_unstable();
+        let percentile = |p: usize| self.frame_times[(frame_count - 1) * p / 100];
+
+        Some(Stats {
+            frame_count,
+            average,
+            p50: percentile(50),
+            p95: percentile(95),
+            p99: percentile(99),
+            max: self.frame_times[frame_count - 1],
+            average_quads: self.total_quads / frame_count as u64,
+        })
+    }
+
+    /// Logs the statistics that have been collected during the benchmark.
+    pub fn report(&mut self) {
+        let Some(stats) = self.stats() else {
+            bns_log::warning!("benchmark finished without recording any frame");
+            return;
+        };
+
+        bns_log::info!("benchmark finished after {} frames", stats.frame_count);
+        bns_log::info!(
+            "frame time: avg {:?}, p50 {:?}, p95 {:?}, p99 {:?}, max {:?}",
+            stats.average,
+            stats.p50,
+            stats.p95,
+            stats.p99,
+            stats.max,
+        );
+        bns_log::info!(
+            "chunks: {} loaded, {} loading at peak",
+            self.loaded_chunks,
+            self.peak_loading_chunks,
+        );
+        bns_log::info!("quads: {} per frame on average", stats.average_quads);
+    }
+}
+
+/// The frame statistics aggregated at the end of a benchmark.
+#[derive(Debug, PartialEq, Eq)]
+struct Stats {
+    /// The number of frames that were recorded.
+    frame_count: usize,
+    /// The average frame time.
+    average: Duration,
+    /// The median frame time.
+    p50: Duration,
+    /// The 95th percentile of the frame times.
+    p95: Duration,
+    /// The 99th percentile of the frame times.
+    p99: Duration,
+    /// The longest frame time.
+    max: Duration,
+    /// The average number of quads rendered every frame.
+    average_quads: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts that the camera of `benchmark` is at the provided location.
+    fn assert_camera(benchmark: &Benchmark, position: Vec3, yaw: f32, pitch: f32) {
+        let (p, y, x) = benchmark.camera();
+        assert!(p.abs_diff_eq(position, 1e-3), "{p} != {position}");
+        assert!((y - yaw).abs() < 1e-5, "{y} != {yaw}");
+        assert!((x - pitch).abs() < 1e-5, "{x} != {pitch}");
+    }
+
+    #[test]
+    fn camera_follows_the_path() {
+        let mut benchmark = Benchmark::new();
+        assert_camera(&benchmark, PATH[0].position, PATH[0].yaw, PATH[0].pitch);
+
+        // Halfway through the first segment.
+        benchmark.tick(SEGMENT_DURATION / 2, 0, 0);
+        assert_camera(&benchmark, Vec3::new(0.0, 40.0, 200.0), 0.0, 0.2);
+
+        // Every waypoint is reached at the end of its segment.
+        for waypoint in &PATH[1..] {
+            benchmark.tick(SEGMENT_DURATION / 2, 0, 0);
+            assert_camera(&benchmark, waypoint.position, waypoint.yaw, waypoint.pitch);
+            benchmark.tick(SEGMENT_DURATION / 2, 0, 0);
+        }
+    }
+
+    #[test]
+    fn finishes_at_the_end_of_the_path() {
+        let mut benchmark = Benchmark::new();
+        let total = SEGMENT_DURATION * (PATH.len() as u32 - 1);
+
+        benchmark.tick(total - Duration::from_millis(1), 0, 0);
+        assert!(!benchmark.is_finished());
+
+        benchmark.tick(Duration::from_millis(1), 0, 0);
+        assert!(benchmark.is_finished());
+
+        // The camera stays at the last waypoint.
+        benchmark.tick(SEGMENT_DURATION, 0, 0);
+        let last = PATH.last().unwrap();
+        assert_camera(&benchmark, last.position, last.yaw, last.pitch);
+    }
+
+    #[test]
+    fn stats_aggregate_recorded_frames() {
+        let mut benchmark = Benchmark::new();
+        assert_eq!(benchmark.stats(), None);
+
+        // Record the frames out of order to make sure they are sorted.
+        for ms in (1..=100).rev() {
+            benchmark.tick(Duration::from_millis(ms), ms as usize % 7, 42);
+            benchmark.record_quads(ms as u32 * 10);
+        }
+
+        assert_eq!(benchmark.peak_loading_chunks, 6);
+        assert_eq!(benchmark.loaded_chunks, 42);
+        assert_eq!(
+            benchmark.stats(),
+            Some(Stats {
+                frame_count: 100,
+                average: Duration::from_micros(50_500),
+                p50: Duration::from_millis(50),
+                p95: Duration::from_millis(95),
+                p99: Duration::from_millis(99),
+                max: Duration::from_millis(100),
+                average_quads: 505,
+            })
+        );
+    }
+}
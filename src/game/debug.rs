@@ -4,12 +4,14 @@ use std::time::Duration;
 use bns_app::{Ctx, KeyCode};
 use bns_core::ChunkPos;
 use bns_render::data::{
-    CharacterInstance, CharacterInstanceCursor, Color, LineFlags, RenderData, Ui,
+    CharacterInstance, CharacterInstanceCursor, Color, LineFlags, LineInstance, RenderData, Ui,
 };
 use bns_render::{DynamicVertexBuffer, Gpu};
 
 use glam::{IVec3, Vec2, Vec3};
 
+use super::atlas_viewer::AtlasViewer;
+
 /// Contains some state that's only used for debugging purposes, such
 /// as the debug overlay and the buffer to store chunk border lines.
 pub struct DebugThings {
@@ -17,6 +19,8 @@ pub struct DebugThings {
     overlay: bool,
     /// The current state of the debug chunk display.
     chunk_state: DebugChunkState,
+    /// The screen that displays the content of the texture atlas.
+    atlas_viewer: AtlasViewer,
 
     /// The content of the debug overlay.
     overlay_buffer: DebugOverlayBuffer,
@@ -43,6 +47,7 @@ impl DebugThings {
         Self {
             overlay: false,
             chunk_state: DebugChunkState::Hidden,
+            atlas_viewer: AtlasViewer::new(gpu.clone()),
             overlay_buffer: DebugOverlayBuffer::new(),
             overlay_gpu_buffer: DynamicVertexBuffer::new(gpu, 64),
             accumulated_frame_time: Duration::ZERO,
@@ -71,6 +76,10 @@ impl DebugThings {
             bns_log::info!("debug chunk state: {}", self.chunk_state.name());
         }
 
+        if ctx.just_pressed(KeyCode::F6) {
+            self.atlas_viewer.toggle();
+        }
+
         // ==================================
         // Frame time
         // ==================================
@@ -108,8 +117,18 @@ impl DebugThings {
     }
 
     /// Renders the debug overlay.
+    ///
+    /// `modified_chunks` is only used when the modified chunks are being displayed.
     #[profiling::function]
-    pub fn render<'res>(&'res mut self, current_chunk: ChunkPos, frame: &mut RenderData<'res>) {
+    pub fn render<'res>(
+        &'res mut self,
+        ctx: &Ctx,
+        current_chunk: ChunkPos,
+        modified_chunks: impl Iterator<Item = ChunkPos>,
+        frame: &mut RenderData<'res>,
+    ) {
+        self.atlas_viewer.render(ctx.width(), ctx.height(), frame);
+
         // If the overlay is enabled, render it.
         if self.overlay {
             // If the overlay buffer has been updated, upload it to the GPU.
@@ -163,10 +182,35 @@ impl DebugThings {
                     }
                 }
             }
+            DebugChunkState::ShowModifiedChunks => {
+                push_modified_chunk_lines(&mut frame.lines, modified_chunks);
+            }
         }
     }
 }
 
+/// Outlines the provided modified chunks.
+///
+/// The outlines are drawn above the rest of the world, making it easy to spot where the edits
+/// are from afar.
+fn push_modified_chunk_lines(
+    lines: &mut Vec<LineInstance>,
+    modified_chunks: impl Iterator<Item = ChunkPos>,
+) {
+    const CHUNK_SIZE: f32 = bns_core::Chunk::SIDE as f32;
+
+    for chunk in modified_chunks {
+        super::utility::push_aabb_lines(
+            lines,
+            chunk.as_vec3() * CHUNK_SIZE,
+            chunk.as_vec3() * CHUNK_SIZE + Vec3::splat(CHUNK_SIZE),
+            Color::MAGENTA,
+            2.0,
+            LineFlags::ABOVE,
+        );
+    }
+}
+
 /// A buffer that implements [`std::fmt::Write`] and writes to a
 /// collection of [`CharacterInstance`]s.
 pub struct DebugOverlayBuffer {
@@ -236,6 +280,8 @@ enum DebugChunkState {
     ShowCurrentChunk,
     /// Draw the chunk grid.
     ShowAllChunks,
+    /// Draw the chunks that contain blocks modified by the player.
+    ShowModifiedChunks,
 }
 
 impl DebugChunkState {
@@ -245,6 +291,7 @@ impl DebugChunkState {
             Self::Hidden => "hidden",
             Self::ShowCurrentChunk => "show current chunk",
             Self::ShowAllChunks => "show all chunks",
+            Self::ShowModifiedChunks => "show modified chunks",
         }
     }
 
@@ -253,7 +300,41 @@ impl DebugChunkState {
         match self {
             Self::Hidden => Self::ShowCurrentChunk,
             Self::ShowCurrentChunk => Self::ShowAllChunks,
-            Self::ShowAllChunks => Self::Hidden,
+            Self::ShowAllChunks => Self::ShowModifiedChunks,
+            Self::ShowModifiedChunks => Self::Hidden,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modified_chunks_are_outlined() {
+        const SIDE: f32 = bns_core::Chunk::SIDE as f32;
+
+        let chunks = [ChunkPos::new(0, 0, 0), ChunkPos::new(-2, 1, 3)];
+        let mut lines = Vec::new();
+        push_modified_chunk_lines(&mut lines, chunks.into_iter());
+
+        // Each chunk is outlined by the 12 edges of its bounding box.
+        assert_eq!(lines.len(), 24);
+
+        for (chunk, edges) in chunks.iter().zip(lines.chunks(12)) {
+            let min = chunk.as_vec3() * SIDE;
+            let max = min + Vec3::splat(SIDE);
+
+            for line in edges {
+                assert_eq!(line.color, Color::MAGENTA);
+                assert!(line.flags.contains(LineFlags::ABOVE));
+
+                // Edges go along a single axis, from one corner of the chunk to another.
+                for end in [line.start, line.end] {
+                    assert!((end.cmpeq(min) | end.cmpeq(max)).all(), "{end}");
+                }
+                assert_eq!(line.start.distance(line.end), SIDE);
+            }
         }
     }
 }
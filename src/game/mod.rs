@@ -4,24 +4,35 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use bns_app::{Ctx, KeyCode};
-use bns_core::ChunkPos;
-use bns_render::data::{ChunkUniforms, Color, FrameFlags, FrameUniforms, LineFlags, RenderData};
+use bns_core::{BlockId, Chunk, ChunkPos};
+use bns_render::data::{
+    ChunkUniforms, Color, FrameFlags, FrameUniforms, LineFlags, PointLight, RenderData,
+};
 use bns_render::Gpu;
-use bns_rng::{DefaultRng, FromRng, Rng};
+use bns_rng::{DefaultRng, Rng};
 use bns_worldgen_std::StandardWorldGenerator;
 
+use bytemuck::Contiguous;
 use glam::{Vec2, Vec3};
 use rodio::Source;
 
+use self::benchmark::Benchmark;
 use self::debug::DebugThings;
-use self::player::{LookingAt, Player};
+use self::particles::Particles;
+use self::player::{LookingAt, Player, Spawn};
+use self::sky::{AmbientLight, DayCyclePalette, SkyPalette};
 use self::sun::Sun;
 use crate::assets::{Assets, Sounds};
-use crate::world::World;
+use crate::settings::Settings;
+use crate::world::{ChunkPriority, Viewer, World};
 
 pub mod player;
 
+mod atlas_viewer;
+mod benchmark;
 mod debug;
+mod particles;
+mod sky;
 mod sun;
 mod utility;
 
@@ -42,8 +53,6 @@ pub struct Game {
     /// The amount of time that has passed since the last time the world has cleaned
     /// up its unused data.
     since_last_cleanup: Duration,
-    /// The seed that was used to create the [`World`].
-    seed: u64,
     /// Some state that's only used for debugging purposes.
     debug: DebugThings,
 
@@ -51,6 +60,18 @@ pub struct Game {
     fog_enabled: bool,
     /// Whether or not the shadows are enabled.
     shadows_enabled: bool,
+    /// The number of cascades the shadow map of the sun is split into.
+    shadow_cascades: u32,
+    /// Whether or not the skybox is enabled.
+    ///
+    /// When disabled, the background is cleared to a flat color.
+    skybox_enabled: bool,
+    /// The color the background is cleared to when the skybox is disabled.
+    ///
+    /// When `None`, the current color of the sky is used.
+    clear_color: Option<Color>,
+    /// Whether or not the UI is rendered.
+    ui_enabled: bool,
 
     /// The handle to the output stream that's used to play the music.
     stream_handle: rodio::OutputStreamHandle,
@@ -62,19 +83,35 @@ pub struct Game {
     /// The random number generator for the game.
     rng: DefaultRng,
 
+    /// The particles that are currently flying around.
+    particles: Particles,
+
     /// The directional light.
     sun: Sun,
+    /// The ambient light color around the player.
+    ambient_light: AmbientLight,
+    /// The colors of the sky over the day, derived from the seed of the world.
+    sky_palette: DayCyclePalette,
+
+    /// The benchmark that's currently running, if any.
+    benchmark: Option<Benchmark>,
 }
 
 impl Game {
-    /// Creates a new [`Game`] with the provided seed.
-    pub fn new(gpu: Arc<Gpu>, sounds: &Sounds) -> Self {
+    /// Creates a new [`Game`] with the provided settings.
+    ///
+    /// When `spawn` is `None`, the player appears at the default [`Spawn`] point.
+    pub fn new(gpu: Arc<Gpu>, sounds: &Sounds, settings: &Settings, spawn: Option<Spawn>) -> Self {
         let seed = bns_rng::entropy();
 
         bns_log::info!("creating a new world with seed: {seed}");
-        let generator = Arc::new(StandardWorldGenerator::from_seed::<DefaultRng>(seed));
-        let world = World::new(gpu.clone(), generator);
-        let player = Player::new(gpu.clone(), Vec3::new(0.0, 16.0, 0.0));
+        let generator = Arc::new(StandardWorldGenerator::new::<DefaultRng>(seed));
+        let mut world = World::new(gpu.clone(), generator);
+        world.set_max_loaded_chunks(settings.max_loaded_chunks);
+        world.set_max_generation_tasks(settings.max_generation_tasks);
+        world.set_chunk_priority(ChunkPriority::LookBiased);
+        let mut player = Player::new(gpu.clone(), spawn.unwrap_or_default());
+        player.set_render_distance(settings.render_distance);
         let debug = DebugThings::new(gpu.clone());
 
         let (_stream, stream_handle) =
@@ -95,17 +132,66 @@ impl Game {
             player,
             world,
             since_last_cleanup: Duration::ZERO,
-            seed,
             debug,
             fog_enabled: true,
-            shadows_enabled: true,
+            shadows_enabled: settings.shadows_enabled,
+            shadow_cascades: settings.shadow_cascades,
+            skybox_enabled: true,
+            clear_color: settings.clear_color,
+            ui_enabled: true,
 
             stream_handle,
             _stream,
 
             rng: DefaultRng::from_entropy(),
 
+            particles: Particles::new(),
+
             sun: Sun::new(),
+            ambient_light: AmbientLight::new(),
+            sky_palette: DayCyclePalette::from_noon(SkyPalette::from_seed(seed)),
+
+            benchmark: None,
+        }
+    }
+
+    /// Starts a benchmark.
+    ///
+    /// The world is re-created with a fixed seed and the player follows a scripted path until
+    /// the benchmark is over. The application is closed once the results have been reported.
+    pub fn start_benchmark(&mut self) {
+        let seed = benchmark::BENCHMARK_SEED;
+        bns_log::info!("starting benchmark with seed: {seed}");
+        let benchmark = Benchmark::new();
+        let (position, yaw, pitch) = benchmark.camera();
+        self.recreate_world(
+            seed,
+            Some(Spawn {
+                position,
+                yaw,
+                pitch,
+            }),
+        );
+        self.benchmark = Some(benchmark);
+    }
+
+    /// Re-creates the world using the provided seed.
+    ///
+    /// The blocks modified by the player are carried over to the new world. When `spawn` is
+    /// provided, the player is moved to it. Otherwise, it stays where it is.
+    fn recreate_world(&mut self, seed: u64, spawn: Option<Spawn>) {
+        let generator = Arc::new(StandardWorldGenerator::new::<DefaultRng>(seed));
+        let mut world = World::new(self.gpu.clone(), generator);
+        world.set_max_loaded_chunks(self.world.max_loaded_chunks());
+        world.set_max_generation_tasks(self.world.max_generation_tasks());
+        world.set_chunk_priority(ChunkPriority::LookBiased);
+        world.set_ambient_occlusion(self.world.ambient_occlusion());
+        world.load_edits(self.world.edits().map(|(pos, block)| (pos, block.clone())));
+        self.world = world;
+        self.sky_palette = DayCyclePalette::from_noon(SkyPalette::from_seed(seed));
+
+        if let Some(spawn) = spawn {
+            self.player.respawn(spawn);
         }
     }
 
@@ -114,20 +200,55 @@ impl Game {
     pub fn tick(&mut self, ctx: &mut Ctx, sounds: &Sounds) {
         self.debug.reset_overlay();
 
-        if ctx.just_pressed(KeyCode::KeyR) {
+        if let Some(benchmark) = &mut self.benchmark {
+            benchmark.tick(
+                ctx.since_last_tick(),
+                self.world.loading_chunk_count(),
+                self.world.loaded_chunk_count(),
+            );
+
+            if benchmark.is_finished() {
+                benchmark.report();
+                self.benchmark = None;
+                ctx.close();
+                return;
+            }
+        }
+
+        if self.benchmark.is_none() && ctx.just_pressed(KeyCode::KeyR) {
             let seed = bns_rng::entropy();
             bns_log::info!("re-creating world with seed: {seed}");
-            let generator = Arc::new(StandardWorldGenerator::from_seed::<DefaultRng>(seed));
-            self.world = World::new(self.gpu.clone(), generator);
-            self.seed = seed;
+            self.recreate_world(seed, None);
+        }
+
+        if ctx.just_pressed(KeyCode::KeyP) {
+            if self.sun.direction_override().is_some() {
+                self.sun.set_direction_override(None);
+            } else {
+                self.sun.set_direction_override(Some(self.sun.direction()));
+            }
         }
 
+        if ctx.just_pressed(KeyCode::F1) {
+            self.ui_enabled = !self.ui_enabled;
+        }
         if ctx.just_pressed(KeyCode::F10) {
             self.fog_enabled = !self.fog_enabled;
         }
         if ctx.just_pressed(KeyCode::F9) {
             self.shadows_enabled = !self.shadows_enabled;
         }
+        if ctx.just_pressed(KeyCode::F7) {
+            self.skybox_enabled = !self.skybox_enabled;
+        }
+        if ctx.just_pressed(KeyCode::F8) {
+            let enabled = !self.world.ambient_occlusion();
+            bns_log::info!(
+                "ambient occlusion: {}",
+                if enabled { "enabled" } else { "disabled" }
+            );
+            self.world.set_ambient_occlusion(enabled);
+        }
 
         self.since_last_cleanup += ctx.since_last_tick();
         if self.since_last_cleanup >= WORLD_CLEAN_UP_INTERVAL {
@@ -138,14 +259,30 @@ impl Game {
             );
             self.since_last_cleanup = Duration::ZERO;
         }
-
-        self.player.tick(
-            &mut self.world,
-            &self.stream_handle,
-            sounds,
-            &mut self.rng,
-            ctx,
+        self.world.enforce_chunk_budget(
+            ChunkPos::from_world_pos(self.player.position()),
+            self.player.render_distance() as u32 + 1,
+            self.player.vertical_render_distance() as u32 + 1,
         );
+
+        if let Some(benchmark) = &self.benchmark {
+            let (position, yaw, pitch) = benchmark.camera();
+            self.player.tick_scripted(ctx, position, yaw, pitch);
+        } else {
+            self.player.tick(
+                &mut self.world,
+                &self.stream_handle,
+                sounds,
+                &mut self.rng,
+                ctx,
+            );
+        }
+        if let Some(pos) = self.player.just_broken() {
+            self.particles
+                .spawn_block_break(pos.as_vec3(), &mut self.rng);
+        }
+        self.particles.tick(ctx.since_last_tick().as_secs_f32());
+
         self.player
             .compute_chunks_in_view(if self.shadows_enabled { 0.0 } else { 16.0 });
 
@@ -153,10 +290,12 @@ impl Game {
             self.world.request_chunk(chunk_pos);
         }
 
-        // Make sure that the chunks that are closest to the player are loaded first.
-        let player_chunk = self.player.position_chunk();
-        self.world
-            .sort_pending_chunks(|p| -player_chunk.distance_squared(p));
+        // Make sure that the chunks that matter the most to the player are loaded first.
+        self.world.prioritize_pending_chunks(&Viewer {
+            chunk: self.player.position_chunk(),
+            position: self.player.position(),
+            camera: self.player.camera(),
+        });
         self.world.flush_pending_chunks();
 
         let _ = writeln!(
@@ -168,6 +307,8 @@ impl Game {
             Loading chunks: {}\n\
             Loaded chunks: {}\n\
             Visible chunks: {}\n\
+            Chunk build: inner {:?}, outer {:?}, upload {:?}\n\
+            Chunk blocks: {}\n\
             \n\
             Looking at: {}\n\
             \n\
@@ -184,8 +325,16 @@ impl Game {
             self.world.loading_chunk_count(),
             self.world.loaded_chunk_count(),
             self.player.chunks_in_view().len(),
+            self.world.build_timings().build_inner,
+            self.world.build_timings().build_outer,
+            self.world.build_timings().upload,
+            DisplayChunkBlocks(
+                self.world
+                    .get_chunk(self.player.position_chunk())
+                    .map(|chunk| chunk.data.block_histogram())
+            ),
             DisplayLookingAt(self.player.looking_at()),
-            self.seed,
+            self.world.generator().seed(),
         );
 
         let _ = self.world.generator().debug_info(
@@ -193,6 +342,17 @@ impl Game {
             self.player.position().floor().as_ivec3(),
         );
 
+        let _ = writeln!(
+            self.debug.overlay_buffer(),
+            "{}",
+            DisplayFields(
+                &self
+                    .world
+                    .generator()
+                    .debug_fields(self.player.position().floor().as_ivec3())
+            ),
+        );
+
         let _ = writeln!(self.debug.overlay_buffer());
 
         self.debug.tick(ctx);
@@ -208,10 +368,20 @@ impl Game {
     ) {
         self.sun.tick(ctx);
 
+        let generator = self.world.generator();
+        self.ambient_light.tick(
+            self.player.position().floor().as_ivec3(),
+            ctx.since_last_tick().as_secs_f32(),
+            |pos| generator.ambient_color(pos),
+        );
+        let ambient_color = self.ambient_light.color();
+
         let mut fog_distance = self.player.render_distance() as f32 * 3.0;
         let mut fog_density = 0.1 / self.player.render_distance() as f32;
-        let mut fog_color = Color::rgb(100, 200, 255);
-        let mut sky_color = Color::rgb(150, 100, 255);
+        let day_phase = self.sun.day_phase();
+        let palette = self.sky_palette.sample(day_phase);
+        let mut fog_color = palette.horizon;
+        let mut sky_color = palette.sky;
         if self.player.is_underwater() {
             fog_distance = 4.0;
             fog_density *= 24.0;
@@ -229,6 +399,11 @@ impl Game {
         let mut frame_flags = FrameFlags::empty();
         frame_flags.set(FrameFlags::UNDERWATER, self.player.is_underwater());
         frame_flags.set(FrameFlags::SHADOWS_ENABLED, self.shadows_enabled);
+        let shadow_cascades = self.sun.cascades(
+            self.player.position(),
+            self.player.camera(),
+            self.shadow_cascades,
+        );
         frame.uniforms = FrameUniforms {
             inverse_projection: projection.inverse(),
             inverse_view: view.inverse(),
@@ -247,21 +422,24 @@ impl Game {
             } else {
                 2.0
             },
-            light_transform: self
-                .sun
-                .matrix(self.player.position(), self.player.camera()),
-            // light_transform: Mat4::orthographic_lh(-50.0, 50.0, -50.0, 50.0, 1.0, 100.0)
-            //     * Mat4::look_to_lh(
-            //         self.player.position() + sun_direction * 50.0,
-            //         -sun_direction,
-            //         Vec3::Y,
-            //     ),
+            light_transforms: shadow_cascades.transforms,
+            shadow_cascade_splits: shadow_cascades.splits,
+            shadow_cascade_count: shadow_cascades.count,
+            sun_intensity: self.sky_palette.sun_intensity(day_phase),
+            ambient_color: Color::rgb(
+                (ambient_color.x * 255.0) as u8,
+                (ambient_color.y * 255.0) as u8,
+                (ambient_color.z * 255.0) as u8,
+            ),
+            ..Default::default()
         };
         frame.fog_enabled = self.fog_enabled;
         frame.shadows_enabled = self.shadows_enabled;
+        frame.skybox_enabled = self.skybox_enabled;
+        frame.ui_enabled = self.ui_enabled;
+        frame.clear_color = self.clear_color.unwrap_or(frame.uniforms.sky_color);
 
         // Register the world geometry.
-        let mut total_quad_count = 0;
         for &chunk_pos in self.player.chunks_in_view() {
             let Some(chunk) = self.world.get_chunk(chunk_pos) else {
                 continue;
@@ -276,18 +454,27 @@ impl Game {
             });
             if let Some(buf) = chunk.geometry.opaque_quad_instances() {
                 frame.quads.register_opaque_quads(chunk_idx, buf.slice());
-                total_quad_count += buf.len();
             }
             if let Some(buf) = chunk.geometry.transparent_quad_instances() {
                 frame
                     .quads
                     .register_transparent_quads(chunk_idx, buf.slice());
-                total_quad_count += buf.len();
             }
         }
 
+        self.particles.render(frame);
         self.player.render_hud(assets, frame);
 
+        // Light up the origin of the structure that's being selected.
+        if let Some(pos) = self.player.structure_block() {
+            frame.push_light(PointLight {
+                position: pos.as_vec3() + Vec3::splat(0.5),
+                radius: 8.0,
+                color: Color::rgb(255, 200, 120),
+                ..Default::default()
+            });
+        }
+
         // Outline the block that the player is looking at.
         if let Some(looking_at) = self.player.looking_at() {
             const PADDING: f32 = 0.0;
@@ -308,13 +495,56 @@ impl Game {
             self.player.render_distance(),
             self.player.vertical_render_distance()
         );
+
+        if let Some((_, progress)) = self.player.break_progress() {
+            let _ = writeln!(
+                self.debug.overlay_buffer(),
+                "Breaking: {:.0}%",
+                progress * 100.0
+            );
+        }
+
+        let stats = frame.stats(&self.gpu);
+        if let Some(benchmark) = &mut self.benchmark {
+            benchmark.record_quads(stats.quads);
+        }
+        let _ = writeln!(
+            self.debug.overlay_buffer(),
+            "Quads: {}, lines: {}, particles: {}, UI: {} ({} chars)",
+            stats.quads,
+            stats.lines,
+            stats.particles,
+            stats.ui_elements,
+            stats.text_chars,
+        );
         let _ = writeln!(
             self.debug.overlay_buffer(),
-            "Total quads: {}",
-            total_quad_count
+            "Draw calls: {}",
+            stats.draw_calls
         );
 
-        self.debug.render(self.player.position_chunk(), frame);
+        self.debug.render(
+            ctx,
+            self.player.position_chunk(),
+            self.world.modified_chunks(),
+            frame,
+        );
+    }
+}
+
+/// A simple wrapper that implement [`std::fmt::Display`] to display
+/// the key/value pairs returned by the world generator on a single line.
+struct DisplayFields<'a>(&'a [(String, String)]);
+
+impl std::fmt::Display for DisplayFields<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (key, value)) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{key}: {value}")?;
+        }
+        Ok(())
     }
 }
 
@@ -340,6 +570,47 @@ impl std::fmt::Display for DisplayLookingAt {
     }
 }
 
+/// A simple wrapper that implements [`std::fmt::Display`] to display the most common blocks
+/// of a chunk, given its histogram.
+struct DisplayChunkBlocks(Option<[u32; BlockId::COUNT]>);
+
+impl std::fmt::Display for DisplayChunkBlocks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        /// The maximum number of blocks to display.
+        const MAX_ENTRIES: usize = 4;
+
+        let Some(histogram) = self.0 else {
+            return write!(f, "not loaded");
+        };
+
+        let mut entries: Vec<(BlockId, u32)> = histogram
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count != 0)
+            .map(|(index, &count)| (BlockId::from_integer(index as u8).unwrap(), count))
+            .collect();
+        entries.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+
+        for (i, (id, count)) in entries.iter().take(MAX_ENTRIES).enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(
+                f,
+                "{:?} {:.1}%",
+                id,
+                *count as f32 * 100.0 / Chunk::SIZE as f32,
+            )?;
+        }
+
+        if entries.len() > MAX_ENTRIES {
+            write!(f, ", +{} more", entries.len() - MAX_ENTRIES)?;
+        }
+
+        Ok(())
+    }
+}
+
 /// A simple wrapper that implement [`std::fmt::Display`] to display the direction
 /// that the player is currently looking at (given its YAW value).
 struct DisplayTowards(f32);
@@ -0,0 +1,84 @@
+//! A simple particle system used for visual effects.
+
+use bns_render::data::{Color, ParticleInstance, RenderData};
+use bns_rng::{DefaultRng, Rng};
+use glam::Vec3;
+
+/// The acceleration applied to every particle, in blocks per second squared.
+const GRAVITY: Vec3 = Vec3::new(0.0, -20.0, 0.0);
+
+/// The number of particles spawned when a block is broken.
+const BLOCK_BREAK_PARTICLES: usize = 24;
+
+/// A particle that's being simulated.
+struct Particle {
+    /// The current position of the particle.
+    position: Vec3,
+    /// The current velocity of the particle.
+    velocity: Vec3,
+    /// The number of seconds the particle still has to live.
+    remaining: f32,
+    /// The size of the particle.
+    size: f32,
+    /// The color of the particle.
+    color: Color,
+}
+
+/// A collection of particles.
+pub struct Particles {
+    /// The particles that are currently alive.
+    particles: Vec<Particle>,
+}
+
+impl Particles {
+    /// Creates a new, empty [`Particles`] collection.
+    pub fn new() -> Self {
+        Self {
+            particles: Vec::new(),
+        }
+    }
+
+    /// Spawns the particles that fly off a block when it is broken.
+    ///
+    /// `pos` is the position of the block that has been broken.
+    pub fn spawn_block_break(&mut self, pos: Vec3, rng: &mut DefaultRng) {
+        let center = pos + Vec3::splat(0.5);
+
+        for _ in 0..BLOCK_BREAK_PARTICLES {
+            let offset = Vec3::new(rng.next_f32_11(), rng.next_f32_11(), rng.next_f32_11()) * 0.4;
+            let shade = 90 + (rng.next_f32_01() * 60.0) as u8;
+
+            self.particles.push(Particle {
+                position: center + offset,
+                velocity: offset * 8.0 + Vec3::Y * 4.0,
+                remaining: 0.4 + rng.next_f32_01() * 0.4,
+                size: 0.08 + rng.next_f32_01() * 0.06,
+                color: Color::rgb(shade, shade - 20, shade - 40),
+            });
+        }
+    }
+
+    /// Advances the simulation of the particles by `dt` seconds.
+    pub fn tick(&mut self, dt: f32) {
+        self.particles.retain_mut(|p| {
+            p.remaining -= dt;
+            p.velocity += GRAVITY * dt;
+            p.position += p.velocity * dt;
+            p.remaining > 0.0
+        });
+    }
+
+    /// Registers the particles to be rendered.
+    pub fn render(&self, frame: &mut RenderData) {
+        for p in &self.particles {
+            // Fade out during the last tenth of a second.
+            let alpha = (p.remaining * 10.0).min(1.0);
+
+            frame.push_particle(ParticleInstance {
+                position: p.position,
+                size: p.size,
+                color: p.color.with_alpha((alpha * 255.0) as u8),
+            });
+        }
+    }
+}
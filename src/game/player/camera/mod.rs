@@ -4,7 +4,7 @@ mod view;
 pub use perspective::*;
 pub use view::*;
 
-use glam::Vec3;
+use glam::{Vec2, Vec3, Vec4};
 
 /// Contains the current state of the camera.
 pub struct Camera {
@@ -58,6 +58,34 @@ impl Camera {
         ]
     }
 
+    /// Computes the ray that goes through the provided point of the screen.
+    ///
+    /// # Arguments
+    ///
+    /// - `eye` - The position of the camera, in world space.
+    ///
+    /// - `ndc` - The point of the screen, in normalized device coordinates. `(-1, -1)` is the
+    ///   bottom-left corner of the screen and `(1, 1)` is the top-right corner.
+    ///
+    /// # Returns
+    ///
+    /// The origin of the ray (on the near plane) and its normalized direction, both in world
+    /// space.
+    pub fn ray_from_ndc(&self, eye: Vec3, ndc: Vec2) -> (Vec3, Vec3) {
+        let inverse = self.view.matrix(eye).inverse() * self.projection.matrix().inverse();
+
+        let unproject = |depth: f32| {
+            let p = inverse * Vec4::new(ndc.x, ndc.y, depth, 1.0);
+            p.truncate() / p.w
+        };
+
+        // The projection maps the near plane to a depth of 0 and the far plane to 1.
+        let near = unproject(0.0);
+        let far = unproject(1.0);
+
+        (near, (far - near).normalize())
+    }
+
     /// Determines whether the provided sphere is in the camera's frustum.
     ///
     /// # Arguments
@@ -95,3 +123,43 @@ impl Camera {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a camera looking slightly down and to the side, on a wide screen.
+    fn camera() -> Camera {
+        let mut camera = Camera::new(0.1, 1024.0, 70f32.to_radians());
+        camera.projection.set_aspect_ratio(16.0 / 9.0);
+        camera.view.set_rotation(0.7, -0.3);
+        camera
+    }
+
+    #[test]
+    fn center_ray_matches_look_at() {
+        let camera = camera();
+        let eye = Vec3::new(10.0, 64.0, -5.0);
+        let (origin, direction) = camera.ray_from_ndc(eye, Vec2::ZERO);
+
+        assert!(direction.abs_diff_eq(camera.view.look_at(), 1e-4));
+        let expected_origin = eye + camera.view.look_at() * camera.projection.near();
+        assert!(origin.abs_diff_eq(expected_origin, 1e-3));
+    }
+
+    #[test]
+    fn edge_rays_follow_field_of_view() {
+        let camera = camera();
+        let forward = camera.view.look_at();
+        let right = camera.view.rotation() * Vec3::X;
+        let up = camera.view.rotation() * Vec3::Y;
+
+        let (_, direction) = camera.ray_from_ndc(Vec3::ZERO, Vec2::X);
+        assert!((direction.angle_between(forward) - camera.projection.fov_x() * 0.5).abs() < 1e-3);
+        assert!(direction.dot(right) > 0.0);
+
+        let (_, direction) = camera.ray_from_ndc(Vec3::ZERO, Vec2::Y);
+        assert!((direction.angle_between(forward) - camera.projection.fov_y() * 0.5).abs() < 1e-3);
+        assert!(direction.dot(up) > 0.0);
+    }
+}
@@ -1,5 +1,20 @@
 use glam::Mat4;
 
+/// Describes how the field of view of a [`Perspective`] adapts to the aspect ratio of the output
+/// display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FovScaling {
+    /// The vertical field of view is fixed, and the horizontal one grows with the aspect ratio.
+    ///
+    /// Wider displays see more of the world on their sides.
+    HorPlus,
+    /// The horizontal field of view is fixed, and the vertical one shrinks as the aspect ratio
+    /// grows.
+    ///
+    /// Wider displays see less of the world above and below the center of the screen.
+    VertMinus,
+}
+
 /// Describes a perspective projection matrix.
 pub struct Perspective {
     /// The nearest any object may approach the camera.
@@ -13,7 +28,11 @@ pub struct Perspective {
     aspect_ratio: f32,
 
     /// The field of view of the camera, in *radians*.
-    fov_y: f32,
+    ///
+    /// Depending on `scaling`, this is either the vertical or the horizontal field of view.
+    fov: f32,
+    /// Describes which axis `fov` applies to.
+    scaling: FovScaling,
 
     /// The near plane of the camera.
     ///
@@ -34,7 +53,8 @@ impl Perspective {
     ///
     /// * `aspect_ratio` - The aspect ratio of the output display. This is the width divided by the
     ///
-    /// * `fov_y` - The vertical field of view of the camera, in radians.
+    /// * `fov_y` - The vertical field of view of the camera, in radians. The projection initially
+    ///   uses [`FovScaling::HorPlus`].
     ///
     /// * `far` - The far plane of the camera. Objects further than this distance will be clipped.
     pub fn new(nearest_distance: f32, aspect_ratio: f32, fov_y: f32, far: f32) -> Self {
@@ -43,22 +63,52 @@ impl Perspective {
         Self {
             nearest_distance,
             aspect_ratio,
-            fov_y,
+            fov: fov_y,
+            scaling: FovScaling::HorPlus,
             near: cached_near,
             far,
         }
     }
 
-    /// Returns the vertical field of view of the projection, in radians.
+    /// Returns the field of view of the projection along the axis that's fixed by its
+    /// [`FovScaling`], in radians.
     #[inline]
+    pub fn fov(&self) -> f32 {
+        self.fov
+    }
+
+    /// Returns the vertical field of view of the projection, in radians.
     pub fn fov_y(&self) -> f32 {
-        self.fov_y
+        match self.scaling {
+            FovScaling::HorPlus => self.fov,
+            FovScaling::VertMinus => fov_x_to_fov_y(self.fov, self.aspect_ratio),
+        }
     }
 
     /// Returns the horizontal field of view of the projection, in radians.
-    #[inline]
     pub fn fov_x(&self) -> f32 {
-        self.fov_y * self.aspect_ratio
+        match self.scaling {
+            FovScaling::HorPlus => fov_y_to_fov_x(self.fov, self.aspect_ratio),
+            FovScaling::VertMinus => self.fov,
+        }
+    }
+
+    /// Returns how the field of view of the projection adapts to the aspect ratio.
+    #[inline]
+    pub fn fov_scaling(&self) -> FovScaling {
+        self.scaling
+    }
+
+    /// Sets how the field of view of the projection adapts to the aspect ratio.
+    ///
+    /// The field of view is converted such that the projection does not change for the current
+    /// aspect ratio.
+    pub fn set_fov_scaling(&mut self, scaling: FovScaling) {
+        self.fov = match scaling {
+            FovScaling::HorPlus => self.fov_y(),
+            FovScaling::VertMinus => self.fov_x(),
+        };
+        self.scaling = scaling;
     }
 
     /// Returns the near plane of the projection.
@@ -78,16 +128,17 @@ impl Perspective {
         self.far = far;
     }
 
-    /// Sets the vertical field of view of the projection.
-    pub fn set_fov_y(&mut self, fov_y: f32) {
-        self.fov_y = fov_y;
-        self.near = compute_near_plane(self.nearest_distance, self.aspect_ratio, fov_y);
+    /// Sets the field of view of the projection along the axis that's fixed by its
+    /// [`FovScaling`].
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov;
+        self.near = compute_near_plane(self.nearest_distance, self.aspect_ratio, self.fov_y());
     }
 
     /// Sets the aspect ratio of the projection.
     pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
         self.aspect_ratio = aspect_ratio;
-        self.near = compute_near_plane(self.nearest_distance, aspect_ratio, self.fov_y);
+        self.near = compute_near_plane(self.nearest_distance, aspect_ratio, self.fov_y());
     }
 
     /// Returns the aspect ratio of the camera.
@@ -99,13 +150,77 @@ impl Perspective {
     /// Returns the projection matrix of the camera.
     #[inline]
     pub fn matrix(&self) -> Mat4 {
-        Mat4::perspective_lh(self.fov_y, self.aspect_ratio, self.near, self.far)
+        Mat4::perspective_lh(self.fov_y(), self.aspect_ratio, self.near, self.far)
     }
 }
 
+/// Computes the horizontal field of view that corresponds to the provided vertical field of view
+/// for a display with the provided aspect ratio.
+pub fn fov_y_to_fov_x(fov_y: f32, aspect_ratio: f32) -> f32 {
+    2.0 * ((fov_y * 0.5).tan() * aspect_ratio).atan()
+}
+
+/// Computes the vertical field of view that corresponds to the provided horizontal field of view
+/// for a display with the provided aspect ratio.
+pub fn fov_x_to_fov_y(fov_x: f32, aspect_ratio: f32) -> f32 {
+    2.0 * ((fov_x * 0.5).tan() / aspect_ratio).atan()
+}
+
 /// Computes the ideal near plane distance from the provided parameters.
 fn compute_near_plane(nearest_distance: f32, aspect_ratio: f32, fov_y: f32) -> f32 {
     // nearPlane = nearestApproachToPlayer / sqrt(1 + tan(fov/2)^2 * (aspectRatio^2 + 1)))
     let tan_fov_y = (fov_y * 0.5).tan();
     nearest_distance / (1.0 + tan_fov_y * tan_fov_y * (aspect_ratio * aspect_ratio + 1.0)).sqrt()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts that `a` and `b` are approximately equal.
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{a} != {b}");
+    }
+
+    #[test]
+    fn hor_plus_keeps_vertical_fov() {
+        let fov_y = 70f32.to_radians();
+        let mut perspective = Perspective::new(0.1, 4.0 / 3.0, fov_y, 100.0);
+        let narrow_fov_x = perspective.fov_x();
+
+        perspective.set_aspect_ratio(21.0 / 9.0);
+        assert_close(perspective.fov_y(), fov_y);
+        assert!(perspective.fov_x() > narrow_fov_x);
+        assert_close(
+            (perspective.fov_x() * 0.5).tan(),
+            (fov_y * 0.5).tan() * 21.0 / 9.0,
+        );
+    }
+
+    #[test]
+    fn vert_minus_keeps_horizontal_fov() {
+        let mut perspective = Perspective::new(0.1, 4.0 / 3.0, 70f32.to_radians(), 100.0);
+        perspective.set_fov_scaling(FovScaling::VertMinus);
+        let fov_x = perspective.fov_x();
+        let tall_fov_y = perspective.fov_y();
+
+        perspective.set_aspect_ratio(21.0 / 9.0);
+        assert_close(perspective.fov_x(), fov_x);
+        assert!(perspective.fov_y() < tall_fov_y);
+        assert_close(
+            (perspective.fov_y() * 0.5).tan(),
+            (fov_x * 0.5).tan() * 9.0 / 21.0,
+        );
+    }
+
+    #[test]
+    fn changing_scaling_keeps_projection() {
+        let mut perspective = Perspective::new(0.1, 16.0 / 9.0, 80f32.to_radians(), 100.0);
+        let matrix = perspective.matrix();
+
+        perspective.set_fov_scaling(FovScaling::VertMinus);
+        assert!(perspective.matrix().abs_diff_eq(matrix, 1e-5));
+        perspective.set_fov_scaling(FovScaling::HorPlus);
+        assert!(perspective.matrix().abs_diff_eq(matrix, 1e-5));
+    }
+}
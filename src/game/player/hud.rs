@@ -127,7 +127,7 @@ impl Hud {
         let hotbar_anchor = Vec2::new(width as f32 / 2.0, height as f32);
         let crosshair_anchor = Vec2::new(width as f32 / 2.0, height as f32 / 2.0);
 
-        self.instances.edit(
+        self.instances.write_at(
             0,
             &[
                 Sprite::dummy()
@@ -218,7 +218,7 @@ impl Hud {
             cur += 1;
         }
 
-        self.icons.edit(0, &icons);
+        self.icons.write_at(0, &icons);
     }
 
     /// Ticks the HUD.
@@ -304,6 +304,7 @@ fn get_icon_textures(block: BlockId) -> (TextureId, TextureId, TextureId) {
         BlockAppearance::Flat(tex) => (tex, tex, tex),
         BlockAppearance::Liquid(liq) => (liq, liq, liq),
         BlockAppearance::Regular { top, bottom, side } => (top, bottom, side),
+        BlockAppearance::Pillar { end, side } => (end, end, side),
         BlockAppearance::Invisible => (TextureId::Bedrock, TextureId::Bedrock, TextureId::Bedrock),
     }
 }
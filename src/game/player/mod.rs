@@ -13,8 +13,8 @@ pub use hud::*;
 mod camera;
 mod physics;
 
-use bns_app::{Ctx, KeyCode, MouseButton};
-use bns_core::{BlockId, Chunk, ChunkPos, Face};
+use bns_app::{Action, Ctx, GamepadAxis, KeyCode};
+use bns_core::{BlockFlags, BlockId, BlockInstance, Chunk, ChunkPos, Face};
 
 use glam::{IVec3, Vec2, Vec3};
 use rodio::Source;
@@ -24,6 +24,18 @@ use crate::world::{QueryResult, World};
 
 use self::physics::{Collider, CollisionContext, Hit};
 
+/// The vertical field of view of the player when not sprinting, in radians.
+const BASE_FOV_Y: f32 = std::f32::consts::FRAC_PI_3;
+
+/// The aspect ratio past which the horizontal field of view of the camera stops growing.
+///
+/// This prevents the image from looking stretched on ultrawide displays.
+const ULTRAWIDE_ASPECT_RATIO: f32 = 2.0;
+
+/// How fast the camera rotates when the right stick of a gamepad is fully tilted, in radians
+/// per second.
+const GAMEPAD_LOOK_SPEED: f32 = 3.0;
+
 /// Contains the state of the player, including camera orientation and computed intent.
 pub struct Player {
     /// The mouse sensitivity of the player.
@@ -68,6 +80,10 @@ pub struct Player {
 
     /// The block at which the player is currently looking at.
     looking_at: Option<LookingAt>,
+    /// The position of the block that the player has broken during the last tick, if any.
+    just_broken: Option<IVec3>,
+    /// The block that the player is currently breaking, along with the progress made so far.
+    breaking: Option<(IVec3, f32)>,
 
     /// The reach of the player, in blocks.
     max_reach: f32,
@@ -106,7 +122,9 @@ pub struct Player {
     /// The speed at which the player swims.
     swim_speed: f32,
 
-    /// The base FOV (vertical) of the player.
+    /// The base FOV of the player.
+    ///
+    /// This applies to the axis that's fixed by the [`FovScaling`] of the camera.
     base_fov: f32,
 
     /// Some state that's used to make collision detection more efficient.
@@ -121,16 +139,37 @@ pub struct Player {
     next_step_sound: f32,
 }
 
+/// Describes where the player appears when a world is created, and which way it faces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spawn {
+    /// The position of the player's feet.
+    pub position: Vec3,
+    /// The yaw of the camera, in radians.
+    pub yaw: f32,
+    /// The pitch of the camera, in radians.
+    pub pitch: f32,
+}
+
+impl Default for Spawn {
+    fn default() -> Self {
+        Self {
+            position: Vec3::new(0.0, 16.0, 0.0),
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+}
+
 impl Player {
     /// Creates a new [`Player`] instance.
-    pub fn new(gpu: Arc<Gpu>, position: Vec3) -> Self {
+    pub fn new(gpu: Arc<Gpu>, spawn: Spawn) -> Self {
         let collider_radius = 0.4;
 
         let render_distance = 8;
         let far_plane = render_distance_to_far_plane(render_distance);
-        let base_fov = 60f32.to_radians();
+        let base_fov = BASE_FOV_Y;
 
-        Self {
+        let mut player = Self {
             mouse_sensitivity: 0.002,
             render_distance,
             vertical_render_distance: 6,
@@ -139,13 +178,15 @@ impl Player {
             swim_speed: 100.0,
             sprint_factor: 3.0,
             sprinting: false,
-            position,
+            position: spawn.position,
             velocity: Vec3::ZERO,
             camera: Camera::new(0.01, far_plane, base_fov),
 
             chunks_in_view: Vec::new(),
 
             looking_at: None,
+            just_broken: None,
+            breaking: None,
             max_reach: 8.0,
 
             hud: Hud::new(gpu),
@@ -180,7 +221,20 @@ impl Player {
             last_forward_input: Duration::ZERO,
 
             next_step_sound: 0.0,
-        }
+        };
+
+        player.camera.view.set_rotation(spawn.yaw, spawn.pitch);
+        player
+    }
+
+    /// Moves the player to the provided spawn point.
+    ///
+    /// The velocity of the player is reset.
+    pub fn respawn(&mut self, spawn: Spawn) {
+        self.position = spawn.position;
+        self.velocity = Vec3::ZERO;
+        self.is_on_ground = None;
+        self.camera.view.set_rotation(spawn.yaw, spawn.pitch);
     }
 
     /// Sets the render distance of the player.
@@ -239,6 +293,27 @@ impl Player {
         self.looking_at
     }
 
+    /// Returns the position of the block that the player has broken during the last tick, if
+    /// any.
+    #[inline]
+    pub fn just_broken(&self) -> Option<IVec3> {
+        self.just_broken
+    }
+
+    /// Returns the block that the player is currently breaking, along with the progress made so
+    /// far (between `0.0` and `1.0`).
+    #[inline]
+    pub fn break_progress(&self) -> Option<(IVec3, f32)> {
+        self.breaking
+    }
+
+    /// Returns the position of the first corner of the structure that the player is currently
+    /// selecting, if any.
+    #[inline]
+    pub fn structure_block(&self) -> Option<IVec3> {
+        self.structure_block
+    }
+
     /// Tick the player state.
     #[profiling::function]
     pub fn tick(
@@ -254,22 +329,36 @@ impl Player {
         // ======================================
 
         if ctx.just_resized() {
-            let aspect_ratio = ctx.width() as f32 / ctx.height() as f32;
-            self.camera.projection.set_aspect_ratio(aspect_ratio);
+            self.set_aspect_ratio(ctx.width() as f32 / ctx.height() as f32);
         }
 
-        if ctx.mouse_delta_x() != 0.0 || ctx.mouse_delta_y() != 0.0 {
+        // Raw deltas ignore the pointer acceleration of the OS, but the cursor delta is the only
+        // one that makes sense while the cursor is free to move around.
+        let (dx, dy) = if ctx.cursor_grabbed() {
+            ctx.raw_mouse_delta()
+        } else {
+            ctx.mouse_delta()
+        };
+        if dx != 0.0 || dy != 0.0 {
             self.camera.view.rotate(
-                ctx.mouse_delta_x() as f32 * self.mouse_sensitivity,
-                ctx.mouse_delta_y() as f32 * self.mouse_sensitivity,
+                dx as f32 * self.mouse_sensitivity,
+                dy as f32 * self.mouse_sensitivity,
             );
         }
 
-        if ctx.just_pressed(KeyCode::ArrowUp) && self.render_distance < 32 {
+        let look_x = ctx.gamepad_axis(GamepadAxis::RightStickX);
+        let look_y = ctx.gamepad_axis(GamepadAxis::RightStickY);
+        if look_x != 0.0 || look_y != 0.0 {
+            // Unlike the mouse, the Y axis of the sticks points upwards.
+            let speed = GAMEPAD_LOOK_SPEED * ctx.delta_seconds();
+            self.camera.view.rotate(look_x * speed, -look_y * speed);
+        }
+
+        if ctx.action_just_pressed(Action::IncreaseRenderDistance) && self.render_distance < 32 {
             self.set_render_distance(self.render_distance + 1);
             bns_log::trace!("render distance: {}", self.render_distance);
         }
-        if ctx.just_pressed(KeyCode::ArrowDown) && self.render_distance > 1 {
+        if ctx.action_just_pressed(Action::DecreaseRenderDistance) && self.render_distance > 1 {
             self.set_render_distance(self.render_distance - 1);
             bns_log::trace!("render distance: {}", self.render_distance);
         }
@@ -277,47 +366,67 @@ impl Player {
         let horizontal_movement_input = compute_horizontal_movement_input(ctx);
         let vertical_movement_input = compute_vertical_movement_input(ctx);
 
-        if ctx.just_pressed(KeyCode::ControlLeft) && ctx.pressing(KeyCode::KeyW) {
+        let moving_forward = horizontal_movement_input.y > 0.0;
+        if ctx.action_just_pressed(Action::Sprint) && moving_forward {
             self.sprinting = true;
         }
-        if !ctx.pressing(KeyCode::KeyW) {
+        if !moving_forward {
             self.sprinting = false;
         }
 
         self.hud.tick(ctx);
 
-        if ctx.just_pressed(MouseButton::Middle) {
+        if ctx.action_just_pressed(Action::PickBlock) {
             if let Some(looking_at) = self.looking_at {
                 *self.hud.current_material_mut() = Some(looking_at.block);
                 self.hud.rebuild_ui(ctx.width(), ctx.height());
             }
         }
 
+        // The crosshair is at the center of the screen.
+        let (_, look_dir) = self.camera.ray_from_ndc(self.head_position(), Vec2::ZERO);
         self.looking_at = world
-            .query_line(
-                self.head_position(),
-                self.camera.view.look_at(),
-                self.max_reach,
-            )
+            .query_line(self.head_position(), look_dir, self.max_reach)
             .ok()
             .map(|q| LookingAt::from_query(&q, self.position));
 
-        if ctx.just_pressed(MouseButton::Left) {
-            if let Some(looking_at) = self.looking_at {
-                world.set_block(looking_at.world_pos, BlockId::Air.into());
-
-                let sound = sounds.get_sound_for_block_break(looking_at.block, rng);
-                stream_handle
-                    .play_raw(
-                        rodio::Decoder::new_vorbis(std::io::Cursor::new(sound))
-                            .unwrap()
-                            .convert_samples(),
-                    )
-                    .unwrap();
+        self.just_broken = None;
+        match self.looking_at {
+            Some(looking_at) if ctx.action_pressing(Action::BreakBlock) => {
+                // Progress is lost as soon as the player looks at another block.
+                let progress = match self.breaking {
+                    Some((pos, progress)) if pos == looking_at.world_pos => progress,
+                    _ => 0.0,
+                };
+
+                let hardness = looking_at.block.info().hardness;
+                let progress = if hardness <= 0.0 {
+                    1.0
+                } else {
+                    progress + ctx.delta_seconds() / hardness
+                };
+
+                if progress < 1.0 {
+                    self.breaking = Some((looking_at.world_pos, progress));
+                } else {
+                    self.breaking = None;
+                    world.set_block(looking_at.world_pos, BlockId::Air.into());
+                    self.just_broken = Some(looking_at.world_pos);
+
+                    let sound = sounds.get_sound_for_block_break(looking_at.block, rng);
+                    stream_handle
+                        .play_raw(
+                            rodio::Decoder::new_vorbis(std::io::Cursor::new(sound))
+                                .unwrap()
+                                .convert_samples(),
+                        )
+                        .unwrap();
+                }
             }
+            _ => self.breaking = None,
         }
 
-        if ctx.just_pressed(MouseButton::Right) {
+        if ctx.action_just_pressed(Action::PlaceBlock) {
             if let Some(looking_at) = self.looking_at {
                 if looking_at.block == BlockId::StructureBlock {
                     match self.structure_block.take() {
@@ -340,8 +449,19 @@ impl Player {
                         }
                     }
                 } else if let Some(material) = self.hud.current_material() {
-                    let target = looking_at.world_pos + looking_at.face.normal();
-                    world.set_block(target, material.into());
+                    // Replaceable blocks are overwritten rather than placed against.
+                    let target = if looking_at
+                        .block
+                        .info()
+                        .flags
+                        .contains(BlockFlags::REPLACEABLE)
+                    {
+                        looking_at.world_pos
+                    } else {
+                        looking_at.world_pos + looking_at.face.normal()
+                    };
+                    let block = BlockInstance::with_axis(material, looking_at.face.axis());
+                    world.set_block(target, block);
                 }
             }
         }
@@ -350,7 +470,7 @@ impl Player {
             self.position = Vec3::new(u16::MAX as f32, 0.0, 0.0);
         }
 
-        let current_fov = self.camera.projection.fov_y();
+        let current_fov = self.camera.projection.fov();
         let target_fov = if self.sprinting {
             self.base_fov * 1.2
         } else {
@@ -359,7 +479,7 @@ impl Player {
         if (current_fov - target_fov).abs() > 0.001 {
             self.camera
                 .projection
-                .set_fov_y(current_fov + (target_fov - current_fov) * 0.075);
+                .set_fov(current_fov + (target_fov - current_fov) * 0.075);
         }
 
         // ======================================
@@ -420,20 +540,23 @@ impl Player {
             ))
             .is_some_and(|b| b == BlockId::Water);
 
+        let jump_pressing = ctx.action_pressing(Action::Jump);
+        let jump_just_pressed = ctx.action_just_pressed(Action::Jump);
+
         if !self.is_flying {
             #[allow(clippy::collapsible_if)]
             if self.are_feet_underwater {
-                if ctx.pressing(KeyCode::Space) {
+                if jump_pressing {
                     self.velocity.y += self.swim_speed * ctx.delta_seconds();
                 }
             } else if self.is_on_ground.is_some() {
-                if ctx.just_pressed(KeyCode::Space) {
+                if jump_just_pressed {
                     self.velocity.y = self.jump_velocity;
                 }
             }
         }
 
-        if ctx.just_pressed(KeyCode::Space) {
+        if jump_just_pressed {
             if self.last_jump_instant + Duration::from_millis(200) > ctx.since_startup() {
                 self.is_flying = !self.is_flying;
             } else {
@@ -441,7 +564,7 @@ impl Player {
             }
         }
 
-        if ctx.just_pressed(KeyCode::KeyW) {
+        if ctx.action_just_pressed(Action::MoveForward) {
             if self.last_forward_input + Duration::from_millis(200) > ctx.since_startup() {
                 self.sprinting = true;
             } else {
@@ -492,6 +615,45 @@ impl Player {
         }
     }
 
+    /// Updates the aspect ratio of the camera.
+    ///
+    /// Up to [`ULTRAWIDE_ASPECT_RATIO`], the vertical field of view is fixed and wider displays
+    /// see more of the world on their sides. Past that point, the horizontal field of view is
+    /// fixed instead to avoid stretching the image on ultrawide displays.
+    fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
+        let scaling = if aspect_ratio > ULTRAWIDE_ASPECT_RATIO {
+            FovScaling::VertMinus
+        } else {
+            FovScaling::HorPlus
+        };
+
+        if scaling != self.camera.projection.fov_scaling() {
+            // The base field of view is chosen such that the transition between the two modes
+            // is seamless.
+            self.base_fov = match scaling {
+                FovScaling::HorPlus => BASE_FOV_Y,
+                FovScaling::VertMinus => fov_y_to_fov_x(BASE_FOV_Y, ULTRAWIDE_ASPECT_RATIO),
+            };
+            self.camera.projection.set_fov_scaling(scaling);
+        }
+
+        self.camera.projection.set_aspect_ratio(aspect_ratio);
+    }
+
+    /// Moves the player along a scripted path, ignoring any input.
+    ///
+    /// This is used instead of [`Player::tick`] when the player is not controlled by the user.
+    pub fn tick_scripted(&mut self, ctx: &Ctx, position: Vec3, yaw: f32, pitch: f32) {
+        if ctx.just_resized() {
+            self.set_aspect_ratio(ctx.width() as f32 / ctx.height() as f32);
+        }
+
+        self.position = position;
+        self.velocity = Vec3::ZERO;
+        self.just_broken = None;
+        self.camera.view.set_rotation(yaw, pitch);
+    }
+
     /// Returns whether the player's head is underwater.
     #[inline]
     pub fn is_underwater(&self) -> bool {
@@ -504,6 +666,8 @@ impl Player {
     }
 
     /// Re-computes the chunks that are in view of the player.
+    ///
+    /// The chunks are sorted by distance to the player, closest first.
     #[profiling::function]
     pub fn compute_chunks_in_view(&mut self, padding: f32) {
         const CHUNK_RADIUS: f32 = (Chunk::SIDE as f32) * 0.8660254; // sqrt(3) / 2
@@ -531,6 +695,10 @@ impl Player {
                 }
             }
         }
+
+        // The closest chunks are the most important ones to load and rebuild.
+        self.chunks_in_view
+            .sort_unstable_by_key(|&pos| pos.distance_squared(center));
     }
 }
 
@@ -568,30 +736,36 @@ fn render_distance_to_far_plane(render_distance: i32) -> f32 {
 fn compute_horizontal_movement_input(ctx: &Ctx) -> Vec2 {
     let mut input = Vec2::ZERO;
 
-    if ctx.pressing(KeyCode::KeyW) {
+    if ctx.action_pressing(Action::MoveForward) {
         input.y += 1.0;
     }
-    if ctx.pressing(KeyCode::KeyS) {
+    if ctx.action_pressing(Action::MoveBackward) {
         input.y -= 1.0;
     }
-    if ctx.pressing(KeyCode::KeyA) {
+    if ctx.action_pressing(Action::MoveLeft) {
         input.x -= 1.0;
     }
-    if ctx.pressing(KeyCode::KeyD) {
+    if ctx.action_pressing(Action::MoveRight) {
         input.x += 1.0;
     }
 
-    input.normalize_or_zero()
+    let stick = Vec2::new(
+        ctx.gamepad_axis(GamepadAxis::LeftStickX),
+        ctx.gamepad_axis(GamepadAxis::LeftStickY),
+    );
+
+    // Unlike keys, sticks can be partially tilted to move slower.
+    (input.normalize_or_zero() + stick).clamp_length_max(1.0)
 }
 
 /// Computes the movement input that the player should have along the vertical axis.
 fn compute_vertical_movement_input(ctx: &Ctx) -> f32 {
     let mut input = 0.0;
 
-    if ctx.pressing(KeyCode::Space) {
+    if ctx.action_pressing(Action::Jump) {
         input += 1.0;
     }
-    if ctx.pressing(KeyCode::ShiftLeft) {
+    if ctx.action_pressing(Action::FlyDown) {
         input -= 1.0;
     }
 
@@ -683,3 +857,35 @@ fn download_file(name: &str, data: &str) {
 
     web_sys::Url::revoke_object_url(&url).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawn_sets_position_and_orientation() {
+        let Some(gpu) = pollster::block_on(Gpu::new_headless()) else {
+            return;
+        };
+
+        let spawn = Spawn {
+            position: Vec3::new(12.0, 80.0, -40.0),
+            yaw: 1.2,
+            pitch: -0.4,
+        };
+        let mut player = Player::new(Arc::new(gpu), spawn);
+        assert_eq!(player.position(), spawn.position);
+        assert_eq!(player.camera.view.yaw(), spawn.yaw);
+        assert_eq!(player.camera.view.pitch(), spawn.pitch);
+
+        let respawn = Spawn {
+            position: Vec3::new(-5.0, 20.0, 3.0),
+            yaw: 4.0,
+            pitch: 0.3,
+        };
+        player.respawn(respawn);
+        assert_eq!(player.position(), respawn.position);
+        assert_eq!(player.camera.view.yaw(), respawn.yaw);
+        assert_eq!(player.camera.view.pitch(), respawn.pitch);
+    }
+}
@@ -0,0 +1,242 @@
+//! Defines the colors of the sky.
+
+use bns_render::data::Color;
+use bns_rng::{DefaultRng, Rng};
+use glam::{IVec2, IVec3, Vec3, Vec3Swizzles};
+
+/// A value mixed into the seed of the world before deriving the palette.
+///
+/// This avoids correlations between the palette and the first values used by the world
+/// generator, which is seeded with the same value.
+const PALETTE_SEED_SALT: u64 = 0x5b1e_c01a_9a1e_77e5;
+
+/// The hue of the horizon in the default palette, in degrees.
+const BASE_HORIZON_HUE: f32 = 204.0;
+/// The hue of the top of the sky in the default palette, in degrees.
+const BASE_SKY_HUE: f32 = 259.0;
+
+/// How far the hues of a palette may wander from the default ones, in degrees.
+const MAX_HUE_SHIFT: f32 = 40.0;
+
+/// The colors used to render the sky of a world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkyPalette {
+    /// The color of the sky right above the player.
+    pub sky: Color,
+    /// The color of the horizon.
+    ///
+    /// This is also the color of the fog, making distant terrain blend with the sky.
+    pub horizon: Color,
+}
+
+impl SkyPalette {
+    /// Derives a [`SkyPalette`] from the seed of a world.
+    ///
+    /// The same seed always yields the same palette.
+    pub fn from_seed(seed: u64) -> Self {
+        let mut rng = DefaultRng::from_seed(seed ^ PALETTE_SEED_SALT);
+
+        // Both hues are shifted together so that the gradient between them keeps looking
+        // natural. A smaller independent shift is then applied to the top of the sky.
+        let shift = rng.next_f32_11() * MAX_HUE_SHIFT;
+        let sky_shift = shift + rng.next_f32_11() * MAX_HUE_SHIFT * 0.25;
+
+        let horizon = hsv(BASE_HORIZON_HUE + shift, 0.5 + rng.next_f32_01() * 0.2, 1.0);
+        let sky = hsv(
+            BASE_SKY_HUE + sky_shift,
+            0.55 + rng.next_f32_01() * 0.2,
+            1.0,
+        );
+
+        Self { sky, horizon }
+    }
+}
+
+/// The colors of the sky at the different times of the day.
+///
+/// The palette is sampled using the phase of the day, which goes from `0.0` to `1.0`. Dawn is at
+/// `0.0`, noon at `0.25`, dusk at `0.5` and the middle of the night at `0.75`. Colors are
+/// interpolated linearly between those keyframes.
+#[derive(Debug, Clone, Copy)]
+pub struct DayCyclePalette {
+    /// The colors of the sky at dawn.
+    pub dawn: SkyPalette,
+    /// The colors of the sky at noon.
+    pub noon: SkyPalette,
+    /// The colors of the sky at dusk.
+    pub dusk: SkyPalette,
+    /// The colors of the sky in the middle of the night.
+    pub night: SkyPalette,
+}
+
+impl DayCyclePalette {
+    /// The apparent brightness of the sun at each keyframe (dawn, noon, dusk, night).
+    const SUN_INTENSITIES: [f32; 4] = [0.7, 1.0, 0.7, 0.0];
+
+    /// Creates a [`DayCyclePalette`] from the colors of the sky at noon.
+    ///
+    /// The other keyframes use fixed colors that work well with most palettes.
+    pub fn from_noon(noon: SkyPalette) -> Self {
+        Self {
+            dawn: SkyPalette {
+                sky: Color::rgb(120, 110, 200),
+                horizon: Color::rgb(255, 170, 120),
+            },
+            noon,
+            dusk: SkyPalette {
+                sky: Color::rgb(90, 60, 160),
+                horizon: Color::rgb(250, 120, 80),
+            },
+            night: SkyPalette {
+                sky: Color::rgb(5, 5, 25),
+                horizon: Color::rgb(15, 20, 50),
+            },
+        }
+    }
+
+    /// Returns the colors of the sky at the provided phase of the day.
+    pub fn sample(&self, phase: f32) -> SkyPalette {
+        let keyframes = [self.dawn, self.noon, self.dusk, self.night];
+        let (a, b, t) = keyframes_at(phase);
+
+        SkyPalette {
+            sky: lerp_color(keyframes[a].sky, keyframes[b].sky, t),
+            horizon: lerp_color(keyframes[a].horizon, keyframes[b].horizon, t),
+        }
+    }
+
+    /// Returns the apparent brightness of the sun at the provided phase of the day, between
+    /// `0.0` and `1.0`.
+    pub fn sun_intensity(&self, phase: f32) -> f32 {
+        let (a, b, t) = keyframes_at(phase);
+        Self::SUN_INTENSITIES[a] + (Self::SUN_INTENSITIES[b] - Self::SUN_INTENSITIES[a]) * t
+    }
+}
+
+/// Returns the indices of the two keyframes surrounding the provided phase of the day, along
+/// with the interpolation factor between them.
+fn keyframes_at(phase: f32) -> (usize, usize, f32) {
+    let scaled = phase.rem_euclid(1.0) * 4.0;
+    let a = (scaled as usize).min(3);
+    (a, (a + 1) % 4, scaled - a as f32)
+}
+
+/// Linearly interpolates between two colors.
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let to_vec = |c: Color| Vec3::new(c.r as f32, c.g as f32, c.b as f32);
+    let c = to_vec(a).lerp(to_vec(b), t);
+    Color::rgb(c.x as u8, c.y as u8, c.z as u8)
+}
+
+/// Creates a [`Color`] from its hue (in degrees), saturation and value.
+fn hsv(hue: f32, saturation: f32, value: f32) -> Color {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let channel = |offset: f32| {
+        let k = (offset + hue) % 6.0;
+        value - value * saturation * k.min(4.0 - k).clamp(0.0, 1.0)
+    };
+
+    let rgb = Vec3::new(channel(5.0), channel(3.0), channel(1.0)) * 255.0;
+    Color::rgb(rgb.x as u8, rgb.y as u8, rgb.z as u8)
+}
+
+/// The ambient light color around the player.
+///
+/// The ambient color of a biome is only sampled again when the player moves to another column
+/// of blocks. The color in use slowly moves towards it to avoid abrupt changes when crossing
+/// biome borders.
+#[derive(Debug, Clone, Copy)]
+pub struct AmbientLight {
+    /// The ambient light color currently in use, in linear RGB.
+    current: Vec3,
+    /// The ambient light color of the biome the player is in.
+    target: Vec3,
+    /// The column in which `target` was sampled.
+    column: Option<IVec2>,
+}
+
+impl AmbientLight {
+    /// How fast the ambient light color moves towards the one of the biome the player is in.
+    ///
+    /// This is the fraction of the remaining distance covered every second.
+    const SPEED: f32 = 1.0;
+
+    /// Creates a new [`AmbientLight`] instance.
+    pub fn new() -> Self {
+        Self {
+            current: Vec3::ZERO,
+            target: Vec3::ZERO,
+            column: None,
+        }
+    }
+
+    /// Moves the ambient light color towards the one at `pos`.
+    ///
+    /// `sample` is only called when `pos` is not in the same column as the last time it was
+    /// called.
+    pub fn tick(&mut self, pos: IVec3, dt: f32, sample: impl FnOnce(IVec3) -> Vec3) {
+        let column = pos.xz();
+        if self.column != Some(column) {
+            self.column = Some(column);
+            self.target = sample(pos);
+        }
+
+        let t = (dt * Self::SPEED).min(1.0);
+        self.current = self.current.lerp(self.target, t);
+    }
+
+    /// Returns the ambient light color currently in use, in linear RGB.
+    #[inline]
+    pub fn color(&self) -> Vec3 {
+        self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ambient_color_is_sampled_once_per_column() {
+        let mut ambient = AmbientLight::new();
+        let mut samples = 0;
+
+        for y in 0..16 {
+            ambient.tick(IVec3::new(3, y, -7), 0.1, |_| {
+                samples += 1;
+                Vec3::ONE
+            });
+        }
+        assert_eq!(samples, 1);
+
+        ambient.tick(IVec3::new(4, 0, -7), 0.1, |_| {
+            samples += 1;
+            Vec3::ONE
+        });
+        assert_eq!(samples, 2);
+    }
+
+    #[test]
+    fn ambient_color_moves_towards_target() {
+        let mut ambient = AmbientLight::new();
+        ambient.tick(IVec3::ZERO, 0.5, |_| Vec3::ONE);
+        assert!(ambient.color().x > 0.0 && ambient.color().x < 1.0);
+
+        ambient.tick(IVec3::ZERO, 10.0, |_| unreachable!());
+        assert_eq!(ambient.color(), Vec3::ONE);
+    }
+
+    #[test]
+    fn palette_is_derived_from_the_seed() {
+        for seed in [0, 1, 42, u64::MAX] {
+            assert_eq!(SkyPalette::from_seed(seed), SkyPalette::from_seed(seed));
+        }
+
+        let palettes: Vec<SkyPalette> = (0..8).map(SkyPalette::from_seed).collect();
+        for (i, a) in palettes.iter().enumerate() {
+            for b in &palettes[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+}
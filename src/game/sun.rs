@@ -1,14 +1,41 @@
 use bns_app::{Ctx, KeyCode};
+use bns_render::data::MAX_SHADOW_CASCADES;
 use glam::{Mat4, Quat, Vec3, Vec4};
 
 use super::player::Camera;
 
+/// How much the splits between the cascades of the shadow map follow a logarithmic distribution
+/// rather than a uniform one.
+///
+/// `0.0` means uniform splits, and `1.0` means logarithmic splits. Logarithmic splits give more
+/// resolution to the cascades that are close to the camera.
+const CASCADE_SPLIT_LAMBDA: f32 = 0.75;
+
+/// The length of a full day, in milliseconds of in-game time.
+const DAY_LENGTH: u64 = 600000;
+
+/// The light-space transforms of the cascades of the shadow map.
+pub struct ShadowCascades {
+    /// The light transform of each cascade.
+    ///
+    /// Only the first `count` matrices are meaningful.
+    pub transforms: [Mat4; MAX_SHADOW_CASCADES],
+    /// The view-space depth at which each cascade ends.
+    pub splits: Vec4,
+    /// The number of cascades that are actually used.
+    pub count: u32,
+}
+
 /// Contains the current state of the sun.
 pub struct Sun {
     /// The direction of the light.
     ///
     /// This vector should always be normalized.
     direction: Vec3,
+    /// When set, the direction of the sun is fixed to this value regardless of the time of day.
+    ///
+    /// This vector should always be normalized.
+    direction_override: Option<Vec3>,
 
     /// The current time of day.
     ///
@@ -22,6 +49,7 @@ impl Sun {
     pub fn new() -> Self {
         Self {
             direction: Vec3::new(0.0, -1.0, 1.5).normalize(),
+            direction_override: None,
             time: 0,
         }
     }
@@ -32,25 +60,97 @@ impl Sun {
         self.direction
     }
 
+    /// Fixes the direction of the sun (and thus of the shadows), regardless of the time of day.
+    ///
+    /// Passing [`None`] makes the sun follow the time of day again. The time keeps advancing
+    /// while the direction is overriden.
+    pub fn set_direction_override(&mut self, direction: Option<Vec3>) {
+        self.direction_override = direction.map(Vec3::normalize);
+        if let Some(direction) = self.direction_override {
+            self.direction = direction;
+        }
+    }
+
+    /// Returns the direction the sun is fixed to, if any.
+    ///
+    /// See [`Sun::set_direction_override`].
+    #[inline]
+    pub fn direction_override(&self) -> Option<Vec3> {
+        self.direction_override
+    }
+
+    /// Returns the current phase of the day, between `0.0` (included) and `1.0` (excluded).
+    #[inline]
+    pub fn day_phase(&self) -> f32 {
+        (self.time % DAY_LENGTH) as f32 / DAY_LENGTH as f32
+    }
+
     /// Ticks this [`Sun`] instance.
     pub fn tick(&mut self, ctx: &mut Ctx) {
+        let mut elapsed = ctx.since_last_tick().as_millis() as u64;
         if ctx.pressing(KeyCode::KeyU) {
-            self.time += ctx.since_last_tick().as_millis() as u64 * 50;
-        } else {
-            self.time += ctx.since_last_tick().as_millis() as u64;
+            elapsed *= 50;
         }
 
-        let sub_day = (self.time % 600000) as f32 / 600000.0;
-        self.direction = Quat::from_rotation_y(sub_day * std::f32::consts::TAU)
+        self.advance(elapsed);
+    }
+
+    /// Advances the time of day by `millis` milliseconds and updates the direction of the sun.
+    fn advance(&mut self, millis: u64) {
+        self.time += millis;
+
+        if let Some(direction) = self.direction_override {
+            self.direction = direction;
+            return;
+        }
+
+        self.direction = Quat::from_rotation_y(self.day_phase() * std::f32::consts::TAU)
             * Vec3::new(0.0, -1.0, 1.5).normalize();
     }
 
-    /// Returns the matrix of the light.
-    pub fn matrix(&self, camera_pos: Vec3, camera: &Camera) -> Mat4 {
-        let points = camera.frustum_quad(
-            camera.projection.near(),
-            (camera.projection.far() / 2.0).clamp(32.0, 64.0),
-        );
+    /// Computes the light transforms of the `count` cascades of the shadow map.
+    ///
+    /// When `count` is `1`, the single cascade only covers the area close to the camera. Otherwise,
+    /// the cascades cover the whole view frustum.
+    ///
+    /// `count` is clamped between `1` and [`MAX_SHADOW_CASCADES`].
+    pub fn cascades(&self, camera_pos: Vec3, camera: &Camera, count: u32) -> ShadowCascades {
+        let count = count.clamp(1, MAX_SHADOW_CASCADES as u32);
+
+        let near = camera.projection.near();
+        let far = if count == 1 {
+            (camera.projection.far() / 2.0).clamp(32.0, 64.0)
+        } else {
+            camera.projection.far()
+        };
+
+        let mut transforms = [Mat4::IDENTITY; MAX_SHADOW_CASCADES];
+        let mut splits = [0.0; MAX_SHADOW_CASCADES];
+
+        let mut start = near;
+        for i in 0..count as usize {
+            // Blend between a logarithmic and a uniform distribution of the splits.
+            let t = (i + 1) as f32 / count as f32;
+            let log = near * (far / near).powf(t);
+            let uniform = near + (far - near) * t;
+            let end = CASCADE_SPLIT_LAMBDA * log + (1.0 - CASCADE_SPLIT_LAMBDA) * uniform;
+
+            transforms[i] = self.matrix(camera_pos, camera, start, end);
+            splits[i] = end;
+            start = end;
+        }
+
+        ShadowCascades {
+            transforms,
+            splits: Vec4::from_array(splits),
+            count,
+        }
+    }
+
+    /// Returns the matrix of the light that covers the part of the camera's frustum between `min`
+    /// and `max`.
+    fn matrix(&self, camera_pos: Vec3, camera: &Camera, min: f32, max: f32) -> Mat4 {
+        let points = camera.frustum_quad(min, max);
 
         let view = Mat4::look_to_lh(Vec3::ZERO, self.direction, Vec3::Y);
 
@@ -74,3 +174,31 @@ impl Sun {
         ) * view
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn override_ignores_time_of_day() {
+        let fixed = Vec3::new(1.0, -2.0, 0.5);
+        let mut sun = Sun::new();
+        sun.set_direction_override(Some(fixed));
+        assert_eq!(sun.direction(), fixed.normalize());
+
+        for _ in 0..8 {
+            let phase = sun.day_phase();
+            sun.advance(DAY_LENGTH / 7);
+            assert_ne!(sun.day_phase(), phase);
+            assert_eq!(sun.direction(), fixed.normalize());
+        }
+
+        // Removing the override makes the sun follow the time of day again.
+        sun.set_direction_override(None);
+        sun.advance(0);
+        let mut reference = Sun::new();
+        reference.advance(sun.time);
+        assert_eq!(sun.direction(), reference.direction());
+        assert_ne!(sun.direction(), fixed.normalize());
+    }
+}
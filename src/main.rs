@@ -4,6 +4,7 @@ mod app;
 mod assets;
 mod game;
 mod panic;
+mod settings;
 mod world;
 
 /// The glorious entry point of the program!
@@ -0,0 +1,347 @@
+//! Contains the settings of the game that can be changed by the player.
+
+use bns_render::data::Color;
+use bns_render::{GpuInfo, GpuKind, RendererConfig};
+
+/// The graphics settings of the game.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// The number of chunks that the player can see from its point of view.
+    pub render_distance: i32,
+    /// Whether shadows are enabled.
+    pub shadows_enabled: bool,
+    /// The number of samples used for multisample anti-aliasing.
+    ///
+    /// A value of `1` means that MSAA is disabled.
+    pub msaa_samples: u32,
+    /// The scale of the resolution at which the world is rendered, relative to the size of the
+    /// window.
+    ///
+    /// The UI is always rendered at the native resolution of the window.
+    pub render_scale: f32,
+    /// The length of the sides of the shadow map, in pixels.
+    pub shadow_map_resolution: u32,
+    /// The number of cascades the shadow map is split into.
+    ///
+    /// A single cascade only covers the area close to the player.
+    pub shadow_cascades: u32,
+    /// The maximum number of chunks that may be kept in memory.
+    ///
+    /// Chunks that are within the render distance are never evicted, even if this budget is
+    /// exceeded.
+    pub max_loaded_chunks: usize,
+    /// The maximum number of chunk generation tasks that may be in flight at any given time.
+    pub max_generation_tasks: usize,
+    /// The color the background is cleared to when the skybox is disabled.
+    ///
+    /// When `None`, the current color of the sky is used.
+    pub clear_color: Option<Color>,
+}
+
+/// A rough estimate of the amount of memory used by a single loaded chunk, in bytes.
+///
+/// This accounts for the blocks themselves, their lighting and the geometry built for them.
+const CHUNK_MEMORY_ESTIMATE: u64 = 128 * 1024;
+
+/// The fraction of the available memory that loaded chunks are allowed to use.
+const CHUNK_MEMORY_FRACTION: u64 = 4;
+
+impl Settings {
+    /// Picks reasonable defaults based on the capabilities of the provided GPU and the amount of
+    /// memory available on the system.
+    ///
+    /// `available_memory` is the number of bytes the game may reasonably use, usually obtained
+    /// through [`available_memory`]. When it is unknown, only the GPU is taken into account.
+    ///
+    /// # Remarks
+    ///
+    /// The returned settings are only a starting point. Any field may be overriden by the caller
+    /// before the settings are actually used (see [`Settings::apply_overrides`]).
+    pub fn auto_detect(info: &GpuInfo, available_memory: Option<u64>) -> Self {
+        let mut settings = match info.kind {
+            GpuKind::Discrete => Self {
+                render_distance: 16,
+                shadows_enabled: true,
+                msaa_samples: 4,
+                render_scale: 1.0,
+                shadow_map_resolution: 4096,
+                shadow_cascades: RendererConfig::DEFAULT_SHADOW_CASCADE_COUNT,
+                max_loaded_chunks: 32768,
+                max_generation_tasks: 512,
+                clear_color: None,
+            },
+            GpuKind::Integrated | GpuKind::Unknown => Self {
+                render_distance: 8,
+                shadows_enabled: true,
+                msaa_samples: 1,
+                render_scale: 1.0,
+                shadow_map_resolution: 2048,
+                shadow_cascades: 2,
+                max_loaded_chunks: 12288,
+                max_generation_tasks: 256,
+                clear_color: None,
+            },
+            GpuKind::Virtual | GpuKind::Cpu => Self {
+                render_distance: 4,
+                shadows_enabled: false,
+                msaa_samples: 1,
+                render_scale: 0.5,
+                shadow_map_resolution: 1024,
+                shadow_cascades: 1,
+                max_loaded_chunks: 4096,
+                max_generation_tasks: 64,
+                clear_color: None,
+            },
+        };
+
+        // The shadow map is a single large texture. GPUs that cannot handle large textures are
+        // usually not very powerful either.
+        if info.max_texture_size < 8192 {
+            settings.shadow_map_resolution = settings
+                .shadow_map_resolution
+                .min(RendererConfig::DEFAULT_SHADOW_MAP_RESOLUTION)
+                .min(info.max_texture_size);
+            settings.render_distance = settings.render_distance.min(8);
+        }
+
+        // Loaded chunks are the bulk of the memory used by the game. Keep them within a
+        // fraction of what's available, and make sure that the chunks within the render
+        // distance (which are never evicted) fit in that budget.
+        if let Some(memory) = available_memory {
+            let budget = (memory / CHUNK_MEMORY_FRACTION / CHUNK_MEMORY_ESTIMATE) as usize;
+            settings.max_loaded_chunks = settings.max_loaded_chunks.min(budget.max(256));
+            settings.max_generation_tasks = settings
+                .max_generation_tasks
+                .min(settings.max_loaded_chunks / 16)
+                .max(16);
+            while settings.render_distance > 2
+                && chunks_in_render_distance(settings.render_distance) > settings.max_loaded_chunks
+            {
+                settings.render_distance -= 1;
+            }
+        }
+
+        bns_log::info!(
+            "auto-detected settings: render distance {}, shadows {} ({}px), msaa x{}",
+            settings.render_distance,
+            if settings.shadows_enabled {
+                "on"
+            } else {
+                "off"
+            },
+            settings.shadow_map_resolution,
+            settings.msaa_samples,
+        );
+
+        settings
+    }
+
+    /// Overrides the fields of these settings with the values provided by `lookup`.
+    ///
+    /// `lookup` is called with the name of each overridable setting (for example
+    /// `BNS_RENDER_DISTANCE`) and returns its value, if any. Values that cannot be parsed are
+    /// ignored with a warning.
+    pub fn apply_overrides(&mut self, lookup: impl Fn(&str) -> Option<String>) {
+        fn apply_with<T>(
+            lookup: &impl Fn(&str) -> Option<String>,
+            name: &str,
+            field: &mut T,
+            parse: impl Fn(&str) -> Option<T>,
+        ) {
+            let Some(value) = lookup(name) else {
+                return;
+            };
+
+            match parse(value.trim()) {
+                Some(parsed) => {
+                    bns_log::info!("{name} overriden to {}", value.trim());
+                    *field = parsed;
+                }
+                None => bns_log::warning!("ignoring invalid value for {name}: {value:?}"),
+            }
+        }
+
+        fn apply<T: std::str::FromStr>(
+            lookup: &impl Fn(&str) -> Option<String>,
+            name: &str,
+            field: &mut T,
+        ) {
+            apply_with(lookup, name, field, |value| value.parse().ok());
+        }
+
+        apply(&lookup, "BNS_RENDER_DISTANCE", &mut self.render_distance);
+        apply(&lookup, "BNS_SHADOWS", &mut self.shadows_enabled);
+        apply(&lookup, "BNS_MSAA_SAMPLES", &mut self.msaa_samples);
+        apply(&lookup, "BNS_RENDER_SCALE", &mut self.render_scale);
+        apply(
+            &lookup,
+            "BNS_SHADOW_MAP_RESOLUTION",
+            &mut self.shadow_map_resolution,
+        );
+        apply(&lookup, "BNS_SHADOW_CASCADES", &mut self.shadow_cascades);
+        apply(
+            &lookup,
+            "BNS_MAX_LOADED_CHUNKS",
+            &mut self.max_loaded_chunks,
+        );
+        apply(
+            &lookup,
+            "BNS_MAX_GENERATION_TASKS",
+            &mut self.max_generation_tasks,
+        );
+        apply_with(
+            &lookup,
+            "BNS_CLEAR_COLOR",
+            &mut self.clear_color,
+            |value| match value {
+                "sky" => Some(None),
+                _ => parse_hex_color(value).map(Some),
+            },
+        );
+    }
+
+    /// Overrides the fields of these settings with the environment variables that are set.
+    ///
+    /// See [`Settings::apply_overrides`] for the list of variables.
+    pub fn apply_env_overrides(&mut self) {
+        self.apply_overrides(|name| std::env::var(name).ok());
+    }
+}
+
+/// Parses a color written as `RRGGBB` hexadecimal digits, optionally prefixed with `#`.
+fn parse_hex_color(s: &str) -> Option<Color> {
+    let s = s.strip_prefix('#').unwrap_or(s);
+    if s.len() != 6 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let rgb = u32::from_str_radix(s, 16).ok()?;
+    Some(Color::rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
+
+/// Returns the number of chunks that are within `render_distance` of the player.
+fn chunks_in_render_distance(render_distance: i32) -> usize {
+    let r = render_distance as f32 + 0.5;
+    (4.0 / 3.0 * std::f32::consts::PI * r * r * r) as usize
+}
+
+/// Returns the amount of memory available on the system, in bytes.
+///
+/// # Returns
+///
+/// `None` if the platform does not expose this information.
+pub fn available_memory() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        // The line looks like `MemAvailable:   16077236 kB`.
+        let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+        let line = meminfo.lines().find(|l| l.starts_with("MemAvailable:"))?;
+        let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kib * 1024)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+
+        // SAFETY: `MEMORYSTATUSEX` is a plain C struct for which all-zeroes is a valid value.
+        let mut status: MEMORYSTATUSEX = unsafe { std::mem::zeroed() };
+        status.dwLength = std::mem::size_of::<MEMORYSTATUSEX>() as u32;
+
+        // SAFETY: `status` is properly initialized and its length is set.
+        if unsafe { GlobalMemoryStatusEx(&mut status) } == 0 {
+            return None;
+        }
+
+        Some(status.ullAvailPhys)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gpu(kind: GpuKind, max_texture_size: u32) -> GpuInfo {
+        GpuInfo {
+            name: String::from("test"),
+            kind,
+            max_texture_size,
+        }
+    }
+
+    #[test]
+    fn defaults_scale_with_gpu_kind() {
+        let discrete = Settings::auto_detect(&gpu(GpuKind::Discrete, 16384), None);
+        let integrated = Settings::auto_detect(&gpu(GpuKind::Integrated, 16384), None);
+        let cpu = Settings::auto_detect(&gpu(GpuKind::Cpu, 16384), None);
+
+        assert!(discrete.render_distance > integrated.render_distance);
+        assert!(integrated.render_distance > cpu.render_distance);
+        assert!(discrete.msaa_samples >= integrated.msaa_samples);
+        assert!(!cpu.shadows_enabled);
+    }
+
+    #[test]
+    fn small_textures_limit_the_shadow_map() {
+        let settings = Settings::auto_detect(&gpu(GpuKind::Discrete, 2048), None);
+        assert!(settings.shadow_map_resolution <= 2048);
+        assert!(settings.render_distance <= 8);
+    }
+
+    #[test]
+    fn defaults_scale_with_available_memory() {
+        let info = gpu(GpuKind::Discrete, 16384);
+        let plenty = Settings::auto_detect(&info, Some(64 << 30));
+        let little = Settings::auto_detect(&info, Some(512 << 20));
+
+        assert_eq!(plenty.max_loaded_chunks, 32768);
+        assert!(little.max_loaded_chunks < plenty.max_loaded_chunks);
+        assert!(little.max_generation_tasks < plenty.max_generation_tasks);
+        assert!(little.render_distance < plenty.render_distance);
+        assert!(chunks_in_render_distance(little.render_distance) <= little.max_loaded_chunks);
+    }
+
+    #[test]
+    fn overrides_replace_detected_values() {
+        let mut settings = Settings::auto_detect(&gpu(GpuKind::Cpu, 16384), None);
+        settings.apply_overrides(|name| match name {
+            "BNS_RENDER_DISTANCE" => Some(String::from("12")),
+            "BNS_SHADOWS" => Some(String::from("true")),
+            "BNS_MSAA_SAMPLES" => Some(String::from("not a number")),
+            _ => None,
+        });
+
+        assert_eq!(settings.render_distance, 12);
+        assert!(settings.shadows_enabled);
+        assert_eq!(settings.msaa_samples, 1);
+    }
+
+    #[test]
+    fn clear_color_override_accepts_hex_or_sky() {
+        let mut settings = Settings::auto_detect(&gpu(GpuKind::Discrete, 16384), None);
+        assert_eq!(settings.clear_color, None);
+
+        settings.apply_overrides(|name| match name {
+            "BNS_CLEAR_COLOR" => Some(String::from("#1a2B3c")),
+            _ => None,
+        });
+        assert_eq!(settings.clear_color, Some(Color::rgb(0x1a, 0x2b, 0x3c)));
+
+        settings.apply_overrides(|name| match name {
+            "BNS_CLEAR_COLOR" => Some(String::from("red")),
+            _ => None,
+        });
+        assert_eq!(settings.clear_color, Some(Color::rgb(0x1a, 0x2b, 0x3c)));
+
+        settings.apply_overrides(|name| match name {
+            "BNS_CLEAR_COLOR" => Some(String::from("sky")),
+            _ => None,
+        });
+        assert_eq!(settings.clear_color, None);
+    }
+}
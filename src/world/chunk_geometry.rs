@@ -1,8 +1,10 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use bns_core::{
-    BlockAppearance, BlockFlags, BlockId, BlockVisibility, Chunk, ChunkPos, Face, LocalPos,
-    TextureId,
+    Axis, BlockAppearance, BlockFlags, BlockId, BlockVisibility, Chunk, ChunkPos, Face, LocalPos,
+    TextureId, MAX_LIGHT_LEVEL,
 };
 use bns_render::data::{QuadFlags, QuadInstance};
 use bns_render::{DynamicVertexBuffer, Gpu};
@@ -10,6 +12,14 @@ use bns_render::{DynamicVertexBuffer, Gpu};
 use bitflags::bitflags;
 use glam::IVec3;
 
+// Quad instances store the local position of their block in 5 bits per axis.
+const _: () = assert!(
+    QuadFlags::X_MASK.bits() >> 7 == (Chunk::SIDE - 1) as u32
+        && QuadFlags::Y_MASK.bits() >> 12 == (Chunk::SIDE - 1) as u32
+        && QuadFlags::Z_MASK.bits() >> 17 == (Chunk::SIDE - 1) as u32,
+    "the local position stored in `QuadFlags` does not match `Chunk::SIDE`",
+);
+
 /// The built geometry of a chunk. This is a wrapper around a vertex buffer that
 /// contains the quad instances of the chunk.
 pub struct ChunkGeometry {
@@ -64,7 +74,10 @@ impl ChunkGeometry {
 /// Contains the state required to upload the geometry of a chunk to the GPU.
 pub struct ChunkUploadContext {
     /// The GPU itself.
-    gpu: Arc<Gpu>,
+    ///
+    /// This is `None` for contexts created with [`ChunkUploadContext::detached`], which can only
+    /// upload empty geometry.
+    gpu: Option<Arc<Gpu>>,
     /// A pool of dynamic vertex buffers used to build the geometry of the chunk.
     buffer_pool: Vec<DynamicVertexBuffer<QuadInstance>>,
 }
@@ -73,16 +86,34 @@ impl ChunkUploadContext {
     /// Creates a new [`ChunkUploadContext`].
     pub fn new(gpu: Arc<Gpu>) -> Self {
         Self {
-            gpu,
+            gpu: Some(gpu),
+            buffer_pool: Vec::new(),
+        }
+    }
+
+    /// Creates a new [`ChunkUploadContext`] that is not connected to any GPU.
+    ///
+    /// # Remarks
+    ///
+    /// Such a context can only be used to upload chunks that have no geometry.
+    #[cfg(test)]
+    pub fn detached() -> Self {
+        Self {
+            gpu: None,
             buffer_pool: Vec::new(),
         }
     }
 
     /// Acquires a buffer from the pool.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the context is not connected to a GPU.
     fn acquire_buffer(&mut self) -> DynamicVertexBuffer<QuadInstance> {
-        self.buffer_pool
-            .pop()
-            .unwrap_or_else(|| DynamicVertexBuffer::new(self.gpu.clone(), 1024))
+        self.buffer_pool.pop().unwrap_or_else(|| {
+            let gpu = self.gpu.clone().expect("uploading geometry requires a GPU");
+            DynamicVertexBuffer::new(gpu, 1024)
+        })
     }
 
     /// Releases a buffer to the pool.
@@ -122,9 +153,28 @@ impl ChunkUploadContext {
 pub struct ChunkBuildContext {
     opaque_quads: Vec<QuadInstance>,
     transparent_quads: Vec<QuadInstance>,
+    /// Whether ambient occlusion should *not* be computed when building faces.
+    ///
+    /// When set, the emitted quads never have any of the `OCCLUDED_*` flags set.
+    no_ambient_occlusion: bool,
+    /// The light emitted by blocks around the chunk.
+    light: LightVolume,
 }
 
 impl ChunkBuildContext {
+    /// Sets whether ambient occlusion should be computed when building the geometry of
+    /// chunks.
+    ///
+    /// # Remarks
+    ///
+    /// Unlike the geometry itself, this setting is not reset by [`clear`].
+    ///
+    /// [`clear`]: ChunkBuildContext::clear
+    #[inline]
+    pub fn set_ambient_occlusion(&mut self, enabled: bool) {
+        self.no_ambient_occlusion = !enabled;
+    }
+
     /// Clears the current state of the builder, ensuring that the geometry
     /// of the previous chunk is not reused.
     pub fn clear(&mut self) {
@@ -135,12 +185,26 @@ impl ChunkBuildContext {
     /// Only build the inner of the chunk.
     ///
     /// This operation does not require lookups to the chunk provider.
+    ///
+    /// # Remarks
+    ///
+    /// When the chunk is made of a single block type that culls itself (such as an underground
+    /// chunk full of stone), it has no inner geometry at all and this function returns
+    /// immediately.
     #[profiling::function]
     pub fn build_inner(&mut self, chunk: &Chunk) {
+        if !has_inner_geometry(chunk) {
+            return;
+        }
+
         LocalPos::iter_all().for_each(|pos| build_block(chunk, pos, self));
     }
 
     /// Only build the outer geometry of the chunk.
+    ///
+    /// This is also where the light received from light-emitting blocks is computed, as it
+    /// depends on the neighboring chunks. It is applied to every quad of the chunk, including
+    /// the ones built by [`build_inner`](Self::build_inner).
     #[profiling::function]
     pub fn build_outer<'a>(&mut self, neighborhood: ChunkNeighborhood<'a>) {
         build_chunk_boundary_x(neighborhood.me, neighborhood.x, self);
@@ -149,6 +213,11 @@ impl ChunkBuildContext {
         build_chunk_boundary_neg_y(neighborhood.me, neighborhood.neg_y, self);
         build_chunk_boundary_z(neighborhood.me, neighborhood.z, self);
         build_chunk_boundary_neg_z(neighborhood.me, neighborhood.neg_z, self);
+
+        if self.light.propagate(&neighborhood) {
+            self.light.apply(&mut self.opaque_quads);
+            self.light.apply(&mut self.transparent_quads);
+        }
     }
 }
 
@@ -179,6 +248,199 @@ impl<'a> ChunkNeighborhood<'a> {
             neg_z: f(center + IVec3::NEG_Z)?,
         })
     }
+
+    /// Returns the chunks of the neighborhood, along with their position relative to the
+    /// center chunk.
+    fn chunks(&self) -> [(IVec3, &'a Chunk); 7] {
+        [
+            (IVec3::ZERO, self.me),
+            (IVec3::X, self.x),
+            (IVec3::NEG_X, self.neg_x),
+            (IVec3::Y, self.y),
+            (IVec3::NEG_Y, self.neg_y),
+            (IVec3::Z, self.z),
+            (IVec3::NEG_Z, self.neg_z),
+        ]
+    }
+
+    /// Returns the block at the provided position, relative to the origin of the center chunk.
+    ///
+    /// If the position is not part of any of the chunks of the neighborhood, `None` is
+    /// returned.
+    fn block_at(&self, pos: IVec3) -> Option<BlockId> {
+        let side = IVec3::splat(Chunk::SIDE);
+        let chunk = match pos.div_euclid(side).to_array() {
+            [0, 0, 0] => self.me,
+            [1, 0, 0] => self.x,
+            [-1, 0, 0] => self.neg_x,
+            [0, 1, 0] => self.y,
+            [0, -1, 0] => self.neg_y,
+            [0, 0, 1] => self.z,
+            [0, 0, -1] => self.neg_z,
+            _ => return None,
+        };
+
+        let local = pos.rem_euclid(side);
+        // SAFETY:
+        //  The remainder of the euclidean division is always less than `Chunk::SIDE`.
+        let local = unsafe { LocalPos::from_xyz_unchecked(local.x, local.y, local.z) };
+        Some(chunk.get_block(local))
+    }
+}
+
+/// The number of blocks past the boundaries of a chunk that are taken into account when
+/// propagating the light emitted by blocks.
+///
+/// Light needs to reach the blocks right outside of the chunk, as those are the ones in front of
+/// its boundary faces. Light emitted farther than that fades out before reaching them.
+const LIGHT_MARGIN: i32 = MAX_LIGHT_LEVEL as i32;
+
+/// The number of blocks along each side of a [`LightVolume`].
+const LIGHT_VOLUME_SIDE: i32 = Chunk::SIDE + 2 * LIGHT_MARGIN;
+
+/// The light levels around a chunk, computed by flood-filling the light emitted by blocks.
+///
+/// Positions are relative to the origin of the chunk being built, and extend [`LIGHT_MARGIN`]
+/// blocks past its boundaries.
+#[derive(Default)]
+struct LightVolume {
+    /// The light level of every block of the volume.
+    levels: Vec<u8>,
+    /// The blocks whose light must still be spread to their neighbors.
+    queue: VecDeque<IVec3>,
+}
+
+impl LightVolume {
+    /// Returns the index of the provided position within `levels`, if it is part of the
+    /// volume.
+    fn index(pos: IVec3) -> Option<usize> {
+        let p = pos + LIGHT_MARGIN;
+        if p.cmplt(IVec3::ZERO).any() || p.cmpge(IVec3::splat(LIGHT_VOLUME_SIDE)).any() {
+            return None;
+        }
+
+        Some((p.x + p.y * LIGHT_VOLUME_SIDE + p.z * LIGHT_VOLUME_SIDE * LIGHT_VOLUME_SIDE) as usize)
+    }
+
+    /// Returns the light level at the provided position.
+    ///
+    /// Positions outside of the volume are not lit.
+    fn get(&self, pos: IVec3) -> u8 {
+        Self::index(pos).map_or(0, |index| self.levels[index])
+    }
+
+    /// Computes the light levels around the center of the provided neighborhood.
+    ///
+    /// Light spreads from light-emitting blocks, losing one level for every block it travels
+    /// through, and is stopped by opaque blocks.
+    ///
+    /// # Remarks
+    ///
+    /// Only the chunks of the neighborhood are considered. Light does not travel through the
+    /// chunks that are diagonally adjacent to the center, and the blocks that emit it aren't
+    /// taken into account.
+    ///
+    /// # Returns
+    ///
+    /// Whether any light-emitting block was found. When none was, the volume is left untouched
+    /// and must not be used.
+    fn propagate(&mut self, neighborhood: &ChunkNeighborhood) -> bool {
+        self.queue.clear();
+        let mut any_emitter = false;
+
+        for (offset, chunk) in neighborhood.chunks() {
+            if !chunk.may_emit_light() {
+                continue;
+            }
+
+            for local in LocalPos::iter_all() {
+                let pos = offset * Chunk::SIDE + local.to_ivec3();
+                let Some(index) = Self::index(pos) else {
+                    continue;
+                };
+
+                let emission = chunk.get_block(local).info().light_emission;
+                if emission == 0 {
+                    continue;
+                }
+
+                if !any_emitter {
+                    any_emitter = true;
+                    self.levels.clear();
+                    self.levels.resize(LIGHT_VOLUME_SIDE.pow(3) as usize, 0);
+                }
+
+                if self.levels[index] < emission {
+                    self.levels[index] = emission;
+                    self.queue.push_back(pos);
+                }
+            }
+        }
+
+        if !any_emitter {
+            return false;
+        }
+
+        while let Some(pos) = self.queue.pop_front() {
+            let level = self.get(pos);
+            if level <= 1 {
+                continue;
+            }
+
+            for face in Face::iter() {
+                let next = pos + face.normal();
+                let Some(index) = Self::index(next) else {
+                    continue;
+                };
+
+                if self.levels[index] >= level - 1 {
+                    continue;
+                }
+
+                // Light does not travel through opaque blocks, nor outside of the neighborhood.
+                if !neighborhood
+                    .block_at(next)
+                    .is_some_and(|b| b.info().visibility != BlockVisibility::Opaque)
+                {
+                    continue;
+                }
+
+                self.levels[index] = level - 1;
+                self.queue.push_back(next);
+            }
+        }
+
+        true
+    }
+
+    /// Sets the block light level of the provided quads.
+    ///
+    /// A quad receives the light of the block in front of it, or the light of its own block if
+    /// that's brighter (for example for light-emitting blocks, or flat blocks that are lit
+    /// from the inside).
+    fn apply(&self, quads: &mut [QuadInstance]) {
+        /// The normals of the quads, indexed by their facing direction.
+        const NORMALS: [IVec3; 6] = [
+            IVec3::X,
+            IVec3::NEG_X,
+            IVec3::Y,
+            IVec3::NEG_Y,
+            IVec3::Z,
+            IVec3::NEG_Z,
+        ];
+
+        for quad in quads {
+            let index = (quad.flags.bits() & QuadFlags::CHUNK_INDEX_MASK.bits()) >> 7;
+            // SAFETY:
+            //  The chunk index is stored on 15 bits, it's always less than `Chunk::SIZE`.
+            let pos = unsafe { LocalPos::new_unchecked(index as usize) }.to_ivec3();
+            // Quads are validated by `push_quad` before being pushed, ensuring that the facing
+            // direction is valid.
+            let normal = NORMALS[(quad.flags.bits() & 0b111) as usize];
+
+            quad.set_block_light(self.get(pos).max(self.get(pos + normal)));
+        }
+    }
 }
 
 bitflags! {
@@ -245,6 +507,14 @@ impl CulledFaces {
     }
 }
 
+/// Returns whether the provided chunk may have some inner geometry.
+///
+/// Chunks made of a single block type that culls itself have none.
+#[inline]
+fn has_inner_geometry(chunk: &Chunk) -> bool {
+    !chunk.is_uniform().is_some_and(|id| is_face_culled(id, id))
+}
+
 /// Returns whether a face of `me` against `other` should be culled.
 #[inline]
 fn is_face_culled(me: BlockId, other: BlockId) -> bool {
@@ -253,11 +523,41 @@ fn is_face_culled(me: BlockId, other: BlockId) -> bool {
         || (me == other && me.info().flags.contains(BlockFlags::CULLS_ITSELF))
 }
 
+/// Whether an invalid facing direction has already been reported by [`push_quad`].
+static REPORTED_INVALID_FACING: AtomicBool = AtomicBool::new(false);
+
+/// Pushes the provided quad to the output buffer, unless it is invalid.
+///
+/// # Remarks
+///
+/// Quads that face an invalid direction would render garbage (or nothing at all). Those are
+/// skipped, and a warning is logged the first time this happens.
+///
+/// Texture IDs always come from a [`TextureId`], so they are always within the texture atlas.
+#[inline]
+fn push_quad(out: &mut Vec<QuadInstance>, quad: QuadInstance) {
+    debug_assert!(
+        ((quad.texture & QuadInstance::TEXTURE_MASK) as usize) < TextureId::COUNT,
+        "quad references a texture outside of the texture atlas",
+    );
+
+    let facing = quad.flags.bits() & 0b111;
+    if facing > QuadFlags::NEG_Z.bits() {
+        if !REPORTED_INVALID_FACING.swap(true, Ordering::Relaxed) {
+            bns_log::warning!("skipped a degenerate quad with facing direction {facing:#b}");
+        }
+        return;
+    }
+
+    out.push(quad);
+}
+
 /// Builds the geometry of one of the inner voxels of the provided chunk.
 fn build_block(chunk: &Chunk, pos: LocalPos, ctx: &mut ChunkBuildContext) {
     let culled = CulledFaces::of(chunk, pos);
     let block = chunk.get_block(pos);
     let metadata = chunk.get_appearance(pos);
+    let ao = !ctx.no_ambient_occlusion;
 
     let mut base_flags = QuadFlags::from_chunk_index(pos.index());
     let buffer = match block.info().visibility {
@@ -269,34 +569,73 @@ fn build_block(chunk: &Chunk, pos: LocalPos, ctx: &mut ChunkBuildContext) {
         BlockAppearance::Invisible => (),
         BlockAppearance::Regular { top, bottom, side } => {
             if !culled.contains(CulledFaces::X) {
-                build_regular_face_x(side, chunk, pos, buffer);
+                build_regular_face_x(side, QuadFlags::empty(), chunk, pos, ao, buffer);
+            }
+            if !culled.contains(CulledFaces::NEG_X) {
+                build_regular_face_neg_x(side, QuadFlags::empty(), chunk, pos, ao, buffer);
+            }
+            if !culled.contains(CulledFaces::Y) {
+                build_regular_face_y(top, QuadFlags::empty(), chunk, pos, ao, buffer);
+            }
+            if !culled.contains(CulledFaces::NEG_Y) {
+                build_regular_face_neg_y(bottom, QuadFlags::empty(), chunk, pos, ao, buffer);
+            }
+            if !culled.contains(CulledFaces::Z) {
+                build_regular_face_z(side, QuadFlags::empty(), chunk, pos, ao, buffer);
+            }
+            if !culled.contains(CulledFaces::NEG_Z) {
+                build_regular_face_neg_z(side, QuadFlags::empty(), chunk, pos, ao, buffer);
+            }
+        }
+        BlockAppearance::Pillar { end, side } => {
+            // SAFETY:
+            //  The block appearance is `Pillar`.
+            let axis = unsafe { metadata.axis };
+
+            if !culled.contains(CulledFaces::X) {
+                let (tex, extra) = pillar_face(end, side, axis, Face::X);
+                build_regular_face_x(tex, extra, chunk, pos, ao, buffer);
             }
             if !culled.contains(CulledFaces::NEG_X) {
-                build_regular_face_neg_x(side, chunk, pos, buffer);
+                let (tex, extra) = pillar_face(end, side, axis, Face::NegX);
+                build_regular_face_neg_x(tex, extra, chunk, pos, ao, buffer);
             }
             if !culled.contains(CulledFaces::Y) {
-                build_regular_face_y(top, chunk, pos, buffer);
+                let (tex, extra) = pillar_face(end, side, axis, Face::Y);
+                build_regular_face_y(tex, extra, chunk, pos, ao, buffer);
             }
             if !culled.contains(CulledFaces::NEG_Y) {
-                build_regular_face_neg_y(bottom, chunk, pos, buffer);
+                let (tex, extra) = pillar_face(end, side, axis, Face::NegY);
+                build_regular_face_neg_y(tex, extra, chunk, pos, ao, buffer);
             }
             if !culled.contains(CulledFaces::Z) {
-                build_regular_face_z(side, chunk, pos, buffer);
+                let (tex, extra) = pillar_face(end, side, axis, Face::Z);
+                build_regular_face_z(tex, extra, chunk, pos, ao, buffer);
             }
             if !culled.contains(CulledFaces::NEG_Z) {
-                build_regular_face_neg_z(side, chunk, pos, buffer);
+                let (tex, extra) = pillar_face(end, side, axis, Face::NegZ);
+                build_regular_face_neg_z(tex, extra, chunk, pos, ao, buffer);
             }
         }
         BlockAppearance::Liquid(surface) => {
             if !culled.contains(CulledFaces::Y) {
-                buffer.push(QuadInstance {
-                    flags: base_flags | QuadFlags::Y | QuadFlags::OFFSET_1 | QuadFlags::LIQUID,
-                    texture: surface as u32,
-                });
-                buffer.push(QuadInstance {
-                    flags: base_flags | QuadFlags::NEG_Y | QuadFlags::OFFSET_7 | QuadFlags::LIQUID,
-                    texture: surface as u32,
-                });
+                push_quad(
+                    buffer,
+                    QuadInstance {
+                        flags: base_flags | QuadFlags::Y | QuadFlags::OFFSET_1 | QuadFlags::LIQUID,
+                        texture: surface as u32,
+                    },
+                );
+                push_quad(
+                    buffer,
+                    QuadInstance {
+                        flags: base_flags
+                            | QuadFlags::NEG_Y
+                            | QuadFlags::OFFSET_7
+                            | QuadFlags::LIQUID,
+                        texture: surface as u32,
+                    },
+                );
             }
         }
         BlockAppearance::Flat(texture) => {
@@ -307,40 +646,58 @@ fn build_block(chunk: &Chunk, pos: LocalPos, ctx: &mut ChunkBuildContext) {
 
             match face {
                 Face::X if !culled.contains(CulledFaces::X) => {
-                    buffer.push(QuadInstance {
-                        flags: base_flags | QuadFlags::X,
-                        texture: texture as u32,
-                    });
+                    push_quad(
+                        buffer,
+                        QuadInstance {
+                            flags: base_flags | QuadFlags::X,
+                            texture: texture as u32,
+                        },
+                    );
                 }
                 Face::NegX if !culled.contains(CulledFaces::NEG_X) => {
-                    buffer.push(QuadInstance {
-                        flags: base_flags | QuadFlags::NEG_X,
-                        texture: texture as u32,
-                    });
+                    push_quad(
+                        buffer,
+                        QuadInstance {
+                            flags: base_flags | QuadFlags::NEG_X,
+                            texture: texture as u32,
+                        },
+                    );
                 }
                 Face::Y if !culled.contains(CulledFaces::Y) => {
-                    buffer.push(QuadInstance {
-                        flags: base_flags | QuadFlags::Y,
-                        texture: texture as u32,
-                    });
+                    push_quad(
+                        buffer,
+                        QuadInstance {
+                            flags: base_flags | QuadFlags::Y,
+                            texture: texture as u32,
+                        },
+                    );
                 }
                 Face::NegY if !culled.contains(CulledFaces::NEG_Y) => {
-                    buffer.push(QuadInstance {
-                        flags: base_flags | QuadFlags::NEG_Y,
-                        texture: texture as u32,
-                    });
+                    push_quad(
+                        buffer,
+                        QuadInstance {
+                            flags: base_flags | QuadFlags::NEG_Y,
+                            texture: texture as u32,
+                        },
+                    );
                 }
                 Face::Z if !culled.contains(CulledFaces::Z) => {
-                    buffer.push(QuadInstance {
-                        flags: base_flags | QuadFlags::Z,
-                        texture: texture as u32,
-                    });
+                    push_quad(
+                        buffer,
+                        QuadInstance {
+                            flags: base_flags | QuadFlags::Z,
+                            texture: texture as u32,
+                        },
+                    );
                 }
                 Face::NegZ if !culled.contains(CulledFaces::NEG_Z) => {
-                    buffer.push(QuadInstance {
-                        flags: base_flags | QuadFlags::NEG_Z,
-                        texture: texture as u32,
-                    });
+                    push_quad(
+                        buffer,
+                        QuadInstance {
+                            flags: base_flags | QuadFlags::NEG_Z,
+                            texture: texture as u32,
+                        },
+                    );
                 }
                 _ => (),
             }
@@ -468,6 +825,7 @@ fn build_chunk_boundary(
 /// Builds a single face of a block.
 fn build_single_face_x(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildContext) {
     let block = chunk.get_block(pos);
+    let ao = !ctx.no_ambient_occlusion;
 
     let buffer = match block.info().visibility {
         BlockVisibility::SemiOpaque | BlockVisibility::Opaque => &mut ctx.opaque_quads,
@@ -477,7 +835,14 @@ fn build_single_face_x(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildContext
     match block.info().appearance {
         BlockAppearance::Invisible => (),
         BlockAppearance::Regular { side, .. } => {
-            build_regular_face_x(side, chunk, pos, buffer);
+            build_regular_face_x(side, QuadFlags::empty(), chunk, pos, ao, buffer);
+        }
+        BlockAppearance::Pillar { end, side } => {
+            // SAFETY:
+            //  The block appearance is `Pillar`.
+            let axis = unsafe { chunk.get_appearance(pos).axis };
+            let (tex, extra) = pillar_face(end, side, axis, Face::X);
+            build_regular_face_x(tex, extra, chunk, pos, ao, buffer);
         }
         BlockAppearance::Liquid(_) => (),
         BlockAppearance::Flat(_) => (),
@@ -487,6 +852,7 @@ fn build_single_face_x(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildContext
 /// Builds a single face of a block.
 fn build_single_face_neg_x(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildContext) {
     let block = chunk.get_block(pos);
+    let ao = !ctx.no_ambient_occlusion;
 
     let buffer = match block.info().visibility {
         BlockVisibility::SemiOpaque | BlockVisibility::Opaque => &mut ctx.opaque_quads,
@@ -496,7 +862,14 @@ fn build_single_face_neg_x(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildCon
     match block.info().appearance {
         BlockAppearance::Invisible => (),
         BlockAppearance::Regular { side, .. } => {
-            build_regular_face_neg_x(side, chunk, pos, buffer);
+            build_regular_face_neg_x(side, QuadFlags::empty(), chunk, pos, ao, buffer);
+        }
+        BlockAppearance::Pillar { end, side } => {
+            // SAFETY:
+            //  The block appearance is `Pillar`.
+            let axis = unsafe { chunk.get_appearance(pos).axis };
+            let (tex, extra) = pillar_face(end, side, axis, Face::NegX);
+            build_regular_face_neg_x(tex, extra, chunk, pos, ao, buffer);
         }
         BlockAppearance::Liquid(_) => (),
         BlockAppearance::Flat(_) => (),
@@ -506,6 +879,7 @@ fn build_single_face_neg_x(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildCon
 /// Builds a single face of a block.
 fn build_single_face_z(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildContext) {
     let block = chunk.get_block(pos);
+    let ao = !ctx.no_ambient_occlusion;
 
     let buffer = match block.info().visibility {
         BlockVisibility::SemiOpaque | BlockVisibility::Opaque => &mut ctx.opaque_quads,
@@ -515,7 +889,14 @@ fn build_single_face_z(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildContext
     match block.info().appearance {
         BlockAppearance::Invisible => (),
         BlockAppearance::Regular { side, .. } => {
-            build_regular_face_z(side, chunk, pos, buffer);
+            build_regular_face_z(side, QuadFlags::empty(), chunk, pos, ao, buffer);
+        }
+        BlockAppearance::Pillar { end, side } => {
+            // SAFETY:
+            //  The block appearance is `Pillar`.
+            let axis = unsafe { chunk.get_appearance(pos).axis };
+            let (tex, extra) = pillar_face(end, side, axis, Face::Z);
+            build_regular_face_z(tex, extra, chunk, pos, ao, buffer);
         }
         BlockAppearance::Liquid(_) => (),
         BlockAppearance::Flat(_) => (),
@@ -525,6 +906,7 @@ fn build_single_face_z(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildContext
 /// Builds a single face of a block.
 fn build_single_face_neg_z(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildContext) {
     let block = chunk.get_block(pos);
+    let ao = !ctx.no_ambient_occlusion;
 
     let buffer = match block.info().visibility {
         BlockVisibility::SemiOpaque | BlockVisibility::Opaque => &mut ctx.opaque_quads,
@@ -534,7 +916,14 @@ fn build_single_face_neg_z(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildCon
     match block.info().appearance {
         BlockAppearance::Invisible => (),
         BlockAppearance::Regular { side, .. } => {
-            build_regular_face_neg_z(side, chunk, pos, buffer);
+            build_regular_face_neg_z(side, QuadFlags::empty(), chunk, pos, ao, buffer);
+        }
+        BlockAppearance::Pillar { end, side } => {
+            // SAFETY:
+            //  The block appearance is `Pillar`.
+            let axis = unsafe { chunk.get_appearance(pos).axis };
+            let (tex, extra) = pillar_face(end, side, axis, Face::NegZ);
+            build_regular_face_neg_z(tex, extra, chunk, pos, ao, buffer);
         }
         BlockAppearance::Liquid(_) => (),
         BlockAppearance::Flat(_) => (),
@@ -545,6 +934,7 @@ fn build_single_face_neg_z(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildCon
 fn build_single_face_y(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildContext) {
     let block = chunk.get_block(pos);
     let metadata = chunk.get_appearance(pos);
+    let ao = !ctx.no_ambient_occlusion;
 
     let buffer = match block.info().visibility {
         BlockVisibility::SemiOpaque | BlockVisibility::Opaque => &mut ctx.opaque_quads,
@@ -554,23 +944,36 @@ fn build_single_face_y(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildContext
     match block.info().appearance {
         BlockAppearance::Invisible => (),
         BlockAppearance::Regular { top, .. } => {
-            build_regular_face_y(top, chunk, pos, buffer);
+            build_regular_face_y(top, QuadFlags::empty(), chunk, pos, ao, buffer);
+        }
+        BlockAppearance::Pillar { end, side } => {
+            // SAFETY:
+            //  The block appearance is `Pillar`.
+            let axis = unsafe { chunk.get_appearance(pos).axis };
+            let (tex, extra) = pillar_face(end, side, axis, Face::Y);
+            build_regular_face_y(tex, extra, chunk, pos, ao, buffer);
         }
         BlockAppearance::Liquid(surface) => {
-            buffer.push(QuadInstance {
-                flags: QuadFlags::from_chunk_index(pos.index())
-                    | QuadFlags::OFFSET_1
-                    | QuadFlags::Y
-                    | QuadFlags::LIQUID,
-                texture: surface as u32,
-            });
-            buffer.push(QuadInstance {
-                flags: QuadFlags::from_chunk_index(pos.index())
-                    | QuadFlags::NEG_Y
-                    | QuadFlags::OFFSET_7
-                    | QuadFlags::LIQUID,
-                texture: surface as u32,
-            });
+            push_quad(
+                buffer,
+                QuadInstance {
+                    flags: QuadFlags::from_chunk_index(pos.index())
+                        | QuadFlags::OFFSET_1
+                        | QuadFlags::Y
+                        | QuadFlags::LIQUID,
+                    texture: surface as u32,
+                },
+            );
+            push_quad(
+                buffer,
+                QuadInstance {
+                    flags: QuadFlags::from_chunk_index(pos.index())
+                        | QuadFlags::NEG_Y
+                        | QuadFlags::OFFSET_7
+                        | QuadFlags::LIQUID,
+                    texture: surface as u32,
+                },
+            );
         }
         BlockAppearance::Flat(texture) => {
             // SAFETY:
@@ -578,12 +981,15 @@ fn build_single_face_y(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildContext
             let face = unsafe { metadata.flat };
 
             if face == Face::Y {
-                buffer.push(QuadInstance {
-                    flags: QuadFlags::from_chunk_index(pos.index())
-                        | QuadFlags::OVERLAY
-                        | QuadFlags::Y,
-                    texture: texture as u32,
-                });
+                push_quad(
+                    buffer,
+                    QuadInstance {
+                        flags: QuadFlags::from_chunk_index(pos.index())
+                            | QuadFlags::OVERLAY
+                            | QuadFlags::Y,
+                        texture: texture as u32,
+                    },
+                );
             }
         }
     }
@@ -592,6 +998,7 @@ fn build_single_face_y(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildContext
 /// Builds a single face of a block.
 fn build_single_face_neg_y(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildContext) {
     let block = chunk.get_block(pos);
+    let ao = !ctx.no_ambient_occlusion;
 
     let buffer = match block.info().visibility {
         BlockVisibility::SemiOpaque | BlockVisibility::Opaque => &mut ctx.opaque_quads,
@@ -601,7 +1008,14 @@ fn build_single_face_neg_y(pos: LocalPos, chunk: &Chunk, ctx: &mut ChunkBuildCon
     match block.info().appearance {
         BlockAppearance::Invisible => (),
         BlockAppearance::Regular { bottom, .. } => {
-            build_regular_face_neg_y(bottom, chunk, pos, buffer);
+            build_regular_face_neg_y(bottom, QuadFlags::empty(), chunk, pos, ao, buffer);
+        }
+        BlockAppearance::Pillar { end, side } => {
+            // SAFETY:
+            //  The block appearance is `Pillar`.
+            let axis = unsafe { chunk.get_appearance(pos).axis };
+            let (tex, extra) = pillar_face(end, side, axis, Face::NegY);
+            build_regular_face_neg_y(tex, extra, chunk, pos, ao, buffer);
         }
         BlockAppearance::Liquid(_) => (),
         BlockAppearance::Flat(_) => (),
@@ -704,101 +1118,349 @@ fn compute_ambient_occlusion(
     flags
 }
 
-/// Builds a face that has the "Regular" appearance.
-fn build_regular_face_x(tex: TextureId, chunk: &Chunk, pos: LocalPos, out: &mut Vec<QuadInstance>) {
-    let mut flags = QuadFlags::from_chunk_index(pos.index()) | QuadFlags::X;
+/// Returns the texture and the additional flags of a face of a block that has the "Pillar"
+/// appearance.
+fn pillar_face(end: TextureId, side: TextureId, axis: Axis, face: Face) -> (TextureId, QuadFlags) {
+    if face.axis() == axis {
+        return (end, QuadFlags::empty());
+    }
+
+    // Side textures are upright on the faces perpendicular to the X and Z axes, and run along
+    // the Z axis on the faces perpendicular to the Y axis. They must be rotated whenever the
+    // pillar does not lie along that direction.
+    let rotate = match axis {
+        Axis::X => true,
+        Axis::Y => false,
+        Axis::Z => face.axis() == Axis::X,
+    };
+
+    if rotate {
+        (side, QuadFlags::ROTATE_90)
+    } else {
+        (side, QuadFlags::empty())
+    }
+}
 
-    if let Some(pos) = pos.next_x() {
+/// Builds a face that has the "Regular" or "Pillar" appearance.
+///
+/// `extra` is added to the flags of the quad.
+fn build_regular_face_x(
+    tex: TextureId,
+    extra: QuadFlags,
+    chunk: &Chunk,
+    pos: LocalPos,
+    ao: bool,
+    out: &mut Vec<QuadInstance>,
+) {
+    let mut flags = QuadFlags::from_chunk_index(pos.index()) | QuadFlags::X | extra;
+
+    if let Some(pos) = pos.next_x().filter(|_| ao) {
         flags |= compute_ambient_occlusion_x(chunk, pos);
     }
 
-    out.push(QuadInstance {
-        flags,
-        texture: tex as u32,
-    });
+    push_quad(
+        out,
+        QuadInstance {
+            flags,
+            texture: tex as u32,
+        },
+    );
 }
 
-/// Builds a face that has the "Regular" appearance.
+/// Builds a face that has the "Regular" or "Pillar" appearance.
+///
+/// `extra` is added to the flags of the quad.
 fn build_regular_face_neg_x(
     tex: TextureId,
+    extra: QuadFlags,
     chunk: &Chunk,
     pos: LocalPos,
+    ao: bool,
     out: &mut Vec<QuadInstance>,
 ) {
-    let mut flags = QuadFlags::from_chunk_index(pos.index()) | QuadFlags::NEG_X;
+    let mut flags = QuadFlags::from_chunk_index(pos.index()) | QuadFlags::NEG_X | extra;
 
-    if let Some(pos) = pos.prev_x() {
+    if let Some(pos) = pos.prev_x().filter(|_| ao) {
         flags |= compute_ambient_occlusion_neg_x(chunk, pos);
     }
 
-    out.push(QuadInstance {
-        flags,
-        texture: tex as u32,
-    });
+    push_quad(
+        out,
+        QuadInstance {
+            flags,
+            texture: tex as u32,
+        },
+    );
 }
 
-/// Builds a face that has the "Regular" appearance.
-fn build_regular_face_z(tex: TextureId, chunk: &Chunk, pos: LocalPos, out: &mut Vec<QuadInstance>) {
-    let mut flags = QuadFlags::from_chunk_index(pos.index()) | QuadFlags::Z;
+/// Builds a face that has the "Regular" or "Pillar" appearance.
+///
+/// `extra` is added to the flags of the quad.
+fn build_regular_face_z(
+    tex: TextureId,
+    extra: QuadFlags,
+    chunk: &Chunk,
+    pos: LocalPos,
+    ao: bool,
+    out: &mut Vec<QuadInstance>,
+) {
+    let mut flags = QuadFlags::from_chunk_index(pos.index()) | QuadFlags::Z | extra;
 
-    if let Some(pos) = pos.next_z() {
+    if let Some(pos) = pos.next_z().filter(|_| ao) {
         flags |= compute_ambient_occlusion_z(chunk, pos);
     }
 
-    out.push(QuadInstance {
-        flags,
-        texture: tex as u32,
-    });
+    push_quad(
+        out,
+        QuadInstance {
+            flags,
+            texture: tex as u32,
+        },
+    );
 }
 
-/// Builds a face that has the "Regular" appearance.
+/// Builds a face that has the "Regular" or "Pillar" appearance.
+///
+/// `extra` is added to the flags of the quad.
 fn build_regular_face_neg_z(
     tex: TextureId,
+    extra: QuadFlags,
     chunk: &Chunk,
     pos: LocalPos,
+    ao: bool,
     out: &mut Vec<QuadInstance>,
 ) {
-    let mut flags = QuadFlags::from_chunk_index(pos.index()) | QuadFlags::NEG_Z;
+    let mut flags = QuadFlags::from_chunk_index(pos.index()) | QuadFlags::NEG_Z | extra;
 
-    if let Some(pos) = pos.prev_z() {
+    if let Some(pos) = pos.prev_z().filter(|_| ao) {
         flags |= compute_ambient_occlusion_neg_z(chunk, pos);
     }
 
-    out.push(QuadInstance {
-        flags,
-        texture: tex as u32,
-    });
+    push_quad(
+        out,
+        QuadInstance {
+            flags,
+            texture: tex as u32,
+        },
+    );
 }
 
-/// Builds a face that has the "Regular" appearance.
-fn build_regular_face_y(tex: TextureId, chunk: &Chunk, pos: LocalPos, out: &mut Vec<QuadInstance>) {
-    let mut flags = QuadFlags::from_chunk_index(pos.index()) | QuadFlags::Y;
+/// Builds a face that has the "Regular" or "Pillar" appearance.
+///
+/// `extra` is added to the flags of the quad.
+fn build_regular_face_y(
+    tex: TextureId,
+    extra: QuadFlags,
+    chunk: &Chunk,
+    pos: LocalPos,
+    ao: bool,
+    out: &mut Vec<QuadInstance>,
+) {
+    let mut flags = QuadFlags::from_chunk_index(pos.index()) | QuadFlags::Y | extra;
 
-    if let Some(pos) = pos.next_y() {
+    if let Some(pos) = pos.next_y().filter(|_| ao) {
         flags |= compute_ambient_occlusion_y(chunk, pos);
     }
 
-    out.push(QuadInstance {
-        flags,
-        texture: tex as u32,
-    });
+    push_quad(
+        out,
+        QuadInstance {
+            flags,
+            texture: tex as u32,
+        },
+    );
 }
 
-/// Builds a face that has the "Regular" appearance.
+/// Builds a face that has the "Regular" or "Pillar" appearance.
+///
+/// `extra` is added to the flags of the quad.
 fn build_regular_face_neg_y(
     tex: TextureId,
+    extra: QuadFlags,
     chunk: &Chunk,
     pos: LocalPos,
+    ao: bool,
     out: &mut Vec<QuadInstance>,
 ) {
-    let mut flags = QuadFlags::from_chunk_index(pos.index()) | QuadFlags::NEG_Y;
+    let mut flags = QuadFlags::from_chunk_index(pos.index()) | QuadFlags::NEG_Y | extra;
 
-    if let Some(pos) = pos.prev_y() {
+    if let Some(pos) = pos.prev_y().filter(|_| ao) {
         flags |= compute_ambient_occlusion_neg_y(chunk, pos);
     }
 
-    out.push(QuadInstance {
-        flags,
-        texture: tex as u32,
-    });
+    push_quad(
+        out,
+        QuadInstance {
+            flags,
+            texture: tex as u32,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a quad that faces the direction encoded in the provided bits.
+    fn quad(facing: u32, texture: u32) -> QuadInstance {
+        QuadInstance {
+            flags: QuadFlags::from_bits_retain(facing),
+            texture,
+        }
+    }
+
+    #[test]
+    fn degenerate_quads_are_skipped() {
+        let mut out = Vec::new();
+        for facing in 0b000..=0b111 {
+            push_quad(&mut out, quad(facing, TextureId::Stone as u32));
+        }
+
+        let facings: Vec<u32> = out.iter().map(|q| q.flags.bits() & 0b111).collect();
+        assert_eq!(facings, [0b000, 0b001, 0b010, 0b011, 0b100, 0b101]);
+    }
+
+    #[test]
+    fn uniform_opaque_chunk_has_no_geometry() {
+        let mut stone = Chunk::empty();
+        for pos in LocalPos::iter_all() {
+            stone.set_block(pos, BlockId::Stone.into());
+        }
+        stone.compact();
+
+        // The inner pass is skipped entirely.
+        assert!(!has_inner_geometry(&stone));
+
+        let mut ctx = ChunkBuildContext::default();
+        ctx.build_inner(&stone);
+        ctx.build_outer(ChunkNeighborhood {
+            me: &stone,
+            x: &stone,
+            neg_x: &stone,
+            y: &stone,
+            neg_y: &stone,
+            z: &stone,
+            neg_z: &stone,
+        });
+        assert!(ctx.opaque_quads.is_empty());
+        assert!(ctx.transparent_quads.is_empty());
+
+        // A single hole is enough to require the inner pass.
+        stone.set_block(LocalPos::from_xyz(8, 8, 8), BlockId::Air.into());
+        assert!(has_inner_geometry(&stone));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "outside of the texture atlas")]
+    fn out_of_range_texture_is_rejected() {
+        push_quad(&mut Vec::new(), quad(0b000, TextureId::COUNT as u32));
+    }
+
+    #[test]
+    fn isolated_block_has_one_valid_quad_per_face() {
+        let mut chunk = Chunk::empty();
+        chunk.set_block(LocalPos::from_xyz(8, 8, 8), BlockId::Stone.into());
+
+        let mut ctx = ChunkBuildContext::default();
+        ctx.build_inner(&chunk);
+
+        assert_eq!(ctx.opaque_quads.len(), 6);
+        assert!(ctx.transparent_quads.is_empty());
+        for quad in &ctx.opaque_quads {
+            assert!(((quad.texture & QuadInstance::TEXTURE_MASK) as usize) < TextureId::COUNT);
+            assert!(quad.flags.bits() & 0b111 <= QuadFlags::NEG_Z.bits());
+        }
+    }
+
+    #[test]
+    fn disabled_ambient_occlusion_sets_no_occlusion_flags() {
+        // A floor with a single block on top of it, which occludes the faces around it.
+        let mut chunk = Chunk::empty();
+        for x in 6..=10 {
+            for z in 6..=10 {
+                chunk.set_block(LocalPos::from_xyz(x, 8, z), BlockId::Stone.into());
+            }
+        }
+        chunk.set_block(LocalPos::from_xyz(8, 9, 8), BlockId::Stone.into());
+
+        let occluded = |ctx: &ChunkBuildContext| {
+            ctx.opaque_quads
+                .iter()
+                .filter(|quad| quad.flags.intersects(QuadFlags::OCCLUDED_MASK))
+                .count()
+        };
+
+        let mut ctx = ChunkBuildContext::default();
+        ctx.build_inner(&chunk);
+        assert!(occluded(&ctx) > 0);
+        let quad_count = ctx.opaque_quads.len();
+
+        ctx.clear();
+        ctx.set_ambient_occlusion(false);
+        ctx.build_inner(&chunk);
+        assert_eq!(ctx.opaque_quads.len(), quad_count);
+        assert_eq!(occluded(&ctx), 0);
+    }
+
+    /// Computes the light volume around `me`, surrounded by the provided neighbors.
+    fn light_around(me: &Chunk, neg_x: &Chunk) -> Option<LightVolume> {
+        let empty = Chunk::empty();
+        let neighborhood = ChunkNeighborhood {
+            me,
+            x: &empty,
+            neg_x,
+            y: &empty,
+            neg_y: &empty,
+            z: &empty,
+            neg_z: &empty,
+        };
+
+        let mut light = LightVolume::default();
+        light.propagate(&neighborhood).then_some(light)
+    }
+
+    #[test]
+    fn light_decays_with_distance_and_is_blocked() {
+        let source = IVec3::new(10, 10, 10);
+        let emission = BlockId::DiamondOre.info().light_emission;
+
+        let mut chunk = Chunk::empty();
+        chunk.set_block(LocalPos::from_xyz(10, 10, 10), BlockId::DiamondOre.into());
+        chunk.set_block(LocalPos::from_xyz(12, 10, 10), BlockId::Stone.into());
+
+        let light = light_around(&chunk, &Chunk::empty()).expect("no light found");
+        assert_eq!(light.get(source), emission);
+
+        // In open air, the light loses one level per block.
+        for d in 1..=emission as i32 {
+            let expected = emission.saturating_sub(d as u8);
+            assert_eq!(light.get(source + IVec3::new(0, d, 0)), expected);
+            assert_eq!(light.get(source + IVec3::new(-d, 0, 0)), expected);
+        }
+        assert_eq!(light.get(source + IVec3::new(-1, 2, 0)), emission - 3);
+
+        // The stone block is not lit, and light has to go around it.
+        assert_eq!(light.get(IVec3::new(12, 10, 10)), 0);
+        assert_eq!(light.get(IVec3::new(13, 10, 10)), emission - 5);
+    }
+
+    #[test]
+    fn light_crosses_chunk_boundaries() {
+        let mut neg_x = Chunk::empty();
+        neg_x.set_block(LocalPos::from_xyz(31, 5, 5), BlockId::DiamondOre.into());
+        let emission = BlockId::DiamondOre.info().light_emission;
+
+        let light = light_around(&Chunk::empty(), &neg_x).expect("no light found");
+        assert_eq!(light.get(IVec3::new(-1, 5, 5)), emission);
+        assert_eq!(light.get(IVec3::new(0, 5, 5)), emission - 1);
+        assert_eq!(light.get(IVec3::new(2, 5, 5)), emission - 3);
+    }
+
+    #[test]
+    fn no_emitter_means_no_light() {
+        let mut chunk = Chunk::empty();
+        chunk.set_block(LocalPos::from_xyz(1, 2, 3), BlockId::Stone.into());
+        assert!(light_around(&chunk, &Chunk::empty()).is_none());
+    }
 }
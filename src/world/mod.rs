@@ -1,15 +1,19 @@
 use std::hash::BuildHasherDefault;
 use std::sync::Arc;
+use std::time::Duration;
 
 use glam::{IVec3, Vec3};
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 
 use bns_core::{BlockFlags, BlockId, BlockInstance, Chunk, ChunkPos, Face, LocalPos};
 use bns_render::Gpu;
-use bns_worldgen_core::WorldGenerator;
+use bns_worldgen_core::{GenerationError, WorldGenerator};
 
 mod chunk_geometry;
 pub use chunk_geometry::*;
+
+mod priority;
+pub use priority::*;
 use smallvec::SmallVec;
 
 use self::task_pool::TaskPool;
@@ -41,6 +45,37 @@ impl LoadedChunk {
     }
 }
 
+/// Keeps track of the time spent building the geometry of chunks on the main thread.
+///
+/// Every field is a rolling average, giving more weight to recent samples.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ChunkBuildTimings {
+    /// The time spent building the inner geometry of a chunk.
+    ///
+    /// This is usually done in a background thread when the chunk is generated, and only
+    /// measured when it has to be done on the main thread (for example, after the chunk has
+    /// been modified).
+    pub build_inner: Duration,
+    /// The time spent building the outer geometry of a chunk.
+    pub build_outer: Duration,
+    /// The time spent uploading the geometry of a chunk to the GPU.
+    pub upload: Duration,
+    /// The total number of chunks that have been built so far.
+    pub built_chunks: u64,
+}
+
+impl ChunkBuildTimings {
+    /// The weight given to new samples when updating the rolling averages.
+    const SMOOTHING: f32 = 0.05;
+
+    /// Blends the provided sample into the rolling average `avg`.
+    fn blend(avg: &mut Duration, sample: Duration) {
+        let avg_secs = avg.as_secs_f32();
+        *avg =
+            Duration::from_secs_f32(avg_secs + (sample.as_secs_f32() - avg_secs) * Self::SMOOTHING);
+    }
+}
+
 /// An entry into the [`Chunks`] map.
 enum ChunkEntry {
     /// The chunk is already properly loaded.
@@ -49,6 +84,18 @@ enum ChunkEntry {
     ///
     /// If the inner chunk has been generated
     Generating,
+    /// The generator failed to produce the chunk.
+    ///
+    /// The chunk is requested again once `retry_at` is reached.
+    Failed {
+        /// The number of times the generator failed to produce the chunk.
+        failures: u32,
+        /// The instant at which the chunk may be requested again.
+        ///
+        /// When [`None`], the generator failed too many times and the chunk will never be
+        /// requested again (unless it's unloaded).
+        retry_at: Option<quanta::Instant>,
+    },
 }
 
 impl ChunkEntry {
@@ -57,7 +104,7 @@ impl ChunkEntry {
     pub fn loaded(&self) -> Option<&LoadedChunk> {
         match self {
             Self::Loaded(chunk) => Some(chunk),
-            Self::Generating => None,
+            Self::Generating | Self::Failed { .. } => None,
         }
     }
 }
@@ -65,6 +112,12 @@ impl ChunkEntry {
 /// A collection of chunks.
 type Chunks = HashMap<ChunkPos, ChunkEntry, BuildHasherDefault<rustc_hash::FxHasher>>;
 
+/// The blocks that have been modified within a chunk, keyed by their position.
+type ChunkEdits = HashMap<LocalPos, BlockInstance, BuildHasherDefault<rustc_hash::FxHasher>>;
+
+/// The blocks that have been modified in the world, grouped by chunk.
+type Edits = HashMap<ChunkPos, ChunkEdits, BuildHasherDefault<rustc_hash::FxHasher>>;
+
 /// A task that's submitted to the task pool.
 struct Task {
     /// The build context that should be used to build the chunk.
@@ -73,6 +126,8 @@ struct Task {
     generator: Arc<dyn WorldGenerator>,
     /// The position of the chunk that must be generated.
     position: ChunkPos,
+    /// The number of times the generator already failed to produce the chunk.
+    failures: u32,
 }
 
 /// The result of a task.
@@ -83,21 +138,27 @@ struct TaskResult {
     ///
     /// This only includes the inner geometry of the chunk.
     geometry: ChunkBuildContext,
-    /// The chunk data.
-    chunk: Chunk,
+    /// The chunk data, or the error that prevented it from being generated.
+    chunk: Result<Chunk, GenerationError>,
+    /// The number of times the generator failed to produce the chunk before this task.
+    failures: u32,
 }
 
 impl task_pool::Task for Task {
     type Output = TaskResult;
 
     fn execute(mut self) -> Self::Output {
-        let chunk = self.generator.generate(self.position);
         self.build_context.clear();
-        self.build_context.build_inner(&chunk);
+        let chunk = self.generator.try_generate(self.position).map(|mut chunk| {
+            chunk.compact();
+            self.build_context.build_inner(&chunk);
+            chunk
+        });
         TaskResult {
             position: self.position,
             geometry: self.build_context,
             chunk,
+            failures: self.failures,
         }
     }
 }
@@ -119,30 +180,203 @@ pub struct World {
     /// This is just a bunch of buffers that are re-used when a new chunk needs its geometry
     /// to be rebuilt.
     chunk_build_context_pool: Vec<ChunkBuildContext>,
+    /// Whether ambient occlusion is computed when building the geometry of chunks.
+    ambient_occlusion: bool,
+    /// The maximum number of chunks whose geometry can be rebuilt between two calls to
+    /// [`World::flush_pending_chunks`].
+    max_rebuilds_per_frame: u32,
+    /// The number of chunks whose geometry has been rebuilt since the last call to
+    /// [`World::flush_pending_chunks`].
+    rebuilds_this_frame: u32,
 
     /// The current world generator. Used to generate new chunks when some are missing.
     generator: Arc<dyn WorldGenerator>,
 
+    /// The blocks that have been modified through [`World::set_block`].
+    ///
+    /// Those are applied on top of the generator's output when a chunk is (re)loaded, ensuring
+    /// that they survive chunks being unloaded.
+    edits: Edits,
+
+    /// The chunks that must be marked dirty at the end of [`World::set_blocks`].
+    ///
+    /// This is used to avoid re-allocating a new set every time blocks are modified.
+    dirty_chunks: HashSet<ChunkPos, BuildHasherDefault<rustc_hash::FxHasher>>,
+
     /// A list of chunks that must be submitted to the task pool for generation.
     ///
     /// This is used to avoid re-allocating a new vector every time we need to perform
     /// a submission.
     tasks_to_submit: Vec<Task>,
+    /// The tasks that are actually submitted to the task pool during the current call to
+    /// [`World::flush_pending_chunks`].
+    ///
+    /// This is used to avoid re-allocating a new vector every time we need to perform
+    /// a submission.
+    submission_batch: Vec<Task>,
+    /// The maximum number of generation tasks that may be in flight at any given time.
+    ///
+    /// See [`World::set_max_generation_tasks`].
+    max_generation_tasks: usize,
+    /// The policy used to decide which chunks are generated first.
+    ///
+    /// See [`World::prioritize_pending_chunks`].
+    chunk_priority: ChunkPriority,
+
+    /// The maximum number of chunks that may be kept in memory.
+    ///
+    /// See [`World::enforce_chunk_budget`].
+    max_loaded_chunks: usize,
+    /// A buffer used by [`World::enforce_chunk_budget`] to sort the chunks that may be evicted.
+    eviction_candidates: Vec<(i32, ChunkPos)>,
+
+    /// The clock used to measure how long building chunks takes.
+    clock: quanta::Clock,
+    /// The time spent building the geometry of chunks.
+    build_timings: ChunkBuildTimings,
 }
 
 impl World {
+    /// The default value for [`World::set_max_rebuilds_per_frame`].
+    pub const DEFAULT_MAX_REBUILDS_PER_FRAME: u32 = 32;
+
+    /// The default value for [`World::set_max_generation_tasks`].
+    pub const DEFAULT_MAX_GENERATION_TASKS: usize = 256;
+
+    /// The time to wait before requesting a chunk whose generation failed again.
+    ///
+    /// This delay is doubled after every failed attempt.
+    const GENERATION_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+    /// The maximum number of times the generation of a chunk is attempted before giving up.
+    const MAX_GENERATION_ATTEMPTS: u32 = 4;
+
     /// Creates a new [`World`] that uses the provided [`WorldGenerator`] to generate chunks.
     pub fn new(gpu: Arc<Gpu>, generator: Arc<dyn WorldGenerator>) -> Self {
+        Self::with_upload_context(ChunkUploadContext::new(gpu), generator)
+    }
+
+    /// Creates a new [`World`] that uploads the geometry of its chunks through the provided
+    /// [`ChunkUploadContext`].
+    fn with_upload_context(
+        chunk_upload_context: ChunkUploadContext,
+        generator: Arc<dyn WorldGenerator>,
+    ) -> Self {
         Self {
             chunks: Chunks::default(),
-            chunk_upload_context: ChunkUploadContext::new(gpu),
+            chunk_upload_context,
             chunk_build_context_pool: Vec::new(),
+            ambient_occlusion: true,
+            max_rebuilds_per_frame: Self::DEFAULT_MAX_REBUILDS_PER_FRAME,
+            rebuilds_this_frame: 0,
             task_pool: TaskPool::new(),
             generator,
+            edits: Edits::default(),
+            dirty_chunks: HashSet::default(),
             tasks_to_submit: Vec::new(),
+            submission_batch: Vec::new(),
+            max_generation_tasks: Self::DEFAULT_MAX_GENERATION_TASKS,
+            chunk_priority: ChunkPriority::default(),
+            max_loaded_chunks: usize::MAX,
+            eviction_candidates: Vec::new(),
+            clock: quanta::Clock::new(),
+            build_timings: ChunkBuildTimings::default(),
+        }
+    }
+
+    /// Returns the time spent building the geometry of chunks on the main thread.
+    #[inline]
+    pub fn build_timings(&self) -> &ChunkBuildTimings {
+        &self.build_timings
+    }
+
+    /// Sets the maximum number of chunks that may be kept in memory.
+    ///
+    /// See [`World::enforce_chunk_budget`] for more information.
+    #[inline]
+    pub fn set_max_loaded_chunks(&mut self, max: usize) {
+        self.max_loaded_chunks = max;
+    }
+
+    /// Sets whether ambient occlusion is computed when building the geometry of chunks.
+    ///
+    /// # Remarks
+    ///
+    /// Changing this setting marks every loaded chunk as dirty, forcing their geometry to be
+    /// rebuilt.
+    pub fn set_ambient_occlusion(&mut self, enabled: bool) {
+        if self.ambient_occlusion == enabled {
+            return;
+        }
+
+        self.ambient_occlusion = enabled;
+
+        for ctx in &mut self.chunk_build_context_pool {
+            ctx.set_ambient_occlusion(enabled);
+        }
+
+        for entry in self.chunks.values_mut() {
+            if let ChunkEntry::Loaded(chunk) = entry {
+                chunk.is_dirty = true;
+
+                // The pre-built inner geometry was built using the previous setting.
+                if let Some(mut ctx) = chunk.pending_inner_geometry.take() {
+                    ctx.set_ambient_occlusion(enabled);
+                    self.chunk_build_context_pool.push(ctx);
+                }
+            }
         }
     }
 
+    /// Sets the maximum number of chunks whose geometry can be rebuilt in a single frame.
+    ///
+    /// A frame ends when [`World::flush_pending_chunks`] is called. When the limit is reached,
+    /// [`World::request_chunk`] stops rebuilding dirty chunks until the next frame. Those chunks
+    /// keep their previous geometry in the meantime.
+    ///
+    /// Chunks are rebuilt in the order they are requested, meaning that the caller is
+    /// responsible for requesting the most important chunks first.
+    #[inline]
+    pub fn set_max_rebuilds_per_frame(&mut self, max: u32) {
+        self.max_rebuilds_per_frame = max;
+    }
+
+    /// Sets the maximum number of chunk generation tasks that may be in flight at any given
+    /// time.
+    ///
+    /// Chunks requested beyond that limit remain queued on the main thread until some of the
+    /// in-flight tasks complete. Queued chunks are re-prioritized every frame by
+    /// [`World::prioritize_pending_chunks`], meaning that a lower limit makes the world react
+    /// faster to the viewer moving around, at the cost of keeping the worker threads less busy.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `max` is zero.
+    #[inline]
+    pub fn set_max_generation_tasks(&mut self, max: usize) {
+        assert!(max != 0, "at least one generation task must be allowed");
+        self.max_generation_tasks = max;
+    }
+
+    /// Returns the maximum number of chunk generation tasks that may be in flight at any given
+    /// time.
+    #[inline]
+    pub fn max_generation_tasks(&self) -> usize {
+        self.max_generation_tasks
+    }
+
+    /// Returns whether ambient occlusion is computed when building the geometry of chunks.
+    #[inline]
+    pub fn ambient_occlusion(&self) -> bool {
+        self.ambient_occlusion
+    }
+
+    /// Returns the maximum number of chunks that may be kept in memory.
+    #[inline]
+    pub fn max_loaded_chunks(&self) -> usize {
+        self.max_loaded_chunks
+    }
+
     /// Returns the generator that the world uses to generate chunks.
     #[inline]
     pub fn generator(&self) -> &dyn WorldGenerator {
@@ -152,7 +386,7 @@ impl World {
     /// Returns the number of chunks that are currently being generated.
     #[inline]
     pub fn loading_chunk_count(&self) -> usize {
-        self.task_pool.pending_tasks()
+        self.task_pool.pending_tasks() + self.tasks_to_submit.len()
     }
 
     /// Returns the number of chunks that are currently loaded in memory.
@@ -177,10 +411,64 @@ impl World {
         self.chunks
             .shrink_to(h_radius as usize * h_radius as usize * v_radius as usize);
 
+        self.tasks_to_submit
+            .retain(|task| retain_chunk(task.position));
         self.task_pool
             .retain_tasks(|task| retain_chunk(task.position));
     }
 
+    /// Evicts chunks until the number of chunks kept in memory is below the budget configured
+    /// with [`World::set_max_loaded_chunks`].
+    ///
+    /// # Remarks
+    ///
+    /// Only the chunks that are outside of the provided radius are considered for eviction,
+    /// starting with the ones that are the farthest from `center`. This means that the budget
+    /// might still be exceeded after this function returns if the radius itself includes more
+    /// chunks than the budget allows.
+    ///
+    /// Unlike [`World::request_cleanup`], this function is cheap to call when the budget is not
+    /// exceeded, and can be called every frame.
+    #[profiling::function]
+    pub fn enforce_chunk_budget(&mut self, center: ChunkPos, h_radius: u32, v_radius: u32) {
+        if self.chunks.len() <= self.max_loaded_chunks {
+            return;
+        }
+
+        let in_radius = |pos: ChunkPos| {
+            let hd = pos.xz().distance_squared(center.xz()) as u32;
+            let vd = (pos.y - center.y).unsigned_abs();
+            hd < h_radius * h_radius && vd < v_radius
+        };
+
+        self.eviction_candidates.clear();
+        self.eviction_candidates.extend(
+            self.chunks
+                .keys()
+                .filter(|&&pos| !in_radius(pos))
+                .map(|&pos| (pos.distance_squared(center), pos)),
+        );
+
+        // Farthest chunks first.
+        self.eviction_candidates
+            .sort_unstable_by(|a, b| b.0.cmp(&a.0));
+
+        let excess = self.chunks.len() - self.max_loaded_chunks;
+        for &(_, pos) in self.eviction_candidates.iter().take(excess) {
+            if let Some(ChunkEntry::Loaded(chunk)) = self.chunks.remove(&pos) {
+                // Keep the pre-built geometry buffers around to avoid re-allocating them.
+                if let Some(ctx) = chunk.pending_inner_geometry {
+                    self.chunk_build_context_pool.push(ctx);
+                }
+            }
+        }
+
+        self.tasks_to_submit
+            .retain(|task| self.chunks.contains_key(&task.position));
+        self.task_pool
+            .retain_tasks(|task| self.chunks.contains_key(&task.position));
+    }
+
     /// Gets the block at the provided position, or [`None`] if the chunk is not loaded yet.
     pub fn get_block_instance(&self, pos: IVec3) -> Option<BlockInstance> {
         let (chunk_pos, local_pos) = bns_core::utility::chunk_and_local_pos(pos);
@@ -246,6 +534,12 @@ impl World {
                             });
                         }
 
+                        if self.rebuilds_this_frame >= self.max_rebuilds_per_frame {
+                            // We already rebuilt too many chunks this frame. The chunk will
+                            // keep its previous geometry until the next one.
+                            return None;
+                        }
+
                         // Reborrow the chunk in a shared manner to allow accessing
                         // neighboring chunks as well.
                         let (mut ctx, missing_inner) = match chunk.pending_inner_geometry.take() {
@@ -255,8 +549,10 @@ impl World {
                             // Otherwise, we need to allocate a new build context (or take
                             // one from the pool) and build the inner geometry of the chunk.
                             None => {
-                                let mut ctx =
-                                    self.chunk_build_context_pool.pop().unwrap_or_default();
+                                let mut ctx = take_build_context(
+                                    &mut self.chunk_build_context_pool,
+                                    self.ambient_occlusion,
+                                );
                                 ctx.clear();
                                 (ctx, true)
                             }
@@ -268,7 +564,7 @@ impl World {
                         let neighborhood = match ChunkNeighborhood::from_fn(pos, |pos: ChunkPos| {
                             match self.chunks.get(&pos) {
                                 Some(ChunkEntry::Loaded(chunk)) => Some(&chunk.data),
-                                Some(ChunkEntry::Generating) => None,
+                                Some(ChunkEntry::Generating | ChunkEntry::Failed { .. }) => None,
                                 None => {
                                     to_request.push(pos);
                                     None
@@ -287,12 +583,13 @@ impl World {
                                         .insert_unique_unchecked(*pos, ChunkEntry::Generating);
 
                                     Task {
-                                        build_context: self
-                                            .chunk_build_context_pool
-                                            .pop()
-                                            .unwrap_or_default(),
+                                        build_context: take_build_context(
+                                            &mut self.chunk_build_context_pool,
+                                            self.ambient_occlusion,
+                                        ),
                                         generator: self.generator.clone(),
                                         position: *pos,
+                                        failures: 0,
                                     }
                                 }));
 
@@ -314,10 +611,13 @@ impl World {
                         };
 
                         // Build the chunk's geometry.
+                        let start = self.clock.now();
                         if missing_inner {
                             ctx.build_inner(neighborhood.me);
                         }
+                        let inner_done = self.clock.now();
                         ctx.build_outer(neighborhood);
+                        let outer_done = self.clock.now();
 
                         // Re-borrow the chunk mutably and return it.
                         // We can use unsafe to hint the compiler that the lookup cannot fail.
@@ -326,8 +626,18 @@ impl World {
                         };
 
                         chunk.is_dirty = false;
+                        self.rebuilds_this_frame += 1;
                         self.chunk_upload_context.upload(&ctx, &mut chunk.geometry);
                         self.chunk_build_context_pool.push(ctx);
+                        let upload_done = self.clock.now();
+
+                        let timings = &mut self.build_timings;
+                        if missing_inner {
+                            ChunkBuildTimings::blend(&mut timings.build_inner, inner_done - start);
+                        }
+                        ChunkBuildTimings::blend(&mut timings.build_outer, outer_done - inner_done);
+                        ChunkBuildTimings::blend(&mut timings.upload, upload_done - outer_done);
+                        timings.built_chunks += 1;
 
                         Some(chunk)
                     }
@@ -336,6 +646,27 @@ impl World {
                         // No need to do anything.
                         None
                     }
+                    ChunkEntry::Failed { failures, retry_at } => {
+                        let failures = *failures;
+                        match *retry_at {
+                            Some(retry_at) if self.clock.now() >= retry_at => (),
+                            _ => return None,
+                        }
+
+                        // Give the generator another chance.
+                        self.chunks.insert(pos, ChunkEntry::Generating);
+                        self.tasks_to_submit.push(Task {
+                            generator: self.generator.clone(),
+                            position: pos,
+                            build_context: take_build_context(
+                                &mut self.chunk_build_context_pool,
+                                self.ambient_occlusion,
+                            ),
+                            failures,
+                        });
+
+                        None
+                    }
                 }
             }
             Entry::Vacant(e) => {
@@ -346,7 +677,11 @@ impl World {
                 self.tasks_to_submit.push(Task {
                     generator: self.generator.clone(),
                     position: pos,
-                    build_context: self.chunk_build_context_pool.pop().unwrap_or_default(),
+                    build_context: take_build_context(
+                        &mut self.chunk_build_context_pool,
+                        self.ambient_occlusion,
+                    ),
+                    failures: 0,
                 });
 
                 None
@@ -354,23 +689,34 @@ impl World {
         }
     }
 
-    /// Sorts the chunks that are currently pending for generation.
+    /// Sets the policy used to decide which chunks are generated first.
     ///
-    /// This function can be used to prioritize the chunks that are the closest to the player.
+    /// See [`World::prioritize_pending_chunks`].
+    #[inline]
+    pub fn set_chunk_priority(&mut self, priority: ChunkPriority) {
+        self.chunk_priority = priority;
+    }
+
+    /// Sorts the chunks that are pending for generation according to the current
+    /// [`ChunkPriority`] policy, as seen from the provided viewer.
     ///
-    /// The *last* chunks in the array will be the first to be submitted to the task pool.
+    /// The chunks with the highest priority will be the first to be submitted to the task pool.
     #[profiling::function]
-    pub fn sort_pending_chunks<F, O>(&mut self, mut key: F)
-    where
-        F: FnMut(ChunkPos) -> O,
-        O: Ord,
-    {
+    pub fn prioritize_pending_chunks(&mut self, viewer: &Viewer) {
+        let priority = &self.chunk_priority;
         self.tasks_to_submit
-            .sort_unstable_by_key(|task| key(task.position))
+            .sort_unstable_by_key(|task| priority.priority(viewer, task.position));
     }
 
     /// Removes any currently pending chunks from the task pool and submits the last chunk that
     /// were requested instead.
+    ///
+    /// At most [`World::set_max_generation_tasks`] tasks are kept in flight. The chunks with the
+    /// highest priority are submitted first, and the remaining ones stay queued until the next
+    /// call to this function.
+    ///
+    /// This function also marks the end of the current frame as far as
+    /// [`World::set_max_rebuilds_per_frame`] is concerned.
     #[profiling::function]
     pub fn flush_pending_chunks(&mut self) {
         // Check if the task pool has sent us some results.
@@ -389,10 +735,50 @@ impl World {
                             // it.
                             self.chunk_build_context_pool.push(result.geometry);
                         }
-                        ChunkEntry::Generating => {
+                        ChunkEntry::Generating | ChunkEntry::Failed { .. } => {
+                            let mut chunk = match result.chunk {
+                                Ok(chunk) => chunk,
+                                Err(err) => {
+                                    let failures = result.failures + 1;
+                                    let retry_at = if failures < Self::MAX_GENERATION_ATTEMPTS {
+                                        bns_log::error!(
+                                            "failed to generate chunk {:?}: {err}",
+                                            result.position,
+                                        );
+                                        let delay =
+                                            Self::GENERATION_RETRY_DELAY * (1 << (failures - 1));
+                                        Some(self.clock.now() + delay)
+                                    } else {
+                                        bns_log::error!(
+                                            "failed to generate chunk {:?}: {err} (giving up after {failures} attempts)",
+                                            result.position,
+                                        );
+                                        None
+                                    };
+
+                                    self.chunk_build_context_pool.push(result.geometry);
+                                    e.insert(ChunkEntry::Failed { failures, retry_at });
+                                    continue;
+                                }
+                            };
+                            let mut geometry = Some(result.geometry);
+
+                            // Apply the edits that were made to this chunk the last time
+                            // it was loaded.
+                            if let Some(edits) = self.edits.get(&result.position) {
+                                for (local_pos, block) in edits {
+                                    chunk.set_block(*local_pos, block.clone());
+                                }
+
+                                // The inner geometry was built without the edits.
+                                if let Some(ctx) = geometry.take() {
+                                    self.chunk_build_context_pool.push(ctx);
+                                }
+                            }
+
                             // Upload the chunk's geometry to the GPU.
-                            let mut loaded = LoadedChunk::new(result.chunk);
-                            loaded.pending_inner_geometry = Some(result.geometry);
+                            let mut loaded = LoadedChunk::new(chunk);
+                            loaded.pending_inner_geometry = geometry;
 
                             // Insert the chunk into the world.
                             // We can't reuse the entry because we accessed the world
@@ -413,7 +799,17 @@ impl World {
             }
         }
 
-        self.task_pool.submit_batch(&mut self.tasks_to_submit);
+        // Only submit as many tasks as the in-flight budget allows. The tasks are sorted by
+        // increasing priority, so the most important ones are at the end of the list.
+        let budget = self
+            .max_generation_tasks
+            .saturating_sub(self.task_pool.in_flight_tasks());
+        let split = self.tasks_to_submit.len().saturating_sub(budget);
+        self.submission_batch
+            .extend(self.tasks_to_submit.drain(split..));
+        self.task_pool.submit_batch(&mut self.submission_batch);
+
+        self.rebuilds_this_frame = 0;
     }
 
     /// Queries the world for the first block that intersects the line defined by `start`,
@@ -440,28 +836,71 @@ impl World {
         &self,
         start: Vec3,
         direction: Vec3,
-        mut length: f32,
+        length: f32,
     ) -> Result<QueryResult, QueryError> {
-        // FIXME: This is the naive implementation.
-        // It's pretty easy to come up with a better one that increases the cursor by the right
-        // amount every time. + that would allow us to properly compute which face has been hit.
-
-        const STEP: f32 = 0.05;
+        // This is an implementation of the voxel traversal algorithm described by
+        // John Amanatides and Andrew Woo in "A Fast Voxel Traversal Algorithm for Ray Tracing".
+        //
+        // Instead of advancing the cursor by a fixed amount, we always move it to the next voxel
+        // boundary. The axis whose boundary is crossed tells us which face of the next voxel
+        // has been entered.
 
-        let mut cur = start;
+        let mut world_pos = bns_core::utility::world_pos_of(start);
 
-        let mut current_chunk = ChunkPos::from_world_pos(cur);
+        let (mut current_chunk, _) = bns_core::utility::chunk_and_local_pos(world_pos);
         let mut chunk = self
             .chunks
             .get(&current_chunk)
             .and_then(ChunkEntry::loaded)
             .ok_or(QueryError::MissingChunk(current_chunk))?;
-        let mut world_pos = bns_core::utility::world_pos_of(cur);
 
-        while length > 0.0 {
-            let new_current_chunk = ChunkPos::from_world_pos(cur);
-            if new_current_chunk != current_chunk {
-                current_chunk = new_current_chunk;
+        // Computes, for a single axis:
+        //
+        // 1. The direction in which the voxel coordinate moves along that axis.
+        // 2. The distance along the line at which the first voxel boundary is crossed.
+        // 3. The distance along the line between two consecutive voxel boundaries.
+        let axis = |d: f32, p: f32, v: i32| -> (i32, f32, f32) {
+            if d > 0.0 {
+                (1, ((v + 1) as f32 - p) / d, 1.0 / d)
+            } else if d < 0.0 {
+                (-1, (p - v as f32) / -d, -1.0 / d)
+            } else {
+                (0, f32::INFINITY, f32::INFINITY)
+            }
+        };
+
+        let (step_x, mut t_max_x, t_delta_x) = axis(direction.x, start.x, world_pos.x);
+        let (step_y, mut t_max_y, t_delta_y) = axis(direction.y, start.y, world_pos.y);
+        let (step_z, mut t_max_z, t_delta_z) = axis(direction.z, start.z, world_pos.z);
+
+        loop {
+            // Move to the next voxel, crossing the closest boundary.
+            let (t, face) = if t_max_x < t_max_y && t_max_x < t_max_z {
+                world_pos.x += step_x;
+                let t = t_max_x;
+                t_max_x += t_delta_x;
+                (t, if step_x > 0 { Face::NegX } else { Face::X })
+            } else if t_max_y < t_max_z {
+                world_pos.y += step_y;
+                let t = t_max_y;
+                t_max_y += t_delta_y;
+                (t, if step_y > 0 { Face::NegY } else { Face::Y })
+            } else {
+                world_pos.z += step_z;
+                let t = t_max_z;
+                t_max_z += t_delta_z;
+                (t, if step_z > 0 { Face::NegZ } else { Face::Z })
+            };
+
+            // This also takes care of the case where `direction` is zero, as `t` is infinite
+            // in that case.
+            if t > length {
+                break;
+            }
+
+            let (chunk_pos, local_pos) = bns_core::utility::chunk_and_local_pos(world_pos);
+            if chunk_pos != current_chunk {
+                current_chunk = chunk_pos;
                 chunk = self
                     .chunks
                     .get(&current_chunk)
@@ -469,22 +908,6 @@ impl World {
                     .ok_or(QueryError::MissingChunk(current_chunk))?;
             }
 
-            let new_world_pos = bns_core::utility::world_pos_of(cur);
-            if new_world_pos == world_pos {
-                cur += direction * STEP;
-                length -= STEP;
-                continue;
-            }
-            world_pos = new_world_pos;
-
-            let local_pos = unsafe {
-                LocalPos::from_xyz_unchecked(
-                    world_pos.x - current_chunk.x * Chunk::SIDE,
-                    world_pos.y - current_chunk.y * Chunk::SIDE,
-                    world_pos.z - current_chunk.z * Chunk::SIDE,
-                )
-            };
-
             if chunk
                 .data
                 .get_block(local_pos)
@@ -492,64 +915,13 @@ impl World {
                 .flags
                 .contains(BlockFlags::TANGIBLE)
             {
-                // Hit!
-
-                let xf = if direction.x > 0.0 {
-                    cur.x - world_pos.x as f32
-                } else {
-                    1.0 - (cur.x - world_pos.x as f32)
-                };
-
-                let yf = if direction.y > 0.0 {
-                    cur.y - world_pos.y as f32
-                } else {
-                    1.0 - (cur.y - world_pos.y as f32)
-                };
-
-                let zf = if direction.z > 0.0 {
-                    cur.z - world_pos.z as f32
-                } else {
-                    1.0 - (cur.z - world_pos.z as f32)
-                };
-
-                #[allow(clippy::collapsible_else_if)]
-                let face = if xf < yf {
-                    if xf < zf {
-                        if direction.x > 0.0 {
-                            Face::NegX
-                        } else {
-                            Face::X
-                        }
-                    } else {
-                        if direction.z > 0.0 {
-                            Face::NegZ
-                        } else {
-                            Face::Z
-                        }
-                    }
-                } else {
-                    if yf < zf {
-                        if direction.y > 0.0 {
-                            Face::NegY
-                        } else {
-                            Face::Y
-                        }
-                    } else {
-                        if direction.z > 0.0 {
-                            Face::NegZ
-                        } else {
-                            Face::Z
-                        }
-                    }
-                };
-
                 return Ok(QueryResult {
                     face,
                     local_pos,
                     world_pos,
                     chunk_pos: current_chunk,
                     chunk: &chunk.data,
-                    hit: cur,
+                    hit: start + direction * t,
                 });
             }
         }
@@ -557,51 +929,238 @@ impl World {
         Err(QueryError::NotFound)
     }
 
+    /// Returns an iterator over the blocks that have been modified in this world, along with
+    /// their world-space position.
+    pub fn edits(&self) -> impl '_ + Iterator<Item = (IVec3, &BlockInstance)> {
+        self.edits.iter().flat_map(|(chunk_pos, edits)| {
+            edits
+                .iter()
+                .map(move |(local_pos, block)| (chunk_pos.origin() + local_pos.to_ivec3(), block))
+        })
+    }
+
+    /// Returns an iterator over the positions of the chunks that contain blocks modified in
+    /// this world.
+    pub fn modified_chunks(&self) -> impl '_ + Iterator<Item = ChunkPos> {
+        self.edits
+            .iter()
+            .filter(|(_, edits)| !edits.is_empty())
+            .map(|(&pos, _)| pos)
+    }
+
+    /// Loads a list of block edits into the world.
+    ///
+    /// Edits are applied immediately to chunks that are already loaded, and will be applied
+    /// to the other ones when they finish generating.
+    pub fn load_edits(&mut self, edits: impl IntoIterator<Item = (IVec3, BlockInstance)>) {
+        for (world_pos, block) in edits {
+            if !self.set_block(world_pos, block.clone()) {
+                let (chunk_pos, local_pos) = bns_core::utility::chunk_and_local_pos(world_pos);
+                record_edit(&mut self.edits, chunk_pos, local_pos, block);
+            }
+        }
+    }
+
     /// Replaces the provided block with another one.
     ///
     /// # Returns
     ///
     /// This function returns `true` if the block was successfully replaced, or `false` if the
     /// the provided position was part of an unloaded chunk.
-    #[profiling::function]
+    #[inline]
     pub fn set_block(&mut self, world_pos: IVec3, block: BlockInstance) -> bool {
-        let (chunk_pos, local_pos) = bns_core::utility::chunk_and_local_pos(world_pos);
+        self.set_blocks(std::iter::once((world_pos, block))) != 0
+    }
 
-        let Some(ChunkEntry::Loaded(chunk)) = self.chunks.get_mut(&chunk_pos) else {
-            return false;
-        };
+    /// Replaces a batch of blocks at once.
+    ///
+    /// This is equivalent to calling [`World::set_block`] for every provided block, except
+    /// that the chunks affected by the changes are only marked dirty once.
+    ///
+    /// # Returns
+    ///
+    /// The number of blocks that have been replaced. Blocks that are part of unloaded chunks
+    /// are skipped.
+    #[profiling::function]
+    pub fn set_blocks(
+        &mut self,
+        blocks: impl IntoIterator<Item = (IVec3, BlockInstance)>,
+    ) -> usize {
+        let mut replaced = 0;
+
+        for (world_pos, block) in blocks {
+            let (chunk_pos, local_pos) = bns_core::utility::chunk_and_local_pos(world_pos);
+
+            let Some(ChunkEntry::Loaded(chunk)) = self.chunks.get_mut(&chunk_pos) else {
+                continue;
+            };
+
+            // The light emitted by the chunk reaches its neighbors. When it might change, they
+            // need to be rebuilt as well.
+            let affects_light = chunk.data.may_emit_light()
+                || chunk.data.get_block(local_pos).info().light_emission != 0
+                || block.id().info().light_emission != 0;
+
+            chunk.data.set_block(local_pos, block.clone());
+            record_edit(&mut self.edits, chunk_pos, local_pos, block);
+            replaced += 1;
+
+            let dirty = &mut self.dirty_chunks;
+            dirty.insert(chunk_pos);
+
+            if affects_light {
+                dirty.insert(chunk_pos + IVec3::X);
+                dirty.insert(chunk_pos - IVec3::X);
+                dirty.insert(chunk_pos + IVec3::Y);
+                dirty.insert(chunk_pos - IVec3::Y);
+                dirty.insert(chunk_pos + IVec3::Z);
+                dirty.insert(chunk_pos - IVec3::Z);
+                continue;
+            }
+
+            if local_pos.is_x_min() {
+                dirty.insert(chunk_pos - IVec3::X);
+            } else if local_pos.is_x_max() {
+                dirty.insert(chunk_pos + IVec3::X);
+            }
+
+            if local_pos.is_y_min() {
+                dirty.insert(chunk_pos - IVec3::Y);
+            } else if local_pos.is_y_max() {
+                dirty.insert(chunk_pos + IVec3::Y);
+            }
 
-        chunk.data.set_block(local_pos, block);
-        chunk.is_dirty = true;
+            if local_pos.is_z_min() {
+                dirty.insert(chunk_pos - IVec3::Z);
+            } else if local_pos.is_z_max() {
+                dirty.insert(chunk_pos + IVec3::Z);
+            }
+        }
 
-        let mut make_dirty = |pos: ChunkPos| {
+        for pos in self.dirty_chunks.drain() {
             if let Some(ChunkEntry::Loaded(chunk)) = self.chunks.get_mut(&pos) {
                 chunk.is_dirty = true;
             }
-        };
-
-        if local_pos.is_x_min() {
-            make_dirty(chunk_pos - IVec3::X);
-        } else if local_pos.is_x_max() {
-            make_dirty(chunk_pos + IVec3::X);
         }
 
-        if local_pos.is_y_min() {
-            make_dirty(chunk_pos - IVec3::Y);
-        } else if local_pos.is_y_max() {
-            make_dirty(chunk_pos + IVec3::Y);
+        replaced
+    }
+
+    /// Removes every solid block within the sphere described by `center` and `radius`.
+    ///
+    /// A block is removed when its center is within the sphere. Blocks that cannot be broken
+    /// (those with an infinite [`hardness`](bns_core::BlockInfo::hardness), such as
+    /// [`BlockId::Bedrock`]) are never removed, and neither are blocks that are not
+    /// [`SOLID`](BlockFlags::SOLID).
+    ///
+    /// The blocks are removed in a single batch (see [`World::set_blocks`]).
+    ///
+    /// # Returns
+    ///
+    /// The number of blocks that have been removed. Blocks that are part of unloaded chunks are
+    /// left untouched.
+    pub fn explode(&mut self, center: Vec3, radius: f32) -> usize {
+        let min = (center - radius).floor().as_ivec3();
+        let max = (center + radius).ceil().as_ivec3();
+        let radius_squared = radius * radius;
+
+        let mut to_remove = Vec::new();
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    let world_pos = IVec3::new(x, y, z);
+                    if (world_pos.as_vec3() + 0.5).distance_squared(center) > radius_squared {
+                        continue;
+                    }
+
+                    let Some(block) = self.get_block(world_pos) else {
+                        continue;
+                    };
+                    let info = block.info();
+                    if info.hardness.is_infinite() || !info.flags.contains(BlockFlags::SOLID) {
+                        continue;
+                    }
+
+                    to_remove.push((world_pos, BlockId::Air.into()));
+                }
+            }
         }
 
-        if local_pos.is_z_min() {
-            make_dirty(chunk_pos - IVec3::Z);
-        } else if local_pos.is_z_max() {
-            make_dirty(chunk_pos + IVec3::Z);
+        self.set_blocks(to_remove)
+    }
+
+    /// Finds the block closest to `from` that satisfies `predicate`.
+    ///
+    /// The search expands in cubic shells around `from`, up to `max_radius` blocks along each
+    /// axis. Among the matching blocks, the one with the smallest euclidean distance to `from`
+    /// is returned.
+    ///
+    /// # Returns
+    ///
+    /// The position of the nearest matching block, or [`None`] if no block within the search
+    /// radius satisfies the predicate.
+    ///
+    /// [`None`] is also returned when the search reaches a chunk that is not loaded yet, as
+    /// it's not possible to know whether a closer block would have been found in it.
+    pub fn find_nearest(
+        &self,
+        from: IVec3,
+        predicate: impl Fn(BlockId) -> bool,
+        max_radius: i32,
+    ) -> Option<IVec3> {
+        let mut best: Option<(IVec3, i32)> = None;
+
+        for r in 0..=max_radius {
+            for dx in -r..=r {
+                for dy in -r..=r {
+                    // Only visit the outer layer of the shell; its interior has already been
+                    // searched in the previous iterations.
+                    let on_shell = dx.abs() == r || dy.abs() == r;
+                    let step = if on_shell { 1 } else { (2 * r).max(1) as usize };
+
+                    for dz in (-r..=r).step_by(step) {
+                        let offset = IVec3::new(dx, dy, dz);
+                        let block = self.get_block(from + offset)?;
+                        if !predicate(block) {
+                            continue;
+                        }
+
+                        let dist = offset.length_squared();
+                        if best.map_or(true, |(_, best_dist)| dist < best_dist) {
+                            best = Some((from + offset, dist));
+                        }
+                    }
+                }
+            }
+
+            // Every block of the next shells is at least `r + 1` blocks away from `from`.
+            if let Some((pos, dist)) = best {
+                if dist <= (r + 1) * (r + 1) {
+                    return Some(pos);
+                }
+            }
         }
 
-        true
+        best.map(|(pos, _)| pos)
     }
 }
 
+/// Records that the block at the provided position has been replaced by `block`.
+fn record_edit(edits: &mut Edits, chunk_pos: ChunkPos, local_pos: LocalPos, block: BlockInstance) {
+    edits.entry(chunk_pos).or_default().insert(local_pos, block);
+}
+
+/// Takes a [`ChunkBuildContext`] from the provided pool, or creates a new one if the pool is
+/// empty.
+fn take_build_context(
+    pool: &mut Vec<ChunkBuildContext>,
+    ambient_occlusion: bool,
+) -> ChunkBuildContext {
+    let mut ctx = pool.pop().unwrap_or_default();
+    ctx.set_ambient_occlusion(ambient_occlusion);
+    ctx
+}
+
 /// An error that might occur while querying a block.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum QueryError {
@@ -627,3 +1186,505 @@ pub struct QueryResult<'a> {
     /// The chunk that the block is in.
     pub chunk: &'a Chunk,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A generator that fills the world with stone, with a single layer of bedrock at `y = 0`.
+    struct Flat;
+
+    impl WorldGenerator for Flat {
+        fn generate(&self, pos: ChunkPos) -> Chunk {
+            let mut chunk = Chunk::empty();
+            for local_pos in LocalPos::iter_all() {
+                let block = if pos.origin().y + local_pos.y() == 0 {
+                    BlockId::Bedrock
+                } else {
+                    BlockId::Stone
+                };
+                chunk.set_block(local_pos, block.into());
+            }
+            chunk
+        }
+
+        fn request_cleanup(&self, _center: ChunkPos, _h_radius: u32, _v_radius: u32) {}
+
+        fn seed(&self) -> u64 {
+            0
+        }
+
+        fn debug_info(&self, _buf: &mut dyn std::fmt::Write, _pos: IVec3) -> std::fmt::Result {
+            Ok(())
+        }
+    }
+
+    /// A generator that behaves like [`Flat`], except that it always fails to generate the chunk
+    /// at the origin.
+    struct BrokenOrigin;
+
+    impl WorldGenerator for BrokenOrigin {
+        fn generate(&self, pos: ChunkPos) -> Chunk {
+            Flat.generate(pos)
+        }
+
+        fn try_generate(&self, pos: ChunkPos) -> Result<Chunk, GenerationError> {
+            if pos == ChunkPos::new(0, 0, 0) {
+                Err(GenerationError::new("the origin is broken"))
+            } else {
+                Ok(self.generate(pos))
+            }
+        }
+
+        fn request_cleanup(&self, _center: ChunkPos, _h_radius: u32, _v_radius: u32) {}
+
+        fn seed(&self) -> u64 {
+            0
+        }
+
+        fn debug_info(&self, _buf: &mut dyn std::fmt::Write, _pos: IVec3) -> std::fmt::Result {
+            Ok(())
+        }
+    }
+
+    /// Creates a [`World`] using the provided generator, without connecting it to a GPU.
+    ///
+    /// Only the chunks that have no geometry can be built in such a world.
+    fn detached_world(generator: impl WorldGenerator + 'static) -> World {
+        World::with_upload_context(ChunkUploadContext::detached(), Arc::new(generator))
+    }
+
+    /// Generates and loads the chunks between `min` and `max` (inclusive) synchronously.
+    fn load_chunks(world: &mut World, min: IVec3, max: IVec3) {
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    let pos = ChunkPos::new(x, y, z);
+                    let chunk = world.generator.generate(pos);
+                    world
+                        .chunks
+                        .insert(pos, ChunkEntry::Loaded(LoadedChunk::new(chunk)));
+                }
+            }
+        }
+    }
+
+    /// Flushes the pending chunks of `world` until the chunk at `pos` is done generating.
+    fn wait_for_chunk(world: &mut World, pos: ChunkPos) {
+        let deadline = std::time::Instant::now() + Duration::from_secs(30);
+        loop {
+            world.flush_pending_chunks();
+            if !matches!(world.chunks.get(&pos), Some(ChunkEntry::Generating)) {
+                return;
+            }
+            assert!(
+                std::time::Instant::now() < deadline,
+                "chunk never generated"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Returns the number of failures and the retry instant of the chunk at `pos`, or [`None`]
+    /// if it's not marked as failed.
+    fn failure(world: &World, pos: ChunkPos) -> Option<(u32, Option<quanta::Instant>)> {
+        match world.chunks.get(&pos) {
+            Some(ChunkEntry::Failed { failures, retry_at }) => Some((*failures, *retry_at)),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the chunk at the provided position is loaded and dirty.
+    fn is_dirty(world: &World, pos: ChunkPos) -> bool {
+        world.chunks[&pos].loaded().unwrap().is_dirty
+    }
+
+    #[test]
+    fn record_edit_keeps_latest_block() {
+        let mut edits = Edits::default();
+        let chunk_pos = ChunkPos::new(1, 2, 3);
+        let local_pos = LocalPos::from_xyz(4, 5, 6);
+
+        record_edit(&mut edits, chunk_pos, local_pos, BlockId::Stone.into());
+        record_edit(&mut edits, chunk_pos, local_pos, BlockId::Air.into());
+        record_edit(
+            &mut edits,
+            chunk_pos,
+            LocalPos::from_xyz(0, 0, 0),
+            BlockId::Stone.into(),
+        );
+
+        let chunk_edits = &edits[&chunk_pos];
+        assert_eq!(chunk_edits.len(), 2);
+        assert_eq!(chunk_edits[&local_pos].id(), BlockId::Air);
+    }
+
+    #[test]
+    fn explode_removes_sphere_but_not_bedrock() {
+        let mut world = detached_world(Flat);
+        load_chunks(&mut world, IVec3::splat(-1), IVec3::ZERO);
+
+        let center = Vec3::splat(0.5);
+        let radius = 4.0;
+        let removed = world.explode(center, radius);
+
+        let mut expected = 0;
+        for x in -6..=6 {
+            for y in -6..=6 {
+                for z in -6..=6 {
+                    let pos = IVec3::new(x, y, z);
+                    let inside = (pos.as_vec3() + 0.5).distance_squared(center) <= radius * radius;
+                    let block = world.get_block(pos).unwrap();
+
+                    if y == 0 {
+                        assert_eq!(block, BlockId::Bedrock, "bedrock destroyed at {pos}");
+                    } else if inside {
+                        assert_eq!(block, BlockId::Air, "block left at {pos}");
+                        expected += 1;
+                    } else {
+                        assert_eq!(block, BlockId::Stone, "block removed at {pos}");
+                    }
+                }
+            }
+        }
+
+        assert_eq!(removed, expected);
+        assert_eq!(world.edits().count(), expected);
+    }
+
+    #[test]
+    fn set_blocks_dirties_touched_chunks() {
+        let mut world = detached_world(Flat);
+        load_chunks(&mut world, IVec3::splat(-1), IVec3::splat(1));
+        for entry in world.chunks.values_mut() {
+            if let ChunkEntry::Loaded(chunk) = entry {
+                chunk.is_dirty = false;
+            }
+        }
+
+        // One block in the middle of the origin chunk, one on its `+X` boundary, and one in an
+        // unloaded chunk.
+        let replaced = world.set_blocks([
+            (IVec3::new(16, 16, 16), BlockId::Air.into()),
+            (IVec3::new(31, 16, 16), BlockId::Air.into()),
+            (IVec3::new(1000, 16, 16), BlockId::Air.into()),
+        ]);
+        assert_eq!(replaced, 2);
+
+        assert!(is_dirty(&world, ChunkPos::new(0, 0, 0)));
+        assert!(is_dirty(&world, ChunkPos::new(1, 0, 0)));
+        assert!(!is_dirty(&world, ChunkPos::new(-1, 0, 0)));
+        assert!(!is_dirty(&world, ChunkPos::new(0, 1, 0)));
+        assert!(world.dirty_chunks.is_empty());
+    }
+
+    #[test]
+    fn failed_chunk_is_marked_errored_and_eventually_abandoned() {
+        let mut world = detached_world(BrokenOrigin);
+        let (clock, mock) = quanta::Clock::mock();
+        world.clock = clock;
+
+        let origin = ChunkPos::new(0, 0, 0);
+        let other = ChunkPos::new(1, 0, 0);
+
+        assert!(world.request_chunk(origin).is_none());
+        assert!(world.request_chunk(other).is_none());
+        wait_for_chunk(&mut world, origin);
+        wait_for_chunk(&mut world, other);
+
+        assert!(world.chunks[&other].loaded().is_some());
+        assert!(world.chunks[&origin].loaded().is_none());
+        assert!(matches!(failure(&world, origin), Some((1, Some(_)))));
+
+        // The chunk is not requested again before the retry delay has elapsed.
+        assert!(world.request_chunk(origin).is_none());
+        assert!(matches!(failure(&world, origin), Some((1, Some(_)))));
+
+        for attempt in 2..=World::MAX_GENERATION_ATTEMPTS {
+            mock.increment(World::GENERATION_RETRY_DELAY * (1 << attempt));
+            assert!(world.request_chunk(origin).is_none());
+            wait_for_chunk(&mut world, origin);
+            assert_eq!(failure(&world, origin).unwrap().0, attempt);
+        }
+
+        // The world gave up on the chunk.
+        assert_eq!(
+            failure(&world, origin),
+            Some((World::MAX_GENERATION_ATTEMPTS, None))
+        );
+        mock.increment(Duration::from_secs(3600));
+        assert!(world.request_chunk(origin).is_none());
+        assert!(!matches!(
+            world.chunks.get(&origin),
+            Some(ChunkEntry::Generating)
+        ));
+    }
+
+    #[test]
+    fn custom_priority_decides_submission_order() {
+        let mut world = detached_world(Flat);
+        world.set_max_generation_tasks(1);
+
+        // Prefer the chunks of the viewer's column, the closest first.
+        world.set_chunk_priority(ChunkPriority::Custom(Box::new(|viewer, pos| {
+            -(pos.y - viewer.chunk.y).abs() as i64 - (pos.x - viewer.chunk.x).abs() as i64 * 100
+        })));
+
+        let chunks = [
+            ChunkPos::new(0, 2, 0),
+            ChunkPos::new(1, 0, 0),
+            ChunkPos::new(0, 1, 0),
+            ChunkPos::new(0, 0, 0),
+        ];
+        for pos in chunks {
+            assert!(world.request_chunk(pos).is_none());
+        }
+
+        let camera = crate::game::player::Camera::new(0.01, 1024.0, 70f32.to_radians());
+        let viewer = Viewer {
+            chunk: ChunkPos::new(0, 0, 0),
+            position: Vec3::ZERO,
+            camera: &camera,
+        };
+
+        world.prioritize_pending_chunks(&viewer);
+        world.flush_pending_chunks();
+
+        // Only the chunk with the highest priority was submitted, and the remaining ones are
+        // queued so that the last one is submitted next.
+        let queued: Vec<ChunkPos> = world
+            .tasks_to_submit
+            .iter()
+            .rev()
+            .map(|task| task.position)
+            .collect();
+        assert_eq!(
+            queued,
+            [
+                ChunkPos::new(0, 1, 0),
+                ChunkPos::new(0, 2, 0),
+                ChunkPos::new(1, 0, 0),
+            ]
+        );
+    }
+
+    /// Inserts a stone chunk at the origin of `world`, with water at the provided local
+    /// positions.
+    fn insert_water_chunk(world: &mut World, water: &[LocalPos]) {
+        let pos = ChunkPos::new(0, 0, 0);
+        let mut chunk = Flat.generate(pos);
+        for &local_pos in water {
+            chunk.set_block(local_pos, BlockId::Water.into());
+        }
+        world
+            .chunks
+            .insert(pos, ChunkEntry::Loaded(LoadedChunk::new(chunk)));
+    }
+
+    #[test]
+    fn find_nearest_returns_the_closest_match() {
+        let mut world = detached_world(Flat);
+
+        // The first block is found in an earlier shell, but the second one is closer.
+        insert_water_chunk(
+            &mut world,
+            &[LocalPos::from_xyz(8, 8, 8), LocalPos::from_xyz(5, 5, 9)],
+        );
+
+        let from = IVec3::new(5, 5, 5);
+        let is_water = |block: BlockId| block == BlockId::Water;
+        assert_eq!(
+            world.find_nearest(from, is_water, 6),
+            Some(IVec3::new(5, 5, 9))
+        );
+        assert_eq!(
+            world.find_nearest(from, |block| block == BlockId::Stone, 6),
+            Some(from)
+        );
+    }
+
+    #[test]
+    fn find_nearest_fails_without_match_or_loaded_chunks() {
+        let mut world = detached_world(Flat);
+        insert_water_chunk(&mut world, &[LocalPos::from_xyz(5, 5, 9)]);
+
+        let is_water = |block: BlockId| block == BlockId::Water;
+
+        // The block is out of the search radius.
+        assert_eq!(world.find_nearest(IVec3::new(5, 5, 5), is_water, 3), None);
+
+        // The search reaches the unloaded chunk at `x = -1` before finding the block.
+        assert_eq!(world.find_nearest(IVec3::new(1, 5, 9), is_water, 6), None);
+    }
+
+    #[test]
+    fn modified_chunks_are_the_ones_with_edits() {
+        let mut world = detached_world(Flat);
+        load_chunks(&mut world, IVec3::splat(-1), IVec3::ZERO);
+        assert_eq!(world.modified_chunks().count(), 0);
+
+        world.set_block(IVec3::new(3, 4, 5), BlockId::Air.into());
+        world.set_block(IVec3::new(6, 7, 8), BlockId::Air.into());
+        world.set_block(IVec3::new(-3, -4, 5), BlockId::Air.into());
+
+        let mut modified: Vec<ChunkPos> = world.modified_chunks().collect();
+        modified.sort_unstable_by_key(|pos| (pos.x, pos.y, pos.z));
+        assert_eq!(modified, [ChunkPos::new(-1, -1, 0), ChunkPos::new(0, 0, 0)]);
+    }
+
+    #[test]
+    fn chunk_budget_evicts_farthest_chunks_first() {
+        let mut world = detached_world(Flat);
+        load_chunks(&mut world, IVec3::new(-4, 0, 0), IVec3::new(4, 0, 0));
+        assert_eq!(world.loaded_chunk_count(), 9);
+
+        // The radius only covers the chunks from `x = -1` to `x = 1`.
+        let center = ChunkPos::new(0, 0, 0);
+        world.set_max_loaded_chunks(5);
+        world.enforce_chunk_budget(center, 2, 1);
+
+        let mut loaded: Vec<i32> = world.chunks.keys().map(|pos| pos.x).collect();
+        loaded.sort_unstable();
+        assert_eq!(loaded, [-2, -1, 0, 1, 2]);
+
+        // The chunks within the radius are never evicted, even if the budget is exceeded.
+        world.set_max_loaded_chunks(1);
+        world.enforce_chunk_budget(center, 2, 1);
+
+        let mut loaded: Vec<i32> = world.chunks.keys().map(|pos| pos.x).collect();
+        loaded.sort_unstable();
+        assert_eq!(loaded, [-1, 0, 1]);
+    }
+
+    /// Inserts an empty chunk at the origin of `world`, with stone at the provided positions.
+    fn insert_air_chunk(world: &mut World, stone: &[IVec3]) {
+        let mut chunk = Chunk::empty();
+        for &pos in stone {
+            let (_, local_pos) = bns_core::utility::chunk_and_local_pos(pos);
+            chunk.set_block(local_pos, BlockId::Stone.into());
+        }
+        world.chunks.insert(
+            ChunkPos::new(0, 0, 0),
+            ChunkEntry::Loaded(LoadedChunk::new(chunk)),
+        );
+    }
+
+    #[test]
+    fn query_line_follows_axis_aligned_rays() {
+        let mut world = detached_world(Flat);
+        insert_air_chunk(&mut world, &[IVec3::new(10, 5, 5), IVec3::new(5, 3, 5)]);
+
+        let hit = world
+            .query_line(Vec3::new(0.5, 5.5, 5.5), Vec3::X, 20.0)
+            .unwrap();
+        assert_eq!(hit.world_pos, IVec3::new(10, 5, 5));
+        assert_eq!(hit.face, Face::NegX);
+        assert_eq!(hit.hit, Vec3::new(10.0, 5.5, 5.5));
+
+        let hit = world
+            .query_line(Vec3::new(5.5, 20.5, 5.5), Vec3::NEG_Y, 20.0)
+            .unwrap();
+        assert_eq!(hit.world_pos, IVec3::new(5, 3, 5));
+        assert_eq!(hit.face, Face::Y);
+
+        // The block is out of reach.
+        assert_eq!(
+            world
+                .query_line(Vec3::new(0.5, 5.5, 5.5), Vec3::X, 5.0)
+                .err(),
+            Some(QueryError::NotFound)
+        );
+
+        // The line leaves the only loaded chunk.
+        assert_eq!(
+            world
+                .query_line(Vec3::new(0.5, 5.5, 5.5), Vec3::NEG_X, 5.0)
+                .err(),
+            Some(QueryError::MissingChunk(ChunkPos::new(-1, 0, 0)))
+        );
+    }
+
+    #[test]
+    fn query_line_hits_grazed_voxel_edge() {
+        // This line crosses `x = 3` at `z = 2.95`, only clipping the corner of the voxel at
+        // `(3, 5, 2)` before entering the next row. It never enters the voxel at `(2, 5, 3)`.
+        let start = Vec3::new(0.5, 5.5, 0.5);
+        let direction = Vec3::new(1.0, 0.0, 0.98).normalize();
+
+        let mut world = detached_world(Flat);
+        insert_air_chunk(&mut world, &[IVec3::new(3, 5, 2)]);
+
+        let hit = world.query_line(start, direction, 8.0).unwrap();
+        assert_eq!(hit.world_pos, IVec3::new(3, 5, 2));
+        assert_eq!(hit.face, Face::NegX);
+        assert!((hit.hit.x - 3.0).abs() < 1e-4);
+        assert!(hit.hit.z < 3.0);
+
+        let mut world = detached_world(Flat);
+        insert_air_chunk(&mut world, &[IVec3::new(2, 5, 3)]);
+
+        assert_eq!(
+            world.query_line(start, direction, 8.0).err(),
+            Some(QueryError::NotFound)
+        );
+    }
+
+    #[test]
+    fn building_a_chunk_updates_build_timings() {
+        let mut world = detached_world(Flat);
+        let (clock, _mock) = quanta::Clock::mock();
+        world.clock = clock;
+
+        // Stone all around, so that the chunk has no geometry to upload.
+        load_chunks(&mut world, IVec3::new(-1, 4, -1), IVec3::new(1, 6, 1));
+
+        let second = Duration::from_secs(1);
+        world.build_timings = ChunkBuildTimings {
+            build_inner: second,
+            build_outer: second,
+            upload: second,
+            built_chunks: 0,
+        };
+
+        let pos = ChunkPos::new(0, 5, 0);
+        assert!(world.request_chunk(pos).is_some());
+
+        // The mock clock never advances, so every sample is zero and pulls the averages down.
+        let timings = *world.build_timings();
+        assert_eq!(timings.built_chunks, 1);
+        assert!(timings.build_inner < second);
+        assert!(timings.build_outer < second);
+        assert!(timings.upload < second);
+
+        // Up-to-date chunks are not built again.
+        assert!(world.request_chunk(pos).is_some());
+        assert_eq!(world.build_timings().built_chunks, 1);
+    }
+
+    #[test]
+    fn rebuilds_are_limited_per_frame() {
+        let mut world = detached_world(Flat);
+        world.set_max_rebuilds_per_frame(2);
+
+        // Four dirty chunks whose neighbors are all loaded.
+        load_chunks(&mut world, IVec3::new(-1, 4, -1), IVec3::new(4, 6, 1));
+        let chunks = [0, 1, 2, 3].map(|x| ChunkPos::new(x, 5, 0));
+
+        assert!(world.request_chunk(chunks[0]).is_some());
+        assert!(world.request_chunk(chunks[1]).is_some());
+        assert!(world.request_chunk(chunks[2]).is_none());
+        assert!(world.request_chunk(chunks[3]).is_none());
+        assert_eq!(world.build_timings().built_chunks, 2);
+        assert!(is_dirty(&world, chunks[2]));
+        assert!(is_dirty(&world, chunks[3]));
+
+        // Chunks that are already built remain available.
+        assert!(world.request_chunk(chunks[0]).is_some());
+
+        // The next frame can rebuild the remaining chunks.
+        world.flush_pending_chunks();
+        assert!(world.request_chunk(chunks[2]).is_some());
+        assert!(world.request_chunk(chunks[3]).is_some());
+        assert_eq!(world.build_timings().built_chunks, 4);
+    }
+}
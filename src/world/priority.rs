@@ -0,0 +1,106 @@
+use bns_core::{Chunk, ChunkPos};
+
+use glam::Vec3;
+
+use crate::game::player::Camera;
+
+/// Describes the point of view from which the priority of chunks is computed.
+#[derive(Clone, Copy)]
+pub struct Viewer<'a> {
+    /// The chunk in which the viewer is.
+    pub chunk: ChunkPos,
+    /// The position of the viewer's eye, in world space.
+    pub position: Vec3,
+    /// The camera through which the viewer sees the world.
+    pub camera: &'a Camera,
+}
+
+/// A policy that decides in which order missing chunks are submitted for generation.
+///
+/// Chunks with a higher priority are submitted first.
+#[derive(Default)]
+pub enum ChunkPriority {
+    /// The chunks that are closest to the viewer are generated first.
+    #[default]
+    Nearest,
+    /// The chunks that are closest to the viewer are generated first, but chunks that are in
+    /// the direction the viewer is looking at are considered closer than they actually are.
+    ///
+    /// A chunk straight ahead of the viewer is considered half as far as it actually is, while
+    /// a chunk right behind it is considered twice as far. Chunks within the camera's frustum
+    /// are additionally considered half as far.
+    LookBiased,
+    /// The priority of chunks is computed by a custom function.
+    Custom(Box<dyn Fn(&Viewer, ChunkPos) -> i64>),
+}
+
+impl ChunkPriority {
+    /// Computes the priority of the chunk at `pos`, as seen from `viewer`.
+    pub fn priority(&self, viewer: &Viewer, pos: ChunkPos) -> i64 {
+        match self {
+            Self::Nearest => -(viewer.chunk.distance_squared(pos) as i64),
+            Self::LookBiased => {
+                const CHUNK_RADIUS: f32 = (Chunk::SIDE as f32) * 0.8660254; // sqrt(3) / 2
+
+                let offset = (pos.as_ivec3() - viewer.chunk.as_ivec3()).as_vec3();
+                let alignment = offset.normalize_or_zero().dot(viewer.camera.view.look_at());
+                let mut factor = 2f32.powf(-alignment);
+
+                let center = (pos.as_vec3() + Vec3::splat(0.5)) * Chunk::SIDE as f32;
+                if viewer
+                    .camera
+                    .is_sphere_in_frustum(center - viewer.position, CHUNK_RADIUS)
+                {
+                    factor *= 0.5;
+                }
+
+                -(offset.length_squared() * factor * factor) as i64
+            }
+            Self::Custom(f) => f(viewer, pos),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn look_biased_prefers_chunks_in_view() {
+        // The default view looks toward +Z.
+        let camera = Camera::new(0.01, 1024.0, 70f32.to_radians());
+        let viewer = Viewer {
+            chunk: ChunkPos::new(0, 0, 0),
+            position: Vec3::splat(Chunk::SIDE as f32 * 0.5),
+            camera: &camera,
+        };
+
+        let mut chunks = [
+            ChunkPos::new(0, 0, -4),
+            ChunkPos::new(4, 0, 0),
+            ChunkPos::new(-4, 0, 0),
+            ChunkPos::new(0, 0, 4),
+        ];
+        chunks.sort_unstable_by_key(|&pos| -ChunkPriority::LookBiased.priority(&viewer, pos));
+
+        assert_eq!(chunks[0], ChunkPos::new(0, 0, 4));
+        assert_eq!(chunks[3], ChunkPos::new(0, 0, -4));
+    }
+
+    #[test]
+    fn look_biased_still_prefers_close_chunks() {
+        let camera = Camera::new(0.01, 1024.0, 70f32.to_radians());
+        let viewer = Viewer {
+            chunk: ChunkPos::new(0, 0, 0),
+            position: Vec3::splat(Chunk::SIDE as f32 * 0.5),
+            camera: &camera,
+        };
+
+        let near = ChunkPos::new(0, 0, 1);
+        let far = ChunkPos::new(0, 0, 8);
+        assert!(
+            ChunkPriority::LookBiased.priority(&viewer, near)
+                > ChunkPriority::LookBiased.priority(&viewer, far)
+        );
+    }
+}
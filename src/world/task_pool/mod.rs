@@ -1,4 +1,10 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::Arc;
+
 mod no_threads;
+#[cfg(all(target_arch = "wasm32", feature = "web-workers"))]
+mod web_workers;
 mod yes_threads;
 
 /// A task that can be executed on a thread pool.
@@ -21,7 +27,16 @@ enum Flavor<T: Task> {
 }
 
 /// Manages a pool of worker threads ready to execute a bunch of tasks.
-pub struct TaskPool<T: Task>(Flavor<T>);
+pub struct TaskPool<T: Task> {
+    /// The flavor of the task pool.
+    flavor: Flavor<T>,
+    /// The number of tasks that have been submitted but that have not finished executing yet.
+    ///
+    /// This includes both the tasks that are waiting to be executed and the ones that are
+    /// currently executing. The counter is shared with the workers, which decrement it as soon
+    /// as a task completes (see [`InFlightGuard`]).
+    in_flight: Arc<AtomicUsize>,
+}
 
 impl<T: Task> TaskPool<T> {
     /// Creates a new [`TaskPool`] instance.
@@ -30,25 +45,51 @@ impl<T: Task> TaskPool<T> {
         T: Task + Send + 'static,
         T::Output: Send,
     {
-        let flavor = match num_threads() {
+        Self::with_threads(num_threads())
+    }
+
+    /// Creates a new [`TaskPool`] instance that uses exactly `count` worker threads.
+    ///
+    /// When `count` is zero, the tasks are executed on the current thread when their outputs
+    /// are fetched.
+    pub fn with_threads(count: usize) -> Self
+    where
+        T: Task + Send + 'static,
+        T::Output: Send,
+    {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+
+        let flavor = match count {
             0 => Flavor::NoThreads(no_threads::NoThreads::new()),
-            num => Flavor::Threads(yes_threads::YesThreads::new(num)),
+            num => Flavor::Threads(yes_threads::YesThreads::new(num, in_flight.clone())),
         };
 
-        Self(flavor)
+        Self { flavor, in_flight }
     }
 
     /// Returns the number of tasks that are waiting to be executed.
     pub fn pending_tasks(&self) -> usize {
-        match &self.0 {
+        match &self.flavor {
             Flavor::NoThreads(f) => f.pending_tasks(),
             Flavor::Threads(f) => f.pending_tasks(),
         }
     }
 
+    /// Returns the number of tasks that have been submitted but that have not finished
+    /// executing yet.
+    ///
+    /// Unlike [`pending_tasks`](Self::pending_tasks), this includes the tasks that are
+    /// currently being executed.
+    #[inline]
+    pub fn in_flight_tasks(&self) -> usize {
+        self.in_flight.load(Relaxed)
+    }
+
     /// Submits the provided tasks to the task pool.
     pub fn submit_batch(&mut self, tasks: &mut Vec<T>) {
-        match &mut self.0 {
+        self.in_flight.fetch_add(tasks.len(), Relaxed);
+
+        match &mut self.flavor {
             Flavor::NoThreads(f) => f.submit_batch(tasks),
             Flavor::Threads(f) => f.submit_batch(tasks),
         }
@@ -92,15 +133,24 @@ impl<T: Task> TaskPool<T> {
             }
         }
 
-        match &mut self.0 {
-            Flavor::NoThreads(f) => Either::Left(f.fetch_outputs()),
+        match &mut self.flavor {
+            Flavor::NoThreads(f) => Either::Left(f.fetch_outputs(&self.in_flight)),
             Flavor::Threads(f) => Either::Right(f.fetch_outputs()),
         }
     }
 
     /// Retains only the tasks that satisfy the provided predicate.
-    pub fn retain_tasks(&mut self, predicate: impl FnMut(&T) -> bool) {
-        match &mut self.0 {
+    pub fn retain_tasks(&mut self, mut predicate: impl FnMut(&T) -> bool) {
+        let in_flight = &self.in_flight;
+        let predicate = |task: &T| {
+            let keep = predicate(task);
+            if !keep {
+                in_flight.fetch_sub(1, Relaxed);
+            }
+            keep
+        };
+
+        match &mut self.flavor {
             Flavor::NoThreads(f) => f.retain_tasks(predicate),
             Flavor::Threads(f) => f.retain_tasks(predicate),
         }
@@ -109,22 +159,66 @@ impl<T: Task> TaskPool<T> {
 
 impl<T: Task> Drop for TaskPool<T> {
     fn drop(&mut self) {
-        match &self.0 {
+        match &self.flavor {
             Flavor::NoThreads(_) => (),
             Flavor::Threads(shared) => shared.stop(),
         }
     }
 }
 
+/// Decrements the in-flight counter of a [`TaskPool`] when dropped.
+///
+/// Workers hold one of those while executing a task, ensuring that the counter is updated
+/// as soon as the task completes, regardless of when its output is fetched.
+struct InFlightGuard<'a>(&'a AtomicUsize);
+
+impl Drop for InFlightGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Relaxed);
+    }
+}
+
+/// The amount of memory that each worker thread is expected to need, in bytes.
+///
+/// Every worker holds the chunks it is generating and the geometry it is building for them.
+const MEMORY_PER_WORKER: u64 = 256 * 1024 * 1024;
+
 /// Returns the number of threads that should be used for the task pool.
 ///
 /// Returns 0 or 1 if the task pool should not use threads at all. This is the case
 /// on single-core machines, or on web.
+///
+/// # Remarks
+///
+/// The `BNS_WORKER_THREADS` environment variable overrides the detected value.
+#[cfg(any(not(target_arch = "wasm32"), feature = "web-workers"))]
 fn num_threads() -> usize {
-    match std::thread::available_parallelism() {
-        Ok(num) => {
-            let num = num.get();
+    // `std::thread::spawn` is not available on web. Tasks are executed by web workers sharing
+    // the module's memory when possible, and on the main thread otherwise.
+    #[cfg(target_arch = "wasm32")]
+    if !web_workers::is_available() {
+        bns_log::info!("web workers cannot share memory, running tasks on the main thread");
+        return 0;
+    }
+
+    if let Ok(value) = std::env::var("BNS_WORKER_THREADS") {
+        match value.trim().parse() {
+            Ok(count) => {
+                bns_log::info!("BNS_WORKER_THREADS overriden to {count}");
+                return count;
+            }
+            Err(_) => bns_log::warning!("ignoring invalid value for BNS_WORKER_THREADS: {value:?}"),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    let parallelism = web_workers::hardware_concurrency().ok_or(());
+    #[cfg(not(target_arch = "wasm32"))]
+    let parallelism = std::thread::available_parallelism().map(|num| num.get());
 
+    match parallelism {
+        Ok(num) => {
             if num <= 2 {
                 bns_log::warning!("very few parallelism available, assuming 0");
 
@@ -135,7 +229,12 @@ fn num_threads() -> usize {
                 // Avoid using ALL available threads because that would probably
                 // just eat up all the CPU resources and make the game laggy.
                 // If the user cannot
-                let used = (num / 2).max(2);
+                let mut used = (num / 2).max(2);
+
+                // Don't spawn more workers than the available memory can sustain.
+                if let Some(memory) = crate::settings::available_memory() {
+                    used = used.min((memory / MEMORY_PER_WORKER).max(1) as usize);
+                }
 
                 bns_log::info!("found {num} available threads, using {used} of them");
 
@@ -151,3 +250,108 @@ fn num_threads() -> usize {
         }
     }
 }
+
+/// Returns the number of threads that should be used for the task pool.
+///
+/// Without the `web-workers` feature, threads are not available on web and tasks are executed
+/// on the main thread.
+#[cfg(all(target_arch = "wasm32", not(feature = "web-workers")))]
+fn num_threads() -> usize {
+    bns_log::info!("threads are not available on web, running tasks on the main thread");
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    /// A task that records how many tasks are executing at the same time.
+    struct Probe {
+        executing: Arc<AtomicUsize>,
+        max_executing: Arc<AtomicUsize>,
+    }
+
+    impl Task for Probe {
+        type Output = ();
+
+        fn execute(self) {
+            let now = self.executing.fetch_add(1, SeqCst) + 1;
+            self.max_executing.fetch_max(now, SeqCst);
+            std::thread::sleep(Duration::from_micros(200));
+            self.executing.fetch_sub(1, SeqCst);
+        }
+    }
+
+    /// Submits `total` probes to `pool`, never keeping more than `limit` of them in flight, and
+    /// returns the maximum number of probes that were executing at the same time.
+    fn run_probes(pool: &mut TaskPool<Probe>, total: usize, limit: usize) -> usize {
+        let executing = Arc::new(AtomicUsize::new(0));
+        let max_executing = Arc::new(AtomicUsize::new(0));
+
+        let deadline = Instant::now() + Duration::from_secs(30);
+        let mut submitted = 0;
+        let mut completed = 0;
+        let mut batch = Vec::new();
+
+        while completed < total {
+            assert!(Instant::now() < deadline, "the task pool made no progress");
+            assert!(pool.in_flight_tasks() <= limit);
+
+            let budget = limit.saturating_sub(pool.in_flight_tasks());
+            let count = budget.min(total - submitted);
+            batch.extend((0..count).map(|_| Probe {
+                executing: executing.clone(),
+                max_executing: max_executing.clone(),
+            }));
+            submitted += count;
+            pool.submit_batch(&mut batch);
+
+            completed += pool.fetch_outputs().count();
+            std::thread::yield_now();
+        }
+
+        max_executing.load(SeqCst)
+    }
+
+    #[test]
+    fn in_flight_returns_to_zero() {
+        for threads in [0, 4] {
+            let mut pool = TaskPool::with_threads(threads);
+            run_probes(&mut pool, 200, usize::MAX);
+            assert_eq!(pool.in_flight_tasks(), 0);
+        }
+    }
+
+    #[test]
+    fn in_flight_limit_is_respected() {
+        const LIMIT: usize = 3;
+
+        let mut pool = TaskPool::with_threads(8);
+        let max_executing = run_probes(&mut pool, 200, LIMIT);
+        assert!(max_executing <= LIMIT);
+        assert_eq!(pool.in_flight_tasks(), 0);
+    }
+
+    #[test]
+    fn retained_out_tasks_are_not_in_flight() {
+        let executing = Arc::new(AtomicUsize::new(0));
+        let max_executing = Arc::new(AtomicUsize::new(0));
+
+        let mut pool = TaskPool::with_threads(0);
+        let mut batch = (0..10)
+            .map(|_| Probe {
+                executing: executing.clone(),
+                max_executing: max_executing.clone(),
+            })
+            .collect();
+        pool.submit_batch(&mut batch);
+        assert_eq!(pool.in_flight_tasks(), 10);
+
+        pool.retain_tasks(|_| false);
+        assert_eq!(pool.in_flight_tasks(), 0);
+    }
+}
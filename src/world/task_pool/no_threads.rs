@@ -1,6 +1,8 @@
+use std::sync::atomic::AtomicUsize;
+
 use smallvec::SmallVec;
 
-use super::Task;
+use super::{InFlightGuard, Task};
 
 /// An implementation of the task pool that does not use threads at all.
 pub struct NoThreads<T> {
@@ -29,7 +31,9 @@ impl<T> NoThreads<T> {
     }
 
     /// Returns an iterator over the outputs of the tasks that have completed.
-    pub fn fetch_outputs(&mut self) -> impl Iterator<Item = T::Output> + '_
+    ///
+    /// `in_flight` is decremented for every task that's executed.
+    pub fn fetch_outputs(&mut self, in_flight: &AtomicUsize) -> impl Iterator<Item = T::Output>
     where
         T: Task,
     {
@@ -40,8 +44,9 @@ impl<T> NoThreads<T> {
 
         // Execute a bunch of tasks on the current thread.
         for _ in 0..BUDGET.min(self.pending.len()) {
-            let output = self.pending.pop().unwrap().execute();
-            result.push(output);
+            let task = self.pending.pop().unwrap();
+            let _guard = InFlightGuard(in_flight);
+            result.push(task.execute());
         }
 
         result.into_iter()
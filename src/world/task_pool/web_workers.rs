@@ -0,0 +1,104 @@
+//! Spawns the worker threads of the task pool as web workers.
+//!
+//! # Remarks
+//!
+//! Web workers can only share memory with the main thread when the module is built with atomics
+//! enabled and the page is cross-origin isolated. The module must be built with
+//! `-C target-feature=+atomics,+bulk-memory,+mutable-globals` and `-Z build-std=panic_abort,std`,
+//! and the bindings generated by `wasm-bindgen --target web` under their default name.
+//!
+//! When any of those conditions is not met, [`is_available`] returns `false` and the task pool
+//! falls back to executing tasks on the main thread.
+//!
+//! The main thread is not allowed to block on web. It still takes the locks of the task pool, but
+//! those are only ever held for very short periods of time.
+
+use web_sys::wasm_bindgen::prelude::*;
+use web_sys::wasm_bindgen::JsCast;
+use web_sys::{js_sys, wasm_bindgen};
+
+#[wasm_bindgen(inline_js = r#"
+const WORKER_SCRIPT = `
+self.onmessage = async (event) => {
+    const [url, module, memory, ptr] = event.data;
+    const bindings = await import(url);
+    await bindings.default(module, memory);
+    bindings.bns_task_pool_worker_entry_point(ptr);
+};
+`;
+
+export function spawn_worker(module, memory, ptr) {
+    // Snippets are placed in `snippets/<crate>-<hash>/` next to the main bindings.
+    const url = new URL("../../blocks_n_stuff.js", import.meta.url).href;
+    const blob = new Blob([WORKER_SCRIPT], { type: "text/javascript" });
+    const worker = new Worker(URL.createObjectURL(blob), { type: "module" });
+    worker.postMessage([url, module, memory, ptr]);
+}
+"#)]
+extern "C" {
+    #[wasm_bindgen(catch)]
+    fn spawn_worker(module: JsValue, memory: JsValue, ptr: u32) -> Result<(), JsValue>;
+}
+
+/// The closure executed by a worker.
+type WorkerMain = Box<dyn FnOnce() + Send>;
+
+/// Returns whether web workers sharing the memory of the module can be spawned.
+pub fn is_available() -> bool {
+    let isolated = js_sys::Reflect::get(&js_sys::global(), &"crossOriginIsolated".into())
+        .map_or(false, |v| v.is_truthy());
+
+    let shared = wasm_bindgen::memory()
+        .unchecked_into::<js_sys::WebAssembly::Memory>()
+        .buffer()
+        .is_instance_of::<js_sys::SharedArrayBuffer>();
+
+    isolated && shared
+}
+
+/// Returns the number of logical processors reported by the browser.
+pub fn hardware_concurrency() -> Option<usize> {
+    let navigator = js_sys::Reflect::get(&js_sys::global(), &"navigator".into()).ok()?;
+    let count = js_sys::Reflect::get(&navigator, &"hardwareConcurrency".into()).ok()?;
+    count.as_f64().map(|count| count as usize)
+}
+
+/// Spawns a new web worker that runs `f`.
+///
+/// # Panics
+///
+/// This function panics if the worker could not be created.
+pub fn spawn(f: impl FnOnce() + Send + 'static) {
+    let ptr = Box::into_raw(Box::new(Box::new(f) as WorkerMain));
+
+    if let Err(err) = spawn_worker(wasm_bindgen::module(), wasm_bindgen::memory(), ptr as u32) {
+        // SAFETY: The worker was not created, so the pointer was not handed to anyone.
+        drop(unsafe { Box::from_raw(ptr) });
+        panic!("failed to spawn a web worker: {err:?}");
+    }
+}
+
+/// The function called by a web worker once it has instantiated the module.
+///
+/// `ptr` is the pointer created by [`spawn`] for that worker.
+#[wasm_bindgen]
+pub fn bns_task_pool_worker_entry_point(ptr: u32) {
+    // SAFETY: This function is only called by the worker script, exactly once, with the
+    // pointer that `spawn` sent to it.
+    let f = unsafe { Box::from_raw(ptr as *mut WorkerMain) };
+    f();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Only checks that the backend builds, with
+    /// `cargo test --target wasm32-unknown-unknown --features web-workers --no-run`.
+    #[test]
+    fn worker_entry_point_is_exported() {
+        let _: fn(fn()) = |f| spawn(f);
+        let _: fn(u32) = bns_task_pool_worker_entry_point;
+        let _: fn() -> bool = is_available;
+    }
+}
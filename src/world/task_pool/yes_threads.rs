@@ -1,10 +1,10 @@
-use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering::Relaxed;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
 use std::sync::Arc;
 
 use parking_lot::{Condvar, Mutex};
 
-use super::Task;
+use super::{InFlightGuard, Task};
 
 /// The state shared between the task pool and worker threads.
 struct Shared<T: Task> {
@@ -19,16 +19,21 @@ struct Shared<T: Task> {
     /// When a worker thead finishes executing a task, it will push the result on top of
     /// this vector.
     outputs: Mutex<Vec<T::Output>>,
+    /// The number of tasks that have been submitted but that have not finished executing yet.
+    ///
+    /// This is shared with the [`TaskPool`](super::TaskPool).
+    in_flight: Arc<AtomicUsize>,
 }
 
 impl<T: Task> Shared<T> {
     /// Creates a new [`Shared`] instance.
-    pub fn new() -> Self {
+    pub fn new(in_flight: Arc<AtomicUsize>) -> Self {
         Self {
             tasks: Mutex::new(Vec::new()),
             condvar: Condvar::new(),
             stop_requested: AtomicBool::new(false),
             outputs: Mutex::new(Vec::new()),
+            in_flight,
         }
     }
 
@@ -99,16 +104,16 @@ pub struct YesThreads<T: Task> {
 
 impl<T: Task> YesThreads<T> {
     /// Creates a new [`Shared`] instance.
-    pub fn new(to_spawn: usize) -> Self
+    pub fn new(to_spawn: usize, in_flight: Arc<AtomicUsize>) -> Self
     where
         T: Send + 'static,
         T::Output: Send,
     {
-        let shared = Arc::new(Shared::new());
+        let shared = Arc::new(Shared::new(in_flight));
 
         for _ in 0..to_spawn {
             let shared = shared.clone();
-            std::thread::spawn(move || {
+            spawn(move || {
                 profiling::register_thread!("task_pool_worker");
                 worker_thread(shared)
             });
@@ -180,6 +185,14 @@ impl<T: Task> YesThreads<T> {
     }
 }
 
+/// Spawns a new worker thread that runs `f`.
+fn spawn(f: impl FnOnce() + Send + 'static) {
+    #[cfg(all(target_arch = "wasm32", feature = "web-workers"))]
+    super::web_workers::spawn(f);
+    #[cfg(not(all(target_arch = "wasm32", feature = "web-workers")))]
+    std::thread::spawn(f);
+}
+
 /// Runs a worker thread until it's requested to stop.
 fn worker_thread<T: Task>(shared: Arc<Shared<T>>) {
     const MAX_TASK_REQUESTS: usize = 8;
@@ -188,7 +201,10 @@ fn worker_thread<T: Task>(shared: Arc<Shared<T>>) {
 
     while shared.fetch_tasks(&mut private_task_list, MAX_TASK_REQUESTS) {
         while let Some(task) = private_task_list.pop() {
-            let output = task.execute();
+            let output = {
+                let _guard = InFlightGuard(&shared.in_flight);
+                task.execute()
+            };
             shared.push_output(output);
 
             if shared.should_stop() {